@@ -0,0 +1,63 @@
+// Package atomicfile writes the files guck keeps its state in, so that a
+// process stopped mid-write never leaves one truncated, and keeps the previous
+// version of a file to recover from should it end up corrupt anyway
+package atomicfile
+
+import (
+	"errors"
+	"io/fs"
+	"os"
+	"path/filepath"
+)
+
+// BackupSuffix names the copy of a file's previous contents kept by Backup
+const BackupSuffix = ".bak"
+
+// Write replaces path with data by writing a temporary file next to it and
+// renaming it into place, so a process stopped mid-write leaves the previous
+// contents rather than a truncated file
+func Write(path string, data []byte) error {
+	f, err := os.CreateTemp(filepath.Dir(path), filepath.Base(path)+".*.tmp")
+	if err != nil {
+		return err
+	}
+	tmp := f.Name()
+	defer func() { _ = os.Remove(tmp) }() // No-op once renamed
+
+	if _, err := f.Write(data); err != nil {
+		_ = f.Close()
+		return err
+	}
+	if err := f.Sync(); err != nil {
+		_ = f.Close()
+		return err
+	}
+	if err := f.Close(); err != nil {
+		return err
+	}
+	if err := os.Chmod(tmp, 0644); err != nil {
+		return err
+	}
+	return os.Rename(tmp, path)
+}
+
+// Backup keeps the current contents of path next to it with BackupSuffix, as
+// a hard link where possible so that no data is copied. A missing path has
+// nothing to back up.
+func Backup(path string) error {
+	backup := path + BackupSuffix
+	if err := os.Remove(backup); err != nil && !errors.Is(err, fs.ErrNotExist) {
+		return err
+	}
+	err := os.Link(path, backup)
+	if err == nil || errors.Is(err, fs.ErrNotExist) {
+		return nil
+	}
+
+	// Some file systems don't support hard links
+	data, err := os.ReadFile(path)
+	if err != nil {
+		return err
+	}
+	return Write(backup, data)
+}
@@ -0,0 +1,40 @@
+package atomicfile
+
+import (
+	"os"
+	"path/filepath"
+	"testing"
+)
+
+func TestWriteAndBackup(t *testing.T) {
+	path := filepath.Join(t.TempDir(), "state.json")
+
+	// Nothing to back up yet
+	if err := Backup(path); err != nil {
+		t.Fatalf("Failed to back up a missing file: %v", err)
+	}
+	if err := Write(path, []byte("first")); err != nil {
+		t.Fatalf("Failed to write: %v", err)
+	}
+	if err := Backup(path); err != nil {
+		t.Fatalf("Failed to back up: %v", err)
+	}
+	if err := Write(path, []byte("second")); err != nil {
+		t.Fatalf("Failed to write: %v", err)
+	}
+
+	if data, _ := os.ReadFile(path); string(data) != "second" {
+		t.Errorf("Expected the new contents, got %q", data)
+	}
+	if data, _ := os.ReadFile(path + BackupSuffix); string(data) != "first" {
+		t.Errorf("Expected the backup to keep the previous contents, got %q", data)
+	}
+
+	entries, err := os.ReadDir(filepath.Dir(path))
+	if err != nil {
+		t.Fatalf("Failed to read directory: %v", err)
+	}
+	if len(entries) != 2 {
+		t.Errorf("Expected no temporary files left behind, got %v", entries)
+	}
+}
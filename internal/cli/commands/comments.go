@@ -11,7 +11,6 @@ import (
 
 // ListComments handles the "guck comments list" command
 func ListComments(c *cli.Context) error {
-	repoPath := c.String("repo")
 	branch := c.String("branch")
 	commit := c.String("commit")
 	filePath := c.String("file")
@@ -19,7 +18,7 @@ func ListComments(c *cli.Context) error {
 
 	// Build params
 	params := mcp.ListCommentsParams{
-		RepoPath: repoPath,
+		RepoPath: ".",
 	}
 
 	if branch != "" {
@@ -62,12 +61,11 @@ func ResolveComment(c *cli.Context) error {
 	}
 
 	commentID := c.Args().Get(0)
-	repoPath := c.String("repo")
 	resolvedBy := c.String("by")
 	format := c.String("format")
 
 	params := mcp.ResolveCommentParams{
-		RepoPath:   repoPath,
+		RepoPath:   ".",
 		CommentID:  commentID,
 		ResolvedBy: resolvedBy,
 	}
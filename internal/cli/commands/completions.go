@@ -0,0 +1,100 @@
+package commands
+
+import (
+	"fmt"
+	"strings"
+
+	"github.com/urfave/cli/v2"
+)
+
+// CompletionShells lists the shells "guck completions" can generate scripts for
+var CompletionShells = []string{"bash", "zsh", "fish", "powershell"}
+
+// The bash, zsh and PowerShell scripts ask guck itself for candidates via
+// --generate-bash-completion, so they stay in sync as commands are added.
+const bashCompletion = `# guck bash completion
+_guck_completions() {
+  local cur opts
+  COMPREPLY=()
+  cur="${COMP_WORDS[COMP_CWORD]}"
+  if [[ "$cur" == "-"* ]]; then
+    opts=$("${COMP_WORDS[@]:0:$COMP_CWORD}" "${cur}" --generate-bash-completion)
+  else
+    opts=$("${COMP_WORDS[@]:0:$COMP_CWORD}" --generate-bash-completion)
+  fi
+  COMPREPLY=($(compgen -W "${opts}" -- "${cur}"))
+  return 0
+}
+complete -o bashdefault -o default -F _guck_completions guck
+`
+
+const zshCompletion = `#compdef guck
+# guck zsh completion
+_guck() {
+  local -a opts
+  local cur
+  cur=${words[-1]}
+  if [[ "$cur" == "-"* ]]; then
+    opts=("${(@f)$(${words[@]:0:#words[@]-1} ${cur} --generate-bash-completion)}")
+  else
+    opts=("${(@f)$(${words[@]:0:#words[@]-1} --generate-bash-completion)}")
+  fi
+  if [[ "${opts[1]}" != "" ]]; then
+    _describe 'values' opts
+  else
+    _files
+  fi
+}
+# Autoloaded from $fpath the file is _guck's body, so complete right away;
+# when eval'd, register the function instead
+if [[ "${funcstack[1]}" == "_guck" ]]; then
+  _guck "$@"
+else
+  compdef _guck guck
+fi
+`
+
+const powershellCompletion = `# guck PowerShell completion
+Register-ArgumentCompleter -Native -CommandName guck -ScriptBlock {
+  param($wordToComplete, $commandAst, $cursorPosition)
+  $elements = @($commandAst.CommandElements | Select-Object -Skip 1 | ForEach-Object { $_.ToString() })
+  if ($wordToComplete -ne '') {
+    $elements = @($elements | Select-Object -SkipLast 1)
+  }
+  if ($wordToComplete.StartsWith('-')) {
+    $elements += $wordToComplete
+  }
+  & guck @elements --generate-bash-completion | Where-Object { $_ -like "$wordToComplete*" } | ForEach-Object {
+    [System.Management.Automation.CompletionResult]::new($_, $_, 'ParameterValue', $_)
+  }
+}
+`
+
+// PrintCompletions handles the "guck completions <shell>" command
+func PrintCompletions(c *cli.Context) error {
+	if c.NArg() != 1 {
+		return fmt.Errorf("requires exactly 1 argument: shell (%s)", strings.Join(CompletionShells, ", "))
+	}
+
+	var script string
+	switch shell := c.Args().Get(0); shell {
+	case "bash":
+		script = bashCompletion
+	case "zsh":
+		script = zshCompletion
+	case "powershell":
+		script = powershellCompletion
+	case "fish":
+		// Fish completions are static, generated from the command definitions
+		fish, err := c.App.ToFishCompletion()
+		if err != nil {
+			return fmt.Errorf("failed to generate fish completion: %w", err)
+		}
+		script = fish
+	default:
+		return fmt.Errorf("unsupported shell '%s' (expected one of %s)", shell, strings.Join(CompletionShells, ", "))
+	}
+
+	_, err := fmt.Fprint(c.App.Writer, script)
+	return err
+}
@@ -0,0 +1,42 @@
+package commands
+
+import (
+	"bytes"
+	"strings"
+	"testing"
+)
+
+func TestPrintCompletions(t *testing.T) {
+	for _, shell := range CompletionShells {
+		c := newTestContext(t, nil, shell)
+		var out bytes.Buffer
+		c.App.Writer = &out
+		if err := PrintCompletions(c); err != nil {
+			t.Errorf("Shell %s: failed to print completions: %v", shell, err)
+			continue
+		}
+		if out.Len() == 0 {
+			t.Errorf("Shell %s: expected a completion script", shell)
+		}
+	}
+}
+
+func TestPrintCompletionsArguments(t *testing.T) {
+	tests := map[string]struct {
+		args     []string
+		expected string
+	}{
+		"missing shell": {args: nil, expected: "requires exactly 1 argument"},
+		"too many":      {args: []string{"bash", "zsh"}, expected: "requires exactly 1 argument"},
+		"unknown shell": {args: []string{"tcsh"}, expected: "unsupported shell 'tcsh'"},
+	}
+
+	for name, tt := range tests {
+		t.Run(name, func(t *testing.T) {
+			err := PrintCompletions(newTestContext(t, nil, tt.args...))
+			if err == nil || !strings.Contains(err.Error(), tt.expected) {
+				t.Errorf("Expected an error containing %q, got %v", tt.expected, err)
+			}
+		})
+	}
+}
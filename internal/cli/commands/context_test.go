@@ -0,0 +1,50 @@
+package commands
+
+import (
+	"flag"
+	"os/exec"
+	"testing"
+
+	"github.com/urfave/cli/v2"
+)
+
+// newTestContext builds the context a command action gets when run with
+// args, which may start with the given flags
+func newTestContext(t *testing.T, flags []cli.Flag, args ...string) *cli.Context {
+	t.Helper()
+
+	set := flag.NewFlagSet("guck", flag.ContinueOnError)
+	for _, f := range flags {
+		if err := f.Apply(set); err != nil {
+			t.Fatalf("Failed to apply flag %v: %v", f.Names(), err)
+		}
+	}
+	if err := set.Parse(args); err != nil {
+		t.Fatalf("Failed to parse arguments %v: %v", args, err)
+	}
+	return cli.NewContext(cli.NewApp(), set, nil)
+}
+
+// setupTestRepo creates a repository with one commit on main and makes it
+// the working directory, with review state kept in a temporary directory
+func setupTestRepo(t *testing.T) string {
+	t.Helper()
+
+	dir := t.TempDir()
+	for _, args := range [][]string{
+		{"init", "--initial-branch=main"},
+		{"config", "user.email", "test@test.com"},
+		{"config", "user.name", "Test User"},
+		{"commit", "--allow-empty", "-m", "Initial commit"},
+	} {
+		cmd := exec.Command("git", args...)
+		cmd.Dir = dir
+		if output, err := cmd.CombinedOutput(); err != nil {
+			t.Fatalf("git %v failed: %v\n%s", args, err, output)
+		}
+	}
+
+	t.Chdir(dir)
+	t.Setenv("GUCK_STATE_DIR", t.TempDir())
+	return dir
+}
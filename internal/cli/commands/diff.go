@@ -0,0 +1,82 @@
+package commands
+
+import (
+	"os"
+
+	"github.com/tuist/guck/internal/cli/formatters"
+	"github.com/tuist/guck/internal/config"
+	"github.com/tuist/guck/internal/git"
+	"github.com/urfave/cli/v2"
+)
+
+// Diff handles the "guck diff" command
+func Diff(c *cli.Context) error {
+	format := c.String("format")
+
+	gitRepo, err := git.Open(".")
+	if err != nil {
+		return err
+	}
+
+	pathspec := c.StringSlice("pathspec")
+
+	var files []git.FileInfo
+	var suppressed []git.SuppressedChange
+	mode := "branch"
+
+	if c.Bool("working") {
+		mode = "working"
+		files, err = gitRepo.GetWorkingChanges(pathspec...)
+		if err != nil {
+			return err
+		}
+	} else {
+		repoRoot, err := gitRepo.RepoPath()
+		if err != nil {
+			return err
+		}
+		cfg, err := config.LoadForWorkingDir(repoRoot)
+		if err != nil {
+			return err
+		}
+
+		baseBranch := c.String("base")
+		if baseBranch == "" {
+			baseBranch = cfg.BaseBranch
+		}
+
+		opts := git.DiffOptions{Backend: cfg.DiffBackend, Textconv: cfg.Textconv, Pathspec: pathspec}
+		if since := c.String("since-commit"); since != "" {
+			mode = "incremental"
+			files, err = gitRepo.GetDiffSince(c.Context, since, opts)
+		} else {
+			files, err = gitRepo.GetDiffFilesWithOptions(baseBranch, opts)
+		}
+		if err != nil {
+			return err
+		}
+
+		if c.Bool("ignore-cherry-picks") && mode == "branch" {
+			files, suppressed, err = gitRepo.SuppressCherryPicks(c.Context, baseBranch, files)
+			if err != nil {
+				return err
+			}
+		}
+	}
+
+	if format == "patch" {
+		// The same unified diff GET /api/diff.patch serves
+		return git.WritePatch(os.Stdout, files)
+	}
+
+	result := map[string]interface{}{
+		"files": files,
+		"count": len(files),
+		"mode":  mode,
+	}
+	if len(suppressed) > 0 {
+		result["suppressed"] = suppressed
+	}
+
+	return formatters.OutputResult(result, format)
+}
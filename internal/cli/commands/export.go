@@ -0,0 +1,274 @@
+package commands
+
+import (
+	"encoding/json"
+	"fmt"
+	"io"
+	"os"
+
+	"github.com/tuist/guck/internal/config"
+	"github.com/tuist/guck/internal/export"
+	"github.com/tuist/guck/internal/git"
+	"github.com/tuist/guck/internal/gitlab"
+	"github.com/tuist/guck/internal/state"
+	"github.com/urfave/cli/v2"
+)
+
+// ExportComments handles the "guck comments export" command
+func ExportComments(c *cli.Context) error {
+	gitRepo, err := git.Open(".")
+	if err != nil {
+		return err
+	}
+
+	repoPath, err := gitRepo.RepoPath()
+	if err != nil {
+		return err
+	}
+
+	branch := c.String("branch")
+	if branch == "" {
+		branch, err = gitRepo.CurrentBranch()
+		if err != nil {
+			return err
+		}
+	}
+
+	commit := c.String("commit")
+	if commit == "" {
+		commit, err = gitRepo.CurrentCommit()
+		if err != nil {
+			return err
+		}
+	}
+
+	cfg, err := config.LoadForWorkingDir(repoPath)
+	if err != nil {
+		return err
+	}
+
+	stateMgr, err := state.NewManager()
+	if err != nil {
+		return fmt.Errorf("failed to load state: %w", err)
+	}
+
+	opts := export.Options{
+		Author:         c.String("author"),
+		ExcludeAuthors: append(cfg.ExcludeAuthors, c.StringSlice("exclude-author")...),
+		Summary:        stateMgr.GetReviewSummary(repoPath, branch, commit),
+		Session:        stateMgr.GetReviewSession(repoPath, branch, commit),
+	}
+	if len(cfg.ReviewChecklist) > 0 {
+		checklist := stateMgr.GetChecklistProgress(repoPath, branch, commit, cfg.ReviewChecklist)
+		opts.Checklist = &checklist
+	}
+
+	comments := stateMgr.GetComments(repoPath, branch, commit, nil)
+	if len(export.FilterComments(comments, opts)) == 0 {
+		fmt.Fprintln(os.Stderr, "No matching comments to export")
+	}
+
+	var w io.Writer = os.Stdout
+	if output := c.String("output"); output != "" {
+		f, err := os.Create(output)
+		if err != nil {
+			return fmt.Errorf("failed to create export file: %w", err)
+		}
+		defer f.Close()
+		w = f
+	}
+
+	return export.Markdown(w, comments, opts)
+}
+
+// ExportReview handles the "guck export" command. It renders the current
+// review, the changed files with their viewed status and the comments on
+// them, in the format --format selects.
+func ExportReview(c *cli.Context) error {
+	if format := c.String("format"); format != "markdown" {
+		return fmt.Errorf("unsupported export format: %s (expected markdown)", format)
+	}
+
+	gitRepo, err := git.Open(".")
+	if err != nil {
+		return err
+	}
+
+	repoPath, err := gitRepo.RepoPath()
+	if err != nil {
+		return err
+	}
+
+	branch, err := gitRepo.CurrentBranch()
+	if err != nil {
+		return err
+	}
+
+	commit, err := gitRepo.CurrentCommit()
+	if err != nil {
+		return err
+	}
+
+	cfg, err := config.LoadForWorkingDir(repoPath)
+	if err != nil {
+		return err
+	}
+
+	baseBranch := c.String("base")
+	if baseBranch == "" {
+		baseBranch = cfg.BaseBranch
+	}
+
+	// Diff the way the daemon does, so patches hash as they did when marked viewed
+	algorithm := cfg.DiffAlgorithm
+	if algorithm == "" {
+		algorithm = gitRepo.ConfiguredDiffAlgorithm()
+	}
+	if git.ValidateDiffAlgorithm(algorithm) != nil {
+		algorithm = ""
+	}
+	diffOpts := git.DiffOptions{
+		Algorithm:         algorithm,
+		RecurseSubmodules: cfg.RecurseSubmodules,
+		Backend:           cfg.DiffBackend,
+		Textconv:          cfg.Textconv,
+	}
+	files, err := gitRepo.GetDiffFilesWithOptions(baseBranch, diffOpts)
+	if err != nil {
+		return fmt.Errorf("failed to get diff against %s: %w", baseBranch, err)
+	}
+
+	stateMgr, err := state.NewManager()
+	if err != nil {
+		return fmt.Errorf("failed to load state: %w", err)
+	}
+
+	reviewFiles := make([]export.ReviewFile, 0, len(files))
+	for _, file := range files {
+		// A file viewed before its patch changed counts as unviewed, as in the UI
+		viewed, _ := stateMgr.ViewedInForm(repoPath, branch, commit, file.Path, file.Patch)
+		reviewFiles = append(reviewFiles, export.ReviewFile{
+			Path:      file.Path,
+			Status:    file.Status,
+			Additions: file.Additions,
+			Deletions: file.Deletions,
+			Viewed:    viewed || state.MatchesAnyPattern(file.Path, cfg.AutoViewPatterns),
+		})
+	}
+
+	opts := export.Options{
+		Author:         c.String("author"),
+		ExcludeAuthors: append(cfg.ExcludeAuthors, c.StringSlice("exclude-author")...),
+		Summary:        stateMgr.GetReviewSummary(repoPath, branch, commit),
+		Session:        stateMgr.GetReviewSession(repoPath, branch, commit),
+	}
+	if len(cfg.ReviewChecklist) > 0 {
+		checklist := stateMgr.GetChecklistProgress(repoPath, branch, commit, cfg.ReviewChecklist)
+		opts.Checklist = &checklist
+	}
+	comments := stateMgr.GetComments(repoPath, branch, commit, nil)
+
+	var w io.Writer = os.Stdout
+	if output := c.String("output"); output != "" {
+		f, err := os.Create(output)
+		if err != nil {
+			return fmt.Errorf("failed to create export file: %w", err)
+		}
+		defer f.Close()
+		w = f
+	}
+
+	return export.ReviewMarkdown(w, reviewFiles, comments, opts)
+}
+
+// ExportGitLab handles the "guck export gitlab" command. It maps the current
+// commit's comments onto GitLab merge request discussions and prints them as
+// JSON, or posts them with --post.
+func ExportGitLab(c *cli.Context) error {
+	iid := c.Int("mr")
+	if iid <= 0 {
+		return fmt.Errorf("invalid merge request number: %d", iid)
+	}
+	project := c.String("project")
+
+	gitRepo, err := git.Open(".")
+	if err != nil {
+		return err
+	}
+
+	repoPath, err := gitRepo.RepoPath()
+	if err != nil {
+		return err
+	}
+
+	branch, err := gitRepo.CurrentBranch()
+	if err != nil {
+		return err
+	}
+
+	commit, err := gitRepo.CurrentCommit()
+	if err != nil {
+		return err
+	}
+
+	cfg, err := config.LoadForWorkingDir(repoPath)
+	if err != nil {
+		return err
+	}
+
+	baseBranch := c.String("base")
+	if baseBranch == "" {
+		baseBranch = cfg.BaseBranch
+	}
+
+	files, err := gitRepo.GetDiffFiles(baseBranch)
+	if err != nil {
+		return fmt.Errorf("failed to get diff against %s: %w", baseBranch, err)
+	}
+
+	stateMgr, err := state.NewManager()
+	if err != nil {
+		return fmt.Errorf("failed to load state: %w", err)
+	}
+
+	refs, err := gitlab.MergeRequestDiffRefs(project, iid)
+	if err != nil {
+		return err
+	}
+	if refs.HeadSHA != commit {
+		fmt.Fprintf(os.Stderr, "Warning: merge request !%d is at %s but HEAD is %s; line positions may not match\n", iid, shortHash(refs.HeadSHA), shortHash(commit))
+	}
+
+	opts := export.Options{
+		Author:         c.String("author"),
+		ExcludeAuthors: append(cfg.ExcludeAuthors, c.StringSlice("exclude-author")...),
+		Summary:        stateMgr.GetReviewSummary(repoPath, branch, commit),
+	}
+	if len(cfg.ReviewChecklist) > 0 {
+		checklist := stateMgr.GetChecklistProgress(repoPath, branch, commit, cfg.ReviewChecklist)
+		opts.Checklist = &checklist
+	}
+	comments := stateMgr.GetComments(repoPath, branch, commit, nil)
+	discussions := export.GitLabDiscussions(comments, opts, refs, files)
+
+	if !c.Bool("post") {
+		encoder := json.NewEncoder(os.Stdout)
+		encoder.SetIndent("", "  ")
+		return encoder.Encode(discussions)
+	}
+
+	for i, discussion := range discussions {
+		if err := gitlab.CreateDiscussion(project, iid, discussion); err != nil {
+			return fmt.Errorf("posted %d of %d discussion(s): %w", i, len(discussions), err)
+		}
+	}
+	fmt.Printf("Posted %d discussion(s) to %s!%d\n", len(discussions), project, iid)
+	return nil
+}
+
+func shortHash(hash string) string {
+	if len(hash) > 8 {
+		return hash[:8]
+	}
+	return hash
+}
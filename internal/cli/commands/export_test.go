@@ -0,0 +1,46 @@
+package commands
+
+import (
+	"strings"
+	"testing"
+
+	"github.com/urfave/cli/v2"
+)
+
+func TestExportReviewRejectsUnknownFormat(t *testing.T) {
+	flags := []cli.Flag{&cli.StringFlag{Name: "format", Value: "markdown"}}
+
+	err := ExportReview(newTestContext(t, flags, "--format", "html"))
+	if err == nil || !strings.Contains(err.Error(), "unsupported export format: html") {
+		t.Errorf("Expected an unsupported format error, got %v", err)
+	}
+}
+
+func TestExportReviewOutsideRepository(t *testing.T) {
+	t.Chdir(t.TempDir())
+	flags := []cli.Flag{&cli.StringFlag{Name: "format", Value: "markdown"}}
+
+	if err := ExportReview(newTestContext(t, flags)); err == nil {
+		t.Error("Expected an error outside a git repository")
+	}
+}
+
+func TestExportGitLabRejectsInvalidMergeRequest(t *testing.T) {
+	flags := []cli.Flag{&cli.IntFlag{Name: "mr"}}
+
+	for _, mr := range []string{"0", "-4"} {
+		err := ExportGitLab(newTestContext(t, flags, "--mr", mr))
+		if err == nil || !strings.Contains(err.Error(), "invalid merge request number") {
+			t.Errorf("Merge request %s: expected an invalid number error, got %v", mr, err)
+		}
+	}
+}
+
+func TestShortHash(t *testing.T) {
+	if got := shortHash("0123456789abcdef"); got != "01234567" {
+		t.Errorf("Expected the first 8 characters, got %q", got)
+	}
+	if got := shortHash("abc"); got != "abc" {
+		t.Errorf("Expected a short hash to be kept, got %q", got)
+	}
+}
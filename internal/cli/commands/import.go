@@ -0,0 +1,223 @@
+package commands
+
+import (
+	"encoding/json"
+	"fmt"
+	"io"
+	"os"
+	"strings"
+
+	"github.com/tuist/guck/internal/git"
+	"github.com/tuist/guck/internal/github"
+	"github.com/tuist/guck/internal/state"
+	"github.com/urfave/cli/v2"
+)
+
+// ImportGitHubComments handles the "guck import github" command. It copies a
+// pull request's review comments into the review state of the current branch
+// and commit. Comments without a line on the new version of the file, such as
+// outdated ones, are attached to the file instead.
+func ImportGitHubComments(c *cli.Context) error {
+	number := c.Int("pr")
+	if number <= 0 {
+		return fmt.Errorf("invalid pull request number: %d", number)
+	}
+
+	gitRepo, err := git.Open(".")
+	if err != nil {
+		return err
+	}
+
+	repoPath, err := gitRepo.RepoPath()
+	if err != nil {
+		return err
+	}
+
+	owner, repo, err := githubRepo(c.String("github-repo"), gitRepo)
+	if err != nil {
+		return err
+	}
+
+	branch, err := gitRepo.CurrentBranch()
+	if err != nil {
+		return err
+	}
+
+	commit, err := gitRepo.CurrentCommit()
+	if err != nil {
+		return err
+	}
+
+	reviewComments, err := github.PullRequestReviewComments(owner, repo, number)
+	if err != nil {
+		return err
+	}
+
+	stateMgr, err := state.NewManager()
+	if err != nil {
+		return err
+	}
+
+	existing := stateMgr.GetComments(repoPath, branch, commit, nil)
+
+	imported, skipped := 0, 0
+	for _, rc := range reviewComments {
+		lineNumber, text := importedPosition(rc)
+		author := "github:" + rc.User.Login
+
+		if hasComment(existing, rc.Path, lineNumber, text, author) {
+			skipped++
+			continue
+		}
+
+		if _, err := stateMgr.AddCommentWithAuthor(repoPath, branch, commit, rc.Path, lineNumber, text, author); err != nil {
+			return fmt.Errorf("failed to import comment %d: %w", rc.ID, err)
+		}
+		imported++
+	}
+
+	fmt.Printf("Imported %d comment(s) from %s/%s#%d", imported, owner, repo, number)
+	if skipped > 0 {
+		fmt.Printf(", skipped %d already imported", skipped)
+	}
+	fmt.Println()
+	return nil
+}
+
+// ImportState handles the "guck import state" command. It merges comments
+// exported from another machine, as a JSON array like GET /api/comments returns
+// or an object with a "comments" array like "guck comments list --format json"
+// prints, into this repository's review state. Comments without a branch and
+// commit go to the current ones.
+func ImportState(c *cli.Context) error {
+	if c.NArg() != 1 {
+		return fmt.Errorf("expected the file to import, or - for stdin")
+	}
+
+	strategy, err := state.ParseMergeStrategy(c.String("strategy"))
+	if err != nil {
+		return err
+	}
+
+	var data []byte
+	if path := c.Args().First(); path == "-" {
+		data, err = io.ReadAll(os.Stdin)
+	} else {
+		data, err = os.ReadFile(path)
+	}
+	if err != nil {
+		return fmt.Errorf("failed to read import file: %w", err)
+	}
+
+	comments, err := parseImportedComments(data)
+	if err != nil {
+		return err
+	}
+
+	gitRepo, err := git.Open(".")
+	if err != nil {
+		return err
+	}
+
+	repoPath, err := gitRepo.RepoPath()
+	if err != nil {
+		return err
+	}
+
+	branch, err := gitRepo.CurrentBranch()
+	if err != nil {
+		return err
+	}
+
+	commit, err := gitRepo.CurrentCommit()
+	if err != nil {
+		return err
+	}
+
+	stateMgr, err := state.NewManager()
+	if err != nil {
+		return err
+	}
+
+	result, err := stateMgr.MergeComments(repoPath, branch, commit, comments, strategy)
+	if err != nil {
+		return err
+	}
+
+	fmt.Printf("Imported %d comment(s): %d added, %d replaced, %d added under a new ID", result.Added+result.Replaced+result.Rekeyed, result.Added, result.Replaced, result.Rekeyed)
+	if result.Kept > 0 {
+		fmt.Printf(", %d conflicting kept as stored", result.Kept)
+	}
+	if result.Unchanged > 0 {
+		fmt.Printf(", %d already up to date", result.Unchanged)
+	}
+	fmt.Println()
+	return nil
+}
+
+// parseImportedComments accepts either a JSON array of comments or an object
+// holding them under "comments"
+func parseImportedComments(data []byte) ([]*state.Comment, error) {
+	var comments []*state.Comment
+	if err := json.Unmarshal(data, &comments); err == nil {
+		return comments, nil
+	}
+
+	var wrapped struct {
+		Comments []*state.Comment `json:"comments"`
+	}
+	if err := json.Unmarshal(data, &wrapped); err != nil {
+		return nil, fmt.Errorf("failed to parse import file: %w", err)
+	}
+	return wrapped.Comments, nil
+}
+
+// githubRepo parses an "owner/name" flag value, falling back to the origin remote
+func githubRepo(flag string, gitRepo *git.Repo) (string, string, error) {
+	if flag != "" {
+		owner, repo, ok := strings.Cut(flag, "/")
+		if !ok || owner == "" || repo == "" || strings.Contains(repo, "/") {
+			return "", "", fmt.Errorf("invalid GitHub repository '%s' (expected owner/name)", flag)
+		}
+		return owner, repo, nil
+	}
+
+	remoteURL, _ := gitRepo.GetRemoteURL() // Ignore error, remote is optional
+	owner, repo, ok := github.ParseRemote(remoteURL)
+	if !ok {
+		return "", "", fmt.Errorf("origin is not a GitHub remote; pass --github-repo owner/name")
+	}
+	return owner, repo, nil
+}
+
+// importedPosition maps a review comment onto a guck line number. Guck comments
+// are on the new version of a file, so comments on removed lines and outdated
+// comments are attached to the file, with the original position in the text.
+func importedPosition(rc github.ReviewComment) (*int, string) {
+	switch {
+	case rc.Line == nil:
+		return nil, "(outdated) " + rc.Body
+	case rc.Side == "LEFT":
+		return nil, fmt.Sprintf("(on removed line %d) %s", *rc.Line, rc.Body)
+	default:
+		line := *rc.Line
+		return &line, rc.Body
+	}
+}
+
+// hasComment reports whether an identical comment is already stored, so
+// importing the same pull request twice doesn't duplicate comments
+func hasComment(comments []*state.Comment, filePath string, lineNumber *int, text, author string) bool {
+	for _, comment := range comments {
+		if comment.FilePath != filePath || comment.Text != text || comment.Author != author {
+			continue
+		}
+		if (comment.LineNumber == nil) != (lineNumber == nil) {
+			continue
+		}
+		if lineNumber == nil || *comment.LineNumber == *lineNumber {
+			return true
+		}
+	}
+	return false
+}
@@ -0,0 +1,91 @@
+package commands
+
+import (
+	"os"
+	"path/filepath"
+	"strings"
+	"testing"
+
+	"github.com/tuist/guck/internal/github"
+	"github.com/urfave/cli/v2"
+)
+
+func TestImportGitHubCommentsRejectsInvalidNumber(t *testing.T) {
+	flags := []cli.Flag{&cli.IntFlag{Name: "pr"}}
+
+	for _, number := range []string{"0", "-1"} {
+		err := ImportGitHubComments(newTestContext(t, flags, "--pr", number))
+		if err == nil || !strings.Contains(err.Error(), "invalid pull request number") {
+			t.Errorf("Pull request %s: expected an invalid number error, got %v", number, err)
+		}
+	}
+}
+
+func TestImportStateErrors(t *testing.T) {
+	dir := t.TempDir()
+	invalid := filepath.Join(dir, "invalid.json")
+	if err := os.WriteFile(invalid, []byte("not json"), 0644); err != nil {
+		t.Fatalf("Failed to write file: %v", err)
+	}
+	strategy := []cli.Flag{&cli.StringFlag{Name: "strategy", Value: "ours"}}
+
+	tests := map[string]struct {
+		args     []string
+		expected string
+	}{
+		"missing file":     {args: nil, expected: "expected the file to import"},
+		"unknown strategy": {args: []string{"--strategy", "mine", invalid}, expected: "unknown merge strategy 'mine'"},
+		"unreadable file":  {args: []string{filepath.Join(dir, "missing.json")}, expected: "failed to read import file"},
+		"invalid file":     {args: []string{invalid}, expected: "failed to parse import file"},
+	}
+
+	for name, tt := range tests {
+		t.Run(name, func(t *testing.T) {
+			err := ImportState(newTestContext(t, strategy, tt.args...))
+			if err == nil || !strings.Contains(err.Error(), tt.expected) {
+				t.Errorf("Expected an error containing %q, got %v", tt.expected, err)
+			}
+		})
+	}
+}
+
+func TestImportState(t *testing.T) {
+	dir := setupTestRepo(t)
+	file := filepath.Join(dir, "comments.json")
+	data := `{"comments": [{"id": "c1", "file_path": "main.go", "text": "Looks off"}]}`
+	if err := os.WriteFile(file, []byte(data), 0644); err != nil {
+		t.Fatalf("Failed to write file: %v", err)
+	}
+	flags := []cli.Flag{&cli.StringFlag{Name: "strategy", Value: "ours"}}
+
+	if err := ImportState(newTestContext(t, flags, file)); err != nil {
+		t.Fatalf("Failed to import comments: %v", err)
+	}
+}
+
+func TestGithubRepo(t *testing.T) {
+	owner, repo, err := githubRepo("tuist/guck", nil)
+	if err != nil || owner != "tuist" || repo != "guck" {
+		t.Errorf("Expected tuist/guck, got %s/%s (%v)", owner, repo, err)
+	}
+
+	for _, flag := range []string{"tuist", "tuist/", "/guck", "tuist/guck/extra"} {
+		if _, _, err := githubRepo(flag, nil); err == nil {
+			t.Errorf("Expected %q to be rejected", flag)
+		}
+	}
+}
+
+func TestImportedPosition(t *testing.T) {
+	line := 7
+
+	if got, text := importedPosition(github.ReviewComment{Line: &line, Side: "RIGHT", Body: "Nit"}); got == nil || *got != 7 || text != "Nit" {
+		t.Errorf("Expected a comment on the new line to keep it, got %v %q", got, text)
+	}
+	if got, text := importedPosition(github.ReviewComment{Line: &line, Side: "LEFT", Body: "Nit"}); got != nil || text != "(on removed line 7) Nit" {
+		t.Errorf("Expected a comment on a removed line to go to the file, got %v %q", got, text)
+	}
+	if got, text := importedPosition(github.ReviewComment{Body: "Nit"}); got != nil || text != "(outdated) Nit" {
+		t.Errorf("Expected an outdated comment to go to the file, got %v %q", got, text)
+	}
+}
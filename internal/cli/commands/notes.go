@@ -13,7 +13,6 @@ import (
 
 // AddNote handles the "guck notes add" command
 func AddNote(c *cli.Context) error {
-	repoPath := c.String("repo")
 	filePath := c.String("file")
 	text := c.String("text")
 	author := c.String("author")
@@ -21,7 +20,7 @@ func AddNote(c *cli.Context) error {
 	format := c.String("format")
 
 	// Get current branch and commit
-	gitRepo, err := git.Open(repoPath)
+	gitRepo, err := git.Open(".")
 	if err != nil {
 		return err
 	}
@@ -37,7 +36,7 @@ func AddNote(c *cli.Context) error {
 	}
 
 	params := mcp.AddNoteParams{
-		RepoPath: repoPath,
+		RepoPath: ".",
 		Branch:   branch,
 		Commit:   commit,
 		FilePath: filePath,
@@ -81,7 +80,6 @@ func AddNote(c *cli.Context) error {
 
 // ListNotes handles the "guck notes list" command
 func ListNotes(c *cli.Context) error {
-	repoPath := c.String("repo")
 	branch := c.String("branch")
 	commit := c.String("commit")
 	filePath := c.String("file")
@@ -89,7 +87,7 @@ func ListNotes(c *cli.Context) error {
 	format := c.String("format")
 
 	params := mcp.ListNotesParams{
-		RepoPath: repoPath,
+		RepoPath: ".",
 	}
 
 	if branch != "" {
@@ -134,12 +132,11 @@ func DismissNote(c *cli.Context) error {
 	}
 
 	noteID := c.Args().Get(0)
-	repoPath := c.String("repo")
 	dismissedBy := c.String("by")
 	format := c.String("format")
 
 	params := mcp.DismissNoteParams{
-		RepoPath:    repoPath,
+		RepoPath:    ".",
 		NoteID:      noteID,
 		DismissedBy: dismissedBy,
 	}
@@ -0,0 +1,84 @@
+package commands
+
+import (
+	"fmt"
+	"os"
+	"os/exec"
+	"path/filepath"
+	"strconv"
+
+	"github.com/tuist/guck/internal/git"
+	"github.com/tuist/guck/internal/github"
+	"github.com/urfave/cli/v2"
+)
+
+// ReviewPullRequest handles the "guck pr <number>" command. It fetches the
+// pull request's head into a local branch, checks it out in a worktree under
+// the git directory, and starts a review daemon there against the PR's base
+// branch.
+func ReviewPullRequest(c *cli.Context) error {
+	if c.NArg() != 1 {
+		return fmt.Errorf("requires exactly 1 argument: pull request number")
+	}
+
+	number, err := strconv.Atoi(c.Args().Get(0))
+	if err != nil || number <= 0 {
+		return fmt.Errorf("invalid pull request number: %s", c.Args().Get(0))
+	}
+
+	gitRepo, err := git.Open(".")
+	if err != nil {
+		return err
+	}
+
+	baseBranch := c.String("base")
+	if baseBranch == "" {
+		remoteURL, _ := gitRepo.GetRemoteURL() // Ignore error, remote is optional
+		owner, repo, ok := github.ParseRemote(remoteURL)
+		if !ok {
+			return fmt.Errorf("origin is not a GitHub remote, so the base branch can't be looked up; pass --base")
+		}
+
+		baseBranch, err = github.PullRequestBase(owner, repo, number)
+		if err != nil {
+			return fmt.Errorf("%w; pass --base to skip the lookup", err)
+		}
+	}
+
+	branch, err := gitRepo.FetchPullRequest(number)
+	if err != nil {
+		return err
+	}
+
+	if err := gitRepo.FetchBase(baseBranch); err != nil {
+		return err
+	}
+
+	// Worktrees are kept inside the git directory, where they can't run into
+	// directories of the user's
+	_, commonDir, err := gitRepo.GitDirs()
+	if err != nil {
+		return err
+	}
+	worktreePath := filepath.Join(commonDir, "guck-worktrees", fmt.Sprintf("pr-%d", number))
+	if err := gitRepo.CheckoutWorktree(worktreePath, branch); err != nil {
+		return err
+	}
+
+	exe, err := os.Executable()
+	if err != nil {
+		return err
+	}
+
+	cmd := exec.Command(exe, "daemon", "start", "--base", baseBranch)
+	cmd.Dir = worktreePath
+	cmd.Stdout = os.Stdout
+	cmd.Stderr = os.Stderr
+	if err := cmd.Run(); err != nil {
+		return fmt.Errorf("failed to start daemon for pull request #%d: %w", number, err)
+	}
+
+	fmt.Printf("Reviewing pull request #%d against %s in %s\n", number, baseBranch, worktreePath)
+	fmt.Printf("Run 'cd %s && guck' to open the review\n", worktreePath)
+	return nil
+}
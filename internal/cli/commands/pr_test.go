@@ -0,0 +1,45 @@
+package commands
+
+import (
+	"strings"
+	"testing"
+)
+
+func TestReviewPullRequestArguments(t *testing.T) {
+	tests := map[string]struct {
+		args     []string
+		expected string
+	}{
+		"missing number":   {args: nil, expected: "requires exactly 1 argument"},
+		"too many":         {args: []string{"1", "2"}, expected: "requires exactly 1 argument"},
+		"not a number":     {args: []string{"abc"}, expected: "invalid pull request number: abc"},
+		"zero":             {args: []string{"0"}, expected: "invalid pull request number: 0"},
+		"trailing garbage": {args: []string{"12x"}, expected: "invalid pull request number: 12x"},
+	}
+
+	for name, tt := range tests {
+		t.Run(name, func(t *testing.T) {
+			err := ReviewPullRequest(newTestContext(t, nil, tt.args...))
+			if err == nil || !strings.Contains(err.Error(), tt.expected) {
+				t.Errorf("Expected an error containing %q, got %v", tt.expected, err)
+			}
+		})
+	}
+}
+
+func TestReviewPullRequestOutsideRepository(t *testing.T) {
+	t.Chdir(t.TempDir())
+
+	if err := ReviewPullRequest(newTestContext(t, nil, "12")); err == nil {
+		t.Error("Expected an error outside a git repository")
+	}
+}
+
+func TestReviewPullRequestNeedsBaseWithoutGitHub(t *testing.T) {
+	setupTestRepo(t)
+
+	err := ReviewPullRequest(newTestContext(t, nil, "12"))
+	if err == nil || !strings.Contains(err.Error(), "pass --base") {
+		t.Errorf("Expected to be asked for --base without a GitHub origin, got %v", err)
+	}
+}
@@ -0,0 +1,89 @@
+package commands
+
+import (
+	"encoding/json"
+	"fmt"
+
+	"github.com/tuist/guck/internal/config"
+	"github.com/tuist/guck/internal/git"
+	"github.com/tuist/guck/internal/state"
+	"github.com/urfave/cli/v2"
+)
+
+// PromptSummary is the review progress "guck prompt --json" reports
+type PromptSummary struct {
+	Branch       string `json:"branch"`
+	Commit       string `json:"commit"`
+	Changed      int    `json:"changed"`
+	Unviewed     int    `json:"unviewed"`
+	OpenComments int    `json:"open_comments"`
+}
+
+// Prompt handles the "guck prompt" command. It's meant to run on every shell
+// prompt, so it only lists changed paths instead of computing the diff, and
+// prints nothing outside a repository or when there's nothing left to review.
+func Prompt(c *cli.Context) error {
+	gitRepo, err := git.Open(".")
+	if err != nil {
+		return nil
+	}
+
+	repoPath, err := gitRepo.RepoPath()
+	if err != nil {
+		return err
+	}
+
+	branch, err := gitRepo.CurrentBranch()
+	if err != nil {
+		return err
+	}
+
+	commit, err := gitRepo.CurrentCommit()
+	if err != nil {
+		return err
+	}
+
+	baseBranch := c.String("base")
+	if baseBranch == "" {
+		cfg, err := config.LoadForWorkingDir(repoPath)
+		if err != nil {
+			return err
+		}
+		baseBranch = cfg.BaseBranch
+	}
+
+	paths, err := gitRepo.ChangedPaths(baseBranch)
+	if err != nil {
+		return err
+	}
+
+	stateMgr, err := state.NewManager()
+	if err != nil {
+		return fmt.Errorf("failed to load state: %w", err)
+	}
+
+	summary := PromptSummary{
+		Branch:  branch,
+		Commit:  commit,
+		Changed: len(paths),
+	}
+	for _, filePath := range paths {
+		if !stateMgr.IsFileViewed(repoPath, branch, commit, filePath) {
+			summary.Unviewed++
+		}
+	}
+	for _, comment := range stateMgr.GetComments(repoPath, branch, commit, nil) {
+		if !comment.Resolved {
+			summary.OpenComments++
+		}
+	}
+
+	if c.Bool("json") {
+		return json.NewEncoder(c.App.Writer).Encode(summary)
+	}
+
+	if summary.Unviewed > 0 || summary.OpenComments > 0 {
+		fmt.Fprintf(c.App.Writer, "guck: %d unviewed, %d open comments\n", summary.Unviewed, summary.OpenComments)
+	}
+	return nil
+}
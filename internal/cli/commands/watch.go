@@ -0,0 +1,149 @@
+package commands
+
+import (
+	"context"
+	"fmt"
+	"os"
+	"os/signal"
+	"sort"
+	"strings"
+	"syscall"
+	"time"
+
+	"github.com/tuist/guck/internal/config"
+	"github.com/tuist/guck/internal/git"
+	"github.com/tuist/guck/internal/state"
+	"github.com/urfave/cli/v2"
+)
+
+// clearScreen moves the cursor home and clears the terminal
+const clearScreen = "\033[H\033[2J"
+
+// Watch handles the "guck watch" command. It polls the review's diff and
+// redraws a summary whenever the changed files or their viewed state change,
+// until interrupted.
+func Watch(c *cli.Context) error {
+	interval := c.Duration("interval")
+	if interval <= 0 {
+		return fmt.Errorf("invalid interval: %s", interval)
+	}
+
+	working := c.Bool("working")
+
+	// Fail fast on a bad repository rather than redrawing the error forever
+	gitRepo, err := git.Open(".")
+	if err != nil {
+		return err
+	}
+
+	baseBranch := c.String("base")
+	if baseBranch == "" && !working {
+		repoRoot, err := gitRepo.RepoPath()
+		if err != nil {
+			return err
+		}
+		cfg, err := config.LoadForWorkingDir(repoRoot)
+		if err != nil {
+			return err
+		}
+		baseBranch = cfg.BaseBranch
+	}
+
+	ctx, stop := signal.NotifyContext(context.Background(), os.Interrupt, syscall.SIGTERM)
+	defer stop()
+
+	ticker := time.NewTicker(interval)
+	defer ticker.Stop()
+
+	last := ""
+	for {
+		summary, err := watchSummary(".", baseBranch, working)
+		if err != nil {
+			// Keep watching, the repository may be mid-rebase or mid-checkout
+			summary = fmt.Sprintf("Error: %v\n", err)
+		}
+
+		if summary != last {
+			fmt.Print(clearScreen + summary)
+			fmt.Printf("\nUpdated %s, refreshing every %s (Ctrl+C to quit)\n", time.Now().Format("15:04:05"), interval)
+			last = summary
+		}
+
+		select {
+		case <-ctx.Done():
+			return nil
+		case <-ticker.C:
+		}
+	}
+}
+
+// watchSummary renders the changed files with their line counts and viewed
+// state. The repository and review state are reopened every time so branch
+// switches and files marked viewed in the web UI are picked up.
+func watchSummary(repoDir, baseBranch string, working bool) (string, error) {
+	gitRepo, err := git.Open(repoDir)
+	if err != nil {
+		return "", err
+	}
+
+	repoPath, err := gitRepo.RepoPath()
+	if err != nil {
+		return "", err
+	}
+
+	branch, err := gitRepo.CurrentBranch()
+	if err != nil {
+		return "", err
+	}
+
+	commit, err := gitRepo.CurrentCommit()
+	if err != nil {
+		return "", err
+	}
+
+	var files []git.FileInfo
+	title := fmt.Sprintf("%s vs %s", branch, baseBranch)
+	reviewCommit := commit
+	if working {
+		files, err = gitRepo.GetWorkingChanges()
+		title = fmt.Sprintf("%s working tree vs HEAD", branch)
+		// Working-tree reviews keep their viewed state under their own key, as the server does
+		reviewCommit = "__working__:" + commit
+	} else {
+		files, err = gitRepo.GetDiffFiles(baseBranch)
+	}
+	if err != nil {
+		return "", err
+	}
+
+	sort.Slice(files, func(i, j int) bool {
+		return files[i].Path < files[j].Path
+	})
+
+	stateMgr, err := state.NewManager()
+	if err != nil {
+		return "", err
+	}
+
+	var lines strings.Builder
+	additions, deletions, viewed := 0, 0, 0
+	for _, file := range files {
+		additions += file.Additions
+		deletions += file.Deletions
+
+		mark := "[ ]"
+		if stateMgr.IsFileViewed(repoPath, branch, reviewCommit, file.Path) {
+			mark = "[x]"
+			viewed++
+		}
+		fmt.Fprintf(&lines, "  %s %-9s %s +%d -%d\n", mark, file.Status, file.Path, file.Additions, file.Deletions)
+	}
+
+	var out strings.Builder
+	fmt.Fprintf(&out, "%s: %d file(s) changed, +%d -%d, %d/%d viewed\n", title, len(files), additions, deletions, viewed, len(files))
+	if len(files) > 0 {
+		out.WriteString("\n")
+		out.WriteString(lines.String())
+	}
+	return out.String(), nil
+}
@@ -0,0 +1,56 @@
+package commands
+
+import (
+	"os"
+	"path/filepath"
+	"strings"
+	"testing"
+	"time"
+
+	"github.com/urfave/cli/v2"
+)
+
+func TestWatchRejectsInvalidInterval(t *testing.T) {
+	flags := []cli.Flag{&cli.DurationFlag{Name: "interval"}}
+
+	for _, interval := range []string{"0s", "-1s"} {
+		err := Watch(newTestContext(t, flags, "--interval", interval))
+		if err == nil || !strings.Contains(err.Error(), "invalid interval") {
+			t.Errorf("Interval %s: expected an invalid interval error, got %v", interval, err)
+		}
+	}
+}
+
+func TestWatchFailsOutsideRepository(t *testing.T) {
+	flags := []cli.Flag{&cli.DurationFlag{Name: "interval"}}
+	t.Chdir(t.TempDir())
+
+	// A bad repository fails right away instead of being redrawn forever
+	done := make(chan error, 1)
+	go func() {
+		done <- Watch(newTestContext(t, flags, "--interval", "1s"))
+	}()
+	select {
+	case err := <-done:
+		if err == nil {
+			t.Error("Expected an error outside a git repository")
+		}
+	case <-time.After(5 * time.Second):
+		t.Fatal("Expected watch to fail right away outside a git repository")
+	}
+}
+
+func TestWatchSummaryWorkingTree(t *testing.T) {
+	dir := setupTestRepo(t)
+	if err := os.WriteFile(filepath.Join(dir, "notes.txt"), []byte("hello\n"), 0644); err != nil {
+		t.Fatalf("Failed to write file: %v", err)
+	}
+
+	summary, err := watchSummary(dir, "", true)
+	if err != nil {
+		t.Fatalf("Failed to summarize the review: %v", err)
+	}
+	if !strings.Contains(summary, "main working tree vs HEAD") || !strings.Contains(summary, "notes.txt") {
+		t.Errorf("Expected the summary to name the review and the changed file, got:\n%s", summary)
+	}
+}
@@ -4,8 +4,10 @@ import (
 	"encoding/json"
 	"fmt"
 	"os"
+	"strings"
 
 	"github.com/fatih/color"
+	"github.com/tuist/guck/internal/git"
 	"github.com/tuist/guck/internal/mcp"
 )
 
@@ -13,6 +15,7 @@ var (
 	successColor = color.New(color.FgGreen, color.Bold)
 	infoColor    = color.New(color.FgCyan)
 	warningColor = color.New(color.FgYellow)
+	errorColor   = color.New(color.FgRed)
 	urlColor     = color.New(color.FgBlue, color.Underline)
 )
 
@@ -66,6 +69,11 @@ func OutputToon(result interface{}) error {
 		}
 	}
 
+	// Check if it's a diff result with changed files
+	if files, ok := resultMap["files"].([]git.FileInfo); ok {
+		return OutputFilesAsToon(files)
+	}
+
 	// For simple results, just output as key-value pairs
 	for k, v := range resultMap {
 		fmt.Printf("%s\t%v\n", k, v)
@@ -139,6 +147,36 @@ func OutputHumanReadable(result interface{}) error {
 		return nil
 	}
 
+	// Check if it's a diff result with changed files
+	if files, ok := resultMap["files"].([]git.FileInfo); ok {
+		if resultMap["mode"] == "working" {
+			infoColor.Printf("Found %d changed file(s) in the working tree vs HEAD:\n\n", len(files))
+		} else {
+			infoColor.Printf("Found %d changed file(s) vs the base branch:\n\n", len(files))
+		}
+
+		for _, file := range files {
+			fmt.Printf("  %-9s ", file.Status)
+			urlColor.Print(file.Path)
+			successColor.Printf(" +%d", file.Additions)
+			errorColor.Printf(" -%d\n", file.Deletions)
+		}
+
+		if suppressed, ok := resultMap["suppressed"].([]git.SuppressedChange); ok {
+			infoColor.Printf("\nLeft out %d file(s) with changes already on the base branch:\n\n", len(suppressed))
+			for _, change := range suppressed {
+				fmt.Print("  ")
+				urlColor.Print(change.Path)
+				if change.WholeFile {
+					fmt.Printf(" (whole file, from %s)\n", shortCommits(change.Commits))
+				} else {
+					fmt.Printf(" (%d hunk(s), from %s)\n", change.Hunks, shortCommits(change.Commits))
+				}
+			}
+		}
+		return nil
+	}
+
 	// For simple success results
 	if success, ok := resultMap["success"].(bool); ok && success {
 		successColor.Println("✓ Operation completed successfully")
@@ -258,9 +296,32 @@ func outputNotesAsToon(notes []interface{}) error {
 	return nil
 }
 
+// OutputFilesAsToon outputs changed files in Toon format
+func OutputFilesAsToon(files []git.FileInfo) error {
+	if len(files) == 0 {
+		fmt.Println("# No changed files")
+		return nil
+	}
+
+	fmt.Println("path\tstatus\tadditions\tdeletions")
+	for _, file := range files {
+		fmt.Printf("%s\t%s\t%d\t%d\n", file.Path, file.Status, file.Additions, file.Deletions)
+	}
+	return nil
+}
+
 func truncate(s string, maxLen int) string {
 	if len(s) <= maxLen {
 		return s
 	}
 	return s[:maxLen-3] + "..."
 }
+
+// shortCommits lists commit hashes abbreviated for display
+func shortCommits(commits []string) string {
+	short := make([]string, len(commits))
+	for i, commit := range commits {
+		short[i] = git.Abbreviate(commit, 7)
+	}
+	return strings.Join(short, ", ")
+}
@@ -1,34 +1,343 @@
 package config
 
 import (
+	"errors"
 	"fmt"
 	"os"
+	"path"
 	"path/filepath"
+	"strings"
 
 	"github.com/BurntSushi/toml"
+	"github.com/tuist/guck/internal/git"
+	"github.com/tuist/guck/internal/logging"
+	"github.com/tuist/guck/internal/state"
+)
+
+// Bounds for CommitAbbrevLen
+const (
+	MinCommitAbbrevLen = 4
+	MaxCommitAbbrevLen = 40
 )
 
 type Config struct {
-	BaseBranch string `toml:"base_branch"`
+	BaseBranch        string   `toml:"base_branch"`
+	Offline           bool     `toml:"offline"`
+	CommitAbbrevLen   int      `toml:"commit_abbrev_len"`
+	ExcludeAuthors    []string `toml:"exclude_authors"`
+	BasePath          string   `toml:"base_path"`
+	MaxCommentLength  int      `toml:"max_comment_length"`
+	DiffAlgorithm     string   `toml:"diff_algorithm"`
+	DiffBackend       string   `toml:"diff_backend"`
+	Textconv          bool     `toml:"textconv"`
+	Notifications     bool     `toml:"notifications"`
+	RecurseSubmodules bool     `toml:"recurse_submodules"`
+	MaxFiles          int      `toml:"max_files"`
+	AutoViewPatterns  []string `toml:"auto_view_patterns"`
+	TLSCert           string   `toml:"tls_cert"`
+	TLSKey            string   `toml:"tls_key"`
+	StaleBaseCommits  int      `toml:"stale_base_commits"`
+	MaxStateSizeMB    int      `toml:"max_state_size_mb"`
+	// ArchiveResolvedDays moves comments resolved longer ago than this into the
+	// repository's resolved comment archive when a daemon starts; 0 disables it
+	ArchiveResolvedDays int `toml:"archive_resolved_days"`
+	// MaxPatchKB truncates larger patches in diff responses; 0 means no cap
+	MaxPatchKB int `toml:"max_patch_kb"`
+	// LogFormat is the daemon's log format, text (the default) or json
+	LogFormat string `toml:"log_format"`
+	// StateGranularity is what branch review state is kept per: commit (the
+	// default), branch or content, see state.Granularity
+	StateGranularity string `toml:"state_granularity"`
+	// AutoStartAllowlist makes the shell integration only start daemons in
+	// repositories enabled with "guck enable"
+	AutoStartAllowlist bool `toml:"auto_start_allowlist"`
+	// ReviewChecklist lists the items reviewers tick off for every review,
+	// such as "Tests added?"
+	ReviewChecklist []string `toml:"review_checklist"`
+	// PortRangeStart and PortRangeEnd bound, inclusively, the ports daemons
+	// are given when none is requested with --port
+	PortRangeStart int `toml:"port_range_start"`
+	PortRangeEnd   int `toml:"port_range_end"`
+	// CommentTemplates maps short keys to canned comment text, which may use
+	// the {file} and {line} placeholders
+	CommentTemplates map[string]string `toml:"comment_templates"`
+	// CarryForwardViewed keeps files viewed on a new commit of the branch,
+	// such as an amend or rebase, when their content didn't change
+	CarryForwardViewed bool `toml:"carry_forward_viewed"`
+}
+
+// ParseError is returned by Load when an existing config file is malformed
+type ParseError struct {
+	Path string
+	Err  error
+}
+
+func (e *ParseError) Error() string {
+	var tomlErr toml.ParseError
+	if errors.As(e.Err, &tomlErr) {
+		return fmt.Sprintf("failed to parse config file %s:\n%s", e.Path, tomlErr.ErrorWithPosition())
+	}
+	return fmt.Sprintf("failed to parse config file %s: %v", e.Path, e.Err)
+}
+
+func (e *ParseError) Unwrap() error {
+	return e.Err
+}
+
+// Default returns the configuration used when no config file exists
+func Default() *Config {
+	return &Config{
+		BaseBranch:         "main",
+		CommitAbbrevLen:    7,
+		MaxCommentLength:   10000,
+		StaleBaseCommits:   20,
+		MaxStateSizeMB:     10,
+		PortRangeStart:     3000,
+		PortRangeEnd:       4000,
+		CarryForwardViewed: true,
+	}
+}
+
+// Validate checks that configured values are within their allowed ranges
+func (c *Config) Validate() error {
+	if c.CommitAbbrevLen < MinCommitAbbrevLen || c.CommitAbbrevLen > MaxCommitAbbrevLen {
+		return fmt.Errorf("commit_abbrev_len must be between %d and %d, got %d", MinCommitAbbrevLen, MaxCommitAbbrevLen, c.CommitAbbrevLen)
+	}
+	if c.MaxCommentLength < 1 {
+		return fmt.Errorf("max_comment_length must be positive, got %d", c.MaxCommentLength)
+	}
+	if c.MaxFiles < 0 {
+		return fmt.Errorf("max_files must not be negative, got %d", c.MaxFiles)
+	}
+	if c.StaleBaseCommits < 0 {
+		return fmt.Errorf("stale_base_commits must not be negative, got %d", c.StaleBaseCommits)
+	}
+	if c.MaxStateSizeMB < 0 {
+		return fmt.Errorf("max_state_size_mb must not be negative, got %d", c.MaxStateSizeMB)
+	}
+	if c.ArchiveResolvedDays < 0 {
+		return fmt.Errorf("archive_resolved_days must not be negative, got %d", c.ArchiveResolvedDays)
+	}
+	if c.MaxPatchKB < 0 {
+		return fmt.Errorf("max_patch_kb must not be negative, got %d", c.MaxPatchKB)
+	}
+	if c.PortRangeStart < 1 || c.PortRangeStart > 65535 {
+		return fmt.Errorf("port_range_start must be between 1 and 65535, got %d", c.PortRangeStart)
+	}
+	if c.PortRangeEnd < 1 || c.PortRangeEnd > 65535 {
+		return fmt.Errorf("port_range_end must be between 1 and 65535, got %d", c.PortRangeEnd)
+	}
+	if c.PortRangeStart >= c.PortRangeEnd {
+		return fmt.Errorf("port_range_start (%d) must be below port_range_end (%d)", c.PortRangeStart, c.PortRangeEnd)
+	}
+	for _, pattern := range c.AutoViewPatterns {
+		if _, err := path.Match(pattern, ""); err != nil {
+			return fmt.Errorf("auto_view_patterns: invalid pattern %q: %w", pattern, err)
+		}
+	}
+	seen := make(map[string]bool, len(c.ReviewChecklist))
+	for _, item := range c.ReviewChecklist {
+		if strings.TrimSpace(item) == "" {
+			return errors.New("review_checklist: items must not be empty")
+		}
+		if seen[item] {
+			return fmt.Errorf("review_checklist: duplicate item %q", item)
+		}
+		seen[item] = true
+	}
+	if err := git.ValidateDiffAlgorithm(c.DiffAlgorithm); err != nil {
+		return fmt.Errorf("diff_algorithm: %w", err)
+	}
+	if err := git.ValidateDiffBackend(c.DiffBackend); err != nil {
+		return fmt.Errorf("diff_backend: %w", err)
+	}
+	if err := logging.ValidateFormat(c.LogFormat); err != nil {
+		return fmt.Errorf("log_format: %w", err)
+	}
+	if _, err := state.ParseGranularity(c.StateGranularity); err != nil {
+		return fmt.Errorf("state_granularity: %w", err)
+	}
+	if strings.ContainsAny(c.BasePath, "?#") {
+		return fmt.Errorf("base_path must be a plain URL path, got %q", c.BasePath)
+	}
+	return nil
+}
+
+// URLScheme returns "https" when TLS is configured and "http" otherwise
+func (c *Config) URLScheme() string {
+	if c.TLSCert != "" && c.TLSKey != "" {
+		return "https"
+	}
+	return "http"
+}
+
+// NormalizeBasePath turns a configured base path like "guck/" into "/guck".
+// The root path normalizes to an empty string.
+func NormalizeBasePath(basePath string) string {
+	trimmed := strings.Trim(strings.TrimSpace(basePath), "/")
+	if trimmed == "" {
+		return ""
+	}
+	return "/" + trimmed
+}
+
+// RepoConfigFile is the name of the optional config file committed at a
+// repository's root
+const RepoConfigFile = ".guck.toml"
+
+// DirConfig holds the settings a [dir."<path>"] section of RepoConfigFile
+// overrides for guck started within that directory of the repository
+type DirConfig struct {
+	BaseBranch       string   `toml:"base_branch"`
+	ExcludeAuthors   []string `toml:"exclude_authors"`
+	AutoViewPatterns []string `toml:"auto_view_patterns"`
+}
+
+type repoConfig struct {
+	Dir map[string]DirConfig `toml:"dir"`
+}
+
+// repoSettings lists the settings the top level of RepoConfigFile may set for
+// the whole repository. Ones tied to the machine, such as TLS, offline mode or
+// the state file, and textconv, which runs programs, stay in the user's config.
+var repoSettings = map[string]bool{
+	"base_branch":           true,
+	"exclude_authors":       true,
+	"auto_view_patterns":    true,
+	"commit_abbrev_len":     true,
+	"max_comment_length":    true,
+	"diff_algorithm":        true,
+	"diff_backend":          true,
+	"recurse_submodules":    true,
+	"max_files":             true,
+	"max_patch_kb":          true,
+	"stale_base_commits":    true,
+	"archive_resolved_days": true,
+	"review_checklist":      true,
+	"comment_templates":     true,
+	"port_range_start":      true,
+	"port_range_end":        true,
+	"carry_forward_viewed":  true,
 }
 
+// LoadForDir is Load with the overrides of the repository's RepoConfigFile
+// applied for dir. Its top-level settings apply to the whole repository, and
+// the most specific [dir."<path>"] section containing dir wins over them,
+// e.g. "packages/web" over "packages" for dir packages/web/src.
+func LoadForDir(repoRoot, dir string) (*Config, error) {
+	cfg, err := Load()
+	if err != nil {
+		return nil, err
+	}
+
+	configPath := filepath.Join(repoRoot, RepoConfigFile)
+	if _, err := os.Stat(configPath); err != nil {
+		return cfg, nil
+	}
+
+	var repoCfg repoConfig
+	meta, err := toml.DecodeFile(configPath, &repoCfg)
+	if err != nil {
+		return nil, &ParseError{Path: configPath, Err: err}
+	}
+	for _, key := range meta.Keys() {
+		if len(key) == 1 && key[0] != "dir" && !repoSettings[key[0]] {
+			return nil, &ParseError{Path: configPath, Err: fmt.Errorf("%s can't be set per repository; set it with guck config set instead", key[0])}
+		}
+	}
+	// Decoding onto the loaded config only replaces the settings the file sets
+	if _, err := toml.DecodeFile(configPath, cfg); err != nil {
+		return nil, &ParseError{Path: configPath, Err: err}
+	}
+
+	section, ok := dirSection(repoCfg.Dir, relativeDir(repoRoot, dir))
+	if !ok {
+		if err := cfg.Validate(); err != nil {
+			return nil, &ParseError{Path: configPath, Err: err}
+		}
+		return cfg, nil
+	}
+	if section.BaseBranch != "" {
+		cfg.BaseBranch = section.BaseBranch
+	}
+	if section.ExcludeAuthors != nil {
+		cfg.ExcludeAuthors = section.ExcludeAuthors
+	}
+	if section.AutoViewPatterns != nil {
+		cfg.AutoViewPatterns = section.AutoViewPatterns
+	}
+
+	if err := cfg.Validate(); err != nil {
+		return nil, &ParseError{Path: configPath, Err: err}
+	}
+	return cfg, nil
+}
+
+// LoadForWorkingDir is LoadForDir for the current working directory
+func LoadForWorkingDir(repoRoot string) (*Config, error) {
+	dir, err := os.Getwd()
+	if err != nil {
+		return nil, fmt.Errorf("failed to determine working directory: %w", err)
+	}
+	return LoadForDir(repoRoot, dir)
+}
+
+// relativeDir returns dir relative to the repository root with forward
+// slashes, or "" when dir is the root or outside the repository
+func relativeDir(repoRoot, dir string) string {
+	// Resolve symlinks such as macOS's /var -> /private/var on both sides
+	if resolved, err := filepath.EvalSymlinks(repoRoot); err == nil {
+		repoRoot = resolved
+	}
+	if resolved, err := filepath.EvalSymlinks(dir); err == nil {
+		dir = resolved
+	}
+
+	rel, err := filepath.Rel(repoRoot, dir)
+	if err != nil || !filepath.IsLocal(rel) {
+		return ""
+	}
+	return filepath.ToSlash(rel)
+}
+
+// dirSection picks the section with the longest path that is dir or one of
+// its parents
+func dirSection(sections map[string]DirConfig, dir string) (DirConfig, bool) {
+	var best DirConfig
+	bestLen := -1
+	for key, section := range sections {
+		prefix := path.Clean(strings.Trim(key, "/"))
+		if prefix == "." || (dir != prefix && !strings.HasPrefix(dir, prefix+"/")) {
+			continue
+		}
+		if len(prefix) > bestLen {
+			best, bestLen = section, len(prefix)
+		}
+	}
+	return best, bestLen >= 0
+}
+
+// Load reads the config file, returning defaults if it doesn't exist.
+// A malformed config file results in a *ParseError rather than silently
+// falling back to defaults.
 func Load() (*Config, error) {
 	configPath, err := getConfigPath()
 	if err != nil {
 		return nil, err
 	}
 
-	cfg := &Config{
-		BaseBranch: "main",
-	}
+	cfg := Default()
 
 	if _, err := os.Stat(configPath); err == nil {
 		if _, err := toml.DecodeFile(configPath, cfg); err != nil {
-			// If decode fails, use defaults
-			cfg.BaseBranch = "main"
+			return nil, &ParseError{Path: configPath, Err: err}
 		}
 	}
 
+	if err := cfg.Validate(); err != nil {
+		return nil, &ParseError{Path: configPath, Err: err}
+	}
+
 	return cfg, nil
 }
 
@@ -57,6 +366,11 @@ func (c *Config) Save() error {
 	return nil
 }
 
+// Path returns the location of the config file, whether or not it exists
+func Path() (string, error) {
+	return getConfigPath()
+}
+
 func getConfigPath() (string, error) {
 	configDir := os.Getenv("XDG_CONFIG_HOME")
 	if configDir == "" {
@@ -0,0 +1,253 @@
+package config
+
+import (
+	"errors"
+	"os"
+	"path/filepath"
+	"strings"
+	"testing"
+)
+
+func writeConfig(t *testing.T, contents string) string {
+	t.Helper()
+
+	configHome := t.TempDir()
+	t.Setenv("XDG_CONFIG_HOME", configHome)
+
+	configDir := filepath.Join(configHome, "guck")
+	if err := os.MkdirAll(configDir, 0755); err != nil {
+		t.Fatalf("Failed to create config dir: %v", err)
+	}
+
+	configPath := filepath.Join(configDir, "config.toml")
+	if err := os.WriteFile(configPath, []byte(contents), 0644); err != nil {
+		t.Fatalf("Failed to write config: %v", err)
+	}
+
+	return configPath
+}
+
+func TestLoadMissingFileUsesDefaults(t *testing.T) {
+	t.Setenv("XDG_CONFIG_HOME", t.TempDir())
+
+	cfg, err := Load()
+	if err != nil {
+		t.Fatalf("Expected no error for missing config, got %v", err)
+	}
+
+	if cfg.BaseBranch != "main" {
+		t.Errorf("Expected default base branch 'main', got '%s'", cfg.BaseBranch)
+	}
+}
+
+func TestLoadValidFile(t *testing.T) {
+	writeConfig(t, "base_branch = \"develop\"\n")
+
+	cfg, err := Load()
+	if err != nil {
+		t.Fatalf("Failed to load config: %v", err)
+	}
+
+	if cfg.BaseBranch != "develop" {
+		t.Errorf("Expected base branch 'develop', got '%s'", cfg.BaseBranch)
+	}
+}
+
+func TestLoadMalformedFileReturnsParseError(t *testing.T) {
+	configPath := writeConfig(t, "base_branch = \"develop\"\nthis is not toml\n")
+
+	cfg, err := Load()
+	if err == nil {
+		t.Fatalf("Expected parse error, got config %+v", cfg)
+	}
+
+	var parseErr *ParseError
+	if !errors.As(err, &parseErr) {
+		t.Fatalf("Expected *ParseError, got %T", err)
+	}
+
+	if parseErr.Path != configPath {
+		t.Errorf("Expected path %s, got %s", configPath, parseErr.Path)
+	}
+
+	if !strings.Contains(err.Error(), "line 2") {
+		t.Errorf("Expected error to mention line 2, got: %s", err.Error())
+	}
+}
+
+func TestLoadRejectsOutOfRangeCommitAbbrevLen(t *testing.T) {
+	writeConfig(t, "commit_abbrev_len = 2\n")
+
+	_, err := Load()
+	if err == nil {
+		t.Fatal("Expected an error for commit_abbrev_len below the minimum")
+	}
+
+	if !strings.Contains(err.Error(), "commit_abbrev_len") {
+		t.Errorf("Expected error to mention commit_abbrev_len, got: %v", err)
+	}
+}
+
+func TestLoadDefaultsCommitAbbrevLen(t *testing.T) {
+	writeConfig(t, "base_branch = \"develop\"\n")
+
+	cfg, err := Load()
+	if err != nil {
+		t.Fatalf("Failed to load config: %v", err)
+	}
+
+	if cfg.CommitAbbrevLen != 7 {
+		t.Errorf("Expected default commit_abbrev_len 7, got %d", cfg.CommitAbbrevLen)
+	}
+}
+
+func TestLoadRejectsNonPositiveMaxCommentLength(t *testing.T) {
+	writeConfig(t, "max_comment_length = 0\n")
+
+	_, err := Load()
+	if err == nil || !strings.Contains(err.Error(), "max_comment_length") {
+		t.Errorf("Expected error to mention max_comment_length, got: %v", err)
+	}
+}
+
+func TestNormalizeBasePath(t *testing.T) {
+	tests := map[string]string{
+		"":        "",
+		"/":       "",
+		"guck":    "/guck",
+		"/guck/":  "/guck",
+		" /a/b/ ": "/a/b",
+	}
+
+	for input, expected := range tests {
+		if got := NormalizeBasePath(input); got != expected {
+			t.Errorf("NormalizeBasePath(%q) = %q, expected %q", input, got, expected)
+		}
+	}
+}
+
+func TestLoadRejectsInvalidAutoViewPattern(t *testing.T) {
+	writeConfig(t, "auto_view_patterns = [\"[\"]\n")
+
+	_, err := Load()
+	if err == nil || !strings.Contains(err.Error(), "auto_view_patterns") {
+		t.Errorf("Expected error to mention auto_view_patterns, got: %v", err)
+	}
+}
+
+func TestLoadRejectsInvalidReviewChecklist(t *testing.T) {
+	for _, checklist := range []string{`["Tests added?", "Tests added?"]`, `["Tests added?", " "]`} {
+		writeConfig(t, "review_checklist = "+checklist+"\n")
+
+		_, err := Load()
+		if err == nil || !strings.Contains(err.Error(), "review_checklist") {
+			t.Errorf("Expected error to mention review_checklist for %s, got: %v", checklist, err)
+		}
+	}
+}
+
+func TestLoadRejectsInvalidPortRange(t *testing.T) {
+	for _, ports := range []string{"port_range_start = 8000\nport_range_end = 8000\n", "port_range_end = 70000\n", "port_range_start = 0\n"} {
+		writeConfig(t, ports)
+
+		_, err := Load()
+		if err == nil || !strings.Contains(err.Error(), "port_range") {
+			t.Errorf("Expected error to mention the port range for %q, got: %v", ports, err)
+		}
+	}
+}
+
+func TestLoadForDir(t *testing.T) {
+	writeConfig(t, "base_branch = \"develop\"\nauto_view_patterns = [\"*.lock\"]\n")
+
+	repoRoot := t.TempDir()
+	repoConfig := `
+[dir."packages"]
+base_branch = "packages-main"
+
+[dir."packages/web/"]
+base_branch = "web-main"
+auto_view_patterns = ["dist/**"]
+`
+	if err := os.WriteFile(filepath.Join(repoRoot, RepoConfigFile), []byte(repoConfig), 0644); err != nil {
+		t.Fatalf("Failed to write repo config: %v", err)
+	}
+
+	tests := []struct {
+		dir      string
+		expected string
+	}{
+		{dir: "", expected: "develop"},
+		{dir: "docs", expected: "develop"},
+		{dir: "packages/api", expected: "packages-main"},
+		{dir: "packages/web/src", expected: "web-main"},
+		{dir: "packages/website", expected: "packages-main"},
+	}
+	for _, tt := range tests {
+		cfg, err := LoadForDir(repoRoot, filepath.Join(repoRoot, tt.dir))
+		if err != nil {
+			t.Fatalf("%s: failed to load config: %v", tt.dir, err)
+		}
+		if cfg.BaseBranch != tt.expected {
+			t.Errorf("%s: expected base branch '%s', got '%s'", tt.dir, tt.expected, cfg.BaseBranch)
+		}
+	}
+
+	cfg, err := LoadForDir(repoRoot, filepath.Join(repoRoot, "packages", "web"))
+	if err != nil {
+		t.Fatalf("Failed to load config: %v", err)
+	}
+	if strings.Join(cfg.AutoViewPatterns, ",") != "dist/**" {
+		t.Errorf("Expected the section's auto-view patterns, got %v", cfg.AutoViewPatterns)
+	}
+	cfg, _ = LoadForDir(repoRoot, filepath.Join(repoRoot, "packages", "api"))
+	if strings.Join(cfg.AutoViewPatterns, ",") != "*.lock" {
+		t.Errorf("Expected settings a section doesn't set to be kept, got %v", cfg.AutoViewPatterns)
+	}
+}
+
+func TestLoadForDirRepoWideSettings(t *testing.T) {
+	writeConfig(t, "base_branch = \"develop\"\nmax_files = 500\n")
+
+	repoRoot := t.TempDir()
+	repoConfig := `
+base_branch = "trunk"
+port_range_start = 8000
+port_range_end = 8100
+
+[dir."packages/web"]
+base_branch = "web-main"
+`
+	if err := os.WriteFile(filepath.Join(repoRoot, RepoConfigFile), []byte(repoConfig), 0644); err != nil {
+		t.Fatalf("Failed to write repo config: %v", err)
+	}
+
+	cfg, err := LoadForDir(repoRoot, repoRoot)
+	if err != nil {
+		t.Fatalf("Failed to load config: %v", err)
+	}
+	if cfg.BaseBranch != "trunk" || cfg.PortRangeStart != 8000 || cfg.PortRangeEnd != 8100 {
+		t.Errorf("Expected the repository's settings to win, got %+v", cfg)
+	}
+	if cfg.MaxFiles != 500 {
+		t.Errorf("Expected settings the repository doesn't set to be kept, got max_files %d", cfg.MaxFiles)
+	}
+
+	cfg, err = LoadForDir(repoRoot, filepath.Join(repoRoot, "packages", "web"))
+	if err != nil {
+		t.Fatalf("Failed to load config: %v", err)
+	}
+	if cfg.BaseBranch != "web-main" {
+		t.Errorf("Expected a directory section to win over the repository's settings, got '%s'", cfg.BaseBranch)
+	}
+
+	for _, repoConfig := range []string{"tls_cert = \"/tmp/cert.pem\"\n", "port_range_start = 9500\n"} {
+		if err := os.WriteFile(filepath.Join(repoRoot, RepoConfigFile), []byte(repoConfig), 0644); err != nil {
+			t.Fatalf("Failed to write repo config: %v", err)
+		}
+		var parseErr *ParseError
+		if _, err := LoadForDir(repoRoot, repoRoot); !errors.As(err, &parseErr) {
+			t.Errorf("Expected a ParseError for %q, got %v", repoConfig, err)
+		}
+	}
+}
@@ -1,34 +1,81 @@
 package daemon
 
 import (
+	"context"
+	"crypto/sha256"
+	"encoding/hex"
 	"encoding/json"
+	"errors"
 	"fmt"
+	"io"
 	"math/rand"
 	"net"
 	"os"
 	"path/filepath"
+	"slices"
 	"strings"
 	"syscall"
+	"time"
+
+	"github.com/tuist/guck/internal/atomicfile"
+	"github.com/tuist/guck/internal/state"
 )
 
 type Info struct {
-	PID        int    `json:"pid"`
-	Port       int    `json:"port"`
+	PID  int `json:"pid"`
+	Port int `json:"port"`
+	// Socket is the Unix domain socket the daemon serves on instead of a TCP
+	// port, in which case Port is 0
+	Socket     string `json:"socket,omitempty"`
 	RepoPath   string `json:"repo_path"`
 	BaseBranch string `json:"base_branch"`
+	StartedAt  int64  `json:"started_at,omitempty"`
+}
+
+// Endpoint describes where the daemon listens, e.g. "port 4000" or
+// "socket /path/to.sock"
+func (i *Info) Endpoint() string {
+	if i.Socket != "" {
+		return "socket " + i.Socket
+	}
+	return fmt.Sprintf("port %d", i.Port)
+}
+
+// IsListening reports whether the daemon accepts connections on its port or socket
+func (i *Info) IsListening() bool {
+	if i.Socket != "" {
+		return IsSocketListening(i.Socket)
+	}
+	return IsPortListening(i.Port)
 }
 
 type Registry struct {
 	Daemons map[string]*Info `json:"daemons"`
 }
 
+// AutoStart lists the repositories opted out of, or in allowlist mode into,
+// having the shell integration start a daemon when you cd into them
+type AutoStart struct {
+	Ignored []string `json:"ignored,omitempty"`
+	Enabled []string `json:"enabled,omitempty"`
+}
+
+// Allows reports whether the shell integration may start a daemon for
+// repoPath. In allowlist mode only enabled repositories are started.
+func (a *AutoStart) Allows(repoPath string, allowlist bool) bool {
+	if slices.Contains(a.Ignored, repoPath) {
+		return false
+	}
+	return !allowlist || slices.Contains(a.Enabled, repoPath)
+}
+
 type Manager struct {
 	registryPath string
 	stateDir     string
 }
 
 func NewManager() (*Manager, error) {
-	stateDir, err := getStateDir()
+	stateDir, err := state.Dir()
 	if err != nil {
 		return nil, err
 	}
@@ -45,6 +92,11 @@ func NewManager() (*Manager, error) {
 	}, nil
 }
 
+// RegistryPath returns the path of the daemon registry file
+func (m *Manager) RegistryPath() string {
+	return m.registryPath
+}
+
 func (m *Manager) loadRegistry() (*Registry, error) {
 	registry := &Registry{
 		Daemons: make(map[string]*Info),
@@ -57,28 +109,121 @@ func (m *Manager) loadRegistry() (*Registry, error) {
 		}
 
 		if err := json.Unmarshal(data, registry); err != nil {
-			// If unmarshal fails, return empty registry
-			return &Registry{Daemons: make(map[string]*Info)}, nil
+			return m.recoverRegistry(err), nil
 		}
 	}
 
 	return registry, nil
 }
 
+// recoverRegistry loads the backup of a registry that failed to parse with
+// parseErr, or returns an empty registry if there's no usable backup. Either
+// way it warns, since daemons missing from the registry are left running.
+func (m *Manager) recoverRegistry(parseErr error) *Registry {
+	backup := m.registryPath + atomicfile.BackupSuffix
+	if data, err := os.ReadFile(backup); err == nil {
+		registry := &Registry{}
+		if err := json.Unmarshal(data, registry); err == nil && registry.Daemons != nil {
+			fmt.Fprintf(os.Stderr, "Warning: daemon registry %s is corrupt (%v); recovered the previous registry from %s.\n", m.registryPath, parseErr, backup)
+			return registry
+		}
+	}
+
+	fmt.Fprintf(os.Stderr, "Warning: daemon registry %s is corrupt (%v) and has no usable backup; starting with an empty registry.\n", m.registryPath, parseErr)
+	return &Registry{Daemons: make(map[string]*Info)}
+}
+
 func (m *Manager) saveRegistry(registry *Registry) error {
 	data, err := json.MarshalIndent(registry, "", "  ")
 	if err != nil {
 		return fmt.Errorf("failed to serialize registry: %w", err)
 	}
 
-	if err := os.WriteFile(m.registryPath, data, 0644); err != nil {
+	// loadRegistry falls back to the backup when the registry can't be parsed
+	if err := atomicfile.Backup(m.registryPath); err != nil {
+		return fmt.Errorf("failed to back up registry: %w", err)
+	}
+	if err := atomicfile.Write(m.registryPath, data); err != nil {
 		return fmt.Errorf("failed to write registry: %w", err)
 	}
 
 	return nil
 }
 
-func (m *Manager) FindAvailablePort() (int, error) {
+func (m *Manager) autoStartPath() string {
+	return filepath.Join(m.stateDir, "autostart.json")
+}
+
+// LoadAutoStart reads the auto-start lists, which are empty until a
+// repository is ignored or enabled
+func (m *Manager) LoadAutoStart() (*AutoStart, error) {
+	autoStart := &AutoStart{}
+
+	data, err := os.ReadFile(m.autoStartPath())
+	if errors.Is(err, os.ErrNotExist) {
+		return autoStart, nil
+	}
+	if err != nil {
+		return nil, fmt.Errorf("failed to read auto-start list: %w", err)
+	}
+	if err := json.Unmarshal(data, autoStart); err != nil {
+		return nil, fmt.Errorf("failed to parse auto-start list %s: %w", m.autoStartPath(), err)
+	}
+	return autoStart, nil
+}
+
+// SetIgnored adds repoPath to, or removes it from, the repositories the shell
+// integration never starts a daemon for
+func (m *Manager) SetIgnored(repoPath string, ignored bool) error {
+	return m.updateAutoStart(func(a *AutoStart) {
+		a.Ignored = setMember(a.Ignored, repoPath, ignored)
+	})
+}
+
+// SetEnabled adds repoPath to, or removes it from, the repositories the shell
+// integration starts a daemon for in allowlist mode
+func (m *Manager) SetEnabled(repoPath string, enabled bool) error {
+	return m.updateAutoStart(func(a *AutoStart) {
+		a.Enabled = setMember(a.Enabled, repoPath, enabled)
+	})
+}
+
+func (m *Manager) updateAutoStart(update func(*AutoStart)) error {
+	autoStart, err := m.LoadAutoStart()
+	if err != nil {
+		return err
+	}
+	update(autoStart)
+
+	data, err := json.MarshalIndent(autoStart, "", "  ")
+	if err != nil {
+		return fmt.Errorf("failed to serialize auto-start list: %w", err)
+	}
+	if err := atomicfile.Write(m.autoStartPath(), data); err != nil {
+		return fmt.Errorf("failed to write auto-start list: %w", err)
+	}
+	return nil
+}
+
+// setMember adds value to or removes it from a sorted list without duplicates
+func setMember(list []string, value string, member bool) []string {
+	list = slices.DeleteFunc(list, func(v string) bool { return v == value })
+	if member {
+		list = append(list, value)
+		slices.Sort(list)
+	}
+	return list
+}
+
+// FindAvailablePort picks a port between start and end, inclusive, that no
+// registered daemon uses and nothing is bound to. It tries ports in order
+// from a random one, so daemons spread over the range but every port is
+// tried before giving up.
+func (m *Manager) FindAvailablePort(start, end int) (int, error) {
+	if start < 1 || end > 65535 || start > end {
+		return 0, fmt.Errorf("invalid port range %d-%d", start, end)
+	}
+
 	registry, err := m.loadRegistry()
 	if err != nil {
 		return 0, err
@@ -89,16 +234,42 @@ func (m *Manager) FindAvailablePort() (int, error) {
 		usedPorts[info.Port] = true
 	}
 
-	maxAttempts := 100
-	for i := 0; i < maxAttempts; i++ {
-		port := rand.Intn(6000) + 3000 // Range: 3000-9000
+	size := end - start + 1
+	offset := rand.Intn(size)
+	for i := 0; i < size; i++ {
+		port := start + (offset+i)%size
 
 		if !usedPorts[port] && isPortAvailable(port) {
 			return port, nil
 		}
 	}
 
-	return 0, fmt.Errorf("could not find an available port after %d attempts", maxAttempts)
+	return 0, fmt.Errorf("no available port between %d and %d (set port-range-start and port-range-end to widen the range)", start, end)
+}
+
+// CheckPort verifies that a specific port can be used for a new daemon: it
+// must not be recorded for another live daemon and nothing may be bound to it
+func (m *Manager) CheckPort(port int) error {
+	if port < 1 || port > 65535 {
+		return fmt.Errorf("invalid port %d: must be between 1 and 65535", port)
+	}
+
+	registry, err := m.loadRegistry()
+	if err != nil {
+		return err
+	}
+
+	for _, info := range registry.Daemons {
+		if info.Port == port && m.IsDaemonRunning(info.PID) {
+			return fmt.Errorf("port %d is already used by the daemon for %s (PID: %d)", port, info.RepoPath, info.PID)
+		}
+	}
+
+	if !isPortAvailable(port) {
+		return fmt.Errorf("port %d is already in use by another process", port)
+	}
+
+	return nil
 }
 
 func isPortAvailable(port int) bool {
@@ -144,6 +315,22 @@ func (m *Manager) UnregisterDaemon(repoPath string) error {
 	return m.saveRegistry(registry)
 }
 
+// UnregisterDaemonPID removes the registry entry for repoPath if it still
+// belongs to the daemon with pid, so a daemon exiting late doesn't unregister
+// one started after it
+func (m *Manager) UnregisterDaemonPID(repoPath string, pid int) error {
+	registry, err := m.loadRegistry()
+	if err != nil {
+		return err
+	}
+
+	if info, ok := registry.Daemons[repoPath]; !ok || info.PID != pid {
+		return nil
+	}
+	delete(registry.Daemons, repoPath)
+	return m.saveRegistry(registry)
+}
+
 func (m *Manager) ListDaemons() ([]*Info, error) {
 	registry, err := m.loadRegistry()
 	if err != nil {
@@ -164,11 +351,15 @@ func (m *Manager) IsDaemonRunning(pid int) bool {
 		return false
 	}
 
-	// Send signal 0 to check if process exists
+	// Send signal 0 to check if process exists. EPERM means it exists but
+	// belongs to another user, which must not be mistaken for a dead daemon.
 	err = process.Signal(syscall.Signal(0))
-	return err == nil
+	return err == nil || errors.Is(err, syscall.EPERM)
 }
 
+// StopDaemon sends the daemon SIGTERM and waits up to stopTimeout for it to
+// exit. The daemon stops accepting connections and drains in-flight requests
+// first, so a review write coinciding with the stop isn't lost.
 func (m *Manager) StopDaemon(pid int) error {
 	process, err := os.FindProcess(pid)
 	if err != nil {
@@ -179,9 +370,20 @@ func (m *Manager) StopDaemon(pid int) error {
 		return fmt.Errorf("failed to send SIGTERM: %w", err)
 	}
 
+	deadline := time.Now().Add(stopTimeout)
+	for m.IsDaemonRunning(pid) {
+		if time.Now().After(deadline) {
+			return fmt.Errorf("daemon (PID %d) did not exit within %s", pid, stopTimeout)
+		}
+		time.Sleep(portPollInterval)
+	}
 	return nil
 }
 
+// stopTimeout bounds how long StopDaemon waits for a daemon to drain and
+// exit. It leaves room for the server's own drain timeout.
+const stopTimeout = 15 * time.Second
+
 func (m *Manager) CleanupStaleDaemons() error {
 	registry, err := m.loadRegistry()
 	if err != nil {
@@ -197,30 +399,233 @@ func (m *Manager) CleanupStaleDaemons() error {
 	return m.saveRegistry(registry)
 }
 
+// ReconcileResult describes what a registry reconciliation pass found
+type ReconcileResult struct {
+	Removed     []*Info // Entries whose process no longer exists
+	Unreachable []*Info // Live processes that aren't accepting connections on their port or socket
+}
+
+// Reconcile removes registry entries for dead daemons and reports live ones
+// whose port isn't reachable. It never signals or stops live processes.
+func (m *Manager) Reconcile() (*ReconcileResult, error) {
+	registry, err := m.loadRegistry()
+	if err != nil {
+		return nil, err
+	}
+
+	result := &ReconcileResult{}
+	for repoPath, info := range registry.Daemons {
+		if !m.IsDaemonRunning(info.PID) {
+			delete(registry.Daemons, repoPath)
+			result.Removed = append(result.Removed, info)
+			continue
+		}
+
+		if !info.IsListening() {
+			result.Unreachable = append(result.Unreachable, info)
+		}
+	}
+
+	if len(result.Removed) > 0 {
+		if err := m.saveRegistry(registry); err != nil {
+			return nil, err
+		}
+	}
+
+	return result, nil
+}
+
+// IsPortListening reports whether something accepts connections on the local port
+func IsPortListening(port int) bool {
+	conn, err := net.DialTimeout("tcp", fmt.Sprintf("127.0.0.1:%d", port), 500*time.Millisecond)
+	if err != nil {
+		return false
+	}
+	conn.Close()
+	return true
+}
+
+// IsSocketListening reports whether something accepts connections on the Unix domain socket
+func IsSocketListening(path string) bool {
+	conn, err := net.DialTimeout("unix", path, 500*time.Millisecond)
+	if err != nil {
+		return false
+	}
+	conn.Close()
+	return true
+}
+
+// WaitForPort polls the local port until it accepts connections, giving up
+// after timeout. A freshly started daemon needs a moment before it binds.
+func WaitForPort(port int, timeout time.Duration) bool {
+	deadline := time.Now().Add(timeout)
+	for {
+		if IsPortListening(port) {
+			return true
+		}
+		if time.Now().After(deadline) {
+			return false
+		}
+		time.Sleep(portPollInterval)
+	}
+}
+
+// WaitForStartup waits until a freshly spawned daemon accepts connections on
+// its port or socket. It fails early if the process exits first, reporting
+// the error received on exited, and gives up after timeout.
+func (i *Info) WaitForStartup(exited <-chan error, timeout time.Duration) error {
+	deadline := time.After(timeout)
+	ticker := time.NewTicker(portPollInterval)
+	defer ticker.Stop()
+
+	for {
+		if i.IsListening() {
+			return nil
+		}
+		select {
+		case err := <-exited:
+			if err == nil {
+				return errors.New("daemon exited during startup")
+			}
+			return fmt.Errorf("daemon exited during startup: %w", err)
+		case <-deadline:
+			return fmt.Errorf("daemon is not accepting connections on %s after %s", i.Endpoint(), timeout)
+		case <-ticker.C:
+		}
+	}
+}
+
+// portPollInterval is how often WaitForPort and WaitForStartup retry
+const portPollInterval = 100 * time.Millisecond
+
 func (m *Manager) GetLogPath(repoPath string) string {
-	// Create a safe filename from repo path
+	return filepath.Join(m.stateDir, fmt.Sprintf("%s.log", safeFileName(repoPath)))
+}
+
+// SocketPath returns the Unix domain socket the daemon for repoPath serves on
+// when it doesn't use a TCP port. The name is a hash of the path, since socket
+// paths are limited to around a hundred bytes.
+func (m *Manager) SocketPath(repoPath string) string {
+	sum := sha256.Sum256([]byte(repoPath))
+	return filepath.Join(m.stateDir, "sockets", hex.EncodeToString(sum[:8])+".sock")
+}
+
+// GetActivityPath returns the file whose modification time records when the
+// daemon for repoPath last served a request
+func (m *Manager) GetActivityPath(repoPath string) string {
+	return filepath.Join(m.stateDir, fmt.Sprintf("%s.activity", safeFileName(repoPath)))
+}
+
+// safeFileName creates a safe filename from a repo path
+func safeFileName(repoPath string) string {
 	safeName := strings.ReplaceAll(repoPath, "/", "_")
 	safeName = strings.ReplaceAll(safeName, "\\", "_")
 	safeName = strings.ReplaceAll(safeName, ":", "_")
+	return safeName
+}
+
+// TouchActivity records that the daemon for repoPath just served a request
+func (m *Manager) TouchActivity(repoPath string) error {
+	activityPath := m.GetActivityPath(repoPath)
+	now := time.Now()
+	if err := os.Chtimes(activityPath, now, now); err == nil {
+		return nil
+	}
 
-	return filepath.Join(m.stateDir, fmt.Sprintf("%s.log", safeName))
+	file, err := os.Create(activityPath)
+	if err != nil {
+		return fmt.Errorf("failed to record daemon activity: %w", err)
+	}
+	return file.Close()
+}
+
+// LastActivity returns when the daemon for repoPath last served a request.
+// Daemons that never recorded activity fall back to their log file's
+// modification time; ok is false when neither file exists.
+func (m *Manager) LastActivity(repoPath string) (time.Time, bool) {
+	for _, path := range []string{m.GetActivityPath(repoPath), m.GetLogPath(repoPath)} {
+		if info, err := os.Stat(path); err == nil {
+			return info.ModTime(), true
+		}
+	}
+	return time.Time{}, false
 }
 
-func getStateDir() (string, error) {
-	// Use XDG_STATE_HOME on Unix, or fallback to XDG_DATA_HOME/LocalAppData
-	if stateHome := os.Getenv("XDG_STATE_HOME"); stateHome != "" {
-		return filepath.Join(stateHome, "guck"), nil
+// PruneOrphanedFiles removes log and activity files that don't belong to a
+// registered daemon, returning the removed paths
+func (m *Manager) PruneOrphanedFiles() ([]string, error) {
+	registry, err := m.loadRegistry()
+	if err != nil {
+		return nil, err
 	}
 
-	if dataHome := os.Getenv("XDG_DATA_HOME"); dataHome != "" {
-		return filepath.Join(dataHome, "guck"), nil
+	keep := make(map[string]bool)
+	for repoPath := range registry.Daemons {
+		keep[m.GetLogPath(repoPath)] = true
+		keep[m.GetActivityPath(repoPath)] = true
 	}
 
-	home, err := os.UserHomeDir()
+	entries, err := os.ReadDir(m.stateDir)
 	if err != nil {
-		return "", fmt.Errorf("failed to determine home directory: %w", err)
+		return nil, fmt.Errorf("failed to read state directory: %w", err)
 	}
 
-	// Platform-specific defaults
-	return filepath.Join(home, ".local", "state", "guck"), nil
+	var removed []string
+	for _, entry := range entries {
+		name := entry.Name()
+		if entry.IsDir() || (!strings.HasSuffix(name, ".log") && !strings.HasSuffix(name, ".activity")) {
+			continue
+		}
+
+		path := filepath.Join(m.stateDir, name)
+		if keep[path] {
+			continue
+		}
+
+		if err := os.Remove(path); err != nil {
+			return removed, fmt.Errorf("failed to remove %s: %w", path, err)
+		}
+		removed = append(removed, path)
+	}
+
+	return removed, nil
+}
+
+// followPollInterval is how often FollowFile checks for new output
+const followPollInterval = 250 * time.Millisecond
+
+// FollowFile copies path to w and then keeps copying appended data, like
+// tail -f, until ctx is done. A truncated file is followed from its start.
+func FollowFile(ctx context.Context, path string, w io.Writer) error {
+	file, err := os.Open(path)
+	if err != nil {
+		return fmt.Errorf("failed to open log file: %w", err)
+	}
+	defer file.Close()
+
+	var offset int64
+	ticker := time.NewTicker(followPollInterval)
+	defer ticker.Stop()
+
+	for {
+		n, err := io.Copy(w, file)
+		if err != nil {
+			return fmt.Errorf("failed to read log file: %w", err)
+		}
+		offset += n
+
+		// The daemon recreates its log on restart
+		if info, err := os.Stat(path); err == nil && info.Size() < offset {
+			if _, err := file.Seek(0, io.SeekStart); err != nil {
+				return fmt.Errorf("failed to rewind log file: %w", err)
+			}
+			offset = 0
+		}
+
+		select {
+		case <-ctx.Done():
+			return nil
+		case <-ticker.C:
+		}
+	}
 }
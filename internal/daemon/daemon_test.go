@@ -0,0 +1,461 @@
+package daemon
+
+import (
+	"bytes"
+	"context"
+	"errors"
+	"fmt"
+	"net"
+	"os"
+	"os/exec"
+	"path/filepath"
+	"strings"
+	"sync"
+	"testing"
+	"time"
+)
+
+func setupTestManager(t *testing.T) *Manager {
+	t.Helper()
+
+	t.Setenv("XDG_STATE_HOME", t.TempDir())
+
+	mgr, err := NewManager()
+	if err != nil {
+		t.Fatalf("Failed to create manager: %v", err)
+	}
+
+	return mgr
+}
+
+// exitedPID returns the PID of a process that has already exited
+func exitedPID(t *testing.T) int {
+	t.Helper()
+
+	cmd := exec.Command("true")
+	if err := cmd.Run(); err != nil {
+		t.Fatalf("Failed to run process: %v", err)
+	}
+	return cmd.Process.Pid
+}
+
+// closedPort returns a port that nothing is listening on
+func closedPort(t *testing.T) int {
+	t.Helper()
+
+	listener, err := net.Listen("tcp", "127.0.0.1:0")
+	if err != nil {
+		t.Fatalf("Failed to listen: %v", err)
+	}
+	port := listener.Addr().(*net.TCPAddr).Port
+	listener.Close()
+	return port
+}
+
+func TestReconcileRemovesDeadDaemons(t *testing.T) {
+	mgr := setupTestManager(t)
+
+	dead := &Info{PID: exitedPID(t), Port: closedPort(t), RepoPath: "/test/dead"}
+	if err := mgr.RegisterDaemon(dead); err != nil {
+		t.Fatalf("Failed to register daemon: %v", err)
+	}
+
+	result, err := mgr.Reconcile()
+	if err != nil {
+		t.Fatalf("Failed to reconcile: %v", err)
+	}
+
+	if len(result.Removed) != 1 || result.Removed[0].RepoPath != dead.RepoPath {
+		t.Errorf("Expected dead daemon to be removed, got %+v", result.Removed)
+	}
+
+	info, err := mgr.GetDaemonForRepo(dead.RepoPath)
+	if err != nil {
+		t.Fatalf("Failed to get daemon: %v", err)
+	}
+	if info != nil {
+		t.Error("Expected dead daemon to be gone from the registry")
+	}
+}
+
+func TestReconcileKeepsLiveDaemons(t *testing.T) {
+	mgr := setupTestManager(t)
+
+	// The test process itself is alive but isn't serving on the port
+	live := &Info{PID: os.Getpid(), Port: closedPort(t), RepoPath: "/test/live"}
+	if err := mgr.RegisterDaemon(live); err != nil {
+		t.Fatalf("Failed to register daemon: %v", err)
+	}
+
+	result, err := mgr.Reconcile()
+	if err != nil {
+		t.Fatalf("Failed to reconcile: %v", err)
+	}
+
+	if len(result.Removed) != 0 {
+		t.Errorf("Expected no removals, got %+v", result.Removed)
+	}
+	if len(result.Unreachable) != 1 {
+		t.Errorf("Expected live daemon to be reported unreachable, got %+v", result.Unreachable)
+	}
+
+	info, err := mgr.GetDaemonForRepo(live.RepoPath)
+	if err != nil {
+		t.Fatalf("Failed to get daemon: %v", err)
+	}
+	if info == nil {
+		t.Error("Expected live daemon to stay registered")
+	}
+}
+
+func TestUnregisterDaemonPID(t *testing.T) {
+	mgr := setupTestManager(t)
+
+	current := &Info{PID: os.Getpid(), Port: closedPort(t), RepoPath: "/test/repo"}
+	if err := mgr.RegisterDaemon(current); err != nil {
+		t.Fatalf("Failed to register daemon: %v", err)
+	}
+
+	// A previous daemon exiting late leaves the current one registered
+	if err := mgr.UnregisterDaemonPID(current.RepoPath, exitedPID(t)); err != nil {
+		t.Fatalf("Failed to unregister daemon: %v", err)
+	}
+	if info, _ := mgr.GetDaemonForRepo(current.RepoPath); info == nil {
+		t.Fatal("Expected the current daemon to stay registered")
+	}
+
+	if err := mgr.UnregisterDaemonPID(current.RepoPath, current.PID); err != nil {
+		t.Fatalf("Failed to unregister daemon: %v", err)
+	}
+	if info, _ := mgr.GetDaemonForRepo(current.RepoPath); info != nil {
+		t.Error("Expected the daemon to be unregistered")
+	}
+}
+
+func TestCheckPort(t *testing.T) {
+	mgr := setupTestManager(t)
+
+	free := closedPort(t)
+	if err := mgr.CheckPort(free); err != nil {
+		t.Errorf("Expected free port %d to be usable, got %v", free, err)
+	}
+
+	listener, err := net.Listen("tcp", "127.0.0.1:0")
+	if err != nil {
+		t.Fatalf("Failed to listen: %v", err)
+	}
+	defer listener.Close()
+	if err := mgr.CheckPort(listener.Addr().(*net.TCPAddr).Port); err == nil {
+		t.Error("Expected an error for a port that's bound")
+	}
+
+	claimed := closedPort(t)
+	if err := mgr.RegisterDaemon(&Info{PID: os.Getpid(), Port: claimed, RepoPath: "/test/live"}); err != nil {
+		t.Fatalf("Failed to register daemon: %v", err)
+	}
+	if err := mgr.CheckPort(claimed); err == nil || !strings.Contains(err.Error(), "/test/live") {
+		t.Errorf("Expected an error naming the daemon that claimed the port, got %v", err)
+	}
+
+	stale := closedPort(t)
+	if err := mgr.RegisterDaemon(&Info{PID: exitedPID(t), Port: stale, RepoPath: "/test/dead"}); err != nil {
+		t.Fatalf("Failed to register daemon: %v", err)
+	}
+	if err := mgr.CheckPort(stale); err != nil {
+		t.Errorf("Expected a port claimed by a dead daemon to be usable, got %v", err)
+	}
+
+	if err := mgr.CheckPort(70000); err == nil {
+		t.Error("Expected an error for an out-of-range port")
+	}
+}
+
+func TestFindAvailablePort(t *testing.T) {
+	mgr := setupTestManager(t)
+
+	free := closedPort(t)
+	if port, err := mgr.FindAvailablePort(free, free); err != nil || port != free {
+		t.Errorf("Expected the only port in the range, %d, got %d (%v)", free, port, err)
+	}
+
+	// A port claimed by a registered daemon isn't handed out again
+	if err := mgr.RegisterDaemon(&Info{PID: os.Getpid(), Port: free, RepoPath: "/test/live"}); err != nil {
+		t.Fatalf("Failed to register daemon: %v", err)
+	}
+	_, err := mgr.FindAvailablePort(free, free)
+	if err == nil || !strings.Contains(err.Error(), fmt.Sprintf("between %d and %d", free, free)) {
+		t.Errorf("Expected an error naming the exhausted range, got %v", err)
+	}
+
+	if _, err := mgr.FindAvailablePort(9000, 3000); err == nil {
+		t.Error("Expected an error for an inverted range")
+	}
+}
+
+func TestAutoStart(t *testing.T) {
+	mgr := setupTestManager(t)
+
+	autoStart, err := mgr.LoadAutoStart()
+	if err != nil {
+		t.Fatalf("Failed to load auto-start list: %v", err)
+	}
+	if !autoStart.Allows("/repo", false) || autoStart.Allows("/repo", true) {
+		t.Error("Expected repositories to auto-start unless in allowlist mode")
+	}
+
+	if err := mgr.SetIgnored("/ignored", true); err != nil {
+		t.Fatalf("Failed to ignore repository: %v", err)
+	}
+	if err := mgr.SetEnabled("/enabled", true); err != nil {
+		t.Fatalf("Failed to enable repository: %v", err)
+	}
+	if err := mgr.SetEnabled("/ignored", true); err != nil {
+		t.Fatalf("Failed to enable repository: %v", err)
+	}
+
+	autoStart, err = mgr.LoadAutoStart()
+	if err != nil {
+		t.Fatalf("Failed to load auto-start list: %v", err)
+	}
+	if autoStart.Allows("/ignored", false) || autoStart.Allows("/ignored", true) {
+		t.Error("Expected an ignored repository never to auto-start")
+	}
+	if !autoStart.Allows("/enabled", true) || autoStart.Allows("/other", true) {
+		t.Error("Expected only enabled repositories to auto-start in allowlist mode")
+	}
+
+	if err := mgr.SetIgnored("/ignored", false); err != nil {
+		t.Fatalf("Failed to stop ignoring repository: %v", err)
+	}
+	if autoStart, _ := mgr.LoadAutoStart(); !autoStart.Allows("/ignored", false) || len(autoStart.Ignored) != 0 {
+		t.Errorf("Expected the repository to auto-start again, got %+v", autoStart)
+	}
+}
+
+func TestTouchActivityUpdatesLastActivity(t *testing.T) {
+	mgr := setupTestManager(t)
+
+	if _, ok := mgr.LastActivity("/repo"); ok {
+		t.Fatal("Expected no activity before the daemon served a request")
+	}
+
+	before := time.Now().Add(-time.Second)
+	if err := mgr.TouchActivity("/repo"); err != nil {
+		t.Fatalf("Failed to touch activity: %v", err)
+	}
+
+	lastActivity, ok := mgr.LastActivity("/repo")
+	if !ok {
+		t.Fatal("Expected activity to be recorded")
+	}
+	if lastActivity.Before(before) {
+		t.Errorf("Expected last activity after %v, got %v", before, lastActivity)
+	}
+}
+
+func TestPruneOrphanedFiles(t *testing.T) {
+	mgr := setupTestManager(t)
+
+	if err := mgr.RegisterDaemon(&Info{PID: os.Getpid(), Port: 4000, RepoPath: "/live"}); err != nil {
+		t.Fatalf("Failed to register daemon: %v", err)
+	}
+
+	for _, path := range []string{mgr.GetLogPath("/live"), mgr.GetLogPath("/gone"), mgr.GetActivityPath("/gone")} {
+		if err := os.WriteFile(path, nil, 0644); err != nil {
+			t.Fatalf("Failed to create %s: %v", path, err)
+		}
+	}
+
+	removed, err := mgr.PruneOrphanedFiles()
+	if err != nil {
+		t.Fatalf("Failed to prune files: %v", err)
+	}
+
+	if len(removed) != 2 {
+		t.Errorf("Expected 2 files removed, got %v", removed)
+	}
+	if _, err := os.Stat(mgr.GetLogPath("/live")); err != nil {
+		t.Errorf("Expected log of registered daemon to be kept: %v", err)
+	}
+	if _, err := os.Stat(mgr.registryPath); err != nil {
+		t.Errorf("Expected registry to be kept: %v", err)
+	}
+}
+
+type syncBuffer struct {
+	mu  sync.Mutex
+	buf bytes.Buffer
+}
+
+func (b *syncBuffer) Write(p []byte) (int, error) {
+	b.mu.Lock()
+	defer b.mu.Unlock()
+	return b.buf.Write(p)
+}
+
+func (b *syncBuffer) String() string {
+	b.mu.Lock()
+	defer b.mu.Unlock()
+	return b.buf.String()
+}
+
+func TestFollowFile(t *testing.T) {
+	logPath := filepath.Join(t.TempDir(), "daemon.log")
+	if err := os.WriteFile(logPath, []byte("first\n"), 0644); err != nil {
+		t.Fatalf("Failed to write log: %v", err)
+	}
+
+	ctx, cancel := context.WithCancel(context.Background())
+	out := &syncBuffer{}
+	done := make(chan error, 1)
+	go func() {
+		done <- FollowFile(ctx, logPath, out)
+	}()
+
+	file, err := os.OpenFile(logPath, os.O_APPEND|os.O_WRONLY, 0644)
+	if err != nil {
+		t.Fatalf("Failed to open log: %v", err)
+	}
+	if _, err := file.WriteString("second\n"); err != nil {
+		t.Fatalf("Failed to append to log: %v", err)
+	}
+	file.Close()
+
+	deadline := time.Now().Add(5 * time.Second)
+	for !strings.Contains(out.String(), "second") && time.Now().Before(deadline) {
+		time.Sleep(50 * time.Millisecond)
+	}
+
+	cancel()
+	if err := <-done; err != nil {
+		t.Fatalf("FollowFile returned an error: %v", err)
+	}
+
+	if got := out.String(); got != "first\nsecond\n" {
+		t.Errorf("Expected both lines, got %q", got)
+	}
+}
+
+func TestStateDirOverride(t *testing.T) {
+	stateDir := t.TempDir()
+	t.Setenv("XDG_STATE_HOME", t.TempDir())
+	t.Setenv("GUCK_STATE_DIR", stateDir)
+
+	mgr, err := NewManager()
+	if err != nil {
+		t.Fatalf("Failed to create manager: %v", err)
+	}
+
+	expected := filepath.Join(stateDir, "daemon-registry.json")
+	if mgr.RegistryPath() != expected {
+		t.Errorf("Expected registry at %s, got %s", expected, mgr.RegistryPath())
+	}
+	if !strings.HasPrefix(mgr.GetLogPath("/tmp/repo"), stateDir) {
+		t.Errorf("Expected log path under %s, got %s", stateDir, mgr.GetLogPath("/tmp/repo"))
+	}
+}
+
+func TestWaitForPort(t *testing.T) {
+	port := closedPort(t)
+	if WaitForPort(port, 200*time.Millisecond) {
+		t.Fatal("Expected nothing to be listening on a closed port")
+	}
+
+	// A server that binds while we're waiting is picked up
+	listening := make(chan net.Listener, 1)
+	go func() {
+		time.Sleep(150 * time.Millisecond)
+		listener, err := net.Listen("tcp", fmt.Sprintf("127.0.0.1:%d", port))
+		if err != nil {
+			listening <- nil
+			return
+		}
+		listening <- listener
+	}()
+
+	ok := WaitForPort(port, 2*time.Second)
+	if listener := <-listening; listener != nil {
+		defer listener.Close()
+	} else {
+		t.Skip("Port was taken by another process")
+	}
+	if !ok {
+		t.Error("Expected the port to be reported listening once bound")
+	}
+}
+
+func TestInfoListeningOnSocket(t *testing.T) {
+	mgr := setupTestManager(t)
+
+	socketPath := mgr.SocketPath("/test/repo")
+	if socketPath == mgr.SocketPath("/test/other") {
+		t.Error("Expected each repository to get its own socket")
+	}
+
+	info := &Info{Socket: socketPath, RepoPath: "/test/repo"}
+	if info.IsListening() {
+		t.Fatal("Expected nothing to be listening before the socket is bound")
+	}
+	if info.Endpoint() != "socket "+socketPath {
+		t.Errorf("Unexpected endpoint %q", info.Endpoint())
+	}
+
+	if err := os.MkdirAll(filepath.Dir(socketPath), 0700); err != nil {
+		t.Fatalf("Failed to create socket directory: %v", err)
+	}
+	listener, err := net.Listen("unix", socketPath)
+	if err != nil {
+		t.Skipf("Unix domain sockets unavailable: %v", err)
+	}
+	defer listener.Close()
+
+	if !info.IsListening() {
+		t.Error("Expected the socket to be reported listening once bound")
+	}
+}
+
+func TestWaitForStartup(t *testing.T) {
+	info := &Info{Port: closedPort(t)}
+
+	exited := make(chan error, 1)
+	exited <- errors.New("exit status 1")
+	if err := info.WaitForStartup(exited, 2*time.Second); err == nil || !strings.Contains(err.Error(), "exited during startup") {
+		t.Errorf("Expected a daemon exiting during startup to be reported, got %v", err)
+	}
+
+	if err := info.WaitForStartup(make(chan error), 200*time.Millisecond); err == nil || !strings.Contains(err.Error(), "not accepting connections") {
+		t.Errorf("Expected a timeout, got %v", err)
+	}
+
+	listener, err := net.Listen("tcp", "127.0.0.1:0")
+	if err != nil {
+		t.Fatalf("Failed to listen: %v", err)
+	}
+	defer listener.Close()
+	info.Port = listener.Addr().(*net.TCPAddr).Port
+	if err := info.WaitForStartup(make(chan error), 2*time.Second); err != nil {
+		t.Errorf("Expected a listening daemon to be reported started, got %v", err)
+	}
+}
+
+func TestLoadRegistryRecoversFromBackup(t *testing.T) {
+	mgr := setupTestManager(t)
+
+	for _, repoPath := range []string{"/first", "/second"} {
+		if err := mgr.RegisterDaemon(&Info{PID: os.Getpid(), Port: 4000, RepoPath: repoPath}); err != nil {
+			t.Fatalf("Failed to register daemon: %v", err)
+		}
+	}
+	if err := os.WriteFile(mgr.registryPath, []byte("{\"daemons\": {"), 0644); err != nil {
+		t.Fatalf("Failed to corrupt registry: %v", err)
+	}
+
+	registry, err := mgr.loadRegistry()
+	if err != nil {
+		t.Fatalf("Failed to load registry: %v", err)
+	}
+	// The backup is the registry as it was before the last save
+	if _, ok := registry.Daemons["/first"]; !ok || len(registry.Daemons) != 1 {
+		t.Errorf("Expected the registry to be recovered from its backup, got %v", registry.Daemons)
+	}
+}
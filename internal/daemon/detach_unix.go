@@ -0,0 +1,12 @@
+//go:build !windows
+
+package daemon
+
+import "syscall"
+
+// DetachAttr returns the process attributes that detach a spawned daemon from
+// the terminal: a new session, so closing the shell or pressing Ctrl+C in it
+// doesn't reach the daemon
+func DetachAttr() *syscall.SysProcAttr {
+	return &syscall.SysProcAttr{Setsid: true}
+}
@@ -0,0 +1,18 @@
+//go:build windows
+
+package daemon
+
+import "syscall"
+
+// detachedProcess is DETACHED_PROCESS, which the syscall package doesn't define
+const detachedProcess = 0x00000008
+
+// DetachAttr returns the process attributes that detach a spawned daemon from
+// the console: no console of its own and a new process group, so closing the
+// console or pressing Ctrl+C in it doesn't reach the daemon
+func DetachAttr() *syscall.SysProcAttr {
+	return &syscall.SysProcAttr{
+		CreationFlags: detachedProcess | syscall.CREATE_NEW_PROCESS_GROUP,
+		HideWindow:    true,
+	}
+}
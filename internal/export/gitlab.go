@@ -0,0 +1,147 @@
+package export
+
+import (
+	"fmt"
+	"regexp"
+	"sort"
+	"strconv"
+	"strings"
+
+	"github.com/tuist/guck/internal/git"
+	"github.com/tuist/guck/internal/gitlab"
+	"github.com/tuist/guck/internal/state"
+)
+
+var hunkRangePattern = regexp.MustCompile(`^@@ -(\d+)(?:,(\d+))? \+(\d+)(?:,(\d+))? @@`)
+
+// GitLabDiscussions maps the filtered comments onto merge request discussions.
+// Line comments are anchored to the diff between refs; files is the branch
+// diff the review was made on, used to find each line's old path and number.
+// Resolved comments are left out, since their feedback has been addressed.
+func GitLabDiscussions(comments []*state.Comment, opts Options, refs gitlab.DiffRefs, files []git.FileInfo) []gitlab.Discussion {
+	discussions := []gitlab.Discussion{}
+	// The summary and checklist open the review as one discussion
+	body := strings.TrimSpace(opts.Summary)
+	if checklist := checklistMarkdown(opts.Checklist); checklist != "" {
+		body = strings.TrimSpace(body + "\n\n" + checklist)
+	}
+	if body != "" {
+		discussions = append(discussions, gitlab.Discussion{Body: body})
+	}
+
+	filesByPath := make(map[string]git.FileInfo, len(files))
+	for _, file := range files {
+		filesByPath[file.Path] = file
+	}
+
+	comments = FilterComments(comments, opts)
+	sort.SliceStable(comments, func(i, j int) bool {
+		if comments[i].FilePath != comments[j].FilePath {
+			return comments[i].FilePath < comments[j].FilePath
+		}
+		return lineOf(comments[i]) < lineOf(comments[j])
+	})
+
+	for _, c := range comments {
+		if c.Resolved {
+			continue
+		}
+
+		body := strings.TrimSpace(c.Text)
+		if c.Author != "" {
+			body += fmt.Sprintf(" _(%s)_", c.Author)
+		}
+
+		file, inDiff := filesByPath[c.FilePath]
+		switch {
+		case c.IsGeneral():
+			discussions = append(discussions, gitlab.Discussion{Body: body})
+		case c.LineNumber == nil:
+			discussions = append(discussions, gitlab.Discussion{Body: fmt.Sprintf("`%s`: %s", c.FilePath, body)})
+		case !inDiff:
+			// GitLab rejects positions on files the merge request doesn't change
+			discussions = append(discussions, gitlab.Discussion{Body: fmt.Sprintf("`%s:%d`: %s", c.FilePath, *c.LineNumber, body)})
+		default:
+			newLine := *c.LineNumber
+			position := &gitlab.Position{
+				PositionType: "text",
+				BaseSHA:      refs.BaseSHA,
+				StartSHA:     refs.StartSHA,
+				HeadSHA:      refs.HeadSHA,
+				OldPath:      oldPath(file),
+				NewPath:      file.Path,
+				NewLine:      &newLine,
+			}
+			if file.Status != "added" {
+				position.OldLine = oldLine(file.Patch, newLine)
+			}
+			discussions = append(discussions, gitlab.Discussion{Body: body, Position: position})
+		}
+	}
+
+	return discussions
+}
+
+// oldPath returns the path a file had before the diff, which differs from its
+// path for renames
+func oldPath(file git.FileInfo) string {
+	if file.OldPath != "" {
+		return file.OldPath
+	}
+	return file.Path
+}
+
+// oldLine returns the line of the old version of a file that newLine of the
+// new version corresponds to, or nil when the patch added newLine. GitLab
+// needs both line numbers to anchor a comment on an unchanged line.
+func oldLine(patch string, newLine int) *int {
+	// Difference between old and new line numbers past the hunks walked so far
+	offset := 0
+	oldN, newN := 0, 0
+	inHunk := false
+
+	for _, line := range strings.Split(patch, "\n") {
+		if match := hunkRangePattern.FindStringSubmatch(line); match != nil {
+			oldN, newN = hunkStart(match[1], match[2]), hunkStart(match[3], match[4])
+			if newLine < newN {
+				break
+			}
+			inHunk = true
+			continue
+		}
+		if !inHunk {
+			continue
+		}
+
+		switch {
+		case strings.HasPrefix(line, "+"):
+			if newN == newLine {
+				return nil
+			}
+			newN++
+		case strings.HasPrefix(line, "-"):
+			oldN++
+		case strings.HasPrefix(line, " "):
+			if newN == newLine {
+				n := oldN
+				return &n
+			}
+			oldN++
+			newN++
+		}
+		offset = oldN - newN
+	}
+
+	n := newLine + offset
+	return &n
+}
+
+// hunkStart returns the first line a hunk range covers. Empty ranges name the
+// line before the change, so the next line is returned instead.
+func hunkStart(start, count string) int {
+	n, _ := strconv.Atoi(start) // The pattern only matches digits
+	if count == "0" {
+		n++
+	}
+	return n
+}
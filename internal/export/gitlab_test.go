@@ -0,0 +1,84 @@
+package export
+
+import (
+	"testing"
+
+	"github.com/tuist/guck/internal/git"
+	"github.com/tuist/guck/internal/gitlab"
+	"github.com/tuist/guck/internal/state"
+)
+
+const renamedPatch = `diff --git a/old.go b/new.go
+similarity index 80%
+rename from old.go
+rename to new.go
+--- a/old.go
++++ b/new.go
+@@ -2,4 +2,5 @@ package main
+ import "fmt"
+-var a = 1
++var a = 2
++var b = 3
+ func main() {}
+@@ -20,2 +21,2 @@ func f() {
+-	return 1
++	return 2
+ }
+`
+
+func TestOldLine(t *testing.T) {
+	tests := []struct {
+		newLine int
+		oldLine int // 0 for added lines
+	}{
+		{newLine: 1, oldLine: 1},   // before the first hunk
+		{newLine: 2, oldLine: 2},   // context
+		{newLine: 3, oldLine: 0},   // added
+		{newLine: 5, oldLine: 4},   // context after an insertion
+		{newLine: 10, oldLine: 9},  // between hunks
+		{newLine: 22, oldLine: 21}, // context in the second hunk
+		{newLine: 30, oldLine: 29}, // past the last hunk
+	}
+
+	for _, tt := range tests {
+		got := oldLine(renamedPatch, tt.newLine)
+		switch {
+		case tt.oldLine == 0 && got != nil:
+			t.Errorf("oldLine(%d) = %d, expected an added line", tt.newLine, *got)
+		case tt.oldLine != 0 && (got == nil || *got != tt.oldLine):
+			t.Errorf("oldLine(%d) = %v, expected %d", tt.newLine, got, tt.oldLine)
+		}
+	}
+}
+
+func TestGitLabDiscussions(t *testing.T) {
+	refs := gitlab.DiffRefs{BaseSHA: "base", StartSHA: "start", HeadSHA: "head"}
+	files := []git.FileInfo{{Path: "new.go", OldPath: "old.go", Status: "renamed", Patch: renamedPatch}}
+	comments := []*state.Comment{
+		{ID: "1", FilePath: "new.go", LineNumber: intPtr(3), Text: "Why 2?", Author: "alice"},
+		{ID: "2", FilePath: "new.go", LineNumber: intPtr(5), Text: "Done", Resolved: true},
+		{ID: "3", Text: "Please update the changelog"},
+		{ID: "4", FilePath: "other.go", LineNumber: intPtr(7), Text: "Not in the diff"},
+	}
+
+	discussions := GitLabDiscussions(comments, Options{Summary: "LGTM with nits"}, refs, files)
+	if len(discussions) != 4 {
+		t.Fatalf("Expected the summary and 3 unresolved comments, got %+v", discussions)
+	}
+
+	if discussions[0].Body != "LGTM with nits" || discussions[1].Body != "Please update the changelog" || discussions[1].Position != nil {
+		t.Errorf("Expected the summary and general comment first without positions, got %+v", discussions[:2])
+	}
+
+	position := discussions[2].Position
+	if position == nil || position.OldPath != "old.go" || position.NewPath != "new.go" || *position.NewLine != 3 || position.OldLine != nil || position.HeadSHA != "head" {
+		t.Errorf("Unexpected position for a comment on an added line: %+v", position)
+	}
+	if discussions[2].Body != "Why 2? _(alice)_" {
+		t.Errorf("Expected author attribution, got %q", discussions[2].Body)
+	}
+
+	if discussions[3].Position != nil || discussions[3].Body != "`other.go:7`: Not in the diff" {
+		t.Errorf("Expected a comment outside the diff to become a general discussion, got %+v", discussions[3])
+	}
+}
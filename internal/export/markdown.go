@@ -0,0 +1,239 @@
+package export
+
+import (
+	"fmt"
+	"io"
+	"sort"
+	"strings"
+	"time"
+
+	"github.com/tuist/guck/internal/state"
+)
+
+// NoMatchingComments is written instead of an empty export
+const NoMatchingComments = "_No matching comments._"
+
+// Options controls which comments end up in an export
+type Options struct {
+	// Author, when set, keeps only comments by this author
+	Author string
+	// ExcludeAuthors drops comments by any of these authors
+	ExcludeAuthors []string
+	// Summary, when set, is rendered as the review body above the comments
+	Summary string
+	// Session, when started, is rendered as a line saying who reviewed and when
+	Session state.ReviewSession
+	// Checklist, when set, is rendered as a task list below the summary
+	Checklist *state.ChecklistProgress
+}
+
+// FilterComments applies the author filters in opts. Author names are
+// compared case-insensitively; unattributed comments never match Author.
+func FilterComments(comments []*state.Comment, opts Options) []*state.Comment {
+	filtered := []*state.Comment{}
+	for _, c := range comments {
+		if opts.Author != "" && !strings.EqualFold(c.Author, opts.Author) {
+			continue
+		}
+		if isExcluded(c.Author, opts.ExcludeAuthors) {
+			continue
+		}
+		filtered = append(filtered, c)
+	}
+	return filtered
+}
+
+func isExcluded(author string, excluded []string) bool {
+	if author == "" {
+		return false
+	}
+	for _, e := range excluded {
+		if strings.EqualFold(author, e) {
+			return true
+		}
+	}
+	return false
+}
+
+// Markdown renders the filtered comments grouped by file
+func Markdown(w io.Writer, comments []*state.Comment, opts Options) error {
+	comments = FilterComments(comments, opts)
+
+	var b strings.Builder
+	b.WriteString("# Review comments\n\n")
+	if line := sessionLine(opts.Session); line != "" {
+		b.WriteString(line + "\n\n")
+	}
+	if summary := strings.TrimSpace(opts.Summary); summary != "" {
+		b.WriteString(summary + "\n\n")
+	}
+	if checklist := checklistMarkdown(opts.Checklist); checklist != "" {
+		b.WriteString(checklist + "\n")
+	}
+
+	if len(comments) == 0 {
+		b.WriteString(NoMatchingComments + "\n")
+		_, err := io.WriteString(w, b.String())
+		return err
+	}
+
+	writeComments(&b, comments, "##")
+
+	_, err := io.WriteString(w, b.String())
+	return err
+}
+
+// ReviewFile is a changed file as listed in a review summary
+type ReviewFile struct {
+	Path      string
+	Status    string
+	Additions int
+	Deletions int
+	Viewed    bool
+}
+
+// ReviewMarkdown renders a summary of the whole review: the changed files
+// with their line counts and viewed status, then the filtered comments
+// grouped by file
+func ReviewMarkdown(w io.Writer, files []ReviewFile, comments []*state.Comment, opts Options) error {
+	comments = FilterComments(comments, opts)
+
+	var b strings.Builder
+	b.WriteString("# Review summary\n\n")
+	if line := sessionLine(opts.Session); line != "" {
+		b.WriteString(line + "\n\n")
+	}
+	if summary := strings.TrimSpace(opts.Summary); summary != "" {
+		b.WriteString(summary + "\n\n")
+	}
+	if checklist := checklistMarkdown(opts.Checklist); checklist != "" {
+		b.WriteString(checklist + "\n")
+	}
+
+	viewed := 0
+	for _, f := range files {
+		if f.Viewed {
+			viewed++
+		}
+	}
+	fmt.Fprintf(&b, "## Files (%d/%d viewed)\n\n", viewed, len(files))
+	if len(files) == 0 {
+		b.WriteString("_No changes._\n\n")
+	} else {
+		b.WriteString("| File | Status | + | - | Viewed |\n")
+		b.WriteString("| --- | --- | ---: | ---: | :---: |\n")
+		for _, f := range files {
+			mark := ""
+			if f.Viewed {
+				mark = "✓"
+			}
+			fmt.Fprintf(&b, "| `%s` | %s | %d | %d | %s |\n", f.Path, f.Status, f.Additions, f.Deletions, mark)
+		}
+		b.WriteString("\n")
+	}
+
+	b.WriteString("## Comments\n\n")
+	if len(comments) == 0 {
+		b.WriteString(NoMatchingComments + "\n")
+	} else {
+		writeComments(&b, comments, "###")
+	}
+
+	_, err := io.WriteString(w, b.String())
+	return err
+}
+
+// writeComments renders comments grouped by file, each file headed at the
+// given Markdown heading level
+func writeComments(b *strings.Builder, comments []*state.Comment, heading string) {
+	byFile := make(map[string][]*state.Comment)
+	files := []string{}
+	for _, c := range comments {
+		if _, ok := byFile[c.FilePath]; !ok {
+			files = append(files, c.FilePath)
+		}
+		byFile[c.FilePath] = append(byFile[c.FilePath], c)
+	}
+	sort.Strings(files)
+
+	for _, file := range files {
+		fileComments := byFile[file]
+		sort.SliceStable(fileComments, func(i, j int) bool {
+			return lineOf(fileComments[i]) < lineOf(fileComments[j])
+		})
+
+		if file == "" {
+			// General comments sort first, as notes on the review as a whole
+			b.WriteString(heading + " General\n\n")
+		} else {
+			fmt.Fprintf(b, "%s `%s`\n\n", heading, file)
+		}
+		for _, c := range fileComments {
+			b.WriteString("- ")
+			if c.LineNumber != nil {
+				fmt.Fprintf(b, "**L%d** ", *c.LineNumber)
+			}
+			b.WriteString(strings.ReplaceAll(strings.TrimSpace(c.Text), "\n", "\n  "))
+			if c.Author != "" {
+				fmt.Fprintf(b, " _(%s)_", c.Author)
+			}
+			if c.Resolved {
+				b.WriteString(" ✓ resolved")
+			} else if c.Acknowledged {
+				b.WriteString(" · acknowledged")
+			}
+			b.WriteString("\n")
+		}
+		b.WriteString("\n")
+	}
+}
+
+// checklistMarkdown renders the review checklist as a task list headed by its
+// progress, or returns an empty string when there is no checklist
+func checklistMarkdown(checklist *state.ChecklistProgress) string {
+	if checklist == nil || checklist.Total == 0 {
+		return ""
+	}
+
+	var b strings.Builder
+	fmt.Fprintf(&b, "**Checklist** (%d/%d)\n\n", checklist.Checked, checklist.Total)
+	for _, item := range checklist.Items {
+		mark := " "
+		if item.Checked {
+			mark = "x"
+		}
+		fmt.Fprintf(&b, "- [%s] %s\n", mark, item.Item)
+	}
+	return b.String()
+}
+
+// sessionLine describes a review session, or returns an empty string for
+// reviews nobody has acted on
+func sessionLine(session state.ReviewSession) string {
+	if session.StartedAt == 0 {
+		return ""
+	}
+
+	parts := []string{}
+	if session.Reviewer != "" {
+		parts = append(parts, "Reviewed by "+session.Reviewer)
+	}
+	parts = append(parts, "started "+formatTime(session.StartedAt))
+	if session.LastActivityAt > session.StartedAt {
+		took := time.Duration(session.LastActivityAt-session.StartedAt) * time.Second
+		parts = append(parts, "last activity "+formatTime(session.LastActivityAt), "took "+took.String())
+	}
+	return "_" + strings.Join(parts, " · ") + "_"
+}
+
+func formatTime(unix int64) string {
+	return time.Unix(unix, 0).UTC().Format("2006-01-02 15:04 UTC")
+}
+
+// lineOf sorts file-level comments before line comments
+func lineOf(c *state.Comment) int {
+	if c.LineNumber == nil {
+		return 0
+	}
+	return *c.LineNumber
+}
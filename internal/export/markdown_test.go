@@ -0,0 +1,171 @@
+package export
+
+import (
+	"bytes"
+	"strings"
+	"testing"
+
+	"github.com/tuist/guck/internal/state"
+)
+
+func intPtr(i int) *int {
+	return &i
+}
+
+func testComments() []*state.Comment {
+	return []*state.Comment{
+		{ID: "1", FilePath: "b.go", LineNumber: intPtr(10), Text: "Rename this", Author: "alice"},
+		{ID: "2", FilePath: "a.go", LineNumber: intPtr(3), Text: "Lint: unused import", Author: "lint-bot"},
+		{ID: "3", FilePath: "a.go", Text: "Looks good overall"},
+		{ID: "4", FilePath: "b.go", LineNumber: intPtr(2), Text: "Add a test", Author: "Alice", Resolved: true},
+	}
+}
+
+func TestFilterCommentsByAuthor(t *testing.T) {
+	filtered := FilterComments(testComments(), Options{Author: "alice"})
+	if len(filtered) != 2 {
+		t.Fatalf("Expected 2 comments by alice, got %d", len(filtered))
+	}
+	for _, c := range filtered {
+		if !strings.EqualFold(c.Author, "alice") {
+			t.Errorf("Unexpected author %q", c.Author)
+		}
+	}
+}
+
+func TestFilterCommentsExcludeAuthors(t *testing.T) {
+	filtered := FilterComments(testComments(), Options{ExcludeAuthors: []string{"LINT-BOT"}})
+	if len(filtered) != 3 {
+		t.Fatalf("Expected 3 comments, got %d", len(filtered))
+	}
+	for _, c := range filtered {
+		if c.Author == "lint-bot" {
+			t.Error("Expected lint-bot comments to be excluded")
+		}
+	}
+}
+
+func TestMarkdownGroupsByFile(t *testing.T) {
+	var buf bytes.Buffer
+	if err := Markdown(&buf, testComments(), Options{}); err != nil {
+		t.Fatalf("Failed to render markdown: %v", err)
+	}
+
+	out := buf.String()
+	aIdx := strings.Index(out, "## `a.go`")
+	bIdx := strings.Index(out, "## `b.go`")
+	if aIdx == -1 || bIdx == -1 || aIdx > bIdx {
+		t.Fatalf("Expected files sorted by path, got:\n%s", out)
+	}
+	if strings.Index(out, "**L2** Add a test") > strings.Index(out, "**L10** Rename this") {
+		t.Errorf("Expected comments sorted by line, got:\n%s", out)
+	}
+	if !strings.Contains(out, "_(lint-bot)_") {
+		t.Errorf("Expected author attribution, got:\n%s", out)
+	}
+}
+
+func TestMarkdownNoMatchingComments(t *testing.T) {
+	var buf bytes.Buffer
+	if err := Markdown(&buf, testComments(), Options{Author: "nobody"}); err != nil {
+		t.Fatalf("Failed to render markdown: %v", err)
+	}
+
+	if !strings.Contains(buf.String(), NoMatchingComments) {
+		t.Errorf("Expected no matching comments message, got:\n%s", buf.String())
+	}
+}
+
+func TestMarkdownIncludesSummary(t *testing.T) {
+	var buf bytes.Buffer
+	if err := Markdown(&buf, testComments(), Options{Summary: "LGTM with nits"}); err != nil {
+		t.Fatalf("Failed to render markdown: %v", err)
+	}
+
+	out := buf.String()
+	summaryIdx := strings.Index(out, "LGTM with nits")
+	if summaryIdx == -1 || summaryIdx > strings.Index(out, "## `a.go`") {
+		t.Errorf("Expected the summary above the comments, got:\n%s", out)
+	}
+}
+
+func TestMarkdownIncludesChecklist(t *testing.T) {
+	checklist := &state.ChecklistProgress{
+		Checked: 1,
+		Total:   2,
+		Items:   []state.ChecklistItem{{Item: "Tests added?", Checked: true}, {Item: "Docs updated?"}},
+	}
+
+	var buf bytes.Buffer
+	if err := Markdown(&buf, testComments(), Options{Summary: "LGTM with nits", Checklist: checklist}); err != nil {
+		t.Fatalf("Failed to render markdown: %v", err)
+	}
+
+	out := buf.String()
+	expected := "LGTM with nits\n\n**Checklist** (1/2)\n\n- [x] Tests added?\n- [ ] Docs updated?\n\n## `a.go`"
+	if !strings.Contains(out, expected) {
+		t.Errorf("Expected the checklist between the summary and the comments, got:\n%s", out)
+	}
+}
+
+func TestMarkdownGeneralComments(t *testing.T) {
+	comments := append(testComments(), &state.Comment{ID: "5", Text: "Please update the changelog"})
+
+	var buf bytes.Buffer
+	if err := Markdown(&buf, comments, Options{}); err != nil {
+		t.Fatalf("Failed to render markdown: %v", err)
+	}
+
+	out := buf.String()
+	generalIdx := strings.Index(out, "## General\n\n- Please update the changelog")
+	if generalIdx == -1 || generalIdx > strings.Index(out, "## `a.go`") {
+		t.Errorf("Expected general comments in their own section before file comments, got:\n%s", out)
+	}
+}
+
+func TestMarkdownRendersReviewSession(t *testing.T) {
+	var buf bytes.Buffer
+	session := state.ReviewSession{StartedAt: 1700000000, LastActivityAt: 1700005400, Reviewer: "alice"}
+	if err := Markdown(&buf, testComments(), Options{Session: session}); err != nil {
+		t.Fatalf("Failed to render: %v", err)
+	}
+
+	expected := "_Reviewed by alice · started 2023-11-14 22:13 UTC · last activity 2023-11-14 23:43 UTC · took 1h30m0s_"
+	if !strings.Contains(buf.String(), expected) {
+		t.Errorf("Expected session line %q, got:\n%s", expected, buf.String())
+	}
+
+	buf.Reset()
+	if err := Markdown(&buf, testComments(), Options{}); err != nil {
+		t.Fatalf("Failed to render: %v", err)
+	}
+	if strings.Contains(buf.String(), "started") {
+		t.Errorf("Expected no session line for an unstarted review, got:\n%s", buf.String())
+	}
+}
+
+func TestReviewMarkdownListsFilesAndComments(t *testing.T) {
+	files := []ReviewFile{
+		{Path: "a.go", Status: "modified", Additions: 4, Deletions: 1, Viewed: true},
+		{Path: "b.go", Status: "added", Additions: 12},
+	}
+
+	var buf bytes.Buffer
+	if err := ReviewMarkdown(&buf, files, testComments(), Options{}); err != nil {
+		t.Fatalf("Failed to render review markdown: %v", err)
+	}
+
+	out := buf.String()
+	if !strings.Contains(out, "## Files (1/2 viewed)") {
+		t.Errorf("Expected viewed progress, got:\n%s", out)
+	}
+	if !strings.Contains(out, "| `a.go` | modified | 4 | 1 | ✓ |") {
+		t.Errorf("Expected viewed file row, got:\n%s", out)
+	}
+	if !strings.Contains(out, "| `b.go` | added | 12 | 0 |  |") {
+		t.Errorf("Expected unviewed file row, got:\n%s", out)
+	}
+	if !strings.Contains(out, "### `a.go`") || !strings.Contains(out, "**L2** Add a test _(Alice)_ ✓ resolved") {
+		t.Errorf("Expected comments grouped by file, got:\n%s", out)
+	}
+}
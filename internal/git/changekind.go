@@ -0,0 +1,67 @@
+package git
+
+import (
+	"github.com/go-git/go-git/v5/plumbing/filemode"
+	"github.com/go-git/go-git/v5/plumbing/object"
+)
+
+// ChangeKind classifies a file change for API clients. Unlike FileInfo.Status,
+// which is meant for display, its values are a stable contract.
+type ChangeKind string
+
+const (
+	ChangeAdded       ChangeKind = "added"
+	ChangeModified    ChangeKind = "modified"
+	ChangeDeleted     ChangeKind = "deleted"
+	ChangeRenamed     ChangeKind = "renamed"
+	ChangeCopied      ChangeKind = "copied"
+	ChangeTypeChanged ChangeKind = "type_changed"
+	ChangeConflicted  ChangeKind = "conflicted"
+)
+
+// ChangeKindFromCode maps a status letter, as printed by git diff
+// --name-status and git status or held in a go-git StatusCode, to a ChangeKind.
+// Untracked files count as added; unknown letters as modified.
+func ChangeKindFromCode(code byte) ChangeKind {
+	switch code {
+	case 'A', '?':
+		return ChangeAdded
+	case 'D':
+		return ChangeDeleted
+	case 'R':
+		return ChangeRenamed
+	case 'C':
+		return ChangeCopied
+	case 'T':
+		return ChangeTypeChanged
+	case 'U':
+		return ChangeConflicted
+	}
+	return ChangeModified
+}
+
+// changeKind classifies a tree change. A file replaced by a symlink or
+// submodule at the same path, or the reverse, is a type change.
+func changeKind(change *object.Change) ChangeKind {
+	switch {
+	case change.From.Name == "":
+		return ChangeAdded
+	case change.To.Name == "":
+		return ChangeDeleted
+	case change.From.Name != change.To.Name:
+		return ChangeRenamed
+	case entryType(change.From.TreeEntry.Mode) != entryType(change.To.TreeEntry.Mode):
+		return ChangeTypeChanged
+	}
+	return ChangeModified
+}
+
+// entryType reduces a file mode to what kind of entry it is, so an executable
+// bit flip doesn't count as a type change
+func entryType(mode filemode.FileMode) filemode.FileMode {
+	switch mode {
+	case filemode.Symlink, filemode.Submodule, filemode.Dir:
+		return mode
+	}
+	return filemode.Regular
+}
@@ -0,0 +1,112 @@
+package git
+
+import (
+	"testing"
+
+	"github.com/go-git/go-git/v5"
+	"github.com/go-git/go-git/v5/plumbing/filemode"
+	"github.com/go-git/go-git/v5/plumbing/object"
+)
+
+func TestChangeKindFromCode(t *testing.T) {
+	tests := []struct {
+		code     byte
+		expected ChangeKind
+	}{
+		{code: byte(git.Added), expected: ChangeAdded},
+		{code: byte(git.Untracked), expected: ChangeAdded},
+		{code: byte(git.Modified), expected: ChangeModified},
+		{code: byte(git.Deleted), expected: ChangeDeleted},
+		{code: byte(git.Renamed), expected: ChangeRenamed},
+		{code: byte(git.Copied), expected: ChangeCopied},
+		{code: byte(git.UpdatedButUnmerged), expected: ChangeConflicted},
+		{code: 'T', expected: ChangeTypeChanged},
+		{code: 'X', expected: ChangeModified},
+	}
+
+	for _, tt := range tests {
+		if got := ChangeKindFromCode(tt.code); got != tt.expected {
+			t.Errorf("ChangeKindFromCode(%q) = %q, expected %q", tt.code, got, tt.expected)
+		}
+	}
+}
+
+func TestChangeKindOfTreeChange(t *testing.T) {
+	entry := func(name string, mode filemode.FileMode) object.ChangeEntry {
+		return object.ChangeEntry{Name: name, TreeEntry: object.TreeEntry{Name: name, Mode: mode}}
+	}
+
+	tests := []struct {
+		name     string
+		change   object.Change
+		expected ChangeKind
+	}{
+		{name: "added", change: object.Change{To: entry("a.go", filemode.Regular)}, expected: ChangeAdded},
+		{name: "deleted", change: object.Change{From: entry("a.go", filemode.Regular)}, expected: ChangeDeleted},
+		{name: "renamed", change: object.Change{From: entry("a.go", filemode.Regular), To: entry("b.go", filemode.Regular)}, expected: ChangeRenamed},
+		{name: "modified", change: object.Change{From: entry("a.go", filemode.Regular), To: entry("a.go", filemode.Regular)}, expected: ChangeModified},
+		{name: "made executable", change: object.Change{From: entry("a.sh", filemode.Regular), To: entry("a.sh", filemode.Executable)}, expected: ChangeModified},
+		{name: "replaced by symlink", change: object.Change{From: entry("a", filemode.Regular), To: entry("a", filemode.Symlink)}, expected: ChangeTypeChanged},
+	}
+
+	for _, tt := range tests {
+		if got := changeKind(&tt.change); got != tt.expected {
+			t.Errorf("%s: expected %q, got %q", tt.name, tt.expected, got)
+		}
+	}
+}
+
+func TestParsePatchChangeKinds(t *testing.T) {
+	patch := `diff --git a/new.go b/new.go
+new file mode 100644
+--- /dev/null
++++ b/new.go
+@@ -0,0 +1 @@
++package main
+diff --git a/old.go b/old.go
+deleted file mode 100644
+--- a/old.go
++++ /dev/null
+@@ -1 +0,0 @@
+-package main
+diff --git a/a.go b/copy.go
+similarity index 100%
+copy from a.go
+copy to copy.go
+diff --git a/b.go b/moved.go
+similarity index 100%
+rename from b.go
+rename to moved.go
+diff --git a/run.sh b/run.sh
+old mode 100644
+new mode 100755
+diff --git a/c.go b/c.go
+--- a/c.go
++++ b/c.go
+@@ -1 +1 @@
+-package a
++package c
+`
+
+	files, err := ParsePatch(patch)
+	if err != nil {
+		t.Fatalf("Failed to parse patch: %v", err)
+	}
+
+	expected := map[string]ChangeKind{
+		"new.go":   ChangeAdded,
+		"old.go":   ChangeDeleted,
+		"copy.go":  ChangeCopied,
+		"moved.go": ChangeRenamed,
+		"run.sh":   ChangeModified,
+		"c.go":     ChangeModified,
+	}
+	if len(files) != len(expected) {
+		t.Fatalf("Expected %d files, got %d", len(expected), len(files))
+	}
+	for _, file := range files {
+		if file.Kind != expected[file.Path] {
+			t.Errorf("%s: expected kind %q, got %q", file.Path, expected[file.Path], file.Kind)
+		}
+	}
+}
@@ -0,0 +1,189 @@
+package git
+
+import (
+	"context"
+	"errors"
+	"fmt"
+	"strings"
+)
+
+// SuppressedChange reports hunks dropped from a branch diff because the
+// branch commit that made them has a patch-id equivalent on the base branch,
+// such as a commit cherry-picked from or to it
+type SuppressedChange struct {
+	Path string `json:"path"`
+	// Hunks is how many of the file's hunks were dropped
+	Hunks int `json:"hunks"`
+	// WholeFile is set when every hunk was dropped, so the file left the diff
+	WholeFile bool `json:"whole_file,omitempty"`
+	// Commits are the branch commits whose change is already on the base
+	Commits []string `json:"commits"`
+}
+
+// CherryPickedCommits returns the commits HEAD has over the base branch whose
+// change the base already has under another commit, the ones git cherry marks
+// with "-". git compares them by patch-id, so rebased and cherry-picked
+// copies of a commit match.
+func (r *Repo) CherryPickedCommits(ctx context.Context, baseBranch string) ([]string, error) {
+	baseRef, _, err := r.resolveBaseRef(baseBranch)
+	if err != nil {
+		return nil, err
+	}
+	repoPath, err := r.RepoPath()
+	if err != nil {
+		return nil, err
+	}
+
+	cmd := gitCommandContext(ctx, repoPath, "cherry", baseRef, "HEAD")
+	output, err := cmd.Output()
+	if err != nil {
+		if ctx.Err() != nil {
+			return nil, ctx.Err()
+		}
+		return nil, fmt.Errorf("failed to find commits already on '%s': %w", baseBranch, commandError(cmd, err))
+	}
+
+	var commits []string
+	for _, line := range strings.Split(string(output), "\n") {
+		if commit, ok := strings.CutPrefix(strings.TrimSpace(line), "- "); ok {
+			commits = append(commits, commit)
+		}
+	}
+	return commits, nil
+}
+
+// SuppressCherryPicks drops the hunks of a branch diff that commits found by
+// CherryPickedCommits made, and the files left without hunks, reporting what
+// it dropped. A hunk is dropped when it adds and removes exactly the lines a
+// hunk of such a commit does; one mixed with other changes of the branch is
+// kept whole. The remaining hunks keep their headers, so their line numbers
+// stay those of the merge base and HEAD.
+func (r *Repo) SuppressCherryPicks(ctx context.Context, baseBranch string, files []FileInfo) ([]FileInfo, []SuppressedChange, error) {
+	commits, err := r.CherryPickedCommits(ctx, baseBranch)
+	if err != nil || len(commits) == 0 {
+		return files, nil, err
+	}
+	repoPath, err := r.RepoPath()
+	if err != nil {
+		return nil, nil, err
+	}
+
+	picked := make(map[string]map[string][]string)
+	for _, commit := range commits {
+		cmd := gitCommandContext(ctx, repoPath, "show", "--format=", "--no-color", "--no-ext-diff", commit)
+		output, err := cmd.Output()
+		if err != nil {
+			if ctx.Err() != nil {
+				return nil, nil, ctx.Err()
+			}
+			return nil, nil, fmt.Errorf("failed to show commit %s: %w", Abbreviate(commit, 7), commandError(cmd, err))
+		}
+
+		commitFiles, err := ParsePatch(string(output))
+		if errors.Is(err, ErrEmptyPatch) {
+			continue
+		}
+		if err != nil {
+			return nil, nil, fmt.Errorf("failed to parse commit %s: %w", Abbreviate(commit, 7), err)
+		}
+		for _, file := range commitFiles {
+			if picked[file.Path] == nil {
+				picked[file.Path] = make(map[string][]string)
+			}
+			_, hunks := splitHunks(file.Patch)
+			for _, hunk := range hunks {
+				key := hunkKey(hunk)
+				picked[file.Path][key] = append(picked[file.Path][key], commit)
+			}
+		}
+	}
+
+	kept, suppressed := suppressHunks(files, picked)
+	return kept, suppressed, nil
+}
+
+// suppressHunks drops the hunks of files found in picked, which maps a path
+// and a hunkKey to the commits that made the hunk
+func suppressHunks(files []FileInfo, picked map[string]map[string][]string) ([]FileInfo, []SuppressedChange) {
+	kept := make([]FileInfo, 0, len(files))
+	var suppressed []SuppressedChange
+	for _, file := range files {
+		header, hunks := splitHunks(file.Patch)
+		keys := picked[file.Path]
+		if len(keys) == 0 || len(hunks) == 0 {
+			kept = append(kept, file)
+			continue
+		}
+
+		var remaining, commits []string
+		seen := make(map[string]bool)
+		for _, hunk := range hunks {
+			by, ok := keys[hunkKey(hunk)]
+			if !ok {
+				remaining = append(remaining, hunk)
+				continue
+			}
+			for _, commit := range by {
+				if !seen[commit] {
+					seen[commit] = true
+					commits = append(commits, commit)
+				}
+			}
+		}
+		if len(remaining) == len(hunks) {
+			kept = append(kept, file)
+			continue
+		}
+
+		suppressed = append(suppressed, SuppressedChange{
+			Path:      file.Path,
+			Hunks:     len(hunks) - len(remaining),
+			WholeFile: len(remaining) == 0,
+			Commits:   commits,
+		})
+		if len(remaining) == 0 {
+			continue
+		}
+		file.Patch = header + strings.Join(remaining, "")
+		file.Additions, file.Deletions = countChanges(file.Patch)
+		kept = append(kept, file)
+	}
+	return kept, suppressed
+}
+
+// splitHunks splits a file's patch into the header before its first hunk and
+// its hunks, each starting with its "@@" line
+func splitHunks(patch string) (string, []string) {
+	lines := strings.SplitAfter(patch, "\n")
+	start := len(lines)
+	for i, line := range lines {
+		if strings.HasPrefix(line, "@@") {
+			start = i
+			break
+		}
+	}
+
+	var hunks []string
+	for i := start; i < len(lines); {
+		end := i + 1
+		for end < len(lines) && !strings.HasPrefix(lines[end], "@@") {
+			end++
+		}
+		hunks = append(hunks, strings.Join(lines[i:end], ""))
+		i = end
+	}
+	return strings.Join(lines[:start], ""), hunks
+}
+
+// hunkKey identifies a hunk by the lines it adds and removes, ignoring its
+// position and context, which differ between a commit and the branch diff
+func hunkKey(hunk string) string {
+	var key strings.Builder
+	for _, line := range strings.SplitAfter(hunk, "\n")[1:] {
+		if strings.HasPrefix(line, "+") || strings.HasPrefix(line, "-") {
+			key.WriteString(strings.TrimRight(line, "\r\n"))
+			key.WriteByte('\n')
+		}
+	}
+	return key.String()
+}
@@ -0,0 +1,80 @@
+package git
+
+import (
+	"context"
+	"os"
+	"path/filepath"
+	"strings"
+	"testing"
+)
+
+func TestSuppressCherryPicks(t *testing.T) {
+	tempDir := setupTestRepo(t)
+	runGit(t, tempDir, "branch", "base")
+
+	if err := os.WriteFile(filepath.Join(tempDir, "feature.txt"), []byte("feature\n"), 0644); err != nil {
+		t.Fatalf("Failed to create file: %v", err)
+	}
+	runGit(t, tempDir, "add", ".")
+	runGit(t, tempDir, "commit", "-m", "Add feature")
+
+	runGit(t, tempDir, "checkout", "base")
+	if err := os.WriteFile(filepath.Join(tempDir, "fix.txt"), []byte("fix\n"), 0644); err != nil {
+		t.Fatalf("Failed to create file: %v", err)
+	}
+	runGit(t, tempDir, "add", ".")
+	runGit(t, tempDir, "commit", "-m", "Add fix")
+	fix := strings.TrimSpace(runGit(t, tempDir, "rev-parse", "HEAD"))
+	runGit(t, tempDir, "checkout", "-")
+	runGit(t, tempDir, "cherry-pick", fix)
+	picked := strings.TrimSpace(runGit(t, tempDir, "rev-parse", "HEAD"))
+
+	repo, err := Open(tempDir)
+	if err != nil {
+		t.Fatalf("Failed to open repo: %v", err)
+	}
+	files, err := repo.GetDiffFiles("base")
+	if err != nil {
+		t.Fatalf("Failed to get diff files: %v", err)
+	}
+	if len(files) != 2 {
+		t.Fatalf("Expected the naive diff to show both files, got %+v", files)
+	}
+
+	kept, suppressed, err := repo.SuppressCherryPicks(context.Background(), "base", files)
+	if err != nil {
+		t.Fatalf("Failed to suppress cherry-picks: %v", err)
+	}
+	if len(kept) != 1 || kept[0].Path != "feature.txt" {
+		t.Errorf("Expected only feature.txt to remain, got %+v", kept)
+	}
+	if len(suppressed) != 1 || suppressed[0].Path != "fix.txt" || !suppressed[0].WholeFile {
+		t.Fatalf("Expected fix.txt to be suppressed whole, got %+v", suppressed)
+	}
+	if len(suppressed[0].Commits) != 1 || suppressed[0].Commits[0] != picked {
+		t.Errorf("Expected the cherry-picked commit %s, got %v", picked, suppressed[0].Commits)
+	}
+}
+
+func TestSuppressHunks(t *testing.T) {
+	header := "diff --git a/main.go b/main.go\n--- a/main.go\n+++ b/main.go\n"
+	picked := "@@ -1,3 +1,3 @@\n one\n-two\n+TWO\n three\n"
+	own := "@@ -10,2 +10,3 @@\n ten\n+eleven\n twelve\n"
+	files := []FileInfo{{Path: "main.go", Patch: header + picked + own, Additions: 2, Deletions: 1}}
+
+	// The commit's hunk has other context and line numbers than the branch diff's
+	_, commitHunks := splitHunks("@@ -5,2 +5,2 @@\n-two\n+TWO\n zero\n")
+	kept, suppressed := suppressHunks(files, map[string]map[string][]string{
+		"main.go": {hunkKey(commitHunks[0]): {"abc123"}},
+	})
+
+	if len(kept) != 1 || kept[0].Patch != header+own {
+		t.Fatalf("Expected only the branch's own hunk to remain, got %+v", kept)
+	}
+	if kept[0].Additions != 1 || kept[0].Deletions != 0 {
+		t.Errorf("Expected the counts to be recomputed to +1 -0, got +%d -%d", kept[0].Additions, kept[0].Deletions)
+	}
+	if len(suppressed) != 1 || suppressed[0].Hunks != 1 || suppressed[0].WholeFile {
+		t.Errorf("Expected one suppressed hunk, got %+v", suppressed)
+	}
+}
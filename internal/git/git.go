@@ -1,19 +1,40 @@
 package git
 
 import (
+	"context"
+	"errors"
 	"fmt"
+	"hash/fnv"
 	"os"
 	"os/exec"
 	"path/filepath"
+	"strconv"
 	"strings"
 
 	"github.com/go-git/go-git/v5"
 	"github.com/go-git/go-git/v5/plumbing"
+	"github.com/go-git/go-git/v5/plumbing/filemode"
 	"github.com/go-git/go-git/v5/plumbing/object"
 )
 
+// ErrOffline is returned by network operations when offline mode is enabled
+var ErrOffline = errors.New("offline mode: base ref is local-only")
+
+// ErrCommitNotInHistory is returned when a commit doesn't exist or isn't reachable from HEAD
+var ErrCommitNotInHistory = errors.New("commit not found in HEAD's history")
+
+// ErrBareRepository is returned by operations that need a working tree, which
+// bare repositories don't have
+var ErrBareRepository = errors.New("bare repository has no working tree; only the branch diff against the base is available")
+
+// ErrFileNotInCommit is returned by Blame for files that don't exist at the commit, such as new uncommitted files
+var ErrFileNotInCommit = errors.New("file not found at commit")
+
 type Repo struct {
-	repo *git.Repository
+	repo    *git.Repository
+	offline bool
+	// bareDir is the repository directory when it's bare, and empty otherwise
+	bareDir string
 }
 
 // StagingStatus indicates whether a file change is staged, unstaged, or committed
@@ -25,30 +46,172 @@ const (
 	StagingStatusUnstaged  StagingStatus = "unstaged"
 )
 
+// DiffAlgorithms lists the supported values for DiffOptions.Algorithm
+var DiffAlgorithms = []string{"myers", "patience", "histogram"}
+
+// Diff backends for DiffOptions.Backend
+const (
+	// DiffBackendGoGit diffs trees in process with go-git
+	DiffBackendGoGit = "go-git"
+	// DiffBackendCLI runs git diff, so git's own settings such as rename
+	// detection and textconv filters apply
+	DiffBackendCLI = "cli"
+)
+
+// DiffOptions tunes how GetDiffFilesWithOptions produces patches
+type DiffOptions struct {
+	// Algorithm is one of DiffAlgorithms. Empty uses the built-in Myers diff.
+	Algorithm string
+	// RecurseSubmodules replaces submodule pointer changes with the file
+	// changes inside the submodule. Only one level of submodules is expanded.
+	RecurseSubmodules bool
+	// Backend is DiffBackendGoGit or DiffBackendCLI. Empty means go-git.
+	Backend string
+	// Textconv runs the textconv filters of files whose diff attribute names a
+	// driver with one, so they diff as text instead of as binary. The filters
+	// are external programs from git config, so this is opt-in.
+	Textconv bool
+	// Pathspec limits the diff to the paths git's pathspecs match, magic such
+	// as ":(exclude)" and ":(glob)" included. git evaluates them, so setting
+	// any diffs with the CLI backend. See ValidatePathspec.
+	Pathspec []string
+}
+
+// ValidateDiffBackend checks that backend is empty or a known diff backend
+func ValidateDiffBackend(backend string) error {
+	switch backend {
+	case "", DiffBackendGoGit, DiffBackendCLI:
+		return nil
+	}
+	return fmt.Errorf("invalid diff backend '%s' (expected %s or %s)", backend, DiffBackendGoGit, DiffBackendCLI)
+}
+
+// pathspecMagic lists the long-form pathspec magic words git implements
+var pathspecMagic = map[string]bool{
+	"top":     true,
+	"literal": true,
+	"glob":    true,
+	"icase":   true,
+	"attr":    true,
+	"exclude": true,
+}
+
+// shortPathspecMagic are the characters git reads as short-form pathspec magic
+// after a leading ':'. Of these it only implements '/' (top) and '!' or '^'
+// (exclude).
+const shortPathspecMagic = "!\"#%&',-/;<=>@^_`~"
+
+// ValidatePathspec checks the magic of pathspecs before they are handed to
+// git, so a malformed one gets a clear error instead of a failed diff
+func ValidatePathspec(pathspec []string) error {
+	for _, spec := range pathspec {
+		if spec == "" {
+			return errors.New("invalid pathspec: empty pathspec")
+		}
+		if err := validatePathspecMagic(spec); err != nil {
+			return fmt.Errorf("invalid pathspec '%s': %w", spec, err)
+		}
+	}
+	return nil
+}
+
+func validatePathspecMagic(spec string) error {
+	rest, ok := strings.CutPrefix(spec, ":")
+	if !ok {
+		return nil
+	}
+
+	if long, ok := strings.CutPrefix(rest, "("); ok {
+		end := strings.Index(long, ")")
+		if end < 0 {
+			return errors.New("missing ')' after magic")
+		}
+		seen := map[string]bool{}
+		for _, word := range strings.Split(long[:end], ",") {
+			name, _, _ := strings.Cut(strings.TrimSpace(word), ":")
+			if !pathspecMagic[name] {
+				return fmt.Errorf("unknown magic '%s' (expected top, literal, glob, icase, attr or exclude)", name)
+			}
+			seen[name] = true
+		}
+		if seen["literal"] && seen["glob"] {
+			return errors.New("'literal' and 'glob' magic can't be combined")
+		}
+		return nil
+	}
+
+	for _, c := range rest {
+		switch {
+		case c == ':':
+			return nil
+		case c == '/' || c == '!' || c == '^':
+		case strings.ContainsRune(shortPathspecMagic, c):
+			return fmt.Errorf("unknown magic '%c' (expected /, ! or ^)", c)
+		default:
+			// The pattern starts at the first character that isn't magic
+			return nil
+		}
+	}
+	return nil
+}
+
+// ValidateDiffAlgorithm checks that algorithm is empty or one of DiffAlgorithms
+func ValidateDiffAlgorithm(algorithm string) error {
+	if algorithm == "" {
+		return nil
+	}
+	for _, supported := range DiffAlgorithms {
+		if algorithm == supported {
+			return nil
+		}
+	}
+	return fmt.Errorf("invalid diff algorithm '%s' (expected one of %s)", algorithm, strings.Join(DiffAlgorithms, ", "))
+}
+
 type FileInfo struct {
-	Path          string        `json:"path"`
-	Status        string        `json:"status"`
+	Path   string `json:"path"`
+	Status string `json:"status"`
+	// Kind is the stable classification of the change, see ChangeKind
+	Kind          ChangeKind    `json:"kind"`
 	Additions     int           `json:"additions"`
 	Deletions     int           `json:"deletions"`
 	Patch         string        `json:"patch"`
 	StagingStatus StagingStatus `json:"staging_status,omitempty"`
+	// OldPath is the path before the change, set for renamed files
+	OldPath string `json:"old_path,omitempty"`
 }
 
 func Open(path string) (*Repo, error) {
 	repo, err := git.PlainOpenWithOptions(path, &git.PlainOpenOptions{
 		DetectDotGit: true,
 	})
+	if errors.Is(err, git.ErrRepositoryNotExists) {
+		// Bare repositories have no .git directory to detect
+		if bare, bareErr := git.PlainOpen(path); bareErr == nil {
+			absPath, absErr := filepath.Abs(path)
+			if absErr != nil {
+				return nil, fmt.Errorf("failed to get absolute path: %w", absErr)
+			}
+			return &Repo{repo: bare, bareDir: absPath}, nil
+		}
+	}
 	if err != nil {
-		return nil, fmt.Errorf("failed to find git repository: %w", err)
+		return nil, fmt.Errorf("failed to find git repository at %s: %w", path, err)
 	}
 
 	return &Repo{repo: repo}, nil
 }
 
+// IsBare reports whether the repository has no working tree. Bare
+// repositories can only be reviewed as a diff between HEAD and the base branch.
+func (r *Repo) IsBare() bool {
+	return r.bareDir != ""
+}
+
 func (r *Repo) CurrentBranch() (string, error) {
 	head, err := r.repo.Head()
 	if err != nil {
-		return "", fmt.Errorf("failed to get HEAD: %w", err)
+		return "", fmt.Errorf("failed to resolve HEAD for current branch: %w", err)
 	}
 
 	if !head.Name().IsBranch() {
@@ -61,16 +224,28 @@ func (r *Repo) CurrentBranch() (string, error) {
 func (r *Repo) CurrentCommit() (string, error) {
 	head, err := r.repo.Head()
 	if err != nil {
-		return "", fmt.Errorf("failed to get HEAD: %w", err)
+		return "", fmt.Errorf("failed to resolve HEAD for current commit: %w", err)
 	}
 
 	return head.Hash().String(), nil
 }
 
+// Abbreviate shortens a commit SHA to the given length for display
+func Abbreviate(commit string, length int) string {
+	if length <= 0 || length >= len(commit) {
+		return commit
+	}
+	return commit[:length]
+}
+
 func (r *Repo) RepoPath() (string, error) {
-	wt, err := r.repo.Worktree()
+	if r.IsBare() {
+		return r.bareDir, nil
+	}
+
+	wt, err := r.worktree()
 	if err != nil {
-		return "", fmt.Errorf("failed to get worktree: %w", err)
+		return "", err
 	}
 
 	absPath, err := filepath.Abs(wt.Filesystem.Root())
@@ -81,284 +256,1672 @@ func (r *Repo) RepoPath() (string, error) {
 	return absPath, nil
 }
 
-// GetRemoteURL returns the URL of the origin remote, or empty string if not found
-func (r *Repo) GetRemoteURL() (string, error) {
-	remote, err := r.repo.Remote("origin")
+// FindRepoPath returns the working tree root of the repository containing dir
+// by looking for its .git entry, as Open does, without opening the repository.
+// For repositories with a working tree it's what RepoPath returns, at a
+// fraction of the cost; ok is false when dir isn't inside one.
+func FindRepoPath(dir string) (string, bool) {
+	dir, err := filepath.Abs(dir)
 	if err != nil {
-		// No origin remote, return empty string
+		return "", false
+	}
+
+	for {
+		if _, err := os.Stat(filepath.Join(dir, ".git")); err == nil {
+			return dir, true
+		}
+		parent := filepath.Dir(dir)
+		if parent == dir {
+			return "", false
+		}
+		dir = parent
+	}
+}
+
+// AmendedFrom returns the commit that HEAD replaced when the latest reflog
+// entry is an amend, or an empty string if HEAD wasn't produced by an amend
+func (r *Repo) AmendedFrom() (string, error) {
+	repoPath, err := r.RepoPath()
+	if err != nil {
+		return "", err
+	}
+
+	cmd := gitCommand(repoPath, "reflog", "-n", "2", "--format=%H%x09%gs", "HEAD")
+	output, err := cmd.Output()
+	if err != nil {
+		return "", fmt.Errorf("failed to read HEAD reflog: %w", commandError(cmd, err))
+	}
+
+	entries := strings.Split(strings.TrimSpace(string(output)), "\n")
+	if len(entries) < 2 {
 		return "", nil
 	}
 
-	if len(remote.Config().URLs) == 0 {
+	_, subject, _ := strings.Cut(entries[0], "\t")
+	if !strings.HasPrefix(subject, "commit (amend)") {
 		return "", nil
 	}
 
-	return remote.Config().URLs[0], nil
+	previous, _, _ := strings.Cut(entries[1], "\t")
+	return previous, nil
 }
 
-func (r *Repo) GetDiffFiles(baseBranch string) ([]FileInfo, error) {
-	// Try to get the remote tracking branch first (origin/baseBranch)
-	// This ensures we compare against the remote version even if local is outdated
-	remoteBranchRef, err := r.repo.Reference(plumbing.NewRemoteReferenceName("origin", baseBranch), true)
-
-	var baseCommit *object.Commit
-	if err == nil {
-		// Remote tracking branch exists, use it
-		baseCommit, err = r.repo.CommitObject(remoteBranchRef.Hash())
-		if err != nil {
-			return nil, fmt.Errorf("failed to get remote base commit: %w", err)
-		}
-	} else {
-		// Fall back to local branch if remote tracking branch doesn't exist
-		baseBranchRef, err := r.repo.Reference(plumbing.NewBranchReferenceName(baseBranch), true)
-		if err != nil {
-			return nil, fmt.Errorf("failed to find branch %s: %w", baseBranch, err)
-		}
+// SetOffline disables network operations such as fetching the base branch,
+// so the diff is computed purely from refs already present locally
+func (r *Repo) SetOffline(offline bool) {
+	r.offline = offline
+}
 
-		baseCommit, err = r.repo.CommitObject(baseBranchRef.Hash())
-		if err != nil {
-			return nil, fmt.Errorf("failed to get base commit: %w", err)
-		}
+// FetchBase updates the origin tracking branch for baseBranch
+func (r *Repo) FetchBase(baseBranch string) error {
+	if r.offline {
+		return ErrOffline
 	}
 
-	// Get the current HEAD commit
-	head, err := r.repo.Head()
+	repoPath, err := r.RepoPath()
 	if err != nil {
-		return nil, fmt.Errorf("failed to get HEAD: %w", err)
+		return err
 	}
 
-	headCommit, err := r.repo.CommitObject(head.Hash())
+	cmd := gitCommand(repoPath, "fetch", "--quiet", "origin", baseBranch)
+	if _, err := cmd.Output(); err != nil {
+		return fmt.Errorf("failed to fetch base ref '%s' from origin: %w", baseBranch, commandError(cmd, err))
+	}
+
+	return nil
+}
+
+// PullRequestBranch is the local branch FetchPullRequest stores a GitHub pull request's head in
+func PullRequestBranch(number int) string {
+	return fmt.Sprintf("guck/pr-%d", number)
+}
+
+// FetchPullRequest fetches refs/pull/<number>/head from origin into the local
+// branch PullRequestBranch(number), overwriting it, and returns the branch name
+func (r *Repo) FetchPullRequest(number int) (string, error) {
+	if r.offline {
+		return "", ErrOffline
+	}
+
+	repoPath, err := r.RepoPath()
 	if err != nil {
-		return nil, fmt.Errorf("failed to get HEAD commit: %w", err)
+		return "", err
 	}
 
-	// Find the merge base between base branch and HEAD
-	mergeBase, err := headCommit.MergeBase(baseCommit)
+	branch := PullRequestBranch(number)
+	refspec := fmt.Sprintf("+refs/pull/%d/head:refs/heads/%s", number, branch)
+	cmd := gitCommand(repoPath, "fetch", "--quiet", "origin", refspec)
+	if _, err := cmd.Output(); err != nil {
+		return "", fmt.Errorf("failed to fetch pull request #%d from origin: %w", number, commandError(cmd, err))
+	}
+
+	return branch, nil
+}
+
+// CheckoutWorktree checks ref out, detached, in a linked worktree at path,
+// creating the worktree if it doesn't exist yet. A path that exists but isn't
+// one of the repository's worktrees is refused rather than checked out over.
+func (r *Repo) CheckoutWorktree(path, ref string) error {
+	repoPath, err := r.RepoPath()
 	if err != nil {
-		return nil, fmt.Errorf("failed to find merge base: %w", err)
+		return err
 	}
 
-	// Use the merge base as the comparison point
-	var baseTree *object.Tree
-	if len(mergeBase) > 0 {
-		baseTree, err = mergeBase[0].Tree()
+	var cmd *exec.Cmd
+	switch info, err := os.Stat(path); {
+	case err == nil:
+		worktree, err := r.isWorktree(info)
 		if err != nil {
-			return nil, fmt.Errorf("failed to get merge base tree: %w", err)
+			return err
 		}
-	} else {
-		// Fallback to base branch if no merge base found
-		baseTree, err = baseCommit.Tree()
-		if err != nil {
-			return nil, fmt.Errorf("failed to get base tree: %w", err)
+		if !worktree {
+			return fmt.Errorf("%s already exists and is not a worktree of %s", path, repoPath)
 		}
+		cmd = gitCommand(path, "checkout", "--quiet", "--detach", ref)
+	case errors.Is(err, os.ErrNotExist):
+		cmd = gitCommand(repoPath, "worktree", "add", "--quiet", "--detach", path, ref)
+	default:
+		return err
 	}
 
-	headTree, err := headCommit.Tree()
-	if err != nil {
-		return nil, fmt.Errorf("failed to get HEAD tree: %w", err)
+	if _, err := cmd.Output(); err != nil {
+		return fmt.Errorf("failed to check out %s in worktree %s: %w", ref, path, commandError(cmd, err))
 	}
 
-	// Get the diff
-	changes, err := baseTree.Diff(headTree)
+	return nil
+}
+
+// Worktrees lists the paths of the repository's worktrees, the main one first
+func (r *Repo) Worktrees() ([]string, error) {
+	repoPath, err := r.RepoPath()
 	if err != nil {
-		return nil, fmt.Errorf("failed to create diff: %w", err)
+		return nil, err
 	}
 
-	files := []FileInfo{}
+	cmd := gitCommand(repoPath, "worktree", "list", "--porcelain")
+	output, err := cmd.Output()
+	if err != nil {
+		return nil, fmt.Errorf("failed to list worktrees: %w", commandError(cmd, err))
+	}
 
-	for _, change := range changes {
-		patch, err := change.Patch()
-		if err != nil {
-			continue
+	var paths []string
+	for _, line := range strings.Split(string(output), "\n") {
+		if path, ok := strings.CutPrefix(line, "worktree "); ok {
+			paths = append(paths, path)
 		}
+	}
+	return paths, nil
+}
 
-		filePath := change.To.Name
-		if filePath == "" {
-			filePath = change.From.Name
+// isWorktree reports whether the directory info describes is one of the
+// repository's worktrees. Directories are compared rather than paths, which
+// git may report with symlinks resolved.
+func (r *Repo) isWorktree(info os.FileInfo) (bool, error) {
+	worktrees, err := r.Worktrees()
+	if err != nil {
+		return false, err
+	}
+	for _, worktree := range worktrees {
+		if worktreeInfo, err := os.Stat(worktree); err == nil && os.SameFile(info, worktreeInfo) {
+			return true, nil
 		}
+	}
+	return false, nil
+}
 
-		status := "modified"
-		switch {
-		case change.From.Name == "":
-			status = "added"
-		case change.To.Name == "":
-			status = "deleted"
-		case change.From.Name != change.To.Name:
-			status = "renamed"
-		}
+// gitCommand builds a git invocation that fails instead of blocking on
+// interactive credential prompts, which would otherwise hang the daemon
+func gitCommand(dir string, args ...string) *exec.Cmd {
+	return gitCommandContext(context.Background(), dir, args...)
+}
 
-		// Count additions and deletions from the patch string
-		additions := 0
-		deletions := 0
-		patchStr := patch.String()
+// gitCommandContext is gitCommand with the process killed once ctx is cancelled
+func gitCommandContext(ctx context.Context, dir string, args ...string) *exec.Cmd {
+	cmd := exec.CommandContext(ctx, "git", args...)
+	cmd.Dir = dir
+	cmd.Env = nonInteractiveEnv()
+	return cmd
+}
 
-		lines := strings.Split(patchStr, "\n")
-		for _, line := range lines {
-			if len(line) == 0 {
-				continue
-			}
-			if strings.HasPrefix(line, "+") && !strings.HasPrefix(line, "+++") {
-				additions++
-			} else if strings.HasPrefix(line, "-") && !strings.HasPrefix(line, "---") {
-				deletions++
-			}
-		}
+// commandError describes a failed git invocation, including its arguments and stderr
+func commandError(cmd *exec.Cmd, err error) error {
+	var exitErr *exec.ExitError
+	if errors.As(err, &exitErr) && len(exitErr.Stderr) > 0 {
+		return fmt.Errorf("%s: %w: %s", strings.Join(cmd.Args, " "), err, strings.TrimSpace(string(exitErr.Stderr)))
+	}
+	return fmt.Errorf("%s: %w", strings.Join(cmd.Args, " "), err)
+}
 
-		files = append(files, FileInfo{
-			Path:      filePath,
-			Status:    status,
-			Additions: additions,
-			Deletions: deletions,
-			Patch:     patchStr,
-		})
+func nonInteractiveEnv() []string {
+	env := append(os.Environ(), "GIT_TERMINAL_PROMPT=0", "GCM_INTERACTIVE=never")
+
+	// Respect a user-provided SSH wrapper, otherwise never prompt for passphrases
+	if os.Getenv("GIT_SSH") == "" && os.Getenv("GIT_SSH_COMMAND") == "" {
+		env = append(env, "GIT_SSH_COMMAND=ssh -o BatchMode=yes")
 	}
 
-	return files, nil
+	return env
 }
 
-// GetUncommittedChanges returns all uncommitted changes (both staged and unstaged)
-func (r *Repo) GetUncommittedChanges() ([]FileInfo, error) {
-	repoPath, err := r.RepoPath()
+// FileAtCommit returns the contents of filePath as of the given commit
+func (r *Repo) FileAtCommit(commit, filePath string) (string, error) {
+	commitObj, err := r.repo.CommitObject(plumbing.NewHash(commit))
 	if err != nil {
-		return nil, err
+		return "", fmt.Errorf("failed to load commit %s: %w", commit, err)
 	}
 
-	wt, err := r.repo.Worktree()
+	file, err := commitObj.File(filePath)
 	if err != nil {
-		return nil, fmt.Errorf("failed to get worktree: %w", err)
+		return "", fmt.Errorf("failed to find %s at commit %s: %w", filePath, commit, err)
 	}
 
-	status, err := wt.Status()
+	contents, err := file.Contents()
 	if err != nil {
-		return nil, fmt.Errorf("failed to get worktree status: %w", err)
+		return "", fmt.Errorf("failed to read %s at commit %s: %w", filePath, commit, err)
 	}
 
-	files := []FileInfo{}
+	return contents, nil
+}
 
-	for filePath, fileStatus := range status {
-		// Check if file has staged changes (index vs HEAD)
-		if fileStatus.Staging != git.Unmodified && fileStatus.Staging != git.Untracked {
-			fileInfo, err := r.getFileInfoWithGitDiff(repoPath, filePath, fileStatus.Staging, StagingStatusStaged)
-			if err == nil {
-				files = append(files, fileInfo)
-			}
+// FileLines returns lines from through to (1-based, inclusive) of filePath,
+// clamped to the bounds of the file, along with the file's total line count.
+// An empty commit reads the file from the working tree.
+func (r *Repo) FileLines(commit, filePath string, from, to int) ([]string, int, error) {
+	var contents string
+	var err error
+	if commit == "" {
+		if !filepath.IsLocal(filePath) {
+			return nil, 0, fmt.Errorf("invalid file path: %s", filePath)
+		}
+		contents, err = r.readWorktreeFile(filePath)
+		if err != nil {
+			return nil, 0, fmt.Errorf("failed to read %s: %w", filePath, err)
 		}
+	} else {
+		contents, err = r.FileAtCommit(commit, filePath)
+		if err != nil {
+			return nil, 0, err
+		}
+	}
 
-		// Check if file has unstaged changes (worktree vs index)
-		if fileStatus.Worktree != git.Unmodified && fileStatus.Worktree != git.Untracked {
-			fileInfo, err := r.getFileInfoWithGitDiff(repoPath, filePath, fileStatus.Worktree, StagingStatusUnstaged)
-			if err == nil {
-				files = append(files, fileInfo)
-			}
+	lines := []string{}
+	if contents != "" {
+		lines = strings.Split(strings.TrimSuffix(contents, "\n"), "\n")
+	}
+
+	start := max(from, 1) - 1
+	end := min(to, len(lines))
+	if start >= end {
+		return []string{}, len(lines), nil
+	}
+	return lines[start:end], len(lines), nil
+}
+
+// BlameLine attributes a line of a file to the commit that last changed it
+type BlameLine struct {
+	Line   int    `json:"line"`
+	Commit string `json:"commit"`
+	Author string `json:"author"`
+	Email  string `json:"email"`
+	Date   int64  `json:"date"` // Unix timestamp of the commit's author date
+	Text   string `json:"text"`
+}
+
+// Blame attributes every line of filePath, as of the given commit, to the
+// commit that last changed it
+func (r *Repo) Blame(commit, filePath string) ([]BlameLine, error) {
+	commitObj, err := r.repo.CommitObject(plumbing.NewHash(commit))
+	if err != nil {
+		return nil, fmt.Errorf("failed to load commit %s: %w", commit, err)
+	}
+
+	if _, err := commitObj.File(filePath); err != nil {
+		if errors.Is(err, object.ErrFileNotFound) {
+			return nil, ErrFileNotInCommit
 		}
+		return nil, fmt.Errorf("failed to find %s at commit %s: %w", filePath, commit, err)
+	}
 
-		// Handle untracked files as unstaged additions
-		if fileStatus.Worktree == git.Untracked {
-			content, err := r.readWorktreeFile(filePath)
-			if err != nil {
-				continue
-			}
-			additions := strings.Count(content, "\n")
-			if len(content) > 0 && !strings.HasSuffix(content, "\n") {
-				additions++
-			}
-			patch := fmt.Sprintf("diff --git a/%s b/%s\nnew file mode 100644\n--- /dev/null\n+++ b/%s\n@@ -0,0 +1,%d @@\n", filePath, filePath, filePath, additions)
-			for _, line := range strings.Split(content, "\n") {
-				if line != "" || !strings.HasSuffix(content, "\n") {
-					patch += "+" + line + "\n"
-				}
-			}
-			files = append(files, FileInfo{
-				Path:          filePath,
-				Status:        "added",
-				Additions:     additions,
-				Deletions:     0,
-				Patch:         patch,
-				StagingStatus: StagingStatusUnstaged,
-			})
+	result, err := git.Blame(commitObj, filePath)
+	if err != nil {
+		return nil, fmt.Errorf("failed to blame %s at commit %s: %w", filePath, commit, err)
+	}
+
+	lines := make([]BlameLine, len(result.Lines))
+	for i, line := range result.Lines {
+		lines[i] = BlameLine{
+			Line:   i + 1,
+			Commit: line.Hash.String(),
+			Author: line.AuthorName,
+			Email:  line.Author,
+			Date:   line.Date.Unix(),
+			Text:   line.Text,
 		}
 	}
 
-	return files, nil
+	return lines, nil
 }
 
-// getFileInfoWithGitDiff uses git diff command for proper unified diff output
-func (r *Repo) getFileInfoWithGitDiff(repoPath, filePath string, statusCode git.StatusCode, stagingStatus StagingStatus) (FileInfo, error) {
-	status := "modified"
-	switch statusCode {
-	case git.Added:
-		status = "added"
-	case git.Deleted:
-		status = "deleted"
-	case git.Renamed:
-		status = "renamed"
-	case git.Copied:
-		status = "added"
+// BranchExists reports whether a local branch with the given name exists
+func (r *Repo) BranchExists(branch string) bool {
+	_, err := r.repo.Reference(plumbing.NewBranchReferenceName(branch), false)
+	return err == nil
+}
+
+// BranchCommit returns the commit a local branch points at, or an empty
+// string if there's no such branch
+func (r *Repo) BranchCommit(branch string) string {
+	ref, err := r.repo.Reference(plumbing.NewBranchReferenceName(branch), true)
+	if err != nil {
+		return ""
 	}
+	return ref.Hash().String()
+}
 
-	// Use git diff command for proper unified diff
-	var cmd *exec.Cmd
-	if stagingStatus == StagingStatusStaged {
-		// Staged changes: compare index to HEAD
-		cmd = exec.Command("git", "diff", "--cached", "--", filePath)
-	} else {
-		// Unstaged changes: compare worktree to index
-		cmd = exec.Command("git", "diff", "--", filePath)
+// CommitExists reports whether the commit is present in the repository
+func (r *Repo) CommitExists(commit string) bool {
+	_, err := r.repo.CommitObject(plumbing.NewHash(commit))
+	return err == nil
+}
+
+// ChangedPathsBetween returns the paths whose content or mode differs between
+// two commits. Renamed files count as changed under both names.
+func (r *Repo) ChangedPathsBetween(from, to string) (map[string]bool, error) {
+	repoPath, err := r.RepoPath()
+	if err != nil {
+		return nil, err
 	}
-	cmd.Dir = repoPath
 
+	cmd := gitCommand(repoPath, "diff-tree", "-r", "-z", "--name-only", "--no-renames", from, to, "--")
 	output, err := cmd.Output()
 	if err != nil {
-		// If git diff fails, return empty patch
-		return FileInfo{
-			Path:          filePath,
-			Status:        status,
-			Additions:     0,
-			Deletions:     0,
-			Patch:         "",
-			StagingStatus: stagingStatus,
-		}, nil
+		return nil, fmt.Errorf("failed to diff %s against %s: %w", to, from, commandError(cmd, err))
 	}
 
-	patch := string(output)
-
-	// Count additions and deletions
-	additions := 0
-	deletions := 0
-	lines := strings.Split(patch, "\n")
-	for _, line := range lines {
-		if len(line) == 0 {
-			continue
-		}
-		if strings.HasPrefix(line, "+") && !strings.HasPrefix(line, "+++") {
-			additions++
-		} else if strings.HasPrefix(line, "-") && !strings.HasPrefix(line, "---") {
-			deletions++
+	changed := make(map[string]bool)
+	for _, filePath := range strings.Split(string(output), "\x00") {
+		if filePath != "" {
+			changed[filePath] = true
 		}
 	}
-
-	return FileInfo{
-		Path:          filePath,
-		Status:        status,
-		Additions:     additions,
-		Deletions:     deletions,
-		Patch:         patch,
-		StagingStatus: stagingStatus,
-	}, nil
+	return changed, nil
 }
 
-func (r *Repo) readWorktreeFile(filePath string) (string, error) {
-	wt, err := r.repo.Worktree()
+// CommitChangedPaths resolves rev to a commit in HEAD's history and returns its
+// full hash along with the paths it changed relative to its first parent.
+// Paths are reported the same way as GetDiffFiles: the new name for renames.
+func (r *Repo) CommitChangedPaths(rev string) (string, []string, error) {
+	commitObj, err := r.historyCommit(rev)
 	if err != nil {
-		return "", err
+		return "", nil, err
 	}
+	hash := commitObj.Hash
 
-	fullPath := filepath.Join(wt.Filesystem.Root(), filePath)
-	content, err := os.ReadFile(fullPath)
+	tree, err := commitObj.Tree()
 	if err != nil {
-		return "", err
+		return "", nil, fmt.Errorf("failed to load tree of commit %s: %w", hash, err)
 	}
-	return string(content), nil
+
+	// Root commits are diffed against an empty tree
+	parentTree := &object.Tree{}
+	if commitObj.NumParents() > 0 {
+		parent, err := commitObj.Parent(0)
+		if err != nil {
+			return "", nil, fmt.Errorf("failed to load parent of commit %s: %w", hash, err)
+		}
+
+		parentTree, err = parent.Tree()
+		if err != nil {
+			return "", nil, fmt.Errorf("failed to load tree of commit %s: %w", parent.Hash, err)
+		}
+	}
+
+	changes, err := parentTree.Diff(tree)
+	if err != nil {
+		return "", nil, fmt.Errorf("failed to diff commit %s against its parent: %w", hash, err)
+	}
+
+	paths := make([]string, 0, len(changes))
+	for _, change := range changes {
+		filePath := change.To.Name
+		if filePath == "" {
+			filePath = change.From.Name
+		}
+		paths = append(paths, filePath)
+	}
+
+	return commitObj.Hash.String(), paths, nil
+}
+
+// historyCommit resolves rev to a commit, failing with ErrCommitNotInHistory
+// unless it's HEAD or one of its ancestors
+func (r *Repo) historyCommit(rev string) (*object.Commit, error) {
+	hash, err := r.repo.ResolveRevision(plumbing.Revision(rev))
+	if err != nil {
+		return nil, fmt.Errorf("failed to resolve commit '%s': %w", rev, ErrCommitNotInHistory)
+	}
+
+	commitObj, err := r.repo.CommitObject(*hash)
+	if err != nil {
+		return nil, fmt.Errorf("failed to load commit %s: %w", hash, err)
+	}
+
+	head, err := r.repo.Head()
+	if err != nil {
+		return nil, fmt.Errorf("failed to resolve HEAD: %w", err)
+	}
+
+	if commitObj.Hash != head.Hash() {
+		headCommit, err := r.repo.CommitObject(head.Hash())
+		if err != nil {
+			return nil, fmt.Errorf("failed to load HEAD commit %s: %w", head.Hash(), err)
+		}
+
+		isAncestor, err := commitObj.IsAncestor(headCommit)
+		if err != nil {
+			return nil, fmt.Errorf("failed to check ancestry of commit %s: %w", hash, err)
+		}
+		if !isAncestor {
+			return nil, fmt.Errorf("commit '%s': %w", rev, ErrCommitNotInHistory)
+		}
+	}
+
+	return commitObj, nil
+}
+
+// emptyTreeHash is the id of the empty tree, which root commits are diffed against
+const emptyTreeHash = "4b825dc642cb6eb9a060e54bf8d69288fbee4904"
+
+// CommitDiff is the change a single commit made
+type CommitDiff struct {
+	Commit  string
+	Parents []string
+	// Combined is set for merge commits. Their files are a combined diff, as
+	// git show --cc prints it, with one prefix column per parent, and only
+	// list files whose merge result differs from every parent.
+	Combined bool
+	Files    []FileInfo
+}
+
+// GetCommitDiff resolves rev to a commit in HEAD's history and diffs it
+// against its parent. Merge commits get a combined diff against all their
+// parents instead, so conflict resolutions aren't hidden by picking one side.
+func (r *Repo) GetCommitDiff(ctx context.Context, rev string, opts DiffOptions) (*CommitDiff, error) {
+	if err := ValidateDiffAlgorithm(opts.Algorithm); err != nil {
+		return nil, err
+	}
+	if err := ValidateDiffBackend(opts.Backend); err != nil {
+		return nil, err
+	}
+	if err := ValidatePathspec(opts.Pathspec); err != nil {
+		return nil, err
+	}
+
+	commitObj, err := r.historyCommit(rev)
+	if err != nil {
+		return nil, err
+	}
+
+	result := &CommitDiff{Commit: commitObj.Hash.String(), Parents: []string{}}
+	for _, parent := range commitObj.ParentHashes {
+		result.Parents = append(result.Parents, parent.String())
+	}
+
+	if commitObj.NumParents() > 1 {
+		result.Combined = true
+		result.Files, err = r.combinedDiffFiles(ctx, result.Commit, opts)
+		if err != nil {
+			return nil, err
+		}
+		return result, nil
+	}
+
+	// Root commits are diffed against an empty tree
+	parentHash := plumbing.NewHash(emptyTreeHash)
+	parentTree := &object.Tree{}
+	if commitObj.NumParents() == 1 {
+		parent, err := commitObj.Parent(0)
+		if err != nil {
+			return nil, fmt.Errorf("failed to load parent of commit %s: %w", commitObj.Hash, err)
+		}
+		parentTree, err = parent.Tree()
+		if err != nil {
+			return nil, fmt.Errorf("failed to load tree of commit %s: %w", parent.Hash, err)
+		}
+		parentHash = parent.Hash
+	}
+
+	result.Files, err = r.diffCommits(ctx, parentHash, parentTree, commitObj, opts)
+	if err != nil {
+		return nil, err
+	}
+	return result, nil
+}
+
+// combinedDiffFiles runs git's combined diff of a merge commit against all its
+// parents and splits it into files. go-git can't produce combined diffs, so
+// this always uses the git CLI.
+func (r *Repo) combinedDiffFiles(ctx context.Context, commit string, opts DiffOptions) ([]FileInfo, error) {
+	repoPath, err := r.RepoPath()
+	if err != nil {
+		return nil, err
+	}
+
+	args := []string{"-c", "core.quotePath=false", "diff-tree", "--cc", "--no-commit-id", "--no-color", "--no-ext-diff", textconvFlag(opts.Textconv)}
+	if opts.Algorithm != "" {
+		args = append(args, "--diff-algorithm="+opts.Algorithm)
+	}
+	args = append(args, commit, "--")
+	args = append(args, opts.Pathspec...)
+
+	cmd := gitCommandContext(ctx, repoPath, args...)
+	output, err := cmd.Output()
+	if err != nil {
+		if ctx.Err() != nil {
+			return nil, ctx.Err()
+		}
+		return nil, fmt.Errorf("failed to build combined diff of merge %s: %w", Abbreviate(commit, 7), commandError(cmd, err))
+	}
+
+	return ParseCombinedPatch(string(output)), nil
+}
+
+// GetRemoteURL returns the URL of the origin remote, or empty string if not found
+func (r *Repo) GetRemoteURL() (string, error) {
+	remote, err := r.repo.Remote("origin")
+	if err != nil {
+		// No origin remote, return empty string
+		return "", nil
+	}
+
+	if len(remote.Config().URLs) == 0 {
+		return "", nil
+	}
+
+	return remote.Config().URLs[0], nil
+}
+
+func (r *Repo) GetDiffFiles(baseBranch string) ([]FileInfo, error) {
+	return r.GetDiffFilesWithOptions(baseBranch, DiffOptions{})
+}
+
+// AheadBehind returns how many commits HEAD is ahead of and behind the base
+// branch, resolved the same way as GetDiffFiles
+func (r *Repo) AheadBehind(baseBranch string) (int, int, error) {
+	baseRef, _, err := r.resolveBaseRef(baseBranch)
+	if err != nil {
+		return 0, 0, err
+	}
+
+	repoPath, err := r.RepoPath()
+	if err != nil {
+		return 0, 0, err
+	}
+
+	cmd := gitCommand(repoPath, "rev-list", "--left-right", "--count", baseRef+"...HEAD")
+	output, err := cmd.Output()
+	if err != nil {
+		return 0, 0, fmt.Errorf("failed to count commits against base ref '%s': %w", baseBranch, commandError(cmd, err))
+	}
+
+	fields := strings.Fields(string(output))
+	if len(fields) != 2 {
+		return 0, 0, fmt.Errorf("unexpected rev-list output: %q", strings.TrimSpace(string(output)))
+	}
+
+	behind, err := strconv.Atoi(fields[0])
+	if err != nil {
+		return 0, 0, fmt.Errorf("unexpected rev-list output: %q", strings.TrimSpace(string(output)))
+	}
+	ahead, err := strconv.Atoi(fields[1])
+	if err != nil {
+		return 0, 0, fmt.Errorf("unexpected rev-list output: %q", strings.TrimSpace(string(output)))
+	}
+
+	return ahead, behind, nil
+}
+
+// ChangedPaths lists the paths the branch changed since it diverged from the
+// base branch. It only asks git for names, so it's much cheaper than
+// GetDiffFiles when the patches aren't needed.
+func (r *Repo) ChangedPaths(baseBranch string) ([]string, error) {
+	baseRef, _, err := r.resolveBaseRef(baseBranch)
+	if err != nil {
+		return nil, err
+	}
+
+	repoPath, err := r.RepoPath()
+	if err != nil {
+		return nil, err
+	}
+
+	// Three dots diff against the merge base, as GetDiffFiles does
+	cmd := gitCommand(repoPath, "diff", "--name-only", "-z", baseRef+"...HEAD", "--")
+	output, err := cmd.Output()
+	if err != nil {
+		return nil, fmt.Errorf("failed to list paths changed against base ref '%s': %w", baseBranch, commandError(cmd, err))
+	}
+
+	paths := []string{}
+	for _, filePath := range strings.Split(string(output), "\x00") {
+		if filePath != "" {
+			paths = append(paths, filePath)
+		}
+	}
+	return paths, nil
+}
+
+// BaseCommit is a commit on the base branch that the current branch doesn't have yet
+type BaseCommit struct {
+	Hash    string `json:"hash"`
+	Author  string `json:"author"`
+	Date    int64  `json:"date"`
+	Subject string `json:"subject"`
+}
+
+// BehindCommits lists the commits that landed on the base branch after the
+// current branch diverged from it (HEAD..base), newest first. A positive limit
+// caps how many are returned.
+func (r *Repo) BehindCommits(baseBranch string, limit int) ([]BaseCommit, error) {
+	baseRef, _, err := r.resolveBaseRef(baseBranch)
+	if err != nil {
+		return nil, err
+	}
+
+	repoPath, err := r.RepoPath()
+	if err != nil {
+		return nil, err
+	}
+
+	args := []string{"log", "--format=%H%x00%an%x00%at%x00%s"}
+	if limit > 0 {
+		args = append(args, fmt.Sprintf("--max-count=%d", limit))
+	}
+	args = append(args, "HEAD.."+baseRef, "--")
+
+	cmd := gitCommand(repoPath, args...)
+	output, err := cmd.Output()
+	if err != nil {
+		return nil, fmt.Errorf("failed to list commits on base ref '%s': %w", baseBranch, commandError(cmd, err))
+	}
+
+	commits := []BaseCommit{}
+	for _, line := range strings.Split(strings.TrimSpace(string(output)), "\n") {
+		if line == "" {
+			continue
+		}
+		fields := strings.SplitN(line, "\x00", 4)
+		if len(fields) != 4 {
+			return nil, fmt.Errorf("unexpected log output: %q", line)
+		}
+		date, err := strconv.ParseInt(fields[2], 10, 64)
+		if err != nil {
+			return nil, fmt.Errorf("unexpected log output: %q", line)
+		}
+		commits = append(commits, BaseCommit{
+			Hash:    fields[0],
+			Author:  fields[1],
+			Date:    date,
+			Subject: fields[3],
+		})
+	}
+
+	return commits, nil
+}
+
+// BaseTip returns the commit the base branch currently points to, resolved the
+// same way as GetDiffFiles
+func (r *Repo) BaseTip(baseBranch string) (string, error) {
+	_, baseHash, err := r.resolveBaseRef(baseBranch)
+	if err != nil {
+		return "", err
+	}
+	return baseHash.String(), nil
+}
+
+// CountCommits returns how many commits are reachable from to but not from from
+func (r *Repo) CountCommits(from, to string) (int, error) {
+	repoPath, err := r.RepoPath()
+	if err != nil {
+		return 0, err
+	}
+
+	cmd := gitCommand(repoPath, "rev-list", "--count", from+".."+to)
+	output, err := cmd.Output()
+	if err != nil {
+		return 0, fmt.Errorf("failed to count commits between %s and %s: %w", Abbreviate(from, 7), Abbreviate(to, 7), commandError(cmd, err))
+	}
+
+	count, err := strconv.Atoi(strings.TrimSpace(string(output)))
+	if err != nil {
+		return 0, fmt.Errorf("unexpected rev-list output: %q", strings.TrimSpace(string(output)))
+	}
+	return count, nil
+}
+
+// resolveBaseRef returns a revision for the base to pass to git, and the
+// commit it points to. It prefers the remote tracking branch
+// origin/<baseBranch>, so the comparison is against the remote even if the
+// local branch is outdated, then the local branch, and finally takes the base
+// as any revision git understands, such as a tag, a commit, origin/main or
+// HEAD~5.
+func (r *Repo) resolveBaseRef(baseBranch string) (string, plumbing.Hash, error) {
+	for _, name := range []plumbing.ReferenceName{
+		plumbing.NewRemoteReferenceName("origin", baseBranch),
+		plumbing.NewBranchReferenceName(baseBranch),
+	} {
+		if ref, err := r.repo.Reference(name, true); err == nil {
+			return name.String(), ref.Hash(), nil
+		}
+	}
+
+	hash, err := r.resolveCommitRevision(baseBranch)
+	if err != nil {
+		return "", plumbing.ZeroHash, fmt.Errorf("failed to resolve base ref '%s' (tried origin/%s, %s and it as a revision): %w", baseBranch, baseBranch, baseBranch, err)
+	}
+	return hash.String(), hash, nil
+}
+
+// resolveCommitRevision resolves a revision to the commit it names, peeling
+// annotated tags, using git's own revision parsing
+func (r *Repo) resolveCommitRevision(revision string) (plumbing.Hash, error) {
+	// Don't let a revision be taken for an option
+	if revision == "" || strings.HasPrefix(revision, "-") {
+		return plumbing.ZeroHash, fmt.Errorf("invalid revision '%s'", revision)
+	}
+
+	repoPath, err := r.RepoPath()
+	if err != nil {
+		return plumbing.ZeroHash, err
+	}
+
+	cmd := gitCommand(repoPath, "rev-parse", "--verify", "--quiet", revision+"^{commit}")
+	output, err := cmd.Output()
+	if err != nil {
+		return plumbing.ZeroHash, fmt.Errorf("unknown revision '%s'", revision)
+	}
+	return plumbing.NewHash(strings.TrimSpace(string(output))), nil
+}
+
+// ConfiguredDiffAlgorithm returns git's diff.algorithm setting for the
+// repository, or an empty string if it isn't set
+func (r *Repo) ConfiguredDiffAlgorithm() string {
+	repoPath, err := r.RepoPath()
+	if err != nil {
+		return ""
+	}
+
+	output, err := gitCommand(repoPath, "config", "--get", "diff.algorithm").Output()
+	if err != nil {
+		return ""
+	}
+
+	// git accepts "default" as an alias for myers
+	algorithm := strings.TrimSpace(string(output))
+	if algorithm == "default" {
+		return "myers"
+	}
+	return algorithm
+}
+
+// GetDiffFilesWithOptions is GetDiffFiles with control over patch generation.
+// Non-default algorithms are delegated to the git CLI, which implements them.
+func (r *Repo) GetDiffFilesWithOptions(baseBranch string, opts DiffOptions) ([]FileInfo, error) {
+	return r.GetDiffFilesContext(context.Background(), baseBranch, opts)
+}
+
+// GetDiffFilesContext is GetDiffFilesWithOptions that stops early and returns
+// ctx.Err() once ctx is cancelled, so an abandoned diff doesn't keep running
+func (r *Repo) GetDiffFilesContext(ctx context.Context, baseBranch string, opts DiffOptions) ([]FileInfo, error) {
+	if err := ValidateDiffAlgorithm(opts.Algorithm); err != nil {
+		return nil, err
+	}
+	if err := ValidateDiffBackend(opts.Backend); err != nil {
+		return nil, err
+	}
+	if err := ValidatePathspec(opts.Pathspec); err != nil {
+		return nil, err
+	}
+
+	_, baseRefHash, err := r.resolveBaseRef(baseBranch)
+	if err != nil {
+		return nil, err
+	}
+	baseCommit, err := r.repo.CommitObject(baseRefHash)
+	if err != nil {
+		return nil, fmt.Errorf("failed to load commit %s for base ref '%s': %w", baseRefHash, baseBranch, err)
+	}
+
+	// Get the current HEAD commit
+	head, err := r.repo.Head()
+	if err != nil {
+		return nil, fmt.Errorf("failed to resolve HEAD for diff: %w", err)
+	}
+
+	headCommit, err := r.repo.CommitObject(head.Hash())
+	if err != nil {
+		return nil, fmt.Errorf("failed to load HEAD commit %s: %w", head.Hash(), err)
+	}
+
+	// Find the merge base between base branch and HEAD
+	mergeBase, err := headCommit.MergeBase(baseCommit)
+	if err != nil {
+		return nil, fmt.Errorf("failed to find merge base between HEAD and base ref '%s': %w", baseBranch, err)
+	}
+
+	// Use the merge base as the comparison point
+	var baseTree *object.Tree
+	baseHash := baseCommit.Hash
+	if len(mergeBase) > 0 {
+		baseHash = mergeBase[0].Hash
+		baseTree, err = mergeBase[0].Tree()
+		if err != nil {
+			return nil, fmt.Errorf("failed to load tree of merge base %s: %w", mergeBase[0].Hash, err)
+		}
+	} else {
+		// Fallback to base branch if no merge base found
+		baseTree, err = baseCommit.Tree()
+		if err != nil {
+			return nil, fmt.Errorf("failed to load tree of base ref '%s': %w", baseBranch, err)
+		}
+	}
+
+	return r.diffCommits(ctx, baseHash, baseTree, headCommit, opts)
+}
+
+// GetDiffSince diffs a previously reviewed commit against HEAD, so a review
+// can pick up where it left off. Unlike GetDiffFiles it compares the two
+// commits directly rather than through their merge base.
+func (r *Repo) GetDiffSince(ctx context.Context, since string, opts DiffOptions) ([]FileInfo, error) {
+	if err := ValidateDiffAlgorithm(opts.Algorithm); err != nil {
+		return nil, err
+	}
+	if err := ValidateDiffBackend(opts.Backend); err != nil {
+		return nil, err
+	}
+	if err := ValidatePathspec(opts.Pathspec); err != nil {
+		return nil, err
+	}
+
+	sinceHash, err := r.repo.ResolveRevision(plumbing.Revision(since))
+	if err != nil {
+		return nil, fmt.Errorf("failed to resolve commit '%s': %w", since, ErrCommitNotInHistory)
+	}
+	sinceCommit, err := r.repo.CommitObject(*sinceHash)
+	if err != nil {
+		return nil, fmt.Errorf("failed to load commit %s: %w", since, err)
+	}
+	sinceTree, err := sinceCommit.Tree()
+	if err != nil {
+		return nil, fmt.Errorf("failed to load tree of commit %s: %w", since, err)
+	}
+
+	head, err := r.repo.Head()
+	if err != nil {
+		return nil, fmt.Errorf("failed to resolve HEAD for diff: %w", err)
+	}
+	headCommit, err := r.repo.CommitObject(head.Hash())
+	if err != nil {
+		return nil, fmt.Errorf("failed to load HEAD commit %s: %w", head.Hash(), err)
+	}
+
+	return r.diffCommits(ctx, sinceCommit.Hash, sinceTree, headCommit, opts)
+}
+
+// diffCommits diffs HEAD's commit against a base commit and its tree
+func (r *Repo) diffCommits(ctx context.Context, baseHash plumbing.Hash, baseTree *object.Tree, headCommit *object.Commit, opts DiffOptions) ([]FileInfo, error) {
+	if opts.Backend == DiffBackendCLI || len(opts.Pathspec) > 0 {
+		return r.cliDiffFiles(ctx, baseHash.String(), headCommit.Hash.String(), opts)
+	}
+
+	headTree, err := headCommit.Tree()
+	if err != nil {
+		return nil, fmt.Errorf("failed to load tree of HEAD commit %s: %w", headCommit.Hash, err)
+	}
+
+	// Get the diff
+	changes, err := baseTree.DiffContext(ctx, headTree)
+	if err != nil {
+		if ctx.Err() != nil {
+			return nil, ctx.Err()
+		}
+		return nil, fmt.Errorf("failed to diff HEAD against %s: %w", Abbreviate(baseHash.String(), 7), err)
+	}
+
+	var textconv map[string]bool
+	if opts.Textconv {
+		textconv, err = r.textconvPaths(ctx, changes)
+		if err != nil {
+			return nil, err
+		}
+	}
+
+	// go-git has no diff algorithms to pick from and doesn't run textconv
+	// filters, so those patches come from a single git diff
+	var cliFiles map[string]FileInfo
+	if opts.Algorithm != "" || len(textconv) > 0 {
+		cliFiles, err = r.cliPatches(ctx, baseHash.String(), headCommit.Hash.String(), opts, changes, textconv)
+		if err != nil {
+			return nil, err
+		}
+	}
+
+	files := []FileInfo{}
+
+	for _, change := range changes {
+		if err := ctx.Err(); err != nil {
+			return nil, err
+		}
+
+		if opts.RecurseSubmodules && change.From.TreeEntry.Mode == filemode.Submodule && change.To.TreeEntry.Mode == filemode.Submodule {
+			if submoduleFiles, ok := r.submoduleDiffFiles(change.To.Name, change.From.TreeEntry.Hash, change.To.TreeEntry.Hash); ok {
+				files = append(files, submoduleFiles...)
+				continue
+			}
+		}
+
+		patch, err := change.PatchContext(ctx)
+		if err != nil {
+			if ctx.Err() != nil {
+				return nil, ctx.Err()
+			}
+			continue
+		}
+
+		filePath := change.To.Name
+		if filePath == "" {
+			filePath = change.From.Name
+		}
+
+		status := changeStatus(change)
+
+		oldPath := ""
+		if status == "renamed" {
+			// Includes case-only renames, whose paths differ only in case
+			oldPath = change.From.Name
+		}
+
+		patchStr := patch.String()
+		// git may pair renamed files up differently; those keep go-git's patch
+		if cli, ok := cliFiles[filePath]; ok && cli.OldPath == oldPath {
+			patchStr = cli.Patch
+		}
+
+		// Count additions and deletions from the patch string
+		additions, deletions := countChanges(patchStr)
+
+		files = append(files, FileInfo{
+			Path:      filePath,
+			OldPath:   oldPath,
+			Status:    status,
+			Kind:      changeKind(change),
+			Additions: additions,
+			Deletions: deletions,
+			Patch:     patchStr,
+		})
+	}
+
+	return files, nil
+}
+
+// changeStatus describes a tree change as added, deleted, renamed or modified
+func changeStatus(change *object.Change) string {
+	switch {
+	case change.From.Name == "":
+		return "added"
+	case change.To.Name == "":
+		return "deleted"
+	case change.From.Name != change.To.Name:
+		return "renamed"
+	}
+	return "modified"
+}
+
+// submoduleDiffFiles diffs the submodule at path between two of its commits,
+// prefixing file paths with the submodule path. It reports false when the
+// submodule isn't initialized or lacks either commit, in which case the caller
+// shows the pointer change instead. Nested submodules aren't expanded.
+func (r *Repo) submoduleDiffFiles(path string, from, to plumbing.Hash) ([]FileInfo, bool) {
+	repoPath, err := r.RepoPath()
+	if err != nil {
+		return nil, false
+	}
+
+	submodule, err := git.PlainOpen(filepath.Join(repoPath, path))
+	if err != nil {
+		return nil, false
+	}
+
+	fromCommit, err := submodule.CommitObject(from)
+	if err != nil {
+		return nil, false
+	}
+	toCommit, err := submodule.CommitObject(to)
+	if err != nil {
+		return nil, false
+	}
+
+	fromTree, err := fromCommit.Tree()
+	if err != nil {
+		return nil, false
+	}
+	toTree, err := toCommit.Tree()
+	if err != nil {
+		return nil, false
+	}
+
+	changes, err := fromTree.Diff(toTree)
+	if err != nil {
+		return nil, false
+	}
+
+	files := []FileInfo{}
+	for _, change := range changes {
+		patch, err := change.Patch()
+		if err != nil {
+			continue
+		}
+
+		filePath := change.To.Name
+		if filePath == "" {
+			filePath = change.From.Name
+		}
+
+		patchStr := prefixPatchPaths(patch.String(), path)
+		additions, deletions := countChanges(patchStr)
+
+		files = append(files, FileInfo{
+			Path:      path + "/" + filePath,
+			Status:    changeStatus(change),
+			Kind:      changeKind(change),
+			Additions: additions,
+			Deletions: deletions,
+			Patch:     patchStr,
+		})
+	}
+
+	return files, true
+}
+
+// prefixPatchPaths rewrites the file headers of a patch so paths are relative
+// to the superproject rather than the submodule at prefix
+func prefixPatchPaths(patch, prefix string) string {
+	lines := strings.Split(patch, "\n")
+	for i, line := range lines {
+		switch {
+		case strings.HasPrefix(line, "diff --git "):
+			line = strings.Replace(line, " a/", " a/"+prefix+"/", 1)
+			lines[i] = strings.Replace(line, " b/", " b/"+prefix+"/", 1)
+		case strings.HasPrefix(line, "--- a/"):
+			lines[i] = "--- a/" + prefix + "/" + strings.TrimPrefix(line, "--- a/")
+		case strings.HasPrefix(line, "+++ b/"):
+			lines[i] = "+++ b/" + prefix + "/" + strings.TrimPrefix(line, "+++ b/")
+		}
+	}
+	return strings.Join(lines, "\n")
+}
+
+// cliPatches diffs two commits with the git CLI in a single run, detecting
+// renames as go-git does, and returns the changed files by path. Without a
+// diff algorithm, only the changes to the paths in textconv are diffed.
+func (r *Repo) cliPatches(ctx context.Context, fromCommit, toCommit string, opts DiffOptions, changes object.Changes, textconv map[string]bool) (map[string]FileInfo, error) {
+	cliOpts := DiffOptions{Algorithm: opts.Algorithm, Textconv: opts.Textconv}
+	if opts.Algorithm == "" {
+		for _, change := range changes {
+			filePath := change.To.Name
+			if filePath == "" {
+				filePath = change.From.Name
+			}
+			if !textconv[filePath] {
+				continue
+			}
+			// Both sides, so git can tell the file was renamed
+			for _, name := range []string{change.From.Name, change.To.Name} {
+				if name != "" {
+					cliOpts.Pathspec = append(cliOpts.Pathspec, ":(literal)"+name)
+				}
+			}
+		}
+	}
+
+	files, err := r.cliDiffFiles(ctx, fromCommit, toCommit, cliOpts)
+	if err != nil {
+		return nil, err
+	}
+	patches := make(map[string]FileInfo, len(files))
+	for _, file := range files {
+		patches[file.Path] = file
+	}
+	return patches, nil
+}
+
+// textconvFlag returns the git diff flag that turns textconv filters on or
+// off. git applies them by default, so they're turned off explicitly.
+func textconvFlag(textconv bool) string {
+	if textconv {
+		return "--textconv"
+	}
+	return "--no-textconv"
+}
+
+// textconvPaths returns the paths among the changed files whose diff
+// attribute names a driver with a textconv filter configured
+func (r *Repo) textconvPaths(ctx context.Context, changes object.Changes) (map[string]bool, error) {
+	repoPath, err := r.RepoPath()
+	if err != nil {
+		return nil, err
+	}
+
+	// Paths go through stdin, since a large diff could exceed the argument limit
+	var input strings.Builder
+	for _, change := range changes {
+		filePath := change.To.Name
+		if filePath == "" {
+			filePath = change.From.Name
+		}
+		input.WriteString(filePath + "\x00")
+	}
+
+	cmd := gitCommandContext(ctx, repoPath, "check-attr", "-z", "--stdin", "diff")
+	cmd.Stdin = strings.NewReader(input.String())
+	output, err := cmd.Output()
+	if err != nil {
+		if ctx.Err() != nil {
+			return nil, ctx.Err()
+		}
+		return nil, fmt.Errorf("failed to read diff attributes: %w", commandError(cmd, err))
+	}
+
+	paths := map[string]bool{}
+	hasTextconv := map[string]bool{}
+	// With -z, entries are NUL-separated "<path>\0diff\0<value>" triples
+	fields := strings.Split(strings.TrimSuffix(string(output), "\x00"), "\x00")
+	for i := 0; i+2 < len(fields); i += 3 {
+		filePath, driver := fields[i], fields[i+2]
+		switch driver {
+		case "unspecified", "unset", "set":
+			continue
+		}
+
+		configured, checked := hasTextconv[driver]
+		if !checked {
+			cmd := gitCommandContext(ctx, repoPath, "config", "--get", "diff."+driver+".textconv")
+			value, _ := cmd.Output() // Exits non-zero when the driver has no textconv
+			configured = strings.TrimSpace(string(value)) != ""
+			hasTextconv[driver] = configured
+		}
+		if configured {
+			paths[filePath] = true
+		}
+	}
+
+	return paths, nil
+}
+
+// cliDiffFiles diffs two commits with the git CLI and splits its output into
+// files. The options are passed on as git diff flags.
+func (r *Repo) cliDiffFiles(ctx context.Context, fromCommit, toCommit string, opts DiffOptions) ([]FileInfo, error) {
+	repoPath, err := r.RepoPath()
+	if err != nil {
+		return nil, err
+	}
+
+	// Unquoted paths, so non-ASCII file names come through as they are
+	args := []string{"-c", "core.quotePath=false", "diff", "--no-color", "--no-ext-diff", "-M", textconvFlag(opts.Textconv)}
+	if opts.Algorithm != "" {
+		args = append(args, "--diff-algorithm="+opts.Algorithm)
+	}
+	if opts.RecurseSubmodules {
+		args = append(args, "--submodule=diff")
+	}
+	args = append(args, fromCommit, toCommit, "--")
+	args = append(args, opts.Pathspec...)
+
+	cmd := gitCommandContext(ctx, repoPath, args...)
+	output, err := cmd.Output()
+	if err != nil {
+		if ctx.Err() != nil {
+			return nil, ctx.Err()
+		}
+		return nil, fmt.Errorf("failed to diff %s..%s with git: %w", Abbreviate(fromCommit, 7), Abbreviate(toCommit, 7), commandError(cmd, err))
+	}
+
+	files, err := ParsePatch(string(output))
+	if errors.Is(err, ErrEmptyPatch) {
+		return []FileInfo{}, nil
+	}
+	if err != nil {
+		return nil, fmt.Errorf("failed to parse git diff output: %w", err)
+	}
+	return files, nil
+}
+
+// IsDirty reports whether the working tree has staged or unstaged
+// modifications to tracked files. Untracked files don't count, and bare
+// repositories are never dirty.
+func (r *Repo) IsDirty() (bool, error) {
+	if r.IsBare() {
+		return false, nil
+	}
+
+	wt, err := r.worktree()
+	if err != nil {
+		return false, err
+	}
+
+	status, err := wt.Status()
+	if err != nil {
+		return false, fmt.Errorf("failed to get worktree status: %w", err)
+	}
+
+	for _, fileStatus := range status {
+		if fileStatus.Staging == git.Untracked && fileStatus.Worktree == git.Untracked {
+			continue
+		}
+		if fileStatus.Staging != git.Unmodified || fileStatus.Worktree != git.Unmodified {
+			return true, nil
+		}
+	}
+
+	return false, nil
+}
+
+// UncommittedPathsMatching returns the paths with staged, unstaged or
+// untracked changes that the pathspecs match, so GetUncommittedChanges,
+// which go-git computes, can be narrowed the way git would
+func (r *Repo) UncommittedPathsMatching(pathspec []string) (map[string]bool, error) {
+	if err := ValidatePathspec(pathspec); err != nil {
+		return nil, err
+	}
+	repoPath, err := r.RepoPath()
+	if err != nil {
+		return nil, err
+	}
+
+	paths := map[string]bool{}
+	for _, args := range [][]string{
+		{"diff", "--cached", "--name-only", "--no-renames", "-z"},
+		{"diff", "--name-only", "--no-renames", "-z"},
+		{"ls-files", "--others", "--exclude-standard", "-z"},
+	} {
+		cmd := gitCommand(repoPath, append(append(args, "--"), pathspec...)...)
+		output, err := cmd.Output()
+		if err != nil {
+			return nil, fmt.Errorf("failed to match uncommitted changes against pathspec: %w", commandError(cmd, err))
+		}
+		for _, filePath := range strings.Split(string(output), "\x00") {
+			if filePath != "" {
+				paths[filePath] = true
+			}
+		}
+	}
+	return paths, nil
+}
+
+// GetUncommittedChanges returns all uncommitted changes (both staged and unstaged)
+func (r *Repo) GetUncommittedChanges() ([]FileInfo, error) {
+	return r.GetUncommittedChangesWithOptions(DiffOptions{})
+}
+
+// GetUncommittedChangesWithOptions is GetUncommittedChanges with the diff
+// algorithm of opts. Uncommitted changes are always diffed with the git CLI,
+// so the other options don't apply.
+func (r *Repo) GetUncommittedChangesWithOptions(opts DiffOptions) ([]FileInfo, error) {
+	if err := ValidateDiffAlgorithm(opts.Algorithm); err != nil {
+		return nil, err
+	}
+
+	repoPath, err := r.RepoPath()
+	if err != nil {
+		return nil, err
+	}
+
+	wt, err := r.worktree()
+	if err != nil {
+		return nil, err
+	}
+
+	status, err := wt.Status()
+	if err != nil {
+		return nil, fmt.Errorf("failed to get worktree status: %w", err)
+	}
+
+	// The staged and the unstaged patches each come from a single git diff
+	staged, err := diffPatches(repoPath, opts.Algorithm, "--cached", "--")
+	if err != nil {
+		return nil, fmt.Errorf("failed to diff the index against HEAD: %w", err)
+	}
+	unstaged, err := diffPatches(repoPath, opts.Algorithm, "--")
+	if err != nil {
+		return nil, fmt.Errorf("failed to diff the working tree against the index: %w", err)
+	}
+
+	files := []FileInfo{}
+
+	for filePath, fileStatus := range status {
+		// Check if file has staged changes (index vs HEAD)
+		if fileStatus.Staging != git.Unmodified && fileStatus.Staging != git.Untracked {
+			files = append(files, uncommittedFileInfo(filePath, fileStatus.Staging, StagingStatusStaged, staged[filePath]))
+		}
+
+		// Check if file has unstaged changes (worktree vs index)
+		if fileStatus.Worktree != git.Unmodified && fileStatus.Worktree != git.Untracked {
+			files = append(files, uncommittedFileInfo(filePath, fileStatus.Worktree, StagingStatusUnstaged, unstaged[filePath]))
+		}
+
+		// Handle untracked files as unstaged additions
+		if fileStatus.Worktree == git.Untracked {
+			fileInfo, err := r.untrackedFileInfo(filePath)
+			if err != nil {
+				continue
+			}
+			fileInfo.StagingStatus = StagingStatusUnstaged
+			files = append(files, fileInfo)
+		}
+	}
+
+	return files, nil
+}
+
+// ChangeFingerprint identifies HEAD together with the state of the index and
+// working tree, so a poller can cheaply tell when the diffs under review may
+// have changed. File contents aren't read: changed files are told apart by
+// their size and modification time. Bare repositories only report HEAD.
+func (r *Repo) ChangeFingerprint() (string, error) {
+	head, err := r.CurrentCommit()
+	if err != nil {
+		return "", err
+	}
+	if r.IsBare() {
+		return head, nil
+	}
+
+	repoPath, err := r.RepoPath()
+	if err != nil {
+		return "", err
+	}
+
+	cmd := gitCommand(repoPath, "status", "--porcelain=v1", "-z", "--no-renames", "--untracked-files=all")
+	// Don't take the index lock to refresh it, which could fail the user's own git commands
+	cmd.Env = append(cmd.Env, "GIT_OPTIONAL_LOCKS=0")
+	output, err := cmd.Output()
+	if err != nil {
+		return "", fmt.Errorf("failed to read working tree status: %w", commandError(cmd, err))
+	}
+
+	h := fnv.New64a()
+	_, _ = h.Write([]byte(head))
+	_, _ = h.Write(output)
+	// With -z and no renames, entries are NUL-terminated "XY <path>"
+	for _, entry := range strings.Split(string(output), "\x00") {
+		if len(entry) < 4 {
+			continue
+		}
+		info, err := os.Lstat(filepath.Join(repoPath, entry[3:]))
+		if err != nil {
+			continue // Deleted, which the status already says
+		}
+		_, _ = fmt.Fprintf(h, "%s\x00%d\x00%d\x00", entry[3:], info.Size(), info.ModTime().UnixNano())
+	}
+	return fmt.Sprintf("%016x", h.Sum64()), nil
+}
+
+// GitDirs returns the repository's git directory and the common directory
+// holding its refs and objects, which differ for linked worktrees
+func (r *Repo) GitDirs() (string, string, error) {
+	repoPath, err := r.RepoPath()
+	if err != nil {
+		return "", "", err
+	}
+
+	cmd := gitCommand(repoPath, "rev-parse", "--absolute-git-dir", "--git-common-dir")
+	output, err := cmd.Output()
+	if err != nil {
+		return "", "", fmt.Errorf("failed to locate git directory: %w", commandError(cmd, err))
+	}
+	lines := strings.Split(strings.TrimSpace(string(output)), "\n")
+	if len(lines) != 2 {
+		return "", "", fmt.Errorf("unexpected rev-parse output: %q", strings.TrimSpace(string(output)))
+	}
+
+	gitDir, commonDir := lines[0], lines[1]
+	if !filepath.IsAbs(commonDir) {
+		commonDir = filepath.Join(repoPath, commonDir)
+	}
+	return gitDir, filepath.Clean(commonDir), nil
+}
+
+// IgnoredDirs lists the untracked directories .gitignore excludes, such as
+// build output, relative to the repository root
+func (r *Repo) IgnoredDirs() ([]string, error) {
+	repoPath, err := r.RepoPath()
+	if err != nil {
+		return nil, err
+	}
+
+	cmd := gitCommand(repoPath, "ls-files", "-z", "--others", "--ignored", "--exclude-standard", "--directory")
+	output, err := cmd.Output()
+	if err != nil {
+		return nil, fmt.Errorf("failed to list ignored directories: %w", commandError(cmd, err))
+	}
+
+	dirs := []string{}
+	for _, entry := range strings.Split(string(output), "\x00") {
+		// Directories are listed with a trailing slash, files without
+		if dir, ok := strings.CutSuffix(entry, "/"); ok {
+			dirs = append(dirs, filepath.FromSlash(dir))
+		}
+	}
+	return dirs, nil
+}
+
+// IgnoredPaths returns which of paths, relative to the repository root, are
+// untracked and excluded by .gitignore
+func (r *Repo) IgnoredPaths(paths []string) (map[string]bool, error) {
+	ignored := make(map[string]bool)
+	if len(paths) == 0 {
+		return ignored, nil
+	}
+
+	repoPath, err := r.RepoPath()
+	if err != nil {
+		return nil, err
+	}
+
+	cmd := gitCommand(repoPath, "check-ignore", "-z", "--stdin")
+	cmd.Stdin = strings.NewReader(strings.Join(paths, "\x00") + "\x00")
+	output, err := cmd.Output()
+	if err != nil {
+		// check-ignore exits with 1 when none of the paths are ignored
+		var exitErr *exec.ExitError
+		if errors.As(err, &exitErr) && exitErr.ExitCode() == 1 {
+			return ignored, nil
+		}
+		return nil, fmt.Errorf("failed to check ignored paths: %w", commandError(cmd, err))
+	}
+
+	for _, filePath := range strings.Split(string(output), "\x00") {
+		if filePath != "" {
+			ignored[filePath] = true
+		}
+	}
+	return ignored, nil
+}
+
+// GetWorkingChanges returns the working tree (including staged changes and
+// untracked files) diffed against HEAD, ignoring the base branch entirely.
+// Pathspecs, if any, limit it to the paths they match.
+func (r *Repo) GetWorkingChanges(pathspec ...string) ([]FileInfo, error) {
+	return r.GetWorkingChangesWithOptions(DiffOptions{Pathspec: pathspec})
+}
+
+// GetWorkingChangesWithOptions is GetWorkingChanges with the diff algorithm
+// and pathspecs of opts. The working tree is always diffed with the git CLI,
+// so the other options don't apply.
+func (r *Repo) GetWorkingChangesWithOptions(opts DiffOptions) ([]FileInfo, error) {
+	if r.IsBare() {
+		return nil, ErrBareRepository
+	}
+	if err := ValidateDiffAlgorithm(opts.Algorithm); err != nil {
+		return nil, err
+	}
+	pathspec := opts.Pathspec
+	if err := ValidatePathspec(pathspec); err != nil {
+		return nil, err
+	}
+
+	repoPath, err := r.RepoPath()
+	if err != nil {
+		return nil, err
+	}
+
+	cmd := gitCommand(repoPath, append([]string{"diff", "HEAD", "--name-status", "--no-renames", "-z", "--"}, pathspec...)...)
+	output, err := cmd.Output()
+	if err != nil {
+		return nil, fmt.Errorf("failed to list working tree changes against HEAD: %w", commandError(cmd, err))
+	}
+
+	// The patches of every changed file come from a single git diff
+	patches, err := diffPatches(repoPath, opts.Algorithm, append([]string{"HEAD", "--"}, pathspec...)...)
+	if err != nil {
+		return nil, fmt.Errorf("failed to diff the working tree against HEAD: %w", err)
+	}
+
+	files := []FileInfo{}
+
+	// With -z, entries are NUL-separated "<status>\0<path>" pairs
+	fields := strings.Split(strings.TrimSuffix(string(output), "\x00"), "\x00")
+	for i := 0; i+1 < len(fields); i += 2 {
+		statusCode, filePath := fields[i], fields[i+1]
+
+		status := "modified"
+		switch statusCode {
+		case "A":
+			status = "added"
+		case "D":
+			status = "deleted"
+		}
+
+		patch := patches[filePath]
+		additions, deletions := countChanges(patch)
+
+		files = append(files, FileInfo{
+			Path:      filePath,
+			Status:    status,
+			Kind:      ChangeKindFromCode(statusCode[0]),
+			Additions: additions,
+			Deletions: deletions,
+			Patch:     patch,
+		})
+	}
+
+	cmd = gitCommand(repoPath, append([]string{"ls-files", "--others", "--exclude-standard", "-z", "--"}, pathspec...)...)
+	output, err = cmd.Output()
+	if err != nil {
+		return nil, fmt.Errorf("failed to list untracked files: %w", commandError(cmd, err))
+	}
+
+	for _, filePath := range strings.Split(string(output), "\x00") {
+		if filePath == "" {
+			continue
+		}
+		fileInfo, err := r.untrackedFileInfo(filePath)
+		if err != nil {
+			continue
+		}
+		files = append(files, fileInfo)
+	}
+
+	return files, nil
+}
+
+// untrackedFileInfo renders an untracked file as an all-additions patch
+func (r *Repo) untrackedFileInfo(filePath string) (FileInfo, error) {
+	content, err := r.readWorktreeFile(filePath)
+	if err != nil {
+		return FileInfo{}, err
+	}
+
+	additions := strings.Count(content, "\n")
+	if len(content) > 0 && !strings.HasSuffix(content, "\n") {
+		additions++
+	}
+	patch := fmt.Sprintf("diff --git a/%s b/%s\nnew file mode 100644\n--- /dev/null\n+++ b/%s\n@@ -0,0 +1,%d @@\n", filePath, filePath, filePath, additions)
+	for _, line := range strings.Split(content, "\n") {
+		if line != "" || !strings.HasSuffix(content, "\n") {
+			patch += "+" + line + "\n"
+		}
+	}
+
+	return FileInfo{
+		Path:      filePath,
+		Status:    "added",
+		Kind:      ChangeAdded,
+		Additions: additions,
+		Deletions: 0,
+		Patch:     patch,
+	}, nil
+}
+
+// countChanges counts added and deleted lines in a unified diff
+func countChanges(patch string) (int, int) {
+	additions := 0
+	deletions := 0
+	for _, line := range strings.Split(patch, "\n") {
+		if len(line) == 0 {
+			continue
+		}
+		if strings.HasPrefix(line, "+") && !strings.HasPrefix(line, "+++") {
+			additions++
+		} else if strings.HasPrefix(line, "-") && !strings.HasPrefix(line, "---") {
+			deletions++
+		}
+	}
+	return additions, deletions
+}
+
+// diffPatches runs git diff with args, which end with "--" and any
+// pathspecs, and returns each file's patch by path. Renames are split into a
+// deletion and an addition, as the callers list changes path by path.
+func diffPatches(repoPath, algorithm string, args ...string) (map[string]string, error) {
+	// Unquoted paths, so non-ASCII file names come through as they are
+	diffArgs := []string{"-c", "core.quotePath=false", "diff", "--no-color", "--no-ext-diff", "--no-renames"}
+	if algorithm != "" {
+		diffArgs = append(diffArgs, "--diff-algorithm="+algorithm)
+	}
+
+	cmd := gitCommand(repoPath, append(diffArgs, args...)...)
+	output, err := cmd.Output()
+	if err != nil {
+		return nil, commandError(cmd, err)
+	}
+
+	patches := map[string]string{}
+	files, err := ParsePatch(string(output))
+	if errors.Is(err, ErrEmptyPatch) {
+		return patches, nil
+	}
+	if err != nil {
+		return nil, fmt.Errorf("failed to parse git diff output: %w", err)
+	}
+	for _, file := range files {
+		patches[file.Path] = file.Patch
+	}
+	return patches, nil
+}
+
+// uncommittedFileInfo describes a staged or unstaged change to a file
+func uncommittedFileInfo(filePath string, statusCode git.StatusCode, stagingStatus StagingStatus, patch string) FileInfo {
+	status := "modified"
+	switch statusCode {
+	case git.Added:
+		status = "added"
+	case git.Deleted:
+		status = "deleted"
+	case git.Renamed:
+		status = "renamed"
+	case git.Copied:
+		status = "added"
+	}
+
+	// Count additions and deletions
+	additions, deletions := countChanges(patch)
+
+	return FileInfo{
+		Path:          filePath,
+		Status:        status,
+		Kind:          ChangeKindFromCode(byte(statusCode)),
+		Additions:     additions,
+		Deletions:     deletions,
+		Patch:         patch,
+		StagingStatus: stagingStatus,
+	}
+}
+
+func (r *Repo) readWorktreeFile(filePath string) (string, error) {
+	wt, err := r.worktree()
+	if err != nil {
+		return "", err
+	}
+
+	fullPath := filepath.Join(wt.Filesystem.Root(), filePath)
+	content, err := os.ReadFile(fullPath)
+	if err != nil {
+		return "", err
+	}
+	return string(content), nil
+}
+
+// worktree returns the repository's working tree, or ErrBareRepository
+func (r *Repo) worktree() (*git.Worktree, error) {
+	wt, err := r.repo.Worktree()
+	if errors.Is(err, git.ErrIsBareRepository) {
+		return nil, ErrBareRepository
+	}
+	if err != nil {
+		return nil, fmt.Errorf("failed to get worktree: %w", err)
+	}
+	return wt, nil
 }
 
 func generateUnifiedDiff(filePath, oldContent, newContent string, status string) string {
@@ -1,6 +1,8 @@
 package git
 
 import (
+	"context"
+	"errors"
 	"os"
 	"os/exec"
 	"path/filepath"
@@ -83,6 +85,30 @@ func TestCurrentBranch(t *testing.T) {
 	}
 }
 
+func TestFindRepoPath(t *testing.T) {
+	tempDir := setupTestRepo(t)
+	subDir := filepath.Join(tempDir, "a", "b")
+	if err := os.MkdirAll(subDir, 0755); err != nil {
+		t.Fatalf("Failed to create directory: %v", err)
+	}
+
+	repo, err := Open(subDir)
+	if err != nil {
+		t.Fatalf("Failed to open repo: %v", err)
+	}
+	repoPath, err := repo.RepoPath()
+	if err != nil {
+		t.Fatalf("Failed to get repo path: %v", err)
+	}
+
+	if found, ok := FindRepoPath(subDir); !ok || found != repoPath {
+		t.Errorf("Expected %s, got %q (found %v)", repoPath, found, ok)
+	}
+	if _, ok := FindRepoPath(t.TempDir()); ok {
+		t.Error("Expected no repository outside one")
+	}
+}
+
 func TestCurrentCommit(t *testing.T) {
 	tempDir := setupTestRepo(t)
 
@@ -354,6 +380,46 @@ func TestGetUncommittedChangesMixedStagedAndUnstaged(t *testing.T) {
 	}
 }
 
+func TestGetUncommittedChangesWithAlgorithm(t *testing.T) {
+	tempDir := setupTestRepo(t)
+
+	// Staged, then changed again in the working tree
+	readme := filepath.Join(tempDir, "README.md")
+	if err := os.WriteFile(readme, []byte("# Test Repo\nstaged\n"), 0644); err != nil {
+		t.Fatalf("Failed to modify file: %v", err)
+	}
+	runGit(t, tempDir, "add", "README.md")
+	if err := os.WriteFile(readme, []byte("# Test Repo\nstaged\nunstaged\n"), 0644); err != nil {
+		t.Fatalf("Failed to modify file: %v", err)
+	}
+
+	repo, err := Open(tempDir)
+	if err != nil {
+		t.Fatalf("Failed to open repo: %v", err)
+	}
+
+	files, err := repo.GetUncommittedChangesWithOptions(DiffOptions{Algorithm: "histogram"})
+	if err != nil {
+		t.Fatalf("Failed to get uncommitted changes: %v", err)
+	}
+	if len(files) != 2 {
+		t.Fatalf("Expected a staged and an unstaged change, got %+v", files)
+	}
+	for _, file := range files {
+		expected := "+staged"
+		if file.StagingStatus == StagingStatusUnstaged {
+			expected = "+unstaged"
+		}
+		if file.Additions != 1 || !strings.Contains(file.Patch, expected) {
+			t.Errorf("Expected the %s patch to add %q, got:\n%s", file.StagingStatus, expected, file.Patch)
+		}
+	}
+
+	if _, err := repo.GetUncommittedChangesWithOptions(DiffOptions{Algorithm: "fastest"}); err == nil {
+		t.Error("Expected an error for an unsupported algorithm")
+	}
+}
+
 func TestGetUncommittedChangesStagedDeletion(t *testing.T) {
 	tempDir := setupTestRepo(t)
 
@@ -472,3 +538,1215 @@ func TestGenerateUnifiedDiff(t *testing.T) {
 		})
 	}
 }
+
+func TestFetchBaseOffline(t *testing.T) {
+	tempDir := setupTestRepo(t)
+
+	repo, err := Open(tempDir)
+	if err != nil {
+		t.Fatalf("Failed to open repo: %v", err)
+	}
+
+	repo.SetOffline(true)
+
+	if err := repo.FetchBase("main"); !errors.Is(err, ErrOffline) {
+		t.Errorf("Expected ErrOffline, got %v", err)
+	}
+}
+
+func TestGitCommandIsNonInteractive(t *testing.T) {
+	t.Setenv("GIT_SSH", "")
+	t.Setenv("GIT_SSH_COMMAND", "")
+
+	cmd := gitCommand(t.TempDir(), "status")
+
+	env := strings.Join(cmd.Env, "\n")
+	for _, expected := range []string{"GIT_TERMINAL_PROMPT=0", "GIT_SSH_COMMAND=ssh -o BatchMode=yes"} {
+		if !strings.Contains(env, expected) {
+			t.Errorf("Expected command environment to contain %s", expected)
+		}
+	}
+}
+
+func TestAmendedFrom(t *testing.T) {
+	tempDir := setupTestRepo(t)
+
+	repo, err := Open(tempDir)
+	if err != nil {
+		t.Fatalf("Failed to open repo: %v", err)
+	}
+
+	amendedFrom, err := repo.AmendedFrom()
+	if err != nil {
+		t.Fatalf("Failed to read reflog: %v", err)
+	}
+	if amendedFrom != "" {
+		t.Errorf("Expected no amend before amending, got %s", amendedFrom)
+	}
+
+	originalCommit := strings.TrimSpace(runGit(t, tempDir, "rev-parse", "HEAD"))
+	runGit(t, tempDir, "commit", "--amend", "-m", "Amended commit")
+
+	amendedFrom, err = repo.AmendedFrom()
+	if err != nil {
+		t.Fatalf("Failed to read reflog: %v", err)
+	}
+	if amendedFrom != originalCommit {
+		t.Errorf("Expected amended-from commit %s, got %s", originalCommit, amendedFrom)
+	}
+}
+
+func TestGetDiffFilesMissingBaseBranchError(t *testing.T) {
+	tempDir := setupTestRepo(t)
+
+	repo, err := Open(tempDir)
+	if err != nil {
+		t.Fatalf("Failed to open repo: %v", err)
+	}
+
+	_, err = repo.GetDiffFiles("does-not-exist")
+	if err == nil {
+		t.Fatal("Expected an error for a missing base branch")
+	}
+
+	if !strings.Contains(err.Error(), "base ref 'does-not-exist'") {
+		t.Errorf("Expected error to name the base ref, got: %v", err)
+	}
+}
+
+func TestGetDiffFilesAgainstRevision(t *testing.T) {
+	tempDir := setupTestRepo(t)
+	initial := strings.TrimSpace(runGit(t, tempDir, "rev-parse", "HEAD"))
+	runGit(t, tempDir, "tag", "-a", "v1.0.0", "-m", "Release")
+
+	for _, name := range []string{"a.txt", "b.txt"} {
+		if err := os.WriteFile(filepath.Join(tempDir, name), []byte(name), 0644); err != nil {
+			t.Fatalf("Failed to create file: %v", err)
+		}
+		runGit(t, tempDir, "add", name)
+		runGit(t, tempDir, "commit", "-m", "Add "+name)
+	}
+
+	repo, err := Open(tempDir)
+	if err != nil {
+		t.Fatalf("Failed to open repo: %v", err)
+	}
+
+	tests := []struct {
+		base  string
+		files int
+	}{
+		{base: "v1.0.0", files: 2},
+		{base: initial, files: 2},
+		{base: initial[:10], files: 2},
+		{base: "HEAD~1", files: 1},
+	}
+	for _, tt := range tests {
+		files, err := repo.GetDiffFiles(tt.base)
+		if err != nil {
+			t.Fatalf("%s: failed to get diff: %v", tt.base, err)
+		}
+		if len(files) != tt.files {
+			t.Errorf("%s: expected %d files, got %d", tt.base, tt.files, len(files))
+		}
+	}
+
+	// Annotated tags are peeled to the commit they tag
+	if tip, err := repo.BaseTip("v1.0.0"); err != nil || tip != initial {
+		t.Errorf("Expected the tag to resolve to %s, got %s (%v)", initial, tip, err)
+	}
+	if _, err := repo.BaseTip("--all"); err == nil {
+		t.Error("Expected an error for a base that looks like an option")
+	}
+}
+
+func TestChangeFingerprint(t *testing.T) {
+	tempDir := setupTestRepo(t)
+
+	repo, err := Open(tempDir)
+	if err != nil {
+		t.Fatalf("Failed to open repo: %v", err)
+	}
+	fingerprint := func() string {
+		t.Helper()
+		fingerprint, err := repo.ChangeFingerprint()
+		if err != nil {
+			t.Fatalf("Failed to fingerprint: %v", err)
+		}
+		return fingerprint
+	}
+
+	clean := fingerprint()
+	if again := fingerprint(); again != clean {
+		t.Errorf("Expected an unchanged repository to keep its fingerprint, got %s and %s", clean, again)
+	}
+
+	readme := filepath.Join(tempDir, "README.md")
+	if err := os.WriteFile(readme, []byte("# Changed\n"), 0644); err != nil {
+		t.Fatalf("Failed to write file: %v", err)
+	}
+	edited := fingerprint()
+	if edited == clean {
+		t.Error("Expected editing a file to change the fingerprint")
+	}
+
+	// Editing an already modified file changes it again
+	if err := os.WriteFile(readme, []byte("# Changed again, and longer\n"), 0644); err != nil {
+		t.Fatalf("Failed to write file: %v", err)
+	}
+	if fingerprint() == edited {
+		t.Error("Expected editing a modified file to change the fingerprint")
+	}
+
+	runGit(t, tempDir, "commit", "-am", "Edit README")
+	if fingerprint() == clean {
+		t.Error("Expected a new commit to change the fingerprint")
+	}
+}
+
+func TestOpenErrorIncludesPath(t *testing.T) {
+	tempDir := t.TempDir()
+
+	_, err := Open(tempDir)
+	if err == nil {
+		t.Fatal("Expected an error for a non-git directory")
+	}
+
+	if !strings.Contains(err.Error(), tempDir) {
+		t.Errorf("Expected error to include the path, got: %v", err)
+	}
+}
+
+func TestFileAtCommit(t *testing.T) {
+	tempDir := setupTestRepo(t)
+
+	repo, err := Open(tempDir)
+	if err != nil {
+		t.Fatalf("Failed to open repo: %v", err)
+	}
+
+	originalCommit, err := repo.CurrentCommit()
+	if err != nil {
+		t.Fatalf("Failed to get current commit: %v", err)
+	}
+
+	// Change the file in a later commit
+	testFile := filepath.Join(tempDir, "README.md")
+	if err := os.WriteFile(testFile, []byte("# Changed\n"), 0644); err != nil {
+		t.Fatalf("Failed to modify file: %v", err)
+	}
+	runGit(t, tempDir, "commit", "-am", "Change README")
+
+	contents, err := repo.FileAtCommit(originalCommit, "README.md")
+	if err != nil {
+		t.Fatalf("Failed to read file at commit: %v", err)
+	}
+	if contents != "# Test Repo\n" {
+		t.Errorf("Expected original contents, got %q", contents)
+	}
+
+	if _, err := repo.FileAtCommit(originalCommit, "missing.txt"); err == nil {
+		t.Error("Expected an error for a file missing at the commit")
+	}
+}
+
+func TestAbbreviate(t *testing.T) {
+	commit := "0123456789abcdef0123456789abcdef01234567"
+
+	if got := Abbreviate(commit, 7); got != "0123456" {
+		t.Errorf("Expected 0123456, got %s", got)
+	}
+	if got := Abbreviate(commit, 40); got != commit {
+		t.Errorf("Expected full commit, got %s", got)
+	}
+	if got := Abbreviate("__uncommitted__", 0); got != "__uncommitted__" {
+		t.Errorf("Expected input unchanged for non-positive length, got %s", got)
+	}
+}
+
+func TestGetWorkingChanges(t *testing.T) {
+	tempDir := setupTestRepo(t)
+
+	repo, err := Open(tempDir)
+	if err != nil {
+		t.Fatalf("Failed to open repo: %v", err)
+	}
+
+	// Unstaged modification
+	if err := os.WriteFile(filepath.Join(tempDir, "README.md"), []byte("# Test Repo\nMore\n"), 0644); err != nil {
+		t.Fatalf("Failed to modify file: %v", err)
+	}
+
+	// Staged new file
+	if err := os.WriteFile(filepath.Join(tempDir, "staged.txt"), []byte("staged\n"), 0644); err != nil {
+		t.Fatalf("Failed to create file: %v", err)
+	}
+	runGit(t, tempDir, "add", "staged.txt")
+
+	// Untracked file
+	if err := os.WriteFile(filepath.Join(tempDir, "untracked.txt"), []byte("new\n"), 0644); err != nil {
+		t.Fatalf("Failed to create file: %v", err)
+	}
+
+	files, err := repo.GetWorkingChanges()
+	if err != nil {
+		t.Fatalf("Failed to get working changes: %v", err)
+	}
+
+	byPath := make(map[string]FileInfo)
+	for _, f := range files {
+		byPath[f.Path] = f
+	}
+
+	if len(byPath) != 3 {
+		t.Fatalf("Expected 3 changed files, got %d: %+v", len(byPath), files)
+	}
+
+	if readme := byPath["README.md"]; readme.Status != "modified" || readme.Additions != 1 {
+		t.Errorf("Expected README.md modified with 1 addition, got %s +%d", readme.Status, readme.Additions)
+	}
+	if staged := byPath["staged.txt"]; staged.Status != "added" {
+		t.Errorf("Expected staged.txt to be added, got %s", staged.Status)
+	}
+	if untracked := byPath["untracked.txt"]; untracked.Status != "added" || untracked.Additions != 1 {
+		t.Errorf("Expected untracked.txt added with 1 addition, got %s +%d", untracked.Status, untracked.Additions)
+	}
+
+	// Each file gets its own patch out of the single git diff
+	files, err = repo.GetWorkingChangesWithOptions(DiffOptions{Algorithm: "patience"})
+	if err != nil {
+		t.Fatalf("Failed to get working changes with an algorithm: %v", err)
+	}
+	for _, file := range files {
+		if file.Path != "untracked.txt" && (!strings.Contains(file.Patch, "b/"+file.Path) || strings.Count(file.Patch, "diff --git") != 1) {
+			t.Errorf("Expected %s to have its own patch, got:\n%s", file.Path, file.Patch)
+		}
+	}
+	if _, err := repo.GetWorkingChangesWithOptions(DiffOptions{Algorithm: "fastest"}); err == nil {
+		t.Error("Expected an error for an unsupported algorithm")
+	}
+}
+
+func TestIsDirtyCleanRepo(t *testing.T) {
+	tempDir := setupTestRepo(t)
+
+	repo, err := Open(tempDir)
+	if err != nil {
+		t.Fatalf("Failed to open repo: %v", err)
+	}
+
+	// Untracked files alone don't make the tree dirty
+	if err := os.WriteFile(filepath.Join(tempDir, "untracked.txt"), []byte("new\n"), 0644); err != nil {
+		t.Fatalf("Failed to create file: %v", err)
+	}
+
+	dirty, err := repo.IsDirty()
+	if err != nil {
+		t.Fatalf("Failed to check dirty state: %v", err)
+	}
+	if dirty {
+		t.Error("Expected clean repo not to be dirty")
+	}
+}
+
+func TestIsDirtyStagedOnly(t *testing.T) {
+	tempDir := setupTestRepo(t)
+
+	repo, err := Open(tempDir)
+	if err != nil {
+		t.Fatalf("Failed to open repo: %v", err)
+	}
+
+	if err := os.WriteFile(filepath.Join(tempDir, "README.md"), []byte("# Staged\n"), 0644); err != nil {
+		t.Fatalf("Failed to modify file: %v", err)
+	}
+	runGit(t, tempDir, "add", "README.md")
+
+	dirty, err := repo.IsDirty()
+	if err != nil {
+		t.Fatalf("Failed to check dirty state: %v", err)
+	}
+	if !dirty {
+		t.Error("Expected repo with staged changes to be dirty")
+	}
+}
+
+func TestIsDirtyUnstagedOnly(t *testing.T) {
+	tempDir := setupTestRepo(t)
+
+	repo, err := Open(tempDir)
+	if err != nil {
+		t.Fatalf("Failed to open repo: %v", err)
+	}
+
+	if err := os.WriteFile(filepath.Join(tempDir, "README.md"), []byte("# Unstaged\n"), 0644); err != nil {
+		t.Fatalf("Failed to modify file: %v", err)
+	}
+
+	dirty, err := repo.IsDirty()
+	if err != nil {
+		t.Fatalf("Failed to check dirty state: %v", err)
+	}
+	if !dirty {
+		t.Error("Expected repo with unstaged changes to be dirty")
+	}
+}
+
+func TestCommitChangedPaths(t *testing.T) {
+	tempDir := setupTestRepo(t)
+
+	if err := os.WriteFile(filepath.Join(tempDir, "a.txt"), []byte("a\n"), 0644); err != nil {
+		t.Fatalf("Failed to create file: %v", err)
+	}
+	if err := os.WriteFile(filepath.Join(tempDir, "README.md"), []byte("# Changed\n"), 0644); err != nil {
+		t.Fatalf("Failed to modify file: %v", err)
+	}
+	runGit(t, tempDir, "add", ".")
+	runGit(t, tempDir, "commit", "-m", "Touch two files")
+	target := strings.TrimSpace(runGit(t, tempDir, "rev-parse", "HEAD"))
+
+	if err := os.WriteFile(filepath.Join(tempDir, "b.txt"), []byte("b\n"), 0644); err != nil {
+		t.Fatalf("Failed to create file: %v", err)
+	}
+	runGit(t, tempDir, "add", ".")
+	runGit(t, tempDir, "commit", "-m", "Unrelated change")
+
+	repo, err := Open(tempDir)
+	if err != nil {
+		t.Fatalf("Failed to open repo: %v", err)
+	}
+
+	resolved, paths, err := repo.CommitChangedPaths(target[:7])
+	if err != nil {
+		t.Fatalf("Failed to get changed paths: %v", err)
+	}
+	if resolved != target {
+		t.Errorf("Expected resolved commit %s, got %s", target, resolved)
+	}
+
+	got := map[string]bool{}
+	for _, p := range paths {
+		got[p] = true
+	}
+	if len(paths) != 2 || !got["a.txt"] || !got["README.md"] {
+		t.Errorf("Expected a.txt and README.md, got %v", paths)
+	}
+}
+
+func TestCommitChangedPathsOutsideHistory(t *testing.T) {
+	tempDir := setupTestRepo(t)
+
+	runGit(t, tempDir, "checkout", "-b", "other")
+	if err := os.WriteFile(filepath.Join(tempDir, "other.txt"), []byte("other\n"), 0644); err != nil {
+		t.Fatalf("Failed to create file: %v", err)
+	}
+	runGit(t, tempDir, "add", ".")
+	runGit(t, tempDir, "commit", "-m", "Other branch")
+	other := strings.TrimSpace(runGit(t, tempDir, "rev-parse", "HEAD"))
+	runGit(t, tempDir, "checkout", "-")
+
+	repo, err := Open(tempDir)
+	if err != nil {
+		t.Fatalf("Failed to open repo: %v", err)
+	}
+
+	if _, _, err := repo.CommitChangedPaths(other); !errors.Is(err, ErrCommitNotInHistory) {
+		t.Errorf("Expected ErrCommitNotInHistory for a commit on another branch, got %v", err)
+	}
+	if _, _, err := repo.CommitChangedPaths("does-not-exist"); !errors.Is(err, ErrCommitNotInHistory) {
+		t.Errorf("Expected ErrCommitNotInHistory for an unknown commit, got %v", err)
+	}
+}
+
+func TestGetCommitDiffMerge(t *testing.T) {
+	tempDir := setupTestRepo(t)
+	write := func(name, content string) {
+		t.Helper()
+		if err := os.WriteFile(filepath.Join(tempDir, name), []byte(content), 0644); err != nil {
+			t.Fatalf("Failed to write %s: %v", name, err)
+		}
+	}
+
+	write("file.txt", "base\n")
+	runGit(t, tempDir, "add", ".")
+	runGit(t, tempDir, "commit", "-m", "Add file")
+
+	runGit(t, tempDir, "checkout", "-b", "side")
+	write("file.txt", "side\n")
+	write("clean.txt", "clean\n")
+	runGit(t, tempDir, "add", ".")
+	runGit(t, tempDir, "commit", "-m", "Side change")
+	runGit(t, tempDir, "checkout", "-")
+
+	write("file.txt", "main\n")
+	runGit(t, tempDir, "commit", "-am", "Main change")
+	mainCommit := strings.TrimSpace(runGit(t, tempDir, "rev-parse", "HEAD"))
+
+	// The merge conflicts, so it exits non-zero until resolved
+	_ = exec.Command("git", "-C", tempDir, "merge", "side").Run()
+	write("file.txt", "resolved\n")
+	runGit(t, tempDir, "add", ".")
+	runGit(t, tempDir, "commit", "--no-edit")
+
+	repo, err := Open(tempDir)
+	if err != nil {
+		t.Fatalf("Failed to open repo: %v", err)
+	}
+
+	diff, err := repo.GetCommitDiff(context.Background(), "HEAD", DiffOptions{})
+	if err != nil {
+		t.Fatalf("Failed to diff merge commit: %v", err)
+	}
+	if !diff.Combined || len(diff.Parents) != 2 || diff.Parents[0] != mainCommit {
+		t.Errorf("Expected a combined diff against 2 parents starting with %s, got %+v", mainCommit, diff)
+	}
+	// clean.txt merged cleanly from one side, so only the resolution shows
+	if len(diff.Files) != 1 || diff.Files[0].Path != "file.txt" {
+		t.Fatalf("Expected only file.txt, got %+v", diff.Files)
+	}
+	if !strings.Contains(diff.Files[0].Patch, "++resolved") {
+		t.Errorf("Expected the resolution in the combined patch, got %q", diff.Files[0].Patch)
+	}
+
+	diff, err = repo.GetCommitDiff(context.Background(), mainCommit, DiffOptions{})
+	if err != nil {
+		t.Fatalf("Failed to diff commit: %v", err)
+	}
+	if diff.Combined || len(diff.Parents) != 1 {
+		t.Errorf("Expected a plain diff against one parent, got %+v", diff)
+	}
+	if len(diff.Files) != 1 || diff.Files[0].Path != "file.txt" || diff.Files[0].Additions != 1 || diff.Files[0].Deletions != 1 {
+		t.Errorf("Expected file.txt changed by one line, got %+v", diff.Files)
+	}
+
+	if _, err := repo.GetCommitDiff(context.Background(), "does-not-exist", DiffOptions{}); !errors.Is(err, ErrCommitNotInHistory) {
+		t.Errorf("Expected ErrCommitNotInHistory for an unknown commit, got %v", err)
+	}
+}
+
+func TestGetDiffFilesWithAlgorithm(t *testing.T) {
+	tempDir := setupTestRepo(t)
+	runGit(t, tempDir, "branch", "base")
+
+	if err := os.WriteFile(filepath.Join(tempDir, "README.md"), []byte("# Test Repo\nmore\n"), 0644); err != nil {
+		t.Fatalf("Failed to modify file: %v", err)
+	}
+	runGit(t, tempDir, "commit", "-am", "Change README")
+
+	repo, err := Open(tempDir)
+	if err != nil {
+		t.Fatalf("Failed to open repo: %v", err)
+	}
+
+	files, err := repo.GetDiffFilesWithOptions("base", DiffOptions{Algorithm: "histogram"})
+	if err != nil {
+		t.Fatalf("Failed to get diff files: %v", err)
+	}
+
+	if len(files) != 1 || files[0].Path != "README.md" {
+		t.Fatalf("Expected README.md to be changed, got %+v", files)
+	}
+	if files[0].Additions != 1 || files[0].Deletions != 0 {
+		t.Errorf("Expected +1 -0, got +%d -%d", files[0].Additions, files[0].Deletions)
+	}
+	if !strings.Contains(files[0].Patch, "+more") {
+		t.Errorf("Expected patch to contain the added line, got:\n%s", files[0].Patch)
+	}
+
+	if _, err := repo.GetDiffFilesWithOptions("base", DiffOptions{Algorithm: "fastest"}); err == nil {
+		t.Error("Expected an error for an unsupported algorithm")
+	}
+}
+
+func TestGetDiffFilesWithAlgorithmKeepsRenames(t *testing.T) {
+	tempDir := setupTestRepo(t)
+	notes := "one\ntwo\nthree\nfour\nfive\nsix\nseven\neight\n"
+	if err := os.WriteFile(filepath.Join(tempDir, "notes.txt"), []byte(notes), 0644); err != nil {
+		t.Fatalf("Failed to create file: %v", err)
+	}
+	runGit(t, tempDir, "add", ".")
+	runGit(t, tempDir, "commit", "-m", "Add notes")
+	runGit(t, tempDir, "branch", "base")
+
+	runGit(t, tempDir, "mv", "notes.txt", "guide.txt")
+	if err := os.WriteFile(filepath.Join(tempDir, "guide.txt"), []byte(notes+"nine\n"), 0644); err != nil {
+		t.Fatalf("Failed to modify file: %v", err)
+	}
+	if err := os.WriteFile(filepath.Join(tempDir, "README.md"), []byte("# Test Repo\nmore\n"), 0644); err != nil {
+		t.Fatalf("Failed to modify file: %v", err)
+	}
+	runGit(t, tempDir, "add", ".")
+	runGit(t, tempDir, "commit", "-m", "Rename notes and change README")
+
+	repo, err := Open(tempDir)
+	if err != nil {
+		t.Fatalf("Failed to open repo: %v", err)
+	}
+
+	for _, backend := range []string{DiffBackendGoGit, DiffBackendCLI} {
+		files, err := repo.GetDiffFilesWithOptions("base", DiffOptions{Algorithm: "histogram", Backend: backend})
+		if err != nil {
+			t.Fatalf("%s: failed to get diff files: %v", backend, err)
+		}
+		byPath := map[string]FileInfo{}
+		for _, file := range files {
+			byPath[file.Path] = file
+		}
+		if len(byPath) != 2 {
+			t.Fatalf("%s: expected the rename and README.md, got %+v", backend, files)
+		}
+		guide := byPath["guide.txt"]
+		if guide.Status != "renamed" || guide.OldPath != "notes.txt" || guide.Additions != 1 || guide.Deletions != 0 {
+			t.Errorf("%s: expected guide.txt renamed from notes.txt with +1 -0, got %+v", backend, guide)
+		}
+		if !strings.Contains(guide.Patch, "rename from notes.txt") || !strings.Contains(guide.Patch, "+nine") {
+			t.Errorf("%s: expected a rename patch adding a line, got:\n%s", backend, guide.Patch)
+		}
+		if readme := byPath["README.md"]; !strings.Contains(readme.Patch, "+more") || strings.Contains(readme.Patch, "guide.txt") {
+			t.Errorf("%s: expected README.md's own patch, got:\n%s", backend, readme.Patch)
+		}
+	}
+}
+
+func TestGetDiffFilesWithCLIBackend(t *testing.T) {
+	tempDir := setupTestRepo(t)
+	runGit(t, tempDir, "branch", "base")
+
+	if err := os.WriteFile(filepath.Join(tempDir, "README.md"), []byte("# Test Repo\nmore\n"), 0644); err != nil {
+		t.Fatalf("Failed to modify file: %v", err)
+	}
+	runGit(t, tempDir, "mv", "README.md", "GUIDE.md")
+	if err := os.WriteFile(filepath.Join(tempDir, "new.txt"), []byte("new\n"), 0644); err != nil {
+		t.Fatalf("Failed to create file: %v", err)
+	}
+	runGit(t, tempDir, "add", ".")
+	runGit(t, tempDir, "commit", "-m", "Rename README and add new.txt")
+
+	repo, err := Open(tempDir)
+	if err != nil {
+		t.Fatalf("Failed to open repo: %v", err)
+	}
+
+	files, err := repo.GetDiffFilesWithOptions("base", DiffOptions{Backend: DiffBackendCLI})
+	if err != nil {
+		t.Fatalf("Failed to get diff files: %v", err)
+	}
+	changes := []string{}
+	for _, file := range files {
+		changes = append(changes, file.Path+":"+file.Status)
+	}
+	if strings.Join(changes, ",") != "GUIDE.md:renamed,new.txt:added" {
+		t.Fatalf("Expected git's rename detection and the added file, got %v", changes)
+	}
+	if files[0].Additions != 1 || !strings.Contains(files[0].Patch, "+more") {
+		t.Errorf("Expected the renamed file's patch to add a line, got:\n%s", files[0].Patch)
+	}
+
+	if _, err := repo.GetDiffFilesWithOptions("base", DiffOptions{Backend: "libgit2"}); err == nil {
+		t.Error("Expected an error for an unknown backend")
+	}
+}
+
+func TestGetDiffFilesWithPathspec(t *testing.T) {
+	tempDir := setupTestRepo(t)
+	runGit(t, tempDir, "branch", "base")
+
+	for _, name := range []string{"src/main.go", "src/main_test.go", "docs/guide.md"} {
+		if err := os.MkdirAll(filepath.Join(tempDir, filepath.Dir(name)), 0755); err != nil {
+			t.Fatalf("Failed to create directory: %v", err)
+		}
+		if err := os.WriteFile(filepath.Join(tempDir, name), []byte("content\n"), 0644); err != nil {
+			t.Fatalf("Failed to create file: %v", err)
+		}
+	}
+	runGit(t, tempDir, "add", ".")
+	runGit(t, tempDir, "commit", "-m", "Add sources and docs")
+
+	repo, err := Open(tempDir)
+	if err != nil {
+		t.Fatalf("Failed to open repo: %v", err)
+	}
+
+	// The go-git backend is overridden, since only git evaluates pathspecs
+	files, err := repo.GetDiffFilesWithOptions("base", DiffOptions{Pathspec: []string{":(glob)src/**", ":(exclude)*_test.go"}})
+	if err != nil {
+		t.Fatalf("Failed to get diff files: %v", err)
+	}
+	if len(files) != 1 || files[0].Path != "src/main.go" {
+		t.Errorf("Expected only src/main.go, got %+v", files)
+	}
+
+	if err := os.WriteFile(filepath.Join(tempDir, "docs/draft.md"), []byte("draft\n"), 0644); err != nil {
+		t.Fatalf("Failed to create file: %v", err)
+	}
+	if err := os.WriteFile(filepath.Join(tempDir, "src/main.go"), []byte("changed\n"), 0644); err != nil {
+		t.Fatalf("Failed to modify file: %v", err)
+	}
+	files, err = repo.GetWorkingChanges(":/docs")
+	if err != nil {
+		t.Fatalf("Failed to get working changes: %v", err)
+	}
+	if len(files) != 1 || files[0].Path != "docs/draft.md" {
+		t.Errorf("Expected only the untracked docs/draft.md, got %+v", files)
+	}
+
+	matching, err := repo.UncommittedPathsMatching([]string{":(exclude)docs"})
+	if err != nil {
+		t.Fatalf("Failed to match uncommitted paths: %v", err)
+	}
+	if len(matching) != 1 || !matching["src/main.go"] {
+		t.Errorf("Expected only src/main.go to match, got %v", matching)
+	}
+
+	if _, err := repo.GetDiffFilesWithOptions("base", DiffOptions{Pathspec: []string{":(exclud)*.md"}}); err == nil {
+		t.Error("Expected an error for unknown pathspec magic")
+	}
+}
+
+func TestValidatePathspec(t *testing.T) {
+	valid := [][]string{
+		nil,
+		{"src"},
+		{"*.go", ":!vendor"},
+		{":/README.md", ":^docs", ":!:weird:name"},
+		{":(top,icase)readme.md", ":(attr:!binary)", ":(exclude,glob)**/*.lock"},
+	}
+	for _, pathspec := range valid {
+		if err := ValidatePathspec(pathspec); err != nil {
+			t.Errorf("Expected %q to be valid, got %v", pathspec, err)
+		}
+	}
+
+	invalid := [][]string{
+		{""},
+		{":(exclude"},
+		{":(exclud)*.md"},
+		{":(literal,glob)src"},
+		{":#src"},
+	}
+	for _, pathspec := range invalid {
+		if err := ValidatePathspec(pathspec); err == nil {
+			t.Errorf("Expected %q to be rejected", pathspec)
+		}
+	}
+}
+
+func TestGetDiffFilesWithTextconv(t *testing.T) {
+	tempDir := setupTestRepo(t)
+	if err := os.WriteFile(filepath.Join(tempDir, ".gitattributes"), []byte("*.bin diff=strip\n"), 0644); err != nil {
+		t.Fatalf("Failed to create .gitattributes: %v", err)
+	}
+	runGit(t, tempDir, "config", "diff.strip.textconv", "tr -d '\\000' <")
+	if err := os.WriteFile(filepath.Join(tempDir, "data.bin"), []byte("old\x00\n"), 0644); err != nil {
+		t.Fatalf("Failed to create file: %v", err)
+	}
+	runGit(t, tempDir, "add", ".")
+	runGit(t, tempDir, "commit", "-m", "Add data.bin")
+	runGit(t, tempDir, "branch", "base")
+
+	if err := os.WriteFile(filepath.Join(tempDir, "data.bin"), []byte("new\x00\n"), 0644); err != nil {
+		t.Fatalf("Failed to modify file: %v", err)
+	}
+	runGit(t, tempDir, "commit", "-am", "Change data.bin")
+
+	repo, err := Open(tempDir)
+	if err != nil {
+		t.Fatalf("Failed to open repo: %v", err)
+	}
+
+	for _, backend := range []string{DiffBackendGoGit, DiffBackendCLI} {
+		files, err := repo.GetDiffFilesWithOptions("base", DiffOptions{Backend: backend, Textconv: true})
+		if err != nil {
+			t.Fatalf("%s: failed to get diff files: %v", backend, err)
+		}
+		if len(files) != 1 || !strings.Contains(files[0].Patch, "-old") || !strings.Contains(files[0].Patch, "+new") {
+			t.Errorf("%s: expected a textual diff of data.bin, got %+v", backend, files)
+		}
+
+		files, err = repo.GetDiffFilesWithOptions("base", DiffOptions{Backend: backend})
+		if err != nil {
+			t.Fatalf("%s: failed to get diff files: %v", backend, err)
+		}
+		if len(files) != 1 || strings.Contains(files[0].Patch, "+new") {
+			t.Errorf("%s: expected textconv to be off by default, got %+v", backend, files)
+		}
+	}
+}
+
+func TestGetDiffFilesCaseOnlyRename(t *testing.T) {
+	tempDir := setupTestRepo(t)
+	runGit(t, tempDir, "branch", "base")
+	runGit(t, tempDir, "mv", "README.md", "readme.md")
+	runGit(t, tempDir, "commit", "-m", "Lowercase README")
+
+	repo, err := Open(tempDir)
+	if err != nil {
+		t.Fatalf("Failed to open repo: %v", err)
+	}
+
+	for _, backend := range []string{DiffBackendGoGit, DiffBackendCLI} {
+		files, err := repo.GetDiffFilesWithOptions("base", DiffOptions{Backend: backend})
+		if err != nil {
+			t.Fatalf("%s: failed to get diff files: %v", backend, err)
+		}
+		if len(files) != 1 || files[0].Path != "readme.md" || files[0].OldPath != "README.md" || files[0].Status != "renamed" {
+			t.Errorf("%s: expected a rename from README.md to readme.md, got %+v", backend, files)
+		}
+	}
+}
+
+func TestGetDiffSince(t *testing.T) {
+	tempDir := setupTestRepo(t)
+	runGit(t, tempDir, "branch", "base")
+	for _, name := range []string{"a.txt", "b.txt"} {
+		if err := os.WriteFile(filepath.Join(tempDir, name), []byte(name), 0644); err != nil {
+			t.Fatalf("Failed to create file: %v", err)
+		}
+		runGit(t, tempDir, "add", name)
+		runGit(t, tempDir, "commit", "-m", "Add "+name)
+	}
+	reviewed := strings.TrimSpace(runGit(t, tempDir, "rev-parse", "HEAD~1"))
+
+	repo, err := Open(tempDir)
+	if err != nil {
+		t.Fatalf("Failed to open repo: %v", err)
+	}
+
+	files, err := repo.GetDiffSince(context.Background(), reviewed, DiffOptions{})
+	if err != nil {
+		t.Fatalf("Failed to get incremental diff: %v", err)
+	}
+	if len(files) != 1 || files[0].Path != "b.txt" {
+		t.Errorf("Expected only b.txt since the reviewed commit, got %+v", files)
+	}
+
+	if _, err := repo.GetDiffSince(context.Background(), "0123456789abcdef0123456789abcdef01234567", DiffOptions{}); !errors.Is(err, ErrCommitNotInHistory) {
+		t.Errorf("Expected ErrCommitNotInHistory for an unknown commit, got %v", err)
+	}
+}
+
+func TestConfiguredDiffAlgorithm(t *testing.T) {
+	tempDir := setupTestRepo(t)
+
+	repo, err := Open(tempDir)
+	if err != nil {
+		t.Fatalf("Failed to open repo: %v", err)
+	}
+
+	runGit(t, tempDir, "config", "diff.algorithm", "patience")
+	if got := repo.ConfiguredDiffAlgorithm(); got != "patience" {
+		t.Errorf("Expected patience, got %q", got)
+	}
+
+	runGit(t, tempDir, "config", "diff.algorithm", "default")
+	if got := repo.ConfiguredDiffAlgorithm(); got != "myers" {
+		t.Errorf("Expected default to map to myers, got %q", got)
+	}
+}
+
+func TestCheckoutWorktree(t *testing.T) {
+	tempDir := setupTestRepo(t)
+	runGit(t, tempDir, "branch", "feature")
+
+	repo, err := Open(tempDir)
+	if err != nil {
+		t.Fatalf("Failed to open repo: %v", err)
+	}
+
+	worktreePath := filepath.Join(t.TempDir(), "review")
+	if err := repo.CheckoutWorktree(worktreePath, "feature"); err != nil {
+		t.Fatalf("Failed to create worktree: %v", err)
+	}
+
+	if _, err := os.Stat(filepath.Join(worktreePath, "README.md")); err != nil {
+		t.Errorf("Expected README.md in worktree: %v", err)
+	}
+
+	// A second checkout reuses the existing worktree
+	if err := repo.CheckoutWorktree(worktreePath, "feature"); err != nil {
+		t.Errorf("Failed to reuse worktree: %v", err)
+	}
+
+	// A directory that isn't a worktree of the repository is left alone
+	unrelated := t.TempDir()
+	if err := os.WriteFile(filepath.Join(unrelated, "notes.txt"), []byte("mine\n"), 0644); err != nil {
+		t.Fatalf("Failed to write file: %v", err)
+	}
+	err = repo.CheckoutWorktree(unrelated, "feature")
+	if err == nil || !strings.Contains(err.Error(), "is not a worktree") {
+		t.Errorf("Expected an unrelated directory to be refused, got %v", err)
+	}
+	if _, err := os.Stat(filepath.Join(unrelated, "README.md")); !os.IsNotExist(err) {
+		t.Error("Expected nothing to be checked out into the unrelated directory")
+	}
+}
+
+func TestFetchPullRequestOffline(t *testing.T) {
+	tempDir := setupTestRepo(t)
+
+	repo, err := Open(tempDir)
+	if err != nil {
+		t.Fatalf("Failed to open repo: %v", err)
+	}
+	repo.SetOffline(true)
+
+	if _, err := repo.FetchPullRequest(1); !errors.Is(err, ErrOffline) {
+		t.Errorf("Expected ErrOffline, got %v", err)
+	}
+}
+
+func TestAheadBehind(t *testing.T) {
+	tempDir := setupTestRepo(t)
+	runGit(t, tempDir, "checkout", "-b", "base")
+	runGit(t, tempDir, "checkout", "-b", "feature")
+
+	for _, name := range []string{"a.txt", "b.txt"} {
+		if err := os.WriteFile(filepath.Join(tempDir, name), []byte(name), 0644); err != nil {
+			t.Fatalf("Failed to create file: %v", err)
+		}
+		runGit(t, tempDir, "add", name)
+		runGit(t, tempDir, "commit", "-m", "Add "+name)
+	}
+
+	runGit(t, tempDir, "checkout", "base")
+	repo, err := Open(tempDir)
+	if err != nil {
+		t.Fatalf("Failed to open repo: %v", err)
+	}
+	oldTip, err := repo.BaseTip("base")
+	if err != nil {
+		t.Fatalf("Failed to resolve base tip: %v", err)
+	}
+
+	if err := os.WriteFile(filepath.Join(tempDir, "base.txt"), []byte("base"), 0644); err != nil {
+		t.Fatalf("Failed to create file: %v", err)
+	}
+	runGit(t, tempDir, "add", "base.txt")
+	runGit(t, tempDir, "commit", "-m", "Advance base")
+	runGit(t, tempDir, "checkout", "feature")
+
+	newTip, err := repo.BaseTip("base")
+	if err != nil {
+		t.Fatalf("Failed to resolve base tip: %v", err)
+	}
+	if moved, err := repo.CountCommits(oldTip, newTip); err != nil || moved != 1 {
+		t.Errorf("Expected the base to have moved by 1 commit, got %d (%v)", moved, err)
+	}
+
+	ahead, behind, err := repo.AheadBehind("base")
+	if err != nil {
+		t.Fatalf("Failed to count commits: %v", err)
+	}
+	if ahead != 2 || behind != 1 {
+		t.Errorf("Expected 2 ahead and 1 behind, got %d ahead and %d behind", ahead, behind)
+	}
+
+	paths, err := repo.ChangedPaths("base")
+	if err != nil {
+		t.Fatalf("Failed to list changed paths: %v", err)
+	}
+	if strings.Join(paths, ",") != "a.txt,b.txt" {
+		t.Errorf("Expected a.txt and b.txt, without the base's own change, got %v", paths)
+	}
+
+	commits, err := repo.BehindCommits("base", 0)
+	if err != nil {
+		t.Fatalf("Failed to list base commits: %v", err)
+	}
+	if len(commits) != 1 || commits[0].Subject != "Advance base" || commits[0].Author != "Test User" {
+		t.Errorf("Expected the single base commit 'Advance base', got %+v", commits)
+	}
+}
+
+func TestBareRepository(t *testing.T) {
+	sourceDir := setupTestRepo(t)
+	runGit(t, sourceDir, "branch", "base")
+	if err := os.WriteFile(filepath.Join(sourceDir, "feature.txt"), []byte("feature\n"), 0644); err != nil {
+		t.Fatalf("Failed to create file: %v", err)
+	}
+	runGit(t, sourceDir, "add", "feature.txt")
+	runGit(t, sourceDir, "commit", "-m", "Add feature")
+
+	bareDir := filepath.Join(t.TempDir(), "repo.git")
+	runGit(t, sourceDir, "clone", "--bare", sourceDir, bareDir)
+
+	repo, err := Open(bareDir)
+	if err != nil {
+		t.Fatalf("Failed to open bare repo: %v", err)
+	}
+	if !repo.IsBare() {
+		t.Fatal("Expected the repository to be bare")
+	}
+
+	repoPath, err := repo.RepoPath()
+	if err != nil || repoPath != bareDir {
+		t.Errorf("Expected repo path %s, got %s (%v)", bareDir, repoPath, err)
+	}
+
+	files, err := repo.GetDiffFiles("base")
+	if err != nil {
+		t.Fatalf("Failed to diff refs in a bare repo: %v", err)
+	}
+	if len(files) != 1 || files[0].Path != "feature.txt" || files[0].Status != "added" {
+		t.Errorf("Expected feature.txt to be added, got %+v", files)
+	}
+
+	if _, err := repo.GetWorkingChanges(); !errors.Is(err, ErrBareRepository) {
+		t.Errorf("Expected ErrBareRepository for working changes, got %v", err)
+	}
+	if _, err := repo.GetUncommittedChanges(); !errors.Is(err, ErrBareRepository) {
+		t.Errorf("Expected ErrBareRepository for uncommitted changes, got %v", err)
+	}
+	if dirty, err := repo.IsDirty(); err != nil || dirty {
+		t.Errorf("Expected a bare repo to be clean, got %v (%v)", dirty, err)
+	}
+}
+
+func TestGetDiffFilesRecursesIntoSubmodules(t *testing.T) {
+	subDir := setupTestRepo(t)
+	tempDir := setupTestRepo(t)
+
+	runGit(t, tempDir, "-c", "protocol.file.allow=always", "submodule", "add", subDir, "vendor/lib")
+	runGit(t, tempDir, "commit", "-m", "Add submodule")
+	runGit(t, tempDir, "branch", "base")
+	runGit(t, tempDir, "checkout", "-b", "feature")
+
+	submodulePath := filepath.Join(tempDir, "vendor", "lib")
+	runGit(t, submodulePath, "config", "user.email", "test@test.com")
+	runGit(t, submodulePath, "config", "user.name", "Test User")
+	if err := os.WriteFile(filepath.Join(submodulePath, "lib.go"), []byte("package lib\n"), 0644); err != nil {
+		t.Fatalf("Failed to create file: %v", err)
+	}
+	runGit(t, submodulePath, "add", "lib.go")
+	runGit(t, submodulePath, "commit", "-m", "Add lib.go")
+	runGit(t, tempDir, "add", "vendor/lib")
+	runGit(t, tempDir, "commit", "-m", "Bump submodule")
+
+	repo, err := Open(tempDir)
+	if err != nil {
+		t.Fatalf("Failed to open repo: %v", err)
+	}
+
+	files, err := repo.GetDiffFiles("base")
+	if err != nil {
+		t.Fatalf("Failed to get diff files: %v", err)
+	}
+	if len(files) != 1 || files[0].Path != "vendor/lib" {
+		t.Fatalf("Expected only the submodule pointer change by default, got %+v", files)
+	}
+
+	files, err = repo.GetDiffFilesWithOptions("base", DiffOptions{RecurseSubmodules: true})
+	if err != nil {
+		t.Fatalf("Failed to get diff files: %v", err)
+	}
+	if len(files) != 1 || files[0].Path != "vendor/lib/lib.go" || files[0].Status != "added" {
+		t.Fatalf("Expected the file inside the submodule, got %+v", files)
+	}
+	if !strings.Contains(files[0].Patch, "+++ b/vendor/lib/lib.go") {
+		t.Errorf("Expected patch paths to be prefixed with the submodule path, got:\n%s", files[0].Patch)
+	}
+
+	// An uninitialized submodule falls back to the pointer change
+	runGit(t, tempDir, "submodule", "deinit", "-f", "vendor/lib")
+	files, err = repo.GetDiffFilesWithOptions("base", DiffOptions{RecurseSubmodules: true})
+	if err != nil {
+		t.Fatalf("Failed to get diff files: %v", err)
+	}
+	if len(files) != 1 || files[0].Path != "vendor/lib" {
+		t.Errorf("Expected the pointer change for an uninitialized submodule, got %+v", files)
+	}
+}
+
+func TestGetDiffFilesContextCancelled(t *testing.T) {
+	tempDir := setupTestRepo(t)
+	runGit(t, tempDir, "checkout", "-b", "base")
+	runGit(t, tempDir, "checkout", "-b", "feature")
+
+	if err := os.WriteFile(filepath.Join(tempDir, "new.txt"), []byte("new\n"), 0644); err != nil {
+		t.Fatalf("Failed to create file: %v", err)
+	}
+	runGit(t, tempDir, "add", "new.txt")
+	runGit(t, tempDir, "commit", "-m", "Add new.txt")
+
+	repo, err := Open(tempDir)
+	if err != nil {
+		t.Fatalf("Failed to open repo: %v", err)
+	}
+
+	ctx, cancel := context.WithCancel(context.Background())
+	cancel()
+
+	if _, err := repo.GetDiffFilesContext(ctx, "base", DiffOptions{}); !errors.Is(err, context.Canceled) {
+		t.Errorf("Expected context.Canceled, got %v", err)
+	}
+	if _, err := repo.GetDiffFilesContext(ctx, "base", DiffOptions{Algorithm: "patience"}); !errors.Is(err, context.Canceled) {
+		t.Errorf("Expected context.Canceled with a CLI algorithm, got %v", err)
+	}
+}
+
+func TestBlame(t *testing.T) {
+	tempDir := setupTestRepo(t)
+
+	if err := os.WriteFile(filepath.Join(tempDir, "README.md"), []byte("# Test Repo\nSecond line\n"), 0644); err != nil {
+		t.Fatalf("Failed to update file: %v", err)
+	}
+	runGit(t, tempDir, "-c", "user.name=Other Author", "-c", "user.email=other@test.com", "commit", "-am", "Add a line")
+
+	repo, err := Open(tempDir)
+	if err != nil {
+		t.Fatalf("Failed to open repo: %v", err)
+	}
+
+	head, err := repo.CurrentCommit()
+	if err != nil {
+		t.Fatalf("Failed to get HEAD: %v", err)
+	}
+
+	lines, err := repo.Blame(head, "README.md")
+	if err != nil {
+		t.Fatalf("Failed to blame: %v", err)
+	}
+	if len(lines) != 2 {
+		t.Fatalf("Expected 2 lines, got %d", len(lines))
+	}
+	if lines[0].Author != "Test User" || lines[0].Commit == head {
+		t.Errorf("Expected the first line from the initial commit, got %+v", lines[0])
+	}
+	if lines[1].Author != "Other Author" || lines[1].Commit != head || lines[1].Text != "Second line" {
+		t.Errorf("Expected the second line from HEAD, got %+v", lines[1])
+	}
+
+	if _, err := repo.Blame(head, "missing.txt"); !errors.Is(err, ErrFileNotInCommit) {
+		t.Errorf("Expected ErrFileNotInCommit, got %v", err)
+	}
+}
+
+func TestFileLines(t *testing.T) {
+	tempDir := setupTestRepo(t)
+
+	if err := os.WriteFile(filepath.Join(tempDir, "lines.txt"), []byte("one\ntwo\nthree\nfour\n"), 0644); err != nil {
+		t.Fatalf("Failed to create file: %v", err)
+	}
+	runGit(t, tempDir, "add", "lines.txt")
+	runGit(t, tempDir, "commit", "-m", "Add lines")
+
+	repo, err := Open(tempDir)
+	if err != nil {
+		t.Fatalf("Failed to open repo: %v", err)
+	}
+
+	head, err := repo.CurrentCommit()
+	if err != nil {
+		t.Fatalf("Failed to get HEAD: %v", err)
+	}
+
+	lines, total, err := repo.FileLines(head, "lines.txt", 2, 3)
+	if err != nil {
+		t.Fatalf("Failed to read lines: %v", err)
+	}
+	if total != 4 || strings.Join(lines, ",") != "two,three" {
+		t.Errorf("Expected lines two and three of 4, got %v of %d", lines, total)
+	}
+
+	// Out-of-range requests are clamped
+	lines, _, err = repo.FileLines(head, "lines.txt", -5, 100)
+	if err != nil || len(lines) != 4 {
+		t.Errorf("Expected all 4 lines when clamped, got %v, %v", lines, err)
+	}
+	lines, _, err = repo.FileLines(head, "lines.txt", 10, 20)
+	if err != nil || len(lines) != 0 {
+		t.Errorf("Expected no lines past the end, got %v, %v", lines, err)
+	}
+
+	// An empty commit reads the working tree
+	if err := os.WriteFile(filepath.Join(tempDir, "lines.txt"), []byte("changed\n"), 0644); err != nil {
+		t.Fatalf("Failed to update file: %v", err)
+	}
+	lines, _, err = repo.FileLines("", "lines.txt", 1, 1)
+	if err != nil || len(lines) != 1 || lines[0] != "changed" {
+		t.Errorf("Expected the working tree version, got %v, %v", lines, err)
+	}
+
+	if _, _, err := repo.FileLines("", "../outside.txt", 1, 1); err == nil {
+		t.Error("Expected an error for a path outside the repository")
+	}
+}
+
+func TestIgnoredPaths(t *testing.T) {
+	tempDir := setupTestRepo(t)
+
+	if err := os.WriteFile(filepath.Join(tempDir, ".gitignore"), []byte("build/\n*.log\n"), 0644); err != nil {
+		t.Fatalf("Failed to write .gitignore: %v", err)
+	}
+	if err := os.MkdirAll(filepath.Join(tempDir, "build", "out"), 0755); err != nil {
+		t.Fatalf("Failed to create directory: %v", err)
+	}
+	if err := os.WriteFile(filepath.Join(tempDir, "build", "out", "app"), []byte("binary"), 0644); err != nil {
+		t.Fatalf("Failed to write file: %v", err)
+	}
+	// Tracked files stay relevant even when they match an ignore pattern
+	if err := os.WriteFile(filepath.Join(tempDir, "kept.log"), []byte("log"), 0644); err != nil {
+		t.Fatalf("Failed to write file: %v", err)
+	}
+	runGit(t, tempDir, "add", "-f", "kept.log")
+
+	repo, err := Open(tempDir)
+	if err != nil {
+		t.Fatalf("Failed to open repo: %v", err)
+	}
+
+	dirs, err := repo.IgnoredDirs()
+	if err != nil {
+		t.Fatalf("Failed to list ignored directories: %v", err)
+	}
+	if len(dirs) != 1 || dirs[0] != "build" {
+		t.Errorf("Expected only build to be ignored, got %v", dirs)
+	}
+
+	ignored, err := repo.IgnoredPaths([]string{"README.md", "build/out/app", "debug.log", "kept.log"})
+	if err != nil {
+		t.Fatalf("Failed to check ignored paths: %v", err)
+	}
+	want := map[string]bool{"build/out/app": true, "debug.log": true}
+	if len(ignored) != len(want) || !ignored["build/out/app"] || !ignored["debug.log"] {
+		t.Errorf("Expected %v to be ignored, got %v", want, ignored)
+	}
+
+	if ignored, err := repo.IgnoredPaths([]string{"README.md"}); err != nil || len(ignored) != 0 {
+		t.Errorf("Expected nothing to be ignored, got %v (%v)", ignored, err)
+	}
+}
+
+func TestChangedPathsBetween(t *testing.T) {
+	tempDir := setupTestRepo(t)
+
+	if err := os.WriteFile(filepath.Join(tempDir, "kept.txt"), []byte("kept\n"), 0644); err != nil {
+		t.Fatalf("Failed to write file: %v", err)
+	}
+	runGit(t, tempDir, "add", ".")
+	runGit(t, tempDir, "commit", "-m", "Add kept.txt")
+	first := strings.TrimSpace(runGit(t, tempDir, "rev-parse", "HEAD"))
+
+	// An amend that only touches README.md
+	if err := os.WriteFile(filepath.Join(tempDir, "README.md"), []byte("# Amended\n"), 0644); err != nil {
+		t.Fatalf("Failed to write file: %v", err)
+	}
+	runGit(t, tempDir, "commit", "-a", "--amend", "--no-edit")
+	amended := strings.TrimSpace(runGit(t, tempDir, "rev-parse", "HEAD"))
+
+	repo, err := Open(tempDir)
+	if err != nil {
+		t.Fatalf("Failed to open repo: %v", err)
+	}
+	changed, err := repo.ChangedPathsBetween(first, amended)
+	if err != nil {
+		t.Fatalf("Failed to diff commits: %v", err)
+	}
+	if len(changed) != 1 || !changed["README.md"] {
+		t.Errorf("Expected only README.md to have changed, got %v", changed)
+	}
+
+	if _, err := repo.ChangedPathsBetween("0000000000000000000000000000000000000000", amended); err == nil {
+		t.Error("Expected an error for a missing commit")
+	}
+}
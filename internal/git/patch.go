@@ -0,0 +1,339 @@
+package git
+
+import (
+	"errors"
+	"fmt"
+	"io"
+	"regexp"
+	"strconv"
+	"strings"
+)
+
+// ErrEmptyPatch is returned by ParsePatch when the input has no file changes
+var ErrEmptyPatch = errors.New("no file changes found in patch")
+
+var hunkHeaderPattern = regexp.MustCompile(`^@@ -(\d+)(?:,(\d+))? \+(\d+)(?:,(\d+))? @@`)
+
+// DiffLine is a line of a patch's hunks. Origin is "+" for an added line, "-"
+// for a removed one and " " for context. OldLineno is 0 for added lines and
+// NewLineno is 0 for removed ones.
+type DiffLine struct {
+	Origin    string `json:"origin"`
+	OldLineno int    `json:"old_lineno,omitempty"`
+	NewLineno int    `json:"new_lineno,omitempty"`
+	Content   string `json:"content"`
+}
+
+// patchFile accumulates one file's section of a patch while it's parsed
+type patchFile struct {
+	oldPath string
+	newPath string
+	added   bool
+	deleted bool
+	copied  bool
+	// oldMode and newMode are set when the file's mode changed
+	oldMode string
+	newMode string
+	inHunks bool
+	body    strings.Builder
+	info    FileInfo
+}
+
+// ParsePatch splits a unified diff, such as the output of git diff or git
+// format-patch, into per-file changes. Anything outside the file sections,
+// like a format-patch email's headers, commit message and signature, is ignored.
+func ParsePatch(patch string) ([]FileInfo, error) {
+	var files []*patchFile
+	var current *patchFile
+	// Lines left in the current hunk on each side, so hunk lines starting with
+	// "---" or "diff" aren't mistaken for headers
+	oldLeft, newLeft := 0, 0
+
+	lines := strings.SplitAfter(patch, "\n")
+	for i, line := range lines {
+		text := strings.TrimRight(line, "\r\n")
+
+		if oldLeft > 0 || newLeft > 0 {
+			switch {
+			case strings.HasPrefix(text, "+"):
+				newLeft--
+				current.info.Additions++
+			case strings.HasPrefix(text, "-"):
+				oldLeft--
+				current.info.Deletions++
+			case strings.HasPrefix(text, `\`):
+				// "\ No newline at end of file" doesn't count against the hunk
+			default:
+				// Context; mail clients sometimes strip the space from empty ones
+				oldLeft--
+				newLeft--
+			}
+			current.body.WriteString(line)
+			continue
+		}
+
+		switch {
+		case strings.HasPrefix(text, "diff --git "):
+			current = &patchFile{}
+			current.oldPath, current.newPath = gitHeaderPaths(strings.TrimPrefix(text, "diff --git "))
+			files = append(files, current)
+		case strings.HasPrefix(text, "--- ") && i+1 < len(lines) && strings.HasPrefix(lines[i+1], "+++ "):
+			// Plain unified diffs have no "diff --git" line to start a file
+			if current == nil || current.inHunks {
+				current = &patchFile{}
+				files = append(files, current)
+			}
+			current.oldPath = patchPath(text[len("--- "):])
+		case strings.HasPrefix(text, "+++ ") && current != nil && !current.inHunks:
+			current.newPath = patchPath(text[len("+++ "):])
+		case text == "-- ":
+			// The signature separator format-patch puts after the last file
+			current = nil
+			continue
+		case strings.HasPrefix(text, "@@") && current != nil:
+			match := hunkHeaderPattern.FindStringSubmatch(text)
+			if match == nil {
+				return nil, fmt.Errorf("malformed hunk header: %q", text)
+			}
+			oldLeft, newLeft = hunkLength(match[2]), hunkLength(match[4])
+			current.inHunks = true
+		case current != nil && !current.inHunks:
+			// Extended header lines between "diff --git" and the first hunk
+			switch {
+			case strings.HasPrefix(text, "new file mode"):
+				current.added = true
+			case strings.HasPrefix(text, "deleted file mode"):
+				current.deleted = true
+			case strings.HasPrefix(text, "rename from "):
+				current.oldPath = strings.TrimPrefix(text, "rename from ")
+			case strings.HasPrefix(text, "rename to "):
+				current.newPath = strings.TrimPrefix(text, "rename to ")
+			case strings.HasPrefix(text, "copy from "):
+				current.oldPath = strings.TrimPrefix(text, "copy from ")
+				current.copied = true
+			case strings.HasPrefix(text, "copy to "):
+				current.newPath = strings.TrimPrefix(text, "copy to ")
+			case strings.HasPrefix(text, "old mode "):
+				current.oldMode = strings.TrimPrefix(text, "old mode ")
+			case strings.HasPrefix(text, "new mode "):
+				current.newMode = strings.TrimPrefix(text, "new mode ")
+			}
+		default:
+			// Outside any file, e.g. a format-patch email's headers and commit message
+			continue
+		}
+		current.body.WriteString(line)
+	}
+
+	if len(files) == 0 {
+		return nil, ErrEmptyPatch
+	}
+
+	result := make([]FileInfo, 0, len(files))
+	for _, file := range files {
+		info := file.info
+		info.Patch = file.body.String()
+		info.Path = file.newPath
+		switch {
+		case file.added || file.oldPath == "":
+			info.Status, info.Kind = "added", ChangeAdded
+		case file.deleted || file.newPath == "":
+			info.Status, info.Kind = "deleted", ChangeDeleted
+			info.Path = file.oldPath
+		case file.copied:
+			info.Status, info.Kind = "added", ChangeCopied
+			info.OldPath = file.oldPath
+		case file.oldPath != file.newPath:
+			info.Status, info.Kind = "renamed", ChangeRenamed
+			info.OldPath = file.oldPath
+		case modeType(file.oldMode) != modeType(file.newMode):
+			info.Status, info.Kind = "modified", ChangeTypeChanged
+		default:
+			info.Status, info.Kind = "modified", ChangeModified
+		}
+		result = append(result, info)
+	}
+	return result, nil
+}
+
+// WritePatch writes the patches of files one after the other as a single
+// unified diff, like git diff prints, for tools such as patch, git apply or
+// delta. It's the inverse of ParsePatch.
+func WritePatch(w io.Writer, files []FileInfo) error {
+	for _, file := range files {
+		if file.Patch == "" {
+			continue
+		}
+		patch := file.Patch
+		if !strings.HasSuffix(patch, "\n") {
+			patch += "\n"
+		}
+		if _, err := io.WriteString(w, patch); err != nil {
+			return err
+		}
+	}
+	return nil
+}
+
+// modeType reduces an octal git file mode from a patch header to the kind of
+// entry it is, as entryType does; an empty mode (unchanged) is a regular file
+func modeType(mode string) string {
+	switch mode {
+	case "120000", "160000":
+		return mode
+	}
+	return "100644"
+}
+
+// PatchLines numbers the hunk lines of a single file's patch, so clients can
+// show line numbers without parsing hunk headers. File headers and "\ No
+// newline at end of file" markers are left out.
+func PatchLines(patch string) []DiffLine {
+	lines := []DiffLine{}
+	for _, hunk := range patchHunks(patch) {
+		lines = append(lines, hunk.lines...)
+	}
+	return lines
+}
+
+// patchHunk is a hunk of a single file's patch with its lines numbered
+type patchHunk struct {
+	header string
+	lines  []DiffLine
+}
+
+func patchHunks(patch string) []patchHunk {
+	hunks := []patchHunk{}
+	oldN, newN := 0, 0
+	oldLeft, newLeft := 0, 0
+
+	// Without the final newline, a patch cut short mid-hunk doesn't end in an
+	// empty context line
+	for _, line := range strings.Split(strings.TrimSuffix(patch, "\n"), "\n") {
+		text := strings.TrimSuffix(line, "\r")
+
+		if oldLeft <= 0 && newLeft <= 0 {
+			if match := hunkHeaderPattern.FindStringSubmatch(text); match != nil {
+				oldN, _ = strconv.Atoi(match[1])
+				newN, _ = strconv.Atoi(match[3])
+				oldLeft, newLeft = hunkLength(match[2]), hunkLength(match[4])
+				hunks = append(hunks, patchHunk{header: text})
+			}
+			continue
+		}
+
+		hunk := &hunks[len(hunks)-1]
+		switch {
+		case strings.HasPrefix(text, "+"):
+			hunk.lines = append(hunk.lines, DiffLine{Origin: "+", NewLineno: newN, Content: text[1:]})
+			newN++
+			newLeft--
+		case strings.HasPrefix(text, "-"):
+			hunk.lines = append(hunk.lines, DiffLine{Origin: "-", OldLineno: oldN, Content: text[1:]})
+			oldN++
+			oldLeft--
+		case strings.HasPrefix(text, `\`):
+			// "\ No newline at end of file" doesn't count against the hunk
+		default:
+			// Context; mail clients sometimes strip the space from empty ones
+			hunk.lines = append(hunk.lines, DiffLine{Origin: " ", OldLineno: oldN, NewLineno: newN, Content: strings.TrimPrefix(text, " ")})
+			oldN++
+			newN++
+			oldLeft--
+			newLeft--
+		}
+	}
+
+	return hunks
+}
+
+// ParseCombinedPatch splits a combined diff of a merge commit, as printed by
+// git diff-tree --cc, into per-file changes. Each hunk line has one prefix
+// column per parent; a line counts as an addition when it was added relative
+// to any parent and as a deletion when it was removed from any of them.
+func ParseCombinedPatch(patch string) []FileInfo {
+	files := []FileInfo{}
+	var current *FileInfo
+	var body strings.Builder
+	// Prefix columns of the lines in the current hunk, 0 outside hunks
+	columns := 0
+
+	flush := func() {
+		if current != nil {
+			current.Patch = body.String()
+			files = append(files, *current)
+		}
+		body.Reset()
+	}
+
+	for _, line := range strings.SplitAfter(patch, "\n") {
+		text := strings.TrimRight(line, "\r\n")
+
+		switch {
+		case strings.HasPrefix(text, "diff --cc ") || strings.HasPrefix(text, "diff --combined "):
+			flush()
+			_, path, _ := strings.Cut(text[len("diff --"):], " ")
+			current = &FileInfo{Path: path, Status: "modified", Kind: ChangeModified}
+			columns = 0
+		case current == nil:
+			continue
+		case strings.HasPrefix(text, "@@@"):
+			columns = len(text) - len(strings.TrimLeft(text, "@")) - 1
+		case columns == 0:
+			// Extended header lines before the first hunk
+			switch {
+			case strings.HasPrefix(text, "new file mode"):
+				current.Status, current.Kind = "added", ChangeAdded
+			case strings.HasPrefix(text, "deleted file mode"):
+				current.Status, current.Kind = "deleted", ChangeDeleted
+			}
+		case len(text) >= columns:
+			prefix := text[:columns]
+			if strings.Contains(prefix, "+") {
+				current.Additions++
+			}
+			if strings.Contains(prefix, "-") {
+				current.Deletions++
+			}
+		}
+		if current != nil && line != "" {
+			body.WriteString(line)
+		}
+	}
+	flush()
+
+	return files
+}
+
+// gitHeaderPaths extracts the old and new paths from the "a/<old> b/<new>"
+// part of a "diff --git" line. Patches that change content also name the
+// paths in their ---/+++ lines, which take precedence.
+func gitHeaderPaths(header string) (string, string) {
+	if i := strings.Index(header, " b/"); i != -1 && strings.HasPrefix(header, "a/") {
+		return header[len("a/"):i], header[i+len(" b/"):]
+	}
+	return header, header
+}
+
+// patchPath turns the file name of a ---/+++ line into a repository path.
+// /dev/null, used for added and deleted files, becomes an empty path.
+func patchPath(name string) string {
+	// Some tools append a timestamp after a tab
+	name, _, _ = strings.Cut(name, "\t")
+	if name == "/dev/null" {
+		return ""
+	}
+	if strings.HasPrefix(name, "a/") || strings.HasPrefix(name, "b/") {
+		return name[2:]
+	}
+	return name
+}
+
+// hunkLength parses the optional line count of a hunk range, which defaults to 1
+func hunkLength(count string) int {
+	if count == "" {
+		return 1
+	}
+	n, _ := strconv.Atoi(count) // The pattern only matches digits
+	return n
+}
@@ -0,0 +1,188 @@
+package git
+
+import (
+	"errors"
+	"strings"
+	"testing"
+)
+
+const formatPatch = `From 1234567890abcdef Mon Sep 17 00:00:00 2001
+From: Test User <test@test.com>
+Subject: [PATCH] Update files
+
+---
+ docs/old.md  | 0
+ main.go      | 3 ++-
+ new.txt      | 2 ++
+ 3 files changed
+
+diff --git a/main.go b/main.go
+index 1111111..2222222 100644
+--- a/main.go
++++ b/main.go
+@@ -1,3 +1,4 @@
+ package main
+--- not a header, a removed line
++// a comment
++func main() {}
+
+diff --git a/new.txt b/new.txt
+new file mode 100644
+index 0000000..3333333
+--- /dev/null
++++ b/new.txt
+@@ -0,0 +1,2 @@
++one
++two
+diff --git a/docs/old.md b/docs/new.md
+similarity index 100%
+rename from docs/old.md
+rename to docs/new.md
+-- 
+2.39.0
+`
+
+func TestParsePatch(t *testing.T) {
+	files, err := ParsePatch(formatPatch)
+	if err != nil {
+		t.Fatalf("Failed to parse patch: %v", err)
+	}
+	if len(files) != 3 {
+		t.Fatalf("Expected 3 files, got %d: %+v", len(files), files)
+	}
+
+	modified := files[0]
+	if modified.Path != "main.go" || modified.Status != "modified" || modified.Additions != 2 || modified.Deletions != 1 {
+		t.Errorf("Unexpected main.go change: %+v", modified)
+	}
+	if !strings.HasPrefix(modified.Patch, "diff --git a/main.go b/main.go\n") || !strings.HasSuffix(modified.Patch, "+func main() {}\n\n") {
+		t.Errorf("Expected main.go's patch to cover its whole section, got:\n%s", modified.Patch)
+	}
+
+	if files[1].Path != "new.txt" || files[1].Status != "added" || files[1].Additions != 2 {
+		t.Errorf("Unexpected new.txt change: %+v", files[1])
+	}
+
+	renamed := files[2]
+	if renamed.Path != "docs/new.md" || renamed.Status != "renamed" {
+		t.Errorf("Unexpected rename: %+v", renamed)
+	}
+	if strings.Contains(renamed.Patch, "2.39.0") {
+		t.Errorf("Expected the email signature to be left out, got:\n%s", renamed.Patch)
+	}
+}
+
+func TestWritePatch(t *testing.T) {
+	files, err := ParsePatch(formatPatch)
+	if err != nil {
+		t.Fatalf("Failed to parse patch: %v", err)
+	}
+	files = append(files, FileInfo{Path: "empty.bin"}, FileInfo{Path: "last.txt", Patch: "diff --git a/last.txt b/last.txt\n--- a/last.txt\n+++ b/last.txt\n@@ -1 +1 @@\n-a\n+b"})
+
+	var out strings.Builder
+	if err := WritePatch(&out, files); err != nil {
+		t.Fatalf("Failed to write patch: %v", err)
+	}
+	if !strings.HasPrefix(out.String(), "diff --git a/main.go b/main.go\n") || !strings.HasSuffix(out.String(), "+b\n") {
+		t.Errorf("Expected the patches concatenated and newline-terminated, got:\n%s", out.String())
+	}
+
+	reparsed, err := ParsePatch(out.String())
+	if err != nil {
+		t.Fatalf("Failed to parse the written patch: %v", err)
+	}
+	if len(reparsed) != 4 {
+		t.Fatalf("Expected the 4 files with patches back, got %d: %+v", len(reparsed), reparsed)
+	}
+	for i, want := range []string{"main.go", "new.txt", "docs/new.md", "last.txt"} {
+		if reparsed[i].Path != want {
+			t.Errorf("Expected %s at %d, got %s", want, i, reparsed[i].Path)
+		}
+	}
+}
+
+func TestPatchLines(t *testing.T) {
+	files, err := ParsePatch(formatPatch)
+	if err != nil {
+		t.Fatalf("Failed to parse patch: %v", err)
+	}
+
+	expected := []DiffLine{
+		{Origin: " ", OldLineno: 1, NewLineno: 1, Content: "package main"},
+		{Origin: "-", OldLineno: 2, Content: "-- not a header, a removed line"},
+		{Origin: "+", NewLineno: 2, Content: "// a comment"},
+		{Origin: "+", NewLineno: 3, Content: "func main() {}"},
+		{Origin: " ", OldLineno: 3, NewLineno: 4, Content: ""},
+	}
+	lines := PatchLines(files[0].Patch)
+	if len(lines) != len(expected) {
+		t.Fatalf("Expected %d lines, got %d: %+v", len(expected), len(lines), lines)
+	}
+	for i, line := range lines {
+		if line != expected[i] {
+			t.Errorf("Line %d: expected %+v, got %+v", i, expected[i], line)
+		}
+	}
+
+	if lines := PatchLines(files[2].Patch); len(lines) != 0 {
+		t.Errorf("Expected a pure rename to have no lines, got %+v", lines)
+	}
+}
+
+func TestParsePatchPlainDiff(t *testing.T) {
+	files, err := ParsePatch("--- old.txt\t2024-01-01\n+++ old.txt\t2024-01-02\n@@ -1 +1 @@\n-before\n+after\n")
+	if err != nil {
+		t.Fatalf("Failed to parse patch: %v", err)
+	}
+	if len(files) != 1 || files[0].Path != "old.txt" || files[0].Status != "modified" || files[0].Additions != 1 || files[0].Deletions != 1 {
+		t.Errorf("Unexpected files: %+v", files)
+	}
+
+	if _, err := ParsePatch("just an email\n"); !errors.Is(err, ErrEmptyPatch) {
+		t.Errorf("Expected ErrEmptyPatch, got %v", err)
+	}
+}
+
+func TestParseCombinedPatch(t *testing.T) {
+	patch := `diff --cc conflict.txt
+index 1111111,2222222..3333333
+--- a/conflict.txt
++++ b/conflict.txt
+@@@ -1,2 -1,2 +1,2 @@@
+  shared
+- ours
+ -theirs
+++resolved
+diff --cc added.txt
+index 0000000,0000000..4444444
+new file mode 100644
+--- /dev/null
++++ b/added.txt
+@@@ -1,0 -1,0 +1,1 @@@
+++evil
+`
+
+	files := ParseCombinedPatch(patch)
+	if len(files) != 2 {
+		t.Fatalf("Expected 2 files, got %d: %+v", len(files), files)
+	}
+
+	conflict := files[0]
+	if conflict.Path != "conflict.txt" || conflict.Status != "modified" {
+		t.Errorf("Unexpected first file: %+v", conflict)
+	}
+	if conflict.Additions != 1 || conflict.Deletions != 2 {
+		t.Errorf("Expected 1 addition and 2 deletions, got %d and %d", conflict.Additions, conflict.Deletions)
+	}
+	if !strings.HasPrefix(conflict.Patch, "diff --cc conflict.txt\n") || !strings.HasSuffix(conflict.Patch, "++resolved\n") {
+		t.Errorf("Expected the file's whole section as its patch, got %q", conflict.Patch)
+	}
+
+	if files[1].Path != "added.txt" || files[1].Status != "added" || files[1].Additions != 1 {
+		t.Errorf("Unexpected second file: %+v", files[1])
+	}
+
+	if files := ParseCombinedPatch(""); len(files) != 0 {
+		t.Errorf("Expected no files for an empty diff, got %+v", files)
+	}
+}
@@ -0,0 +1,73 @@
+package git
+
+// SplitLineType tells what a side of a side-by-side diff row holds
+type SplitLineType string
+
+const (
+	SplitContext SplitLineType = "context"
+	SplitRemoved SplitLineType = "removed"
+	SplitAdded   SplitLineType = "added"
+	// SplitEmpty pads the side opposite a line the other side doesn't have
+	SplitEmpty SplitLineType = "empty"
+)
+
+// SplitLine is one side of a row of a side-by-side diff. Lineno is the line's
+// number in the old file on the left and in the new file on the right, and 0
+// for padding.
+type SplitLine struct {
+	Type    SplitLineType `json:"type"`
+	Lineno  int           `json:"lineno,omitempty"`
+	Content string        `json:"content"`
+}
+
+// SplitHunk is a hunk laid out in two columns, the old file on the left and
+// the new one on the right. Left and Right always have the same length, and
+// the lines at an index form one row.
+type SplitHunk struct {
+	Header string      `json:"header"`
+	Left   []SplitLine `json:"left"`
+	Right  []SplitLine `json:"right"`
+}
+
+// SplitPatch lays out the hunks of a single file's patch side by side. Within
+// a run of changed lines, removed lines are paired with added ones in order,
+// so a replaced line sits next to its replacement, and the shorter side is
+// padded to keep the context after the run aligned.
+func SplitPatch(patch string) []SplitHunk {
+	hunks := []SplitHunk{}
+	for _, hunk := range patchHunks(patch) {
+		split := SplitHunk{Header: hunk.header, Left: []SplitLine{}, Right: []SplitLine{}}
+		var removed, added []DiffLine
+		flush := func() {
+			for i := 0; i < max(len(removed), len(added)); i++ {
+				left := SplitLine{Type: SplitEmpty}
+				if i < len(removed) {
+					left = SplitLine{Type: SplitRemoved, Lineno: removed[i].OldLineno, Content: removed[i].Content}
+				}
+				right := SplitLine{Type: SplitEmpty}
+				if i < len(added) {
+					right = SplitLine{Type: SplitAdded, Lineno: added[i].NewLineno, Content: added[i].Content}
+				}
+				split.Left = append(split.Left, left)
+				split.Right = append(split.Right, right)
+			}
+			removed, added = nil, nil
+		}
+
+		for _, line := range hunk.lines {
+			switch line.Origin {
+			case "-":
+				removed = append(removed, line)
+			case "+":
+				added = append(added, line)
+			default:
+				flush()
+				split.Left = append(split.Left, SplitLine{Type: SplitContext, Lineno: line.OldLineno, Content: line.Content})
+				split.Right = append(split.Right, SplitLine{Type: SplitContext, Lineno: line.NewLineno, Content: line.Content})
+			}
+		}
+		flush()
+		hunks = append(hunks, split)
+	}
+	return hunks
+}
@@ -0,0 +1,63 @@
+package git
+
+import "testing"
+
+func TestSplitPatch(t *testing.T) {
+	patch := "diff --git a/main.go b/main.go\n" +
+		"--- a/main.go\n" +
+		"+++ b/main.go\n" +
+		"@@ -1,5 +1,5 @@\n" +
+		" package main\n" +
+		"-var a = 1\n" +
+		"-var b = 2\n" +
+		"+var a = 10\n" +
+		"+var b = 20\n" +
+		"+var c = 30\n" +
+		" \n" +
+		"-func old() {}\n" +
+		"@@ -20,2 +21,3 @@ func main() {\n" +
+		" \tfmt.Println()\n" +
+		"+\tos.Exit(0)\n" +
+		" }\n"
+
+	hunks := SplitPatch(patch)
+	if len(hunks) != 2 {
+		t.Fatalf("Expected 2 hunks, got %d: %+v", len(hunks), hunks)
+	}
+
+	type row struct {
+		left, right SplitLine
+	}
+	expected := [][]row{
+		{
+			{SplitLine{SplitContext, 1, "package main"}, SplitLine{SplitContext, 1, "package main"}},
+			{SplitLine{SplitRemoved, 2, "var a = 1"}, SplitLine{SplitAdded, 2, "var a = 10"}},
+			{SplitLine{SplitRemoved, 3, "var b = 2"}, SplitLine{SplitAdded, 3, "var b = 20"}},
+			{SplitLine{Type: SplitEmpty}, SplitLine{SplitAdded, 4, "var c = 30"}},
+			{SplitLine{SplitContext, 4, ""}, SplitLine{SplitContext, 5, ""}},
+			{SplitLine{SplitRemoved, 5, "func old() {}"}, SplitLine{Type: SplitEmpty}},
+		},
+		{
+			{SplitLine{SplitContext, 20, "\tfmt.Println()"}, SplitLine{SplitContext, 21, "\tfmt.Println()"}},
+			{SplitLine{Type: SplitEmpty}, SplitLine{SplitAdded, 22, "\tos.Exit(0)"}},
+			{SplitLine{SplitContext, 21, "}"}, SplitLine{SplitContext, 23, "}"}},
+		},
+	}
+
+	for h, hunk := range hunks {
+		if len(hunk.Left) != len(hunk.Right) {
+			t.Fatalf("Hunk %d: columns differ in length, %d left and %d right", h, len(hunk.Left), len(hunk.Right))
+		}
+		if len(hunk.Left) != len(expected[h]) {
+			t.Fatalf("Hunk %d: expected %d rows, got %d: %+v", h, len(expected[h]), len(hunk.Left), hunk)
+		}
+		for i, want := range expected[h] {
+			if hunk.Left[i] != want.left || hunk.Right[i] != want.right {
+				t.Errorf("Hunk %d row %d: expected %+v | %+v, got %+v | %+v", h, i, want.left, want.right, hunk.Left[i], hunk.Right[i])
+			}
+		}
+	}
+	if hunks[1].Header != "@@ -20,2 +21,3 @@ func main() {" {
+		t.Errorf("Unexpected hunk header %q", hunks[1].Header)
+	}
+}
@@ -0,0 +1,110 @@
+package github
+
+import (
+	"encoding/json"
+	"errors"
+	"fmt"
+	"net/http"
+	"os"
+	"regexp"
+	"strings"
+	"time"
+)
+
+// apiBaseURL is a variable so tests can point it at a local server
+var apiBaseURL = "https://api.github.com"
+
+var remotePattern = regexp.MustCompile(`^(?:https?://(?:[^@/]+@)?github\.com/|(?:ssh://)?git@github\.com[:/])([^/]+)/([^/]+?)(?:\.git)?/?$`)
+
+// ParseRemote extracts the owner and repository name from a GitHub remote URL.
+// ok is false for remotes that aren't hosted on github.com.
+func ParseRemote(remoteURL string) (owner, repo string, ok bool) {
+	matches := remotePattern.FindStringSubmatch(strings.TrimSpace(remoteURL))
+	if matches == nil {
+		return "", "", false
+	}
+	return matches[1], matches[2], true
+}
+
+// ReviewComment is a pull request review comment as returned by the GitHub API
+type ReviewComment struct {
+	ID   int64  `json:"id"`
+	Path string `json:"path"`
+	// Line is nil for comments on code the pull request has since changed
+	Line *int   `json:"line"`
+	Side string `json:"side"` // "LEFT" for the old version of the file, "RIGHT" for the new one
+	Body string `json:"body"`
+	User struct {
+		Login string `json:"login"`
+	} `json:"user"`
+}
+
+// reviewCommentsPageSize is the largest page the GitHub API serves
+const reviewCommentsPageSize = 100
+
+// PullRequestBase returns the name of the branch a pull request targets.
+// GITHUB_TOKEN is used for authentication when set, which private repos require.
+func PullRequestBase(owner, repo string, number int) (string, error) {
+	var pr struct {
+		Base struct {
+			Ref string `json:"ref"`
+		} `json:"base"`
+	}
+	url := fmt.Sprintf("%s/repos/%s/%s/pulls/%d", apiBaseURL, owner, repo, number)
+	if err := get(url, &pr); err != nil {
+		return "", fmt.Errorf("failed to fetch pull request #%d from GitHub: %w", number, err)
+	}
+
+	if pr.Base.Ref == "" {
+		return "", fmt.Errorf("pull request #%d has no base branch", number)
+	}
+
+	return pr.Base.Ref, nil
+}
+
+// PullRequestReviewComments returns every review comment on a pull request,
+// oldest first. Like PullRequestBase, it authenticates with GITHUB_TOKEN when set.
+func PullRequestReviewComments(owner, repo string, number int) ([]ReviewComment, error) {
+	comments := []ReviewComment{}
+	for page := 1; ; page++ {
+		var batch []ReviewComment
+		url := fmt.Sprintf("%s/repos/%s/%s/pulls/%d/comments?per_page=%d&page=%d", apiBaseURL, owner, repo, number, reviewCommentsPageSize, page)
+		if err := get(url, &batch); err != nil {
+			return nil, fmt.Errorf("failed to fetch review comments of pull request #%d from GitHub: %w", number, err)
+		}
+
+		comments = append(comments, batch...)
+		if len(batch) < reviewCommentsPageSize {
+			return comments, nil
+		}
+	}
+}
+
+// get fetches a GitHub API URL and decodes the JSON response into v
+func get(url string, v interface{}) error {
+	req, err := http.NewRequest("GET", url, nil)
+	if err != nil {
+		return fmt.Errorf("failed to build GitHub request: %w", err)
+	}
+
+	req.Header.Set("Accept", "application/vnd.github+json")
+	if token := os.Getenv("GITHUB_TOKEN"); token != "" {
+		req.Header.Set("Authorization", "Bearer "+token)
+	}
+
+	client := &http.Client{Timeout: 10 * time.Second}
+	resp, err := client.Do(req)
+	if err != nil {
+		return err
+	}
+	defer resp.Body.Close()
+
+	if resp.StatusCode != http.StatusOK {
+		return errors.New(resp.Status)
+	}
+
+	if err := json.NewDecoder(resp.Body).Decode(v); err != nil {
+		return fmt.Errorf("failed to parse response: %w", err)
+	}
+	return nil
+}
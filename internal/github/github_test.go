@@ -0,0 +1,94 @@
+package github
+
+import (
+	"net/http"
+	"net/http/httptest"
+	"testing"
+)
+
+func TestParseRemote(t *testing.T) {
+	tests := []struct {
+		url   string
+		owner string
+		repo  string
+		ok    bool
+	}{
+		{"https://github.com/tuist/guck.git", "tuist", "guck", true},
+		{"https://github.com/tuist/guck", "tuist", "guck", true},
+		{"git@github.com:tuist/guck.git", "tuist", "guck", true},
+		{"ssh://git@github.com/tuist/guck.git", "tuist", "guck", true},
+		{"https://token@github.com/tuist/guck.git", "tuist", "guck", true},
+		{"https://gitlab.com/tuist/guck.git", "", "", false},
+		{"", "", "", false},
+	}
+
+	for _, tt := range tests {
+		owner, repo, ok := ParseRemote(tt.url)
+		if owner != tt.owner || repo != tt.repo || ok != tt.ok {
+			t.Errorf("ParseRemote(%q) = (%q, %q, %v), expected (%q, %q, %v)", tt.url, owner, repo, ok, tt.owner, tt.repo, tt.ok)
+		}
+	}
+}
+
+func TestPullRequestBase(t *testing.T) {
+	srv := httptest.NewServer(http.HandlerFunc(func(w http.ResponseWriter, r *http.Request) {
+		if r.URL.Path != "/repos/tuist/guck/pulls/42" {
+			http.NotFound(w, r)
+			return
+		}
+		_, _ = w.Write([]byte(`{"number": 42, "base": {"ref": "develop"}}`))
+	}))
+	defer srv.Close()
+
+	original := apiBaseURL
+	apiBaseURL = srv.URL
+	defer func() { apiBaseURL = original }()
+
+	base, err := PullRequestBase("tuist", "guck", 42)
+	if err != nil {
+		t.Fatalf("Failed to get base: %v", err)
+	}
+	if base != "develop" {
+		t.Errorf("Expected develop, got %s", base)
+	}
+
+	if _, err := PullRequestBase("tuist", "guck", 7); err == nil {
+		t.Error("Expected an error for a missing pull request")
+	}
+}
+
+func TestPullRequestReviewComments(t *testing.T) {
+	srv := httptest.NewServer(http.HandlerFunc(func(w http.ResponseWriter, r *http.Request) {
+		if r.URL.Path != "/repos/tuist/guck/pulls/42/comments" {
+			http.NotFound(w, r)
+			return
+		}
+		_, _ = w.Write([]byte(`[
+			{"id": 1, "path": "main.go", "line": 12, "side": "RIGHT", "body": "Rename this", "user": {"login": "octocat"}},
+			{"id": 2, "path": "old.go", "line": null, "side": "RIGHT", "body": "Outdated", "user": {"login": "hubot"}}
+		]`))
+	}))
+	defer srv.Close()
+
+	original := apiBaseURL
+	apiBaseURL = srv.URL
+	defer func() { apiBaseURL = original }()
+
+	comments, err := PullRequestReviewComments("tuist", "guck", 42)
+	if err != nil {
+		t.Fatalf("Failed to get review comments: %v", err)
+	}
+	if len(comments) != 2 {
+		t.Fatalf("Expected 2 comments, got %d", len(comments))
+	}
+	if comments[0].Line == nil || *comments[0].Line != 12 || comments[0].User.Login != "octocat" {
+		t.Errorf("Unexpected first comment: %+v", comments[0])
+	}
+	if comments[1].Line != nil {
+		t.Errorf("Expected the outdated comment to have no line, got %d", *comments[1].Line)
+	}
+
+	if _, err := PullRequestReviewComments("tuist", "guck", 7); err == nil {
+		t.Error("Expected an error for a missing pull request")
+	}
+}
@@ -0,0 +1,133 @@
+package gitlab
+
+import (
+	"bytes"
+	"encoding/json"
+	"errors"
+	"fmt"
+	"io"
+	"net/http"
+	"net/url"
+	"os"
+	"strings"
+	"time"
+)
+
+// defaultBaseURL is used when GITLAB_URL isn't set
+const defaultBaseURL = "https://gitlab.com"
+
+// DiffRefs are the commits a merge request's diff is computed between. Every
+// diff position must name them.
+type DiffRefs struct {
+	BaseSHA  string `json:"base_sha"`
+	StartSHA string `json:"start_sha"`
+	HeadSHA  string `json:"head_sha"`
+}
+
+// Position anchors a discussion to a line of a merge request's diff. NewLine
+// alone marks an added line, OldLine alone a removed one, and both an
+// unchanged one.
+type Position struct {
+	PositionType string `json:"position_type"`
+	BaseSHA      string `json:"base_sha"`
+	StartSHA     string `json:"start_sha"`
+	HeadSHA      string `json:"head_sha"`
+	OldPath      string `json:"old_path"`
+	NewPath      string `json:"new_path"`
+	OldLine      *int   `json:"old_line,omitempty"`
+	NewLine      *int   `json:"new_line,omitempty"`
+}
+
+// Discussion is the body of a request to start a merge request discussion.
+// Without a position it's a general discussion on the merge request.
+type Discussion struct {
+	Body     string    `json:"body"`
+	Position *Position `json:"position,omitempty"`
+}
+
+// MergeRequestDiffRefs returns the diff refs of a merge request. project is
+// the numeric ID or the full path, such as "group/name".
+// GITLAB_TOKEN is used for authentication when set, which private projects require.
+func MergeRequestDiffRefs(project string, iid int) (DiffRefs, error) {
+	var mr struct {
+		DiffRefs *DiffRefs `json:"diff_refs"`
+	}
+	if err := do("GET", mergeRequestURL(project, iid), nil, &mr); err != nil {
+		return DiffRefs{}, fmt.Errorf("failed to fetch merge request !%d from GitLab: %w", iid, err)
+	}
+
+	if mr.DiffRefs == nil || mr.DiffRefs.HeadSHA == "" {
+		return DiffRefs{}, fmt.Errorf("merge request !%d has no diff yet", iid)
+	}
+
+	return *mr.DiffRefs, nil
+}
+
+// CreateDiscussion starts a discussion on a merge request. It requires GITLAB_TOKEN.
+func CreateDiscussion(project string, iid int, discussion Discussion) error {
+	if os.Getenv("GITLAB_TOKEN") == "" {
+		return errors.New("GITLAB_TOKEN must be set to post discussions")
+	}
+
+	if err := do("POST", mergeRequestURL(project, iid)+"/discussions", discussion, nil); err != nil {
+		return fmt.Errorf("failed to create discussion on merge request !%d: %w", iid, err)
+	}
+	return nil
+}
+
+func mergeRequestURL(project string, iid int) string {
+	baseURL := strings.TrimSuffix(os.Getenv("GITLAB_URL"), "/")
+	if baseURL == "" {
+		baseURL = defaultBaseURL
+	}
+	return fmt.Sprintf("%s/api/v4/projects/%s/merge_requests/%d", baseURL, url.PathEscape(project), iid)
+}
+
+// do sends a GitLab API request with an optional JSON body and decodes the
+// JSON response into v, if given
+func do(method, endpoint string, body, v interface{}) error {
+	var reqBody io.Reader
+	if body != nil {
+		data, err := json.Marshal(body)
+		if err != nil {
+			return fmt.Errorf("failed to encode GitLab request: %w", err)
+		}
+		reqBody = bytes.NewReader(data)
+	}
+
+	req, err := http.NewRequest(method, endpoint, reqBody)
+	if err != nil {
+		return fmt.Errorf("failed to build GitLab request: %w", err)
+	}
+
+	if body != nil {
+		req.Header.Set("Content-Type", "application/json")
+	}
+	if token := os.Getenv("GITLAB_TOKEN"); token != "" {
+		req.Header.Set("PRIVATE-TOKEN", token)
+	}
+
+	client := &http.Client{Timeout: 10 * time.Second}
+	resp, err := client.Do(req)
+	if err != nil {
+		return err
+	}
+	defer resp.Body.Close()
+
+	if resp.StatusCode < 200 || resp.StatusCode >= 300 {
+		// GitLab explains rejected positions in the body
+		message, _ := io.ReadAll(io.LimitReader(resp.Body, 1024))
+		if len(bytes.TrimSpace(message)) > 0 {
+			return fmt.Errorf("%s: %s", resp.Status, bytes.TrimSpace(message))
+		}
+		return errors.New(resp.Status)
+	}
+
+	if v == nil {
+		return nil
+	}
+	if err := json.NewDecoder(resp.Body).Decode(v); err != nil {
+		return fmt.Errorf("failed to parse response: %w", err)
+	}
+	return nil
+}
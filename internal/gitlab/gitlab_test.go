@@ -0,0 +1,63 @@
+package gitlab
+
+import (
+	"encoding/json"
+	"net/http"
+	"net/http/httptest"
+	"testing"
+)
+
+func TestMergeRequestDiffRefs(t *testing.T) {
+	srv := httptest.NewServer(http.HandlerFunc(func(w http.ResponseWriter, r *http.Request) {
+		if r.URL.EscapedPath() != "/api/v4/projects/group%2Fguck/merge_requests/42" {
+			http.NotFound(w, r)
+			return
+		}
+		_, _ = w.Write([]byte(`{"iid": 42, "diff_refs": {"base_sha": "b", "start_sha": "s", "head_sha": "h"}}`))
+	}))
+	defer srv.Close()
+	t.Setenv("GITLAB_URL", srv.URL)
+
+	refs, err := MergeRequestDiffRefs("group/guck", 42)
+	if err != nil {
+		t.Fatalf("Failed to get diff refs: %v", err)
+	}
+	if refs != (DiffRefs{BaseSHA: "b", StartSHA: "s", HeadSHA: "h"}) {
+		t.Errorf("Unexpected diff refs: %+v", refs)
+	}
+
+	if _, err := MergeRequestDiffRefs("group/guck", 7); err == nil {
+		t.Error("Expected an error for a missing merge request")
+	}
+}
+
+func TestCreateDiscussion(t *testing.T) {
+	var received Discussion
+	var token string
+	srv := httptest.NewServer(http.HandlerFunc(func(w http.ResponseWriter, r *http.Request) {
+		token = r.Header.Get("PRIVATE-TOKEN")
+		if err := json.NewDecoder(r.Body).Decode(&received); err != nil {
+			http.Error(w, err.Error(), http.StatusBadRequest)
+			return
+		}
+		w.WriteHeader(http.StatusCreated)
+		_, _ = w.Write([]byte(`{"id": "abc"}`))
+	}))
+	defer srv.Close()
+	t.Setenv("GITLAB_URL", srv.URL)
+
+	t.Setenv("GITLAB_TOKEN", "")
+	if err := CreateDiscussion("1", 42, Discussion{Body: "Hi"}); err == nil {
+		t.Error("Expected an error without GITLAB_TOKEN")
+	}
+
+	t.Setenv("GITLAB_TOKEN", "secret")
+	line := 3
+	discussion := Discussion{Body: "Why?", Position: &Position{PositionType: "text", NewPath: "a.go", OldPath: "a.go", NewLine: &line}}
+	if err := CreateDiscussion("1", 42, discussion); err != nil {
+		t.Fatalf("Failed to create discussion: %v", err)
+	}
+	if token != "secret" || received.Body != "Why?" || received.Position == nil || *received.Position.NewLine != 3 {
+		t.Errorf("Unexpected request: token %q, body %+v", token, received)
+	}
+}
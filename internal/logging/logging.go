@@ -0,0 +1,49 @@
+package logging
+
+import (
+	"fmt"
+	"io"
+	"log/slog"
+	"os"
+)
+
+// Log formats for the log_format config
+const (
+	// FormatText writes human-readable key=value lines
+	FormatText = "text"
+	// FormatJSON writes one JSON object per line, for log aggregators
+	FormatJSON = "json"
+)
+
+// EnvFormat overrides the log_format config when set
+const EnvFormat = "GUCK_LOG_FORMAT"
+
+// ValidateFormat checks that format is empty or a known log format
+func ValidateFormat(format string) error {
+	switch format {
+	case "", FormatText, FormatJSON:
+		return nil
+	}
+	return fmt.Errorf("invalid log format %q (expected %s or %s)", format, FormatText, FormatJSON)
+}
+
+// ResolveFormat returns the log format to use: GUCK_LOG_FORMAT when set,
+// otherwise the configured one
+func ResolveFormat(configured string) (string, error) {
+	format := configured
+	if env := os.Getenv(EnvFormat); env != "" {
+		format = env
+	}
+	if err := ValidateFormat(format); err != nil {
+		return "", fmt.Errorf("%s: %w", EnvFormat, err)
+	}
+	return format, nil
+}
+
+// New returns a logger writing to w in the given format. An empty format is text.
+func New(w io.Writer, format string) *slog.Logger {
+	if format == FormatJSON {
+		return slog.New(slog.NewJSONHandler(w, nil))
+	}
+	return slog.New(slog.NewTextHandler(w, nil))
+}
@@ -0,0 +1,47 @@
+package logging
+
+import (
+	"bytes"
+	"encoding/json"
+	"strings"
+	"testing"
+)
+
+func TestNewJSON(t *testing.T) {
+	var buf bytes.Buffer
+	New(&buf, FormatJSON).Info("starting server", "url", "http://127.0.0.1:3000/")
+
+	var record map[string]interface{}
+	if err := json.Unmarshal(buf.Bytes(), &record); err != nil {
+		t.Fatalf("Expected a JSON line, got %q: %v", buf.String(), err)
+	}
+	if record["msg"] != "starting server" || record["url"] != "http://127.0.0.1:3000/" || record["level"] != "INFO" {
+		t.Errorf("Unexpected record: %v", record)
+	}
+}
+
+func TestNewDefaultsToText(t *testing.T) {
+	var buf bytes.Buffer
+	New(&buf, "").Info("starting server", "url", "http://127.0.0.1:3000/")
+
+	if !strings.Contains(buf.String(), `msg="starting server" url=http://127.0.0.1:3000/`) {
+		t.Errorf("Expected a key=value line, got %q", buf.String())
+	}
+}
+
+func TestResolveFormat(t *testing.T) {
+	t.Setenv(EnvFormat, "")
+	if format, err := ResolveFormat(FormatText); err != nil || format != FormatText {
+		t.Errorf("Expected the configured format, got %q, %v", format, err)
+	}
+
+	t.Setenv(EnvFormat, FormatJSON)
+	if format, err := ResolveFormat(FormatText); err != nil || format != FormatJSON {
+		t.Errorf("Expected %s to override the config, got %q, %v", EnvFormat, format, err)
+	}
+
+	t.Setenv(EnvFormat, "xml")
+	if _, err := ResolveFormat(""); err == nil {
+		t.Error("Expected an error for an unknown format")
+	}
+}
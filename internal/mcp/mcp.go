@@ -60,6 +60,11 @@ type CommentResult struct {
 	Resolved   bool   `json:"resolved"`
 	ResolvedBy string `json:"resolved_by,omitempty"`
 	ResolvedAt int64  `json:"resolved_at,omitempty"`
+	Author     string `json:"author,omitempty"`
+	// Acknowledged comments have been seen by the author but not yet resolved
+	Acknowledged   bool   `json:"acknowledged"`
+	AcknowledgedBy string `json:"acknowledged_by,omitempty"`
+	AcknowledgedAt int64  `json:"acknowledged_at,omitempty"`
 }
 
 type NoteResult struct {
@@ -277,6 +282,20 @@ func ListCommentsWithManager(paramsRaw json.RawMessage, stateMgr *state.Manager)
 		comments = stateMgr.GetAllComments(absPath)
 	}
 
+	// Resolved comments may have been moved to the repository's archive
+	if params.Resolved != nil && *params.Resolved {
+		var archived []*state.Comment
+		if params.Branch != nil && params.Commit != nil {
+			archived, err = stateMgr.GetArchivedComments(absPath, *params.Branch, *params.Commit, params.FilePath)
+		} else {
+			archived, err = stateMgr.GetAllArchivedComments(absPath)
+		}
+		if err != nil {
+			return nil, err
+		}
+		comments = state.MergeArchived(comments, archived)
+	}
+
 	// Filter by resolution status if specified
 	if params.Resolved != nil {
 		filtered := []*state.Comment{}
@@ -303,16 +322,20 @@ func ListCommentsWithManager(paramsRaw json.RawMessage, stateMgr *state.Manager)
 	results := make([]CommentResult, len(comments))
 	for i, c := range comments {
 		results[i] = CommentResult{
-			ID:         c.ID,
-			FilePath:   c.FilePath,
-			LineNumber: c.LineNumber,
-			Text:       c.Text,
-			Timestamp:  c.Timestamp,
-			Branch:     c.Branch,
-			Commit:     c.Commit,
-			Resolved:   c.Resolved,
-			ResolvedBy: c.ResolvedBy,
-			ResolvedAt: c.ResolvedAt,
+			ID:             c.ID,
+			FilePath:       c.FilePath,
+			LineNumber:     c.LineNumber,
+			Text:           c.Text,
+			Timestamp:      c.Timestamp,
+			Branch:         c.Branch,
+			Commit:         c.Commit,
+			Resolved:       c.Resolved,
+			ResolvedBy:     c.ResolvedBy,
+			ResolvedAt:     c.ResolvedAt,
+			Author:         c.Author,
+			Acknowledged:   c.Acknowledged,
+			AcknowledgedBy: c.AcknowledgedBy,
+			AcknowledgedAt: c.AcknowledgedAt,
 		}
 	}
 
@@ -0,0 +1,32 @@
+package notify
+
+import (
+	"strings"
+	"testing"
+)
+
+func TestCommandQuotesArguments(t *testing.T) {
+	cmd, err := command("darwin", `Say "hi"`, `back\slash`)
+	if err != nil {
+		t.Fatalf("Failed to build command: %v", err)
+	}
+
+	script := cmd.Args[len(cmd.Args)-1]
+	if !strings.Contains(script, `"Say \"hi\""`) || !strings.Contains(script, `"back\\slash"`) {
+		t.Errorf("Expected AppleScript strings to be escaped, got: %s", script)
+	}
+
+	cmd, err = command("windows", "It's", "done")
+	if err != nil {
+		t.Fatalf("Failed to build command: %v", err)
+	}
+	if !strings.Contains(cmd.Args[len(cmd.Args)-1], "'It''s'") {
+		t.Errorf("Expected PowerShell string to be escaped, got: %s", cmd.Args[len(cmd.Args)-1])
+	}
+}
+
+func TestCommandUnsupportedPlatform(t *testing.T) {
+	if _, err := command("plan9", "title", "message"); err == nil {
+		t.Error("Expected an error for an unsupported platform")
+	}
+}
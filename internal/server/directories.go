@@ -0,0 +1,71 @@
+package server
+
+import (
+	"fmt"
+	"net/http"
+	"path"
+	"sort"
+	"strings"
+)
+
+// DiffGroupDirectory is the ?group value that adds per-directory review
+// progress to a diff response
+const DiffGroupDirectory = "directory"
+
+// DirectoryProgress rolls up the review status of the changed files under a
+// directory, at any depth
+type DirectoryProgress struct {
+	Path   string `json:"path"`
+	Viewed int    `json:"viewed"`
+	Total  int    `json:"total"`
+}
+
+// directoryRollup counts viewed and total files for every directory holding a
+// changed file. Files are added before ?unviewed_only and paging are applied,
+// so the counts always cover the whole diff.
+type directoryRollup map[string]*DirectoryProgress
+
+func (d directoryRollup) add(filePath string, viewed bool) {
+	for dir := path.Dir(filePath); dir != "." && dir != "/"; dir = path.Dir(dir) {
+		progress := d[dir]
+		if progress == nil {
+			progress = &DirectoryProgress{Path: dir}
+			d[dir] = progress
+		}
+		progress.Total++
+		if viewed {
+			progress.Viewed++
+		}
+	}
+}
+
+// list returns the rollups sorted by path, or nil unless the request asked
+// for them with ?group=directory
+func (d directoryRollup) list(r *http.Request) []DirectoryProgress {
+	if r.URL.Query().Get("group") != DiffGroupDirectory {
+		return nil
+	}
+	directories := make([]DirectoryProgress, 0, len(d))
+	for _, progress := range d {
+		directories = append(directories, *progress)
+	}
+	sort.Slice(directories, func(i, j int) bool {
+		return directories[i].Path < directories[j].Path
+	})
+	return directories
+}
+
+// validateGroup checks the ?group parameter of a diff request
+func validateGroup(r *http.Request) error {
+	switch group := r.URL.Query().Get("group"); group {
+	case "", DiffGroupDirectory:
+		return nil
+	default:
+		return fmt.Errorf("invalid group: %s (expected directory)", group)
+	}
+}
+
+// inDirectory reports whether filePath is somewhere under dir
+func inDirectory(filePath, dir string) bool {
+	return strings.HasPrefix(filePath, strings.Trim(dir, "/")+"/")
+}
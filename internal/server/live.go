@@ -0,0 +1,191 @@
+package server
+
+import (
+	"context"
+	"fmt"
+	"io"
+	"net/http"
+	"net/url"
+	"path/filepath"
+	"sync"
+	"time"
+
+	"github.com/tuist/guck/internal/git"
+	"golang.org/x/net/websocket"
+)
+
+// liveInterval is how often the repository is polled for changes while
+// clients are connected to /api/ws and it can't be watched
+const liveInterval = 2 * time.Second
+
+// apiWriteGrace is how long after a request that may change the repository
+// a detected change is taken to be that request's doing: long enough for the
+// watcher to settle or the next poll to see it
+const apiWriteGrace = liveInterval + watchDebounce
+
+// LiveEventDiffChanged tells clients that HEAD, the index or the working tree
+// changed, so the diff they show may be outdated
+const LiveEventDiffChanged = "diff-changed"
+
+// LiveEvent is a message pushed to the clients of /api/ws
+type LiveEvent struct {
+	Type string `json:"type"`
+}
+
+// liveClients fans live events out to every connected client
+type liveClients struct {
+	mu      sync.Mutex
+	clients map[chan LiveEvent]struct{}
+}
+
+func newLiveClients() *liveClients {
+	return &liveClients{clients: make(map[chan LiveEvent]struct{})}
+}
+
+func (l *liveClients) subscribe() chan LiveEvent {
+	events := make(chan LiveEvent, 1)
+	l.mu.Lock()
+	defer l.mu.Unlock()
+	l.clients[events] = struct{}{}
+	return events
+}
+
+func (l *liveClients) unsubscribe(events chan LiveEvent) {
+	l.mu.Lock()
+	defer l.mu.Unlock()
+	delete(l.clients, events)
+}
+
+func (l *liveClients) connected() bool {
+	l.mu.Lock()
+	defer l.mu.Unlock()
+	return len(l.clients) > 0
+}
+
+// publish sends event to every client without waiting on slow ones. A client
+// that still has an event queued misses this one, which is fine since every
+// event just means "refetch".
+func (l *liveClients) publish(event LiveEvent) {
+	l.mu.Lock()
+	defer l.mu.Unlock()
+	for events := range l.clients {
+		select {
+		case events <- event:
+		default:
+		}
+	}
+}
+
+// watchChanges reports when the repository changed, until ctx is cancelled.
+// Changes come from the file system watcher where there is one; otherwise, or
+// once it stops working, the repository is polled while clients are connected
+// or notifications are enabled, so other idle daemons stay idle.
+func (s *AppState) watchChanges(ctx context.Context) {
+	ticker := time.NewTicker(liveInterval)
+	defer ticker.Stop()
+
+	var changes <-chan struct{}
+	if s.watcher != nil {
+		changes = s.watcher.changes
+	}
+
+	last := ""
+	for {
+		select {
+		case <-ctx.Done():
+			return
+		case _, ok := <-changes:
+			if !ok {
+				changes = nil
+				continue
+			}
+			s.repoChanged()
+			continue
+		case <-ticker.C:
+		}
+
+		if changes != nil || (!s.live.connected() && s.notify == nil) {
+			last = ""
+			continue
+		}
+
+		gitRepo, err := git.Open(".")
+		if err != nil {
+			continue
+		}
+		fingerprint, err := gitRepo.ChangeFingerprint()
+		if err != nil {
+			// Mid-rebase or mid-checkout; try again on the next tick
+			continue
+		}
+		if last != "" && fingerprint != last {
+			s.repoChanged()
+		}
+		last = fingerprint
+	}
+}
+
+// repoChanged tells connected clients the repository changed and, unless a
+// request to the API just did that, shows a desktop notification. Comments
+// and viewed files only change through the API, so they never notify.
+func (s *AppState) repoChanged() {
+	s.live.publish(LiveEvent{Type: LiveEventDiffChanged})
+
+	if s.notify == nil || time.Since(time.Unix(0, s.lastAPIWrite.Load())) < apiWriteGrace {
+		return
+	}
+	go s.notify("guck diff changed", fmt.Sprintf("The review of %s has new changes", filepath.Base(s.RepoPath)))
+}
+
+func (s *AppState) wsHandler(w http.ResponseWriter, r *http.Request) {
+	server := websocket.Server{
+		Handshake: checkSameOrigin,
+		Handler:   s.streamLiveEvents,
+	}
+	server.ServeHTTP(w, r)
+}
+
+// streamLiveEvents sends live events to a client until it disconnects or the
+// server shuts down
+func (s *AppState) streamLiveEvents(ws *websocket.Conn) {
+	defer ws.Close()
+
+	events := s.live.subscribe()
+	defer s.live.unsubscribe(events)
+
+	// Clients only listen, so a read returning means the client went away
+	gone := make(chan struct{})
+	go func() {
+		_, _ = io.Copy(io.Discard, ws)
+		close(gone)
+	}()
+
+	ctx := ws.Request().Context()
+	for {
+		select {
+		case event := <-events:
+			if err := websocket.JSON.Send(ws, event); err != nil {
+				return
+			}
+		case <-gone:
+			return
+		case <-ctx.Done():
+			return
+		}
+	}
+}
+
+// checkSameOrigin refuses WebSocket connections opened by pages of other
+// sites, which browsers allow, so they can't follow the repository's changes.
+// Clients outside a browser send no Origin and are let through.
+func checkSameOrigin(_ *websocket.Config, r *http.Request) error {
+	origin := r.Header.Get("Origin")
+	if origin == "" {
+		return nil
+	}
+	u, err := url.Parse(origin)
+	if err != nil || u.Host != r.Host {
+		return fmt.Errorf("cross-origin WebSocket connection from %s", origin)
+	}
+	return nil
+}
@@ -0,0 +1,95 @@
+package server
+
+import (
+	"net/http/httptest"
+	"testing"
+	"time"
+)
+
+func TestLiveClients(t *testing.T) {
+	live := newLiveClients()
+	if live.connected() {
+		t.Fatal("Expected no clients initially")
+	}
+
+	first, second := live.subscribe(), live.subscribe()
+	if !live.connected() {
+		t.Fatal("Expected subscribed clients to be connected")
+	}
+
+	// A client that hasn't read its last event doesn't hold up the others
+	live.publish(LiveEvent{Type: LiveEventDiffChanged})
+	live.publish(LiveEvent{Type: LiveEventDiffChanged})
+	for _, events := range []chan LiveEvent{first, second} {
+		if event := <-events; event.Type != LiveEventDiffChanged {
+			t.Errorf("Expected a %s event, got %+v", LiveEventDiffChanged, event)
+		}
+	}
+
+	live.unsubscribe(first)
+	live.publish(LiveEvent{Type: LiveEventDiffChanged})
+	select {
+	case event := <-first:
+		t.Errorf("Expected no events after unsubscribing, got %+v", event)
+	default:
+	}
+	<-second
+
+	live.unsubscribe(second)
+	if live.connected() {
+		t.Error("Expected no clients after everyone unsubscribed")
+	}
+}
+
+func TestRepoChangedSkipsAPIWrites(t *testing.T) {
+	notified := make(chan string, 1)
+	s := &AppState{
+		RepoPath: "/repos/app",
+		live:     newLiveClients(),
+		notify:   func(title, message string) { notified <- message },
+	}
+	events := s.live.subscribe()
+
+	// A change right after a request to the API is the request's own doing
+	s.lastAPIWrite.Store(time.Now().UnixNano())
+	s.repoChanged()
+	<-events
+	select {
+	case message := <-notified:
+		t.Errorf("Expected no notification right after an API write, got %q", message)
+	case <-time.After(50 * time.Millisecond):
+	}
+
+	s.lastAPIWrite.Store(time.Now().Add(-apiWriteGrace).UnixNano())
+	s.repoChanged()
+	<-events
+	select {
+	case message := <-notified:
+		if message != "The review of app has new changes" {
+			t.Errorf("Unexpected notification %q", message)
+		}
+	case <-time.After(time.Second):
+		t.Error("Expected a notification for a change made outside the API")
+	}
+}
+
+func TestCheckSameOrigin(t *testing.T) {
+	tests := []struct {
+		origin string
+		ok     bool
+	}{
+		{origin: "", ok: true},
+		{origin: "http://localhost:3000", ok: true},
+		{origin: "http://localhost:4000", ok: false},
+		{origin: "https://evil.example", ok: false},
+	}
+	for _, tt := range tests {
+		r := httptest.NewRequest("GET", "http://localhost:3000/api/ws", nil)
+		if tt.origin != "" {
+			r.Header.Set("Origin", tt.origin)
+		}
+		if err := checkSameOrigin(nil, r); (err == nil) != tt.ok {
+			t.Errorf("Origin %q: expected ok=%t, got %v", tt.origin, tt.ok, err)
+		}
+	}
+}
@@ -0,0 +1,190 @@
+package server
+
+import (
+	"encoding/json"
+	"net/http"
+	"reflect"
+	"sort"
+	"strings"
+)
+
+// OpenAPIVersion is the version of the API contract described by OpenAPISpec
+const OpenAPIVersion = "1.0.0"
+
+// OpenAPISpec returns an OpenAPI 3 document describing the /api endpoints.
+// Schemas are derived from the Go request and response types via their json tags.
+func OpenAPISpec() map[string]interface{} {
+	gen := &schemaGenerator{schemas: make(map[string]interface{})}
+	paths := make(map[string]map[string]interface{})
+
+	// Handlers are never invoked, so a zero AppState is enough to list routes
+	for _, rt := range (&AppState{}).routes() {
+		operation := map[string]interface{}{
+			"summary": rt.Summary,
+		}
+
+		params := []map[string]interface{}{}
+		for _, pp := range rt.PathParams {
+			params = append(params, parameterSpec(pp, "path", true))
+		}
+		for _, qp := range rt.QueryParams {
+			params = append(params, parameterSpec(qp, "query", false))
+		}
+		if len(params) > 0 {
+			operation["parameters"] = params
+		}
+
+		if rt.Request != nil {
+			operation["requestBody"] = map[string]interface{}{
+				"required": true,
+				"content": map[string]interface{}{
+					"application/json": map[string]interface{}{
+						"schema": gen.schemaFor(reflect.TypeOf(rt.Request)),
+					},
+				},
+			}
+		}
+
+		okResponse := map[string]interface{}{
+			"description": "Success",
+		}
+		if rt.TextResponse {
+			okResponse["content"] = map[string]interface{}{
+				"text/plain": map[string]interface{}{
+					"schema": map[string]interface{}{"type": "string"},
+				},
+			}
+		}
+		if rt.Response != nil {
+			schema := gen.schemaFor(reflect.TypeOf(rt.Response))
+			if rt.AltResponse != nil {
+				schema = map[string]interface{}{
+					"oneOf": []interface{}{schema, gen.schemaFor(reflect.TypeOf(rt.AltResponse))},
+				}
+			}
+			okResponse["content"] = map[string]interface{}{
+				"application/json": map[string]interface{}{
+					"schema": schema,
+				},
+			}
+		}
+		operation["responses"] = map[string]interface{}{
+			"200": okResponse,
+			"default": map[string]interface{}{
+				"description": "Error message as plain text",
+			},
+		}
+
+		if paths[rt.Path] == nil {
+			paths[rt.Path] = make(map[string]interface{})
+		}
+		paths[rt.Path][strings.ToLower(rt.Method)] = operation
+	}
+
+	return map[string]interface{}{
+		"openapi": "3.0.3",
+		"info": map[string]interface{}{
+			"title":   "Guck API",
+			"version": OpenAPIVersion,
+		},
+		"paths": paths,
+		"components": map[string]interface{}{
+			"schemas": gen.schemas,
+		},
+	}
+}
+
+func parameterSpec(param apiParam, in string, required bool) map[string]interface{} {
+	return map[string]interface{}{
+		"name":        param.Name,
+		"in":          in,
+		"required":    required,
+		"description": param.Description,
+		"schema":      map[string]interface{}{"type": param.Type},
+	}
+}
+
+func openAPIHandler(w http.ResponseWriter, r *http.Request) {
+	w.Header().Set("Content-Type", "application/json")
+	_ = json.NewEncoder(w).Encode(OpenAPISpec()) // Ignore encode error for HTTP response
+}
+
+type schemaGenerator struct {
+	schemas map[string]interface{}
+}
+
+func (g *schemaGenerator) schemaFor(t reflect.Type) map[string]interface{} {
+	switch t.Kind() {
+	case reflect.Ptr:
+		return g.schemaFor(t.Elem())
+	case reflect.String:
+		return map[string]interface{}{"type": "string"}
+	case reflect.Bool:
+		return map[string]interface{}{"type": "boolean"}
+	case reflect.Int, reflect.Int8, reflect.Int16, reflect.Int32, reflect.Uint, reflect.Uint8, reflect.Uint16, reflect.Uint32:
+		return map[string]interface{}{"type": "integer"}
+	case reflect.Int64, reflect.Uint64:
+		return map[string]interface{}{"type": "integer", "format": "int64"}
+	case reflect.Float32, reflect.Float64:
+		return map[string]interface{}{"type": "number"}
+	case reflect.Slice, reflect.Array:
+		return map[string]interface{}{
+			"type":  "array",
+			"items": g.schemaFor(t.Elem()),
+		}
+	case reflect.Map:
+		return map[string]interface{}{
+			"type":                 "object",
+			"additionalProperties": g.schemaFor(t.Elem()),
+		}
+	case reflect.Struct:
+		name := t.Name()
+		if _, ok := g.schemas[name]; !ok {
+			// Reserve the name first so self-referencing types terminate
+			g.schemas[name] = map[string]interface{}{}
+			g.schemas[name] = g.structSchema(t)
+		}
+		return map[string]interface{}{"$ref": "#/components/schemas/" + name}
+	default:
+		return map[string]interface{}{}
+	}
+}
+
+func (g *schemaGenerator) structSchema(t reflect.Type) map[string]interface{} {
+	properties := make(map[string]interface{})
+	required := []string{}
+
+	for i := 0; i < t.NumField(); i++ {
+		field := t.Field(i)
+		if field.PkgPath != "" {
+			continue // unexported
+		}
+
+		tag := field.Tag.Get("json")
+		if tag == "-" {
+			continue
+		}
+
+		name, opts, _ := strings.Cut(tag, ",")
+		if name == "" {
+			name = field.Name
+		}
+
+		properties[name] = g.schemaFor(field.Type)
+
+		if !strings.Contains(opts, "omitempty") && field.Type.Kind() != reflect.Ptr {
+			required = append(required, name)
+		}
+	}
+
+	schema := map[string]interface{}{
+		"type":       "object",
+		"properties": properties,
+	}
+	if len(required) > 0 {
+		sort.Strings(required)
+		schema["required"] = required
+	}
+
+	return schema
+}
@@ -0,0 +1,69 @@
+package server
+
+import (
+	"encoding/json"
+	"strings"
+	"testing"
+)
+
+func TestOpenAPISpecCoversAllRoutes(t *testing.T) {
+	spec := OpenAPISpec()
+
+	paths, ok := spec["paths"].(map[string]map[string]interface{})
+	if !ok {
+		t.Fatal("Expected paths to be a map")
+	}
+
+	for _, rt := range (&AppState{}).routes() {
+		operations, ok := paths[rt.Path]
+		if !ok {
+			t.Errorf("Expected path %s in spec", rt.Path)
+			continue
+		}
+		if _, ok := operations[strings.ToLower(rt.Method)]; !ok {
+			t.Errorf("Expected %s %s in spec", rt.Method, rt.Path)
+		}
+	}
+}
+
+func TestOpenAPISpecSchemas(t *testing.T) {
+	spec := OpenAPISpec()
+
+	components := spec["components"].(map[string]interface{})
+	schemas := components["schemas"].(map[string]interface{})
+
+	for _, name := range []string{"DiffResponse", "FileDiff", "Comment", "Note", "AddCommentRequest"} {
+		if _, ok := schemas[name]; !ok {
+			t.Errorf("Expected schema %s in components", name)
+		}
+	}
+
+	comment := schemas["Comment"].(map[string]interface{})
+	properties := comment["properties"].(map[string]interface{})
+	if _, ok := properties["file_path"]; !ok {
+		t.Error("Expected Comment schema to use json tag names")
+	}
+
+	// The document must be serializable as-is
+	if _, err := json.Marshal(spec); err != nil {
+		t.Fatalf("Failed to marshal spec: %v", err)
+	}
+}
+
+func TestOpenAPISpecAltResponse(t *testing.T) {
+	spec := OpenAPISpec()
+	paths := spec["paths"].(map[string]map[string]interface{})
+
+	get := paths["/api/comments"]["get"].(map[string]interface{})
+	ok := get["responses"].(map[string]interface{})["200"].(map[string]interface{})
+	schema := ok["content"].(map[string]interface{})["application/json"].(map[string]interface{})["schema"].(map[string]interface{})
+	oneOf, found := schema["oneOf"].([]interface{})
+	if !found || len(oneOf) != 2 {
+		t.Fatalf("Expected GET /api/comments to list both response schemas, got %v", schema)
+	}
+
+	schemas := spec["components"].(map[string]interface{})["schemas"].(map[string]interface{})
+	if _, ok := schemas["CommentChangesResponse"]; !ok {
+		t.Error("Expected schema CommentChangesResponse in components")
+	}
+}
@@ -0,0 +1,329 @@
+package server
+
+import (
+	"net/http"
+
+	"github.com/tuist/guck/internal/state"
+)
+
+// route describes an API endpoint. The same table drives both the router
+// and the OpenAPI document so the published contract can't drift from the
+// handlers actually registered.
+type route struct {
+	Method       string
+	Path         string
+	Summary      string
+	Handler      http.HandlerFunc
+	PathParams   []apiParam
+	QueryParams  []apiParam
+	Request      interface{} // Request body type, nil if the endpoint takes no body
+	Response     interface{} // Response body type, nil if the endpoint returns no body
+	TextResponse bool        // The response body is plain text rather than JSON
+	NeedsRepo    bool        // Unavailable when reviewing a patch file rather than a repository
+	// AltResponse is returned instead of Response for some parameters, which
+	// the summary or the parameter docs name
+	AltResponse interface{}
+}
+
+type apiParam struct {
+	Name        string
+	Type        string
+	Description string
+}
+
+func (s *AppState) routes() []route {
+	return []route{
+		{
+			Method:  "GET",
+			Path:    "/api/diff",
+			Summary: "Get the diff between HEAD and the base branch, plus uncommitted changes",
+			Handler: s.diffHandler,
+			QueryParams: []apiParam{
+				{Name: "carry_forward", Type: "boolean", Description: "Move review state from an amended-away commit onto HEAD"},
+				{Name: "mode", Type: "string", Description: "branch (default) diffs against the base branch; working diffs the working tree against HEAD; commit diffs a single commit against its parent, or all parents for merges"},
+				{Name: "commit", Type: "string", Description: "Commit to diff in commit mode (required there); must be HEAD or one of its ancestors"},
+				{Name: "algorithm", Type: "string", Description: "Diff algorithm: myers, patience or histogram, in every mode and for uncommitted_files. Defaults to the diff_algorithm config, then git's diff.algorithm. Not supported when reviewing a patch file"},
+				{Name: "recurse_submodules", Type: "boolean", Description: "Show the file changes inside changed submodules (one level deep) instead of pointer bumps. Defaults to the recurse_submodules config"},
+				{Name: "offset", Type: "integer", Description: "Skip this many files; use with limit to page through large diffs"},
+				{Name: "limit", Type: "integer", Description: "Return at most this many files. Defaults to, and is capped at, the max_files config"},
+				{Name: "unviewed_only", Type: "boolean", Description: "Only include files not yet marked as viewed; indices are recomputed over the remaining files"},
+				{Name: "only_files_from", Type: "string", Description: "Only include files changed by this commit; files it touched with no net branch change are listed in reverted_files"},
+				{Name: "lines", Type: "boolean", Description: "Include each file's hunk lines with their old and new line numbers in lines"},
+				{Name: "split", Type: "boolean", Description: "Include each file's hunks laid out side by side in split, with removed and added lines paired into rows; patch stays unified. A separate parameter rather than mode=split, since mode selects what is diffed. Built from the patch after max_patch_kb truncation"},
+				{Name: "ignore_cherry_picks", Type: "boolean", Description: "Drop hunks made by branch commits whose patch-id matches a base commit, listing them in suppressed. Ignored for incremental diffs"},
+				{Name: "group", Type: "string", Description: "directory adds the viewed and total file counts of every changed directory in directories"},
+				{Name: "pathspec", Type: "string", Description: "Limit the diff to paths matching this git pathspec, magic such as :(exclude) and :(glob) included. Repeat for several. Not supported when reviewing a patch file"},
+			},
+			Response: DiffResponse{},
+		},
+		{
+			Method:   "GET",
+			Path:     "/api/config",
+			Summary:  "Get the server settings the UI needs, such as the base path",
+			Handler:  s.configHandler,
+			Response: ConfigResponse{},
+		},
+		{
+			Method:  "POST",
+			Path:    "/api/mark-viewed",
+			Summary: "Mark a file, or every file under a directory, as viewed",
+			Handler: s.markViewedHandler,
+			Request: MarkViewedRequest{},
+		},
+		{
+			Method:  "POST",
+			Path:    "/api/unmark-viewed",
+			Summary: "Unmark a file, or every file under a directory, as viewed",
+			Handler: s.unmarkViewedHandler,
+			Request: MarkViewedRequest{},
+		},
+		{
+			Method:   "GET",
+			Path:     "/api/status",
+			Summary:  "Get the repository path, branch and commit being reviewed, with the review checklist's progress",
+			Handler:  s.statusHandler,
+			Response: StatusResponse{},
+		},
+		{
+			Method:    "POST",
+			Path:      "/api/fetch-base",
+			Summary:   "Fetch the base branch from origin (fails with 409 in offline mode)",
+			Handler:   s.fetchBaseHandler,
+			NeedsRepo: true,
+		},
+		{
+			Method:  "GET",
+			Path:    "/api/comments",
+			Summary: "List comments for the current branch and commit. With updated_since, returns a CommentChangesResponse instead",
+			Handler: s.getCommentsHandler,
+			QueryParams: []apiParam{
+				{Name: "file_path", Type: "string", Description: "Only return comments on this file. General comments are excluded"},
+				{Name: "general", Type: "boolean", Description: "Only return general comments on the whole review, which have an empty file_path"},
+				{Name: "resolved", Type: "boolean", Description: "Only return resolved (true) or unresolved (false) comments; ignored with updated_since so resolutions are still reported"},
+				{Name: "updated_since", Type: "integer", Description: "Unix timestamp; only return comments changed since then"},
+				{Name: "order", Type: "string", Description: "insertion (default), priority, which lists pinned comments first, or oldest/newest by creation time"},
+				{Name: "limit", Type: "integer", Description: "Return at most this many comments, after filtering and ordering; ignored with updated_since"},
+			},
+			Response:    []state.Comment{},
+			AltResponse: CommentChangesResponse{},
+		},
+		{
+			Method:   "POST",
+			Path:     "/api/comments",
+			Summary:  "Add a comment",
+			Handler:  s.addCommentHandler,
+			Request:  AddCommentRequest{},
+			Response: state.Comment{},
+		},
+		{
+			Method:  "GET",
+			Path:    "/api/comments/{id}/context",
+			Summary: "Get the file contents around a comment as of the commit it was written on",
+			Handler: s.commentContextHandler,
+			PathParams: []apiParam{
+				{Name: "id", Type: "string", Description: "Comment ID"},
+			},
+			QueryParams: []apiParam{
+				{Name: "lines", Type: "integer", Description: "Lines of context on each side of the commented line (default 5)"},
+			},
+			Response:  CommentContextResponse{},
+			NeedsRepo: true,
+		},
+		{
+			Method:   "POST",
+			Path:     "/api/comments/edit",
+			Summary:  "Replace the text of a comment",
+			Handler:  s.editCommentHandler,
+			Request:  EditCommentRequest{},
+			Response: state.Comment{},
+		},
+		{
+			Method:  "DELETE",
+			Path:    "/api/comments",
+			Summary: "Delete a comment",
+			Handler: s.deleteCommentHandler,
+			Request: DeleteCommentRequest{},
+		},
+		{
+			Method:  "POST",
+			Path:    "/api/comments/resolve",
+			Summary: "Resolve a comment",
+			Handler: s.resolveCommentHandler,
+			Request: ResolveCommentRequest{},
+		},
+		{
+			Method:  "POST",
+			Path:    "/api/comments/acknowledge",
+			Summary: "Acknowledge a comment without resolving it",
+			Handler: s.acknowledgeCommentHandler,
+			Request: AcknowledgeCommentRequest{},
+		},
+		{
+			Method:  "POST",
+			Path:    "/api/comments/pin",
+			Summary: "Pin or unpin a comment",
+			Handler: s.pinCommentHandler,
+			Request: PinCommentRequest{},
+		},
+		{
+			Method:  "GET",
+			Path:    "/api/diff/file",
+			Summary: "Get one file's patch from the diff, in full with full=true, for files whose patch /api/diff truncated",
+			Handler: s.fileDiffHandler,
+			QueryParams: []apiParam{
+				{Name: "file_path", Type: "string", Description: "File whose patch to return (required)"},
+				{Name: "full", Type: "boolean", Description: "Bypass the max_patch_kb cap; patches are still cut off at 16 MiB"},
+				{Name: "mode", Type: "string", Description: "branch (default), working or commit, as for /api/diff"},
+				{Name: "commit", Type: "string", Description: "Commit to diff in commit mode"},
+				{Name: "since", Type: "string", Description: "Diff HEAD against this earlier commit instead of the base branch, as for /api/diff"},
+				{Name: "staging_status", Type: "string", Description: "staged or unstaged selects an uncommitted change of a branch review; defaults to committed"},
+				{Name: "algorithm", Type: "string", Description: "Diff algorithm, as for /api/diff"},
+			},
+			Response: FilePatchResponse{},
+		},
+		{
+			Method:  "GET",
+			Path:    "/api/diff.patch",
+			Summary: "Get the whole diff as a single unified diff in plain text, for tools such as patch or delta",
+			Handler: s.diffPatchHandler,
+			QueryParams: []apiParam{
+				{Name: "mode", Type: "string", Description: "branch (default), working or commit, as for /api/diff"},
+				{Name: "commit", Type: "string", Description: "Commit to diff in commit mode"},
+				{Name: "since", Type: "string", Description: "Diff HEAD against this earlier commit instead of the base branch, as for /api/diff"},
+				{Name: "staging_status", Type: "string", Description: "staged or unstaged selects the uncommitted changes of a branch review; defaults to committed"},
+				{Name: "algorithm", Type: "string", Description: "Diff algorithm, as for /api/diff"},
+				{Name: "only_files_from", Type: "string", Description: "Only include files changed by this commit, as for /api/diff"},
+				{Name: "ignore_cherry_picks", Type: "boolean", Description: "Drop hunks already on the base branch through cherry-picked commits, as for /api/diff"},
+				{Name: "pathspec", Type: "string", Description: "Limit the diff to paths matching this git pathspec, as for /api/diff. Not supported with staged or unstaged staging_status"},
+			},
+			TextResponse: true,
+		},
+		{
+			Method:  "GET",
+			Path:    "/api/export/markdown",
+			Summary: "Get the current review as a Markdown summary of the changed files, their viewed status and the comments on them",
+			Handler: s.exportMarkdownHandler,
+			QueryParams: []apiParam{
+				{Name: "mode", Type: "string", Description: "branch (default), working or commit, as for /api/diff; viewed status is that of the same review"},
+				{Name: "commit", Type: "string", Description: "Commit to export in commit mode"},
+				{Name: "since", Type: "string", Description: "Export the diff of HEAD against this earlier commit, as for /api/diff; files count as viewed whatever their patch"},
+				{Name: "staging_status", Type: "string", Description: "staged or unstaged exports the uncommitted changes of a branch review; defaults to committed"},
+				{Name: "algorithm", Type: "string", Description: "Diff algorithm, as for /api/diff"},
+			},
+			TextResponse: true,
+		},
+		{
+			Method:  "GET",
+			Path:    "/api/diff/expand",
+			Summary: "Get lines of a file's new version, to expand unchanged regions folded between hunks",
+			Handler: s.diffExpandHandler,
+			QueryParams: []apiParam{
+				{Name: "file_path", Type: "string", Description: "File to read (required)"},
+				{Name: "from_line", Type: "integer", Description: "First line to return, 1-based (required); clamped to the file"},
+				{Name: "to_line", Type: "integer", Description: "Last line to return, inclusive (required); clamped to the file"},
+				{Name: "mode", Type: "string", Description: "working reads the file from the working tree instead of HEAD"},
+			},
+			Response:  DiffExpandResponse{},
+			NeedsRepo: true,
+		},
+		{
+			Method:  "GET",
+			Path:    "/api/base/commits",
+			Summary: "List the commits that landed on the base branch after the current branch diverged, newest first",
+			Handler: s.baseCommitsHandler,
+			QueryParams: []apiParam{
+				{Name: "limit", Type: "integer", Description: "Maximum number of commits to return (default 50)"},
+			},
+			Response:  BaseCommitsResponse{},
+			NeedsRepo: true,
+		},
+		{
+			Method:  "GET",
+			Path:    "/api/blame",
+			Summary: "Get the commit, author and date that last changed each line around a line of a file at HEAD",
+			Handler: s.blameHandler,
+			QueryParams: []apiParam{
+				{Name: "file_path", Type: "string", Description: "File to blame (required)"},
+				{Name: "line", Type: "integer", Description: "Line to center on; omit to blame the whole file"},
+				{Name: "lines", Type: "integer", Description: "Number of lines of context on each side of line (default 5)"},
+			},
+			Response:  BlameResponse{},
+			NeedsRepo: true,
+		},
+		{
+			Method:   "GET",
+			Path:     "/api/reviews",
+			Summary:  "List the branches and commits of the repository that have viewed files or comments, most recently worked on first",
+			Handler:  s.reviewsHandler,
+			Response: ReviewsResponse{},
+		},
+		{
+			Method:    "GET",
+			Path:      "/api/ws",
+			Summary:   "Upgrade to a WebSocket that pushes a diff-changed event whenever HEAD, the index or the working tree changes, so clients can refetch the diff; changes are polled for every 2 seconds where the repository can't be watched, such as on Windows",
+			Handler:   s.wsHandler,
+			Response:  LiveEvent{},
+			NeedsRepo: true,
+		},
+		{
+			Method:  "GET",
+			Path:    "/api/events",
+			Summary: "List the logged review actions of the repository, such as files viewed and comments added or resolved, oldest first",
+			Handler: s.eventsHandler,
+			QueryParams: []apiParam{
+				{Name: "since", Type: "integer", Description: "Unix timestamp; only return events logged at or after then"},
+			},
+			Response: []state.Event{},
+		},
+		{
+			Method:  "GET",
+			Path:    "/api/review/summary",
+			Summary: "Get the overall summary of the review of the current branch and commit",
+			Handler: s.getReviewSummaryHandler,
+			QueryParams: []apiParam{
+				{Name: "mode", Type: "string", Description: "working reads the summary of the working-tree review"},
+			},
+			Response: ReviewSummary{},
+		},
+		{
+			Method:  "POST",
+			Path:    "/api/review/summary",
+			Summary: "Set the overall summary of the review; an empty summary clears it",
+			Handler: s.setReviewSummaryHandler,
+			Request: ReviewSummary{},
+		},
+		{
+			Method:   "POST",
+			Path:     "/api/review/checklist",
+			Summary:  "Tick or untick an item of the configured review checklist; returns the checklist's progress",
+			Handler:  s.setChecklistItemHandler,
+			Request:  ChecklistRequest{},
+			Response: state.ChecklistProgress{},
+		},
+		{
+			Method:  "GET",
+			Path:    "/api/notes",
+			Summary: "List AI agent notes for the current branch and commit",
+			Handler: s.getNotesHandler,
+			QueryParams: []apiParam{
+				{Name: "file_path", Type: "string", Description: "Only return notes on this file"},
+			},
+			Response: []state.Note{},
+		},
+		{
+			Method:   "POST",
+			Path:     "/api/notes",
+			Summary:  "Add an AI agent note",
+			Handler:  s.addNoteHandler,
+			Request:  AddNoteRequest{},
+			Response: state.Note{},
+		},
+		{
+			Method:  "POST",
+			Path:    "/api/notes/dismiss",
+			Summary: "Dismiss an AI agent note",
+			Handler: s.dismissNoteHandler,
+			Request: DismissNoteRequest{},
+		},
+	}
+}
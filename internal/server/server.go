@@ -1,34 +1,208 @@
 package server
 
 import (
+	"context"
+	"crypto/sha256"
+	"crypto/tls"
 	_ "embed"
+	"encoding/hex"
 	"encoding/json"
+	"errors"
 	"fmt"
+	"io/fs"
+	"log/slog"
+	"math"
+	"net"
 	"net/http"
+	"net/url"
+	"os"
+	"os/signal"
+	"path/filepath"
+	"slices"
+	"sort"
+	"strconv"
+	"strings"
 	"sync"
+	"sync/atomic"
+	"syscall"
+	"time"
+	"unicode/utf8"
 
 	"github.com/gorilla/mux"
+	"github.com/tuist/guck/internal/export"
 	"github.com/tuist/guck/internal/git"
+	"github.com/tuist/guck/internal/logging"
 	"github.com/tuist/guck/internal/state"
 )
 
 //go:embed static/index.html
 var indexHTML string
 
+// Options holds optional server behavior
+type Options struct {
+	// Offline disables network git operations such as fetching the base branch
+	Offline bool
+	// CommitAbbrevLen is the length of the abbreviated commit SHA in responses
+	CommitAbbrevLen int
+	// BasePath serves every route under a URL prefix, e.g. "/guck" behind a reverse proxy
+	BasePath string
+	// MaxCommentLength caps the length of comment text accepted by the API
+	MaxCommentLength int
+	// DiffAlgorithm is the default diff algorithm; empty defers to git's diff.algorithm
+	DiffAlgorithm string
+	// DiffBackend selects how branch diffs are generated, see git.DiffOptions.Backend
+	DiffBackend string
+	// Textconv runs the textconv filters configured for files' diff drivers
+	Textconv bool
+	// RecurseSubmodules shows the file changes inside changed submodules by default
+	RecurseSubmodules bool
+	// MaxFiles caps how many files a single /api/diff response returns; 0 means no cap
+	MaxFiles int
+	// MaxPatchKB truncates larger patches in /api/diff responses; 0 means no cap
+	MaxPatchKB int
+	// Socket, if set, is the path of a Unix domain socket to serve on instead
+	// of the TCP port
+	Socket string
+	// TLSCert and TLSKey are PEM file paths; when both are set the server speaks HTTPS
+	TLSCert string
+	TLSKey  string
+	// AutoViewPatterns are globs for files that count as viewed without being
+	// marked, such as lockfiles
+	AutoViewPatterns []string
+	// CommentTemplates maps short keys to canned comment text for quick insertion
+	CommentTemplates map[string]string
+	// ReviewChecklist lists the items reviewers tick off for every review
+	ReviewChecklist []string
+	// StaleBaseCommits is how many commits the base branch may gain during a
+	// review before diffs warn that it moved; 0 disables the warning
+	StaleBaseCommits int
+	// CarryForwardViewed keeps files viewed on a new commit of the branch when
+	// their content didn't change, see state.Manager.CarryForwardViewed
+	CarryForwardViewed bool
+	// Watchdog exits with a distinct ExitError code when the server ends up in
+	// a state it can't recover from, so a supervisor can restart it
+	Watchdog bool
+	// Notify, if set, shows a desktop notification when the repository changes other than through the API
+	Notify func(title, message string)
+	// OnActivity, if set, is called when requests are served, at most once per activityInterval
+	OnActivity func()
+	// Logger receives the server's log output; nil logs text to stdout
+	Logger *slog.Logger
+}
+
+// activityInterval rate-limits Options.OnActivity
+const activityInterval = 30 * time.Second
+
+// shutdownTimeout bounds how long a graceful shutdown waits for in-flight
+// requests to finish before cancelling them
+const shutdownTimeout = 10 * time.Second
+
 type AppState struct {
-	RepoPath     string
-	BaseBranch   string
-	StateManager *state.Manager
-	mu           sync.Mutex
+	RepoPath          string
+	BaseBranch        string
+	Offline           bool
+	CommitAbbrevLen   int
+	BasePath          string
+	MaxCommentLength  int
+	DiffAlgorithm     string
+	DiffBackend       string
+	Textconv          bool
+	RecurseSubmodules bool
+	MaxFiles          int
+	MaxPatchKB        int
+	AutoViewPatterns  []string
+	CommentTemplates  map[string]string
+	ReviewChecklist   []string
+	StaleBaseCommits  int
+	StateManager      *state.Manager
+	mu                sync.Mutex
+	watchdog          *watchdog
+	notify            func(title, message string)
+	blameCache        map[blameKey][]git.BlameLine
+	patch             *patchReview
+	live              *liveClients
+	watcher           *repoWatcher
+	logger            *slog.Logger
+	// CarryForwardViewed and viewedCarried, the commit each branch last
+	// inherited viewed files on, see carryForwardViewed
+	CarryForwardViewed bool
+	viewedCarried      map[string]string
+	// reviewBases is the base tip each branch's review started against
+	reviewBases map[string]string
+	// lastAPIWrite is when a request that may change the repository or the
+	// review last finished, in Unix nanoseconds
+	lastAPIWrite atomic.Int64
+}
+
+// patchReview is a patch file served for review in place of a repository's diff
+type patchReview struct {
+	// hash identifies the patch contents and stands in for the commit
+	hash  string
+	files []git.FileInfo
+}
+
+// PatchBranch is the branch the review state of a patch file is stored under
+const PatchBranch = "__patch__"
+
+// DiffModePatch is the mode reported by diffs of a patch file
+const DiffModePatch = "patch"
+
+// blameKey identifies a cached blame; a file's blame never changes at a given commit
+type blameKey struct {
+	commit   string
+	filePath string
 }
 
+// maxBlameCacheEntries bounds the blame cache, which is dropped when full
+const maxBlameCacheEntries = 64
+
 type DiffResponse struct {
 	Files            []FileDiff `json:"files"`
 	UncommittedFiles []FileDiff `json:"uncommitted_files,omitempty"`
 	Branch           string     `json:"branch"`
 	Commit           string     `json:"commit"`
+	CommitShort      string     `json:"commit_short"`
 	RepoPath         string     `json:"repo_path"`
 	RemoteURL        string     `json:"remote_url,omitempty"`
+	AmendedFrom      string     `json:"amended_from,omitempty"`
+	Mode             string     `json:"mode,omitempty"`
+	OnlyFilesFrom    string     `json:"only_files_from,omitempty"`
+	RevertedFiles    []string   `json:"reverted_files,omitempty"`
+	Since            string     `json:"since,omitempty"`
+	Total            int        `json:"total"`
+	TotalUnfiltered  int        `json:"total_unfiltered"`
+	DiffAlgorithm    string     `json:"diff_algorithm,omitempty"`
+	Ahead            *int       `json:"ahead,omitempty"`
+	Behind           *int       `json:"behind,omitempty"`
+	Offset           int        `json:"offset"`
+	Limit            int        `json:"limit,omitempty"`
+	BaseMoved        *BaseMoved `json:"base_moved,omitempty"`
+	Merge            *MergeInfo `json:"merge,omitempty"`
+	// Suppressed lists the hunks ?ignore_cherry_picks=true dropped because the
+	// base branch already has the commits that made them
+	Suppressed []git.SuppressedChange `json:"suppressed,omitempty"`
+	// Directories is the review progress of every directory holding a changed
+	// file, over all files rather than the returned page, with ?group=directory
+	Directories []DirectoryProgress `json:"directories,omitempty"`
+}
+
+// MergeInfo marks a commit-mode diff of a merge commit. Its files are a
+// combined diff whose hunk lines have one prefix column per parent, in the
+// order of Parents, and only files the merge changed relative to every
+// parent are listed.
+type MergeInfo struct {
+	Parents []string `json:"parents"`
+	// Octopus is set for merges of more than two parents
+	Octopus bool `json:"octopus,omitempty"`
+}
+
+// BaseMoved warns that the base branch gained more commits than the
+// configured threshold since the review started, so the review may be
+// against an outdated base
+type BaseMoved struct {
+	From   string `json:"from"`
+	To     string `json:"to"`
+	Behind int    `json:"behind"`
 }
 
 type FileDiff struct {
@@ -39,26 +213,270 @@ type FileDiff struct {
 	Patch         string `json:"patch"`
 	Viewed        bool   `json:"viewed"`
 	StagingStatus string `json:"staging_status,omitempty"`
+	Index         int    `json:"index"`
+	PatchHash     string `json:"patch_hash"`
+	// Kind classifies the change for clients to branch on; Status is for display
+	Kind git.ChangeKind `json:"kind"`
+	// AutoViewed is set when the file counts as viewed only because it matches
+	// an auto_view_patterns glob
+	AutoViewed bool `json:"auto_viewed,omitempty"`
+	// OldPath is the path before the change, set for renamed files
+	OldPath string `json:"old_path,omitempty"`
+	// Lines is the patch's hunk lines with their line numbers, included with ?lines=true
+	Lines []git.DiffLine `json:"lines,omitempty"`
+	// Truncated is set when Patch was cut short at the max_patch_kb cap; the
+	// full patch is served by /api/diff/file
+	Truncated bool `json:"truncated,omitempty"`
+	// CommentCount counts all comments on the file, resolved or not, and
+	// UnresolvedCommentCount only those still open
+	CommentCount           int `json:"comment_count"`
+	UnresolvedCommentCount int `json:"unresolved_comment_count"`
+	// Split is the patch's hunks laid out side by side, included with
+	// ?split=true; Patch stays the unified diff
+	Split []git.SplitHunk `json:"split,omitempty"`
+	// ChangedSinceViewed is set when the file was marked viewed but its patch
+	// has changed since, so it's reported unviewed until reviewed again
+	ChangedSinceViewed bool `json:"changed_since_viewed,omitempty"`
 }
 
 type MarkViewedRequest struct {
 	FilePath string `json:"file_path"`
+	Mode     string `json:"mode,omitempty"`
+	// PatchHash is the FileDiff.PatchHash that was reviewed. When set, the file
+	// reverts to unviewed once its patch changes.
+	PatchHash string `json:"patch_hash,omitempty"`
+	// Commit is the reviewed commit's full hash, required with mode "commit"
+	Commit string `json:"commit,omitempty"`
+	// Directory, set instead of FilePath, applies the request to every file
+	// under the directory. Marking uses the diff's current files and patch
+	// hashes; unmarking also clears files that have since left the diff.
+	Directory string `json:"directory,omitempty"`
+}
+
+// reviewedCommit returns the commit the request's review state belongs to:
+// the requested commit in commit mode, and HEAD otherwise
+func (req MarkViewedRequest) reviewedCommit(currentCommit string) (string, error) {
+	if req.Mode != DiffModeCommit {
+		return currentCommit, nil
+	}
+	if req.Commit == "" {
+		return "", errors.New("commit is required with mode commit")
+	}
+	return req.Commit, nil
+}
+
+// DiffModeWorking reviews the working tree against HEAD instead of the base branch
+const DiffModeWorking = "working"
+
+// DiffModeCommit reviews the change a single commit in HEAD's history made
+const DiffModeCommit = "commit"
+
+// reviewCommit returns the commit key review state is stored under for a diff mode.
+// Working-tree and single-commit reviews get synthetic keys so they never mix
+// with branch-review state.
+func reviewCommit(mode, currentCommit string) string {
+	switch mode {
+	case DiffModeWorking:
+		return "__working__:" + currentCommit
+	case DiffModeCommit:
+		return "__commit__:" + currentCommit
+	}
+	return currentCommit
+}
+
+// splitReviewCommit undoes reviewCommit, returning the diff mode and commit a
+// review state key refers to
+func splitReviewCommit(key string) (string, string) {
+	if key == state.BranchReviewKey {
+		return "", ""
+	}
+	for _, mode := range []string{DiffModeWorking, DiffModeCommit} {
+		if commit, ok := strings.CutPrefix(key, "__"+mode+"__:"); ok {
+			return mode, commit
+		}
+	}
+	return "", key
+}
+
+// ReviewStateExists returns a predicate reporting whether the repository,
+// branch and commit a review state entry refers to still exist
+func ReviewStateExists() func(repoPath, branch, commit string) bool {
+	repos := make(map[string]*git.Repo)
+	// unreachable holds repositories that couldn't be checked, such as ones
+	// that aren't readable or live on a volume that isn't mounted
+	unreachable := make(map[string]bool)
+
+	return func(repoPath, branch, commit string) bool {
+		// Patch file reviews are kept for as long as the patch file exists
+		if branch == PatchBranch {
+			_, err := os.Stat(repoPath)
+			return !errors.Is(err, fs.ErrNotExist)
+		}
+
+		gitRepo, cached := repos[repoPath]
+		if !cached {
+			_, err := os.Stat(repoPath)
+			switch {
+			case err == nil:
+				gitRepo, _ = git.Open(repoPath)
+			case !errors.Is(err, fs.ErrNotExist):
+				unreachable[repoPath] = true
+			}
+			repos[repoPath] = gitRepo
+		}
+		// Only state of repositories that are gone for sure is pruned
+		if unreachable[repoPath] {
+			return true
+		}
+		if gitRepo == nil {
+			return false
+		}
+
+		if branch != "HEAD" && !gitRepo.BranchExists(branch) {
+			return false
+		}
+
+		// Pseudo-commits for uncommitted and whole-branch reviews
+		if commit == "__uncommitted__" || commit == state.BranchReviewKey {
+			return true
+		}
+		// Working tree and commit mode reviews are keyed by the commit too
+		_, commit = splitReviewCommit(commit)
+
+		return gitRepo.CommitExists(commit)
+	}
+}
+
+// revision returns the branch and commit review state is stored under: the
+// checked-out ones, or stand-ins derived from the patch when reviewing a patch file
+func (s *AppState) revision() (string, string, error) {
+	if s.patch != nil {
+		return PatchBranch, s.patch.hash, nil
+	}
+
+	gitRepo, err := git.Open(".")
+	if err != nil {
+		return "", "", err
+	}
+
+	branch, err := gitRepo.CurrentBranch()
+	if err != nil {
+		return "", "", err
+	}
+
+	commit, err := gitRepo.CurrentCommit()
+	if err != nil {
+		return "", "", err
+	}
+
+	return branch, commit, nil
+}
+
+// repoOnlyHandler answers endpoints that need a repository while a patch file is reviewed
+func repoOnlyHandler(w http.ResponseWriter, r *http.Request) {
+	http.Error(w, "not available when reviewing a patch file", http.StatusBadRequest)
 }
 
 type AddCommentRequest struct {
 	FilePath   string `json:"file_path"`
 	LineNumber *int   `json:"line_number,omitempty"`
 	Text       string `json:"text"`
+	Author     string `json:"author,omitempty"`
+	// TemplateKey fills the comment from a configured template; any Text is
+	// appended after it
+	TemplateKey string `json:"template_key,omitempty"`
+	// ParentID makes the comment a reply to another one, whose file and line
+	// it takes
+	ParentID string `json:"parent_id,omitempty"`
 }
 
 type GetCommentsQuery struct {
 	FilePath string `json:"file_path,omitempty"`
 }
 
+type CommentChangesResponse struct {
+	Comments  []*state.Comment `json:"comments"`
+	Deleted   []string         `json:"deleted"`
+	Timestamp int64            `json:"timestamp"`
+}
+
+type CommentContextResponse struct {
+	CommentID  string   `json:"comment_id"`
+	Commit     string   `json:"commit"`
+	FilePath   string   `json:"file_path"`
+	LineNumber *int     `json:"line_number,omitempty"`
+	StartLine  int      `json:"start_line"`
+	Lines      []string `json:"lines"`
+	Outdated   bool     `json:"outdated"`
+}
+
+type EditCommentRequest struct {
+	CommentID string `json:"comment_id"`
+	Text      string `json:"text"`
+}
+
+type DeleteCommentRequest struct {
+	CommentID string `json:"comment_id"`
+}
+
 type ResolveCommentRequest struct {
 	CommentID string `json:"comment_id"`
 }
 
+type AcknowledgeCommentRequest struct {
+	CommentID string `json:"comment_id"`
+}
+
+type PinCommentRequest struct {
+	CommentID string `json:"comment_id"`
+	Pinned    bool   `json:"pinned"`
+}
+
+// DiffExpandResponse holds file lines hidden between diff hunks. FromLine and
+// ToLine are the requested range clamped to the file.
+type DiffExpandResponse struct {
+	FilePath   string   `json:"file_path"`
+	FromLine   int      `json:"from_line"`
+	ToLine     int      `json:"to_line"`
+	TotalLines int      `json:"total_lines"`
+	Lines      []string `json:"lines"`
+}
+
+// BlameResponse attributes lines around the requested line to the commits
+// that last changed them. Uncommitted is set, with no lines, for files that
+// don't exist at HEAD yet.
+type BlameResponse struct {
+	FilePath    string          `json:"file_path"`
+	Commit      string          `json:"commit"`
+	Lines       []git.BlameLine `json:"lines"`
+	Uncommitted bool            `json:"uncommitted,omitempty"`
+}
+
+// BaseCommitsResponse lists the commits on the base branch that the current
+// branch would pick up by rebasing. Behind counts all of them, even when
+// Commits is cut short by the limit.
+type BaseCommitsResponse struct {
+	BaseBranch string           `json:"base_branch"`
+	Behind     int              `json:"behind"`
+	Commits    []git.BaseCommit `json:"commits"`
+}
+
+// defaultBaseCommitsLimit caps /api/base/commits when no limit is given
+const defaultBaseCommitsLimit = 50
+
+// ReviewSummary is the body of the /api/review/summary endpoints
+type ReviewSummary struct {
+	Summary string `json:"summary"`
+	Mode    string `json:"mode,omitempty"`
+}
+
+// ChecklistRequest ticks or unticks an item of the review checklist
+type ChecklistRequest struct {
+	Item    string `json:"item"`
+	Checked bool   `json:"checked"`
+	Mode    string `json:"mode,omitempty"`
+}
+
 type AddNoteRequest struct {
 	FilePath   string            `json:"file_path"`
 	LineNumber *int              `json:"line_number,omitempty"`
@@ -72,364 +490,2500 @@ type DismissNoteRequest struct {
 	NoteID string `json:"note_id"`
 }
 
-type StatusResponse struct {
-	RepoPath string `json:"repo_path"`
-	Branch   string `json:"branch"`
-	Commit   string `json:"commit"`
-}
+type StatusResponse struct {
+	RepoPath    string `json:"repo_path"`
+	Branch      string `json:"branch"`
+	Commit      string `json:"commit"`
+	CommitShort string `json:"commit_short"`
+	Dirty       bool   `json:"dirty"`
+	// Bare repositories can't be reviewed in working mode
+	Bare bool `json:"bare,omitempty"`
+	// Review is when the review of this branch and commit was started and last
+	// worked on, and by whom
+	Review state.ReviewSession `json:"review"`
+	// Checklist is how much of the review checklist is ticked, when one is configured
+	Checklist *state.ChecklistProgress `json:"checklist,omitempty"`
+}
+
+// ReviewsResponse lists the reviews with state in the repository, grouped by branch
+type ReviewsResponse struct {
+	RepoPath string                   `json:"repo_path"`
+	Branches map[string][]ReviewEntry `json:"branches"`
+}
+
+// ReviewEntry counts the review state of one commit
+type ReviewEntry struct {
+	// Commit is empty for the review state of a whole branch, kept with the
+	// branch and content state granularities
+	Commit      string `json:"commit"`
+	CommitShort string `json:"commit_short"`
+	// Mode is working or commit for reviews made in those diff modes, and empty
+	// for branch reviews
+	Mode               string `json:"mode,omitempty"`
+	ViewedFiles        int    `json:"viewed_files"`
+	Comments           int    `json:"comments"`
+	UnresolvedComments int    `json:"unresolved_comments"`
+	LastActivityAt     int64  `json:"last_activity_at,omitempty"`
+}
+
+// ConfigResponse exposes the server settings the UI needs to build URLs
+type ConfigResponse struct {
+	BasePath        string `json:"base_path"`
+	BaseBranch      string `json:"base_branch"`
+	Offline         bool   `json:"offline"`
+	CommitAbbrevLen int    `json:"commit_abbrev_len"`
+	MaxFiles        int    `json:"max_files"`
+	// CommentTemplates maps template keys to their unexpanded text
+	CommentTemplates map[string]string `json:"comment_templates,omitempty"`
+	// ReviewChecklist lists the items to tick off with /api/review/checklist
+	ReviewChecklist []string `json:"review_checklist,omitempty"`
+}
+
+func Start(port int, baseBranch string, opts Options) error {
+	gitRepo, err := git.Open(".")
+	if err != nil {
+		return err
+	}
+
+	repoPath, err := gitRepo.RepoPath()
+	if err != nil {
+		return err
+	}
+
+	appState, err := newAppState(repoPath, baseBranch, opts)
+	if err != nil {
+		return err
+	}
+
+	return appState.run(port, opts)
+}
+
+// StartPatch serves the review UI for a patch file, such as one made by git
+// format-patch, without applying it. Review state is keyed by a hash of the
+// patch, so reviewing an updated version of the file starts afresh.
+func StartPatch(port int, patchFile string, opts Options) error {
+	data, err := os.ReadFile(patchFile)
+	if err != nil {
+		return fmt.Errorf("failed to read patch file: %w", err)
+	}
+
+	files, err := git.ParsePatch(string(data))
+	if err != nil {
+		return fmt.Errorf("failed to parse %s: %w", patchFile, err)
+	}
+	sortFilesByPath(files)
+
+	absPath, err := filepath.Abs(patchFile)
+	if err != nil {
+		return fmt.Errorf("failed to resolve patch file path: %w", err)
+	}
+
+	appState, err := newAppState(absPath, "", opts)
+	if err != nil {
+		return err
+	}
+
+	sum := sha256.Sum256(data)
+	appState.patch = &patchReview{
+		hash:  hex.EncodeToString(sum[:]),
+		files: files,
+	}
+
+	return appState.run(port, opts)
+}
+
+func newAppState(repoPath, baseBranch string, opts Options) (*AppState, error) {
+	stateMgr, err := state.NewManager()
+	if err != nil {
+		return nil, err
+	}
+
+	logger := opts.Logger
+	if logger == nil {
+		logger = logging.New(os.Stdout, logging.FormatText)
+	}
+
+	return &AppState{
+		RepoPath:           repoPath,
+		BaseBranch:         baseBranch,
+		Offline:            opts.Offline,
+		CommitAbbrevLen:    opts.CommitAbbrevLen,
+		BasePath:           opts.BasePath,
+		MaxCommentLength:   opts.MaxCommentLength,
+		DiffAlgorithm:      opts.DiffAlgorithm,
+		DiffBackend:        opts.DiffBackend,
+		Textconv:           opts.Textconv,
+		RecurseSubmodules:  opts.RecurseSubmodules,
+		MaxFiles:           opts.MaxFiles,
+		MaxPatchKB:         opts.MaxPatchKB,
+		AutoViewPatterns:   opts.AutoViewPatterns,
+		CommentTemplates:   opts.CommentTemplates,
+		ReviewChecklist:    opts.ReviewChecklist,
+		StaleBaseCommits:   opts.StaleBaseCommits,
+		StateManager:       stateMgr,
+		notify:             opts.Notify,
+		live:               newLiveClients(),
+		logger:             logger,
+		CarryForwardViewed: opts.CarryForwardViewed,
+		viewedCarried:      make(map[string]string),
+	}, nil
+}
+
+// run serves the UI and API until the server fails or is interrupted
+func (s *AppState) run(port int, opts Options) error {
+	network, addr := "tcp", fmt.Sprintf("127.0.0.1:%d", port)
+	if opts.Socket != "" {
+		network, addr = "unix", opts.Socket
+	}
+	if opts.Watchdog {
+		s.watchdog = newWatchdog(s.RepoPath, network, addr)
+	}
+
+	r := mux.NewRouter()
+	router := r
+	if s.BasePath != "" {
+		// The UI uses relative URLs, so the prefix itself must end in a slash
+		r.Handle(s.BasePath, http.RedirectHandler(s.BasePath+"/", http.StatusMovedPermanently))
+		router = r.PathPrefix(s.BasePath).Subrouter()
+	}
+
+	router.HandleFunc("/", s.indexHandler).Methods("GET")
+	router.HandleFunc("/api/openapi.json", openAPIHandler).Methods("GET")
+	for _, rt := range s.routes() {
+		handler := rt.Handler
+		if rt.NeedsRepo && s.patch != nil {
+			handler = repoOnlyHandler
+		}
+		if rt.Method != "GET" {
+			handler = s.trackAPIWrite(handler)
+		}
+		router.HandleFunc(rt.Path, handler).Methods(rt.Method)
+	}
+
+	tlsConfig, err := LoadTLSConfig(opts.TLSCert, opts.TLSKey)
+	if err != nil {
+		return err
+	}
+
+	scheme := "http"
+	if tlsConfig != nil {
+		scheme = "https"
+	}
+
+	logger := s.logger
+	if network == "unix" {
+		logger.Info("starting server", "socket", addr, "scheme", scheme, "base_path", s.BasePath+"/")
+	} else {
+		logger.Info("starting server", "url", fmt.Sprintf("%s://%s%s/", scheme, addr, s.BasePath))
+	}
+	if s.patch != nil {
+		logger.Info("reviewing patch file", "path", s.RepoPath)
+	} else {
+		logger.Info("comparing against base branch", "base_branch", s.BaseBranch)
+	}
+	if opts.Offline {
+		logger.Info("offline mode: base ref is local-only")
+	}
+
+	var handler http.Handler = r
+	if opts.OnActivity != nil {
+		handler = trackActivity(r, opts.OnActivity)
+	}
+
+	ln, err := listen(network, addr)
+	if err != nil {
+		return err
+	}
+
+	ctx, stop := signal.NotifyContext(context.Background(), os.Interrupt, syscall.SIGTERM)
+	defer stop()
+
+	// Requests outlive ctx, so in-flight diffs and state writes can finish
+	// while the server drains; they are only cancelled if draining times out
+	requestCtx, cancelRequests := context.WithCancel(context.Background())
+	defer cancelRequests()
+	srv := &http.Server{
+		Handler:     handler,
+		TLSConfig:   tlsConfig,
+		BaseContext: func(net.Listener) context.Context { return requestCtx },
+	}
+	srv.RegisterOnShutdown(func() {
+		logger.Info("shutting down, draining in-flight requests", "timeout", shutdownTimeout)
+	})
+	defer s.waitForStateWrites()
+
+	if s.patch == nil {
+		watcher, err := newRepoWatcher(s.RepoPath)
+		if err == nil {
+			s.watcher = watcher
+			defer watcher.close()
+		} else if !errors.Is(err, errWatchUnsupported) {
+			logger.Info("polling for changes instead of watching", "error", err)
+		}
+		go s.watchChanges(requestCtx)
+	}
+
+	if s.watchdog != nil {
+		srv.Handler = s.watchdog.recoverPanics(handler)
+		return serveWithWatchdog(ctx, srv, ln, s.watchdog, cancelRequests)
+	}
+
+	return serve(ctx, srv, ln, cancelRequests)
+}
+
+// listen binds the TCP address or Unix domain socket path to serve on. A
+// socket file left behind by a daemon that didn't shut down cleanly is
+// replaced, and new sockets are only accessible to the current user.
+func listen(network, addr string) (net.Listener, error) {
+	if network != "unix" {
+		return net.Listen(network, addr)
+	}
+
+	if conn, err := net.DialTimeout("unix", addr, time.Second); err == nil {
+		conn.Close()
+		return nil, fmt.Errorf("socket %s is already in use", addr)
+	}
+	if err := os.Remove(addr); err != nil && !errors.Is(err, fs.ErrNotExist) {
+		return nil, fmt.Errorf("failed to remove stale socket %s: %w", addr, err)
+	}
+	if err := os.MkdirAll(filepath.Dir(addr), 0700); err != nil {
+		return nil, fmt.Errorf("failed to create socket directory: %w", err)
+	}
+
+	ln, err := net.Listen("unix", addr)
+	if err != nil {
+		return nil, err
+	}
+	if err := os.Chmod(addr, 0600); err != nil {
+		ln.Close()
+		return nil, fmt.Errorf("failed to restrict socket permissions: %w", err)
+	}
+	return ln, nil
+}
+
+// LoadTLSConfig loads the certificate and key used to serve HTTPS. It returns
+// nil when neither is configured, so the server falls back to plain HTTP.
+func LoadTLSConfig(certFile, keyFile string) (*tls.Config, error) {
+	if certFile == "" && keyFile == "" {
+		return nil, nil
+	}
+	if certFile == "" || keyFile == "" {
+		return nil, errors.New("tls_cert and tls_key must be set together")
+	}
+
+	cert, err := tls.LoadX509KeyPair(certFile, keyFile)
+	if err != nil {
+		return nil, fmt.Errorf("failed to load TLS certificate %s and key %s: %w", certFile, keyFile, err)
+	}
+
+	return &tls.Config{
+		Certificates: []tls.Certificate{cert},
+		MinVersion:   tls.VersionTLS12,
+	}, nil
+}
+
+// serveListener serves HTTPS on ln when srv has a TLS config and HTTP otherwise
+func serveListener(srv *http.Server, ln net.Listener) error {
+	if srv.TLSConfig != nil {
+		return srv.ServeTLS(ln, "", "")
+	}
+	return srv.Serve(ln)
+}
+
+// serve runs srv on ln until it fails or ctx is cancelled, then shuts it down
+func serve(ctx context.Context, srv *http.Server, ln net.Listener, cancelRequests context.CancelFunc) error {
+	serveErr := make(chan error, 1)
+	go func() {
+		serveErr <- serveListener(srv, ln)
+	}()
+
+	select {
+	case err := <-serveErr:
+		return err
+	case <-ctx.Done():
+		return shutdown(srv, cancelRequests)
+	}
+}
+
+// shutdown stops srv gracefully: it stops accepting connections and waits for
+// in-flight requests to finish. Requests still running after shutdownTimeout
+// are cancelled and their connections closed.
+func shutdown(srv *http.Server, cancelRequests context.CancelFunc) error {
+	shutdownCtx, cancel := context.WithTimeout(context.Background(), shutdownTimeout)
+	defer cancel()
+
+	if err := srv.Shutdown(shutdownCtx); err != nil {
+		if errors.Is(err, context.DeadlineExceeded) {
+			cancelRequests()
+			return srv.Close()
+		}
+		return fmt.Errorf("failed to shut down server: %w", err)
+	}
+	return nil
+}
+
+// waitForStateWrites waits, up to shutdownTimeout, for a handler cancelled by
+// a timed out drain to finish writing review state, then keeps any other from
+// starting before the process exits
+func (s *AppState) waitForStateWrites() {
+	locked := make(chan struct{})
+	go func() {
+		s.mu.Lock()
+		close(locked)
+	}()
+
+	select {
+	case <-locked:
+	case <-time.After(shutdownTimeout):
+	}
+}
+
+// trackActivity calls onActivity for served requests, at most once per activityInterval
+func trackActivity(next http.Handler, onActivity func()) http.Handler {
+	var mu sync.Mutex
+	var last time.Time
+
+	return http.HandlerFunc(func(w http.ResponseWriter, r *http.Request) {
+		mu.Lock()
+		due := time.Since(last) >= activityInterval
+		if due {
+			last = time.Now()
+		}
+		mu.Unlock()
+
+		if due {
+			onActivity()
+		}
+		next.ServeHTTP(w, r)
+	})
+}
+
+// trackAPIWrite records when a request that may change the repository, such
+// as fetching the base, or the review finished, so the changes it causes
+// aren't notified as if someone else made them
+func (s *AppState) trackAPIWrite(next http.HandlerFunc) http.HandlerFunc {
+	return func(w http.ResponseWriter, r *http.Request) {
+		defer func() { s.lastAPIWrite.Store(time.Now().UnixNano()) }()
+		next(w, r)
+	}
+}
+
+func (s *AppState) indexHandler(w http.ResponseWriter, r *http.Request) {
+	w.Header().Set("Content-Type", "text/html")
+	_, _ = w.Write([]byte(indexHTML)) // Ignore write error for HTTP response
+}
+
+func (s *AppState) configHandler(w http.ResponseWriter, r *http.Request) {
+	response := ConfigResponse{
+		BasePath:         s.BasePath,
+		BaseBranch:       s.BaseBranch,
+		Offline:          s.Offline,
+		CommitAbbrevLen:  s.CommitAbbrevLen,
+		MaxFiles:         s.MaxFiles,
+		CommentTemplates: s.CommentTemplates,
+		ReviewChecklist:  s.ReviewChecklist,
+	}
+
+	w.Header().Set("Content-Type", "application/json")
+	_ = json.NewEncoder(w).Encode(response) // Ignore encode error for HTTP response
+}
+
+func (s *AppState) diffHandler(w http.ResponseWriter, r *http.Request) {
+	s.mu.Lock()
+	defer s.mu.Unlock()
+
+	if _, err := parseOptionalBool(r, "lines"); err != nil {
+		http.Error(w, err.Error(), http.StatusBadRequest)
+		return
+	}
+	if _, err := parseOptionalBool(r, "split"); err != nil {
+		http.Error(w, err.Error(), http.StatusBadRequest)
+		return
+	}
+	if err := validateGroup(r); err != nil {
+		http.Error(w, err.Error(), http.StatusBadRequest)
+		return
+	}
+	pathspec, err := parsePathspec(r)
+	if err != nil {
+		http.Error(w, err.Error(), http.StatusBadRequest)
+		return
+	}
+
+	if s.patch != nil {
+		if len(pathspec) > 0 {
+			http.Error(w, "pathspec is not supported when reviewing a patch file", http.StatusBadRequest)
+			return
+		}
+		if r.URL.Query().Get("algorithm") != "" {
+			http.Error(w, "algorithm is not supported when reviewing a patch file", http.StatusBadRequest)
+			return
+		}
+		s.patchDiff(w, r)
+		return
+	}
+
+	gitRepo, err := git.Open(".")
+	if err != nil {
+		http.Error(w, err.Error(), http.StatusInternalServerError)
+		return
+	}
+
+	currentBranch, err := gitRepo.CurrentBranch()
+	if err != nil {
+		http.Error(w, err.Error(), http.StatusInternalServerError)
+		return
+	}
+
+	currentCommit, err := gitRepo.CurrentCommit()
+	if err != nil {
+		http.Error(w, err.Error(), http.StatusInternalServerError)
+		return
+	}
+
+	remoteURL, _ := gitRepo.GetRemoteURL() // Ignore error, remote is optional
+
+	switch mode := r.URL.Query().Get("mode"); mode {
+	case DiffModeWorking:
+		s.workingDiff(w, r, gitRepo, currentBranch, currentCommit, remoteURL)
+		return
+	case DiffModeCommit:
+		s.commitDiff(w, r, gitRepo, currentBranch, currentCommit, remoteURL)
+		return
+	case "", "branch":
+	default:
+		http.Error(w, fmt.Sprintf("invalid mode: %s (expected branch, working or commit)", mode), http.StatusBadRequest)
+		return
+	}
+
+	// Review state left on an amended-away commit is offered for carry-forward
+	amendedFrom := ""
+	// With per-branch review state there's nothing to carry
+	if previous, err := gitRepo.AmendedFrom(); err == nil && previous != "" && s.StateManager.Granularity(s.RepoPath) == state.GranularityCommit &&
+		s.StateManager.HasReviewState(s.RepoPath, currentBranch, previous) {
+		if r.URL.Query().Get("carry_forward") == "true" {
+			if _, _, err := s.StateManager.CarryForward(s.RepoPath, currentBranch, previous, currentCommit); err != nil {
+				http.Error(w, err.Error(), http.StatusInternalServerError)
+				return
+			}
+		} else {
+			amendedFrom = previous
+		}
+	}
+
+	unviewedOnly, err := parseOptionalBool(r, "unviewed_only")
+	if err != nil {
+		http.Error(w, err.Error(), http.StatusBadRequest)
+		return
+	}
+
+	pg, err := parsePage(r, s.MaxFiles)
+	if err != nil {
+		http.Error(w, err.Error(), http.StatusBadRequest)
+		return
+	}
+
+	algorithm, err := s.diffAlgorithm(r, gitRepo)
+	if err != nil {
+		http.Error(w, err.Error(), http.StatusBadRequest)
+		return
+	}
+
+	recurseSubmodules := s.RecurseSubmodules
+	if r.URL.Query().Get("recurse_submodules") != "" {
+		recurseSubmodules, err = parseOptionalBool(r, "recurse_submodules")
+		if err != nil {
+			http.Error(w, err.Error(), http.StatusBadRequest)
+			return
+		}
+	}
+
+	// An incremental review diffs HEAD against an earlier commit instead of the
+	// base branch, falling back to the full diff when nothing was reviewed yet
+	since := r.URL.Query().Get("since")
+	sinceLastReview, err := parseOptionalBool(r, "since_last_review")
+	if err != nil {
+		http.Error(w, err.Error(), http.StatusBadRequest)
+		return
+	}
+	if since == "" && sinceLastReview {
+		since = s.StateManager.LastReviewedCommit(s.RepoPath, currentBranch, currentCommit)
+	}
+
+	ignoreCherryPicks, err := parseOptionalBool(r, "ignore_cherry_picks")
+	if err != nil {
+		http.Error(w, err.Error(), http.StatusBadRequest)
+		return
+	}
+
+	diffOptions := git.DiffOptions{
+		Algorithm:         algorithm,
+		RecurseSubmodules: recurseSubmodules,
+		Backend:           s.DiffBackend,
+		Textconv:          s.Textconv,
+		Pathspec:          pathspec,
+	}
+	var files []git.FileInfo
+	if since != "" {
+		files, err = gitRepo.GetDiffSince(r.Context(), since, diffOptions)
+		if errors.Is(err, git.ErrCommitNotInHistory) {
+			if r.URL.Query().Get("since") != "" {
+				http.Error(w, err.Error(), http.StatusBadRequest)
+				return
+			}
+			// The last reviewed commit was garbage collected after a rebase
+			since = ""
+		}
+	}
+	if since == "" {
+		files, err = gitRepo.GetDiffFilesContext(r.Context(), s.BaseBranch, diffOptions)
+	}
+	if r.Context().Err() != nil {
+		// The client disconnected or the server is shutting down, so nobody is
+		// waiting for the response and the abandoned diff isn't a failure
+		return
+	}
+	s.watchdog.recordDiffResult(err)
+	if err != nil {
+		http.Error(w, err.Error(), http.StatusInternalServerError)
+		return
+	}
+	sortFilesByPath(files)
+	totalUnfiltered := len(files)
+
+	if since == "" {
+		if err := s.carryForwardViewed(gitRepo, currentBranch, currentCommit, files); err != nil {
+			http.Error(w, err.Error(), stateErrorStatus(err))
+			return
+		}
+	}
+
+	// Changes the base already has through cherry-picked commits aren't worth
+	// reviewing again. An incremental diff isn't against the base, so it's
+	// left alone.
+	var suppressed []git.SuppressedChange
+	if ignoreCherryPicks && since == "" {
+		files, suppressed, err = gitRepo.SuppressCherryPicks(r.Context(), s.BaseBranch, files)
+		if r.Context().Err() != nil {
+			return
+		}
+		if err != nil {
+			http.Error(w, err.Error(), http.StatusInternalServerError)
+			return
+		}
+	}
+
+	if err := s.canonicalizeStatePaths(currentBranch, currentCommit, files); err != nil {
+		http.Error(w, err.Error(), stateErrorStatus(err))
+		return
+	}
+
+	// Narrow the cumulative diff to the files a single commit touched. Files that
+	// commit touched but which have no net change in the branch are reported as
+	// reverted rather than silently dropped.
+	onlyFilesFrom := ""
+	var revertedFiles []string
+	if rev := r.URL.Query().Get("only_files_from"); rev != "" {
+		resolved, touched, err := gitRepo.CommitChangedPaths(rev)
+		if err != nil {
+			status := http.StatusInternalServerError
+			if errors.Is(err, git.ErrCommitNotInHistory) {
+				status = http.StatusBadRequest
+			}
+			http.Error(w, err.Error(), status)
+			return
+		}
+
+		onlyFilesFrom = resolved
+		files, revertedFiles = filterFilesByPaths(files, touched)
+	}
+
+	fileDiffs := []FileDiff{}
+	directories := directoryRollup{}
+	for _, file := range files {
+		hash := state.PatchHash(file.Patch)
+		var viewed, changedSinceViewed bool
+		if since != "" {
+			// The incremental patch isn't the one a viewed hash was recorded
+			// against, so it must neither invalidate nor record one
+			hash = ""
+			viewed = s.StateManager.IsFileViewed(s.RepoPath, currentBranch, currentCommit, file.Path)
+		} else {
+			viewed, changedSinceViewed = s.viewedInCurrentForm(currentBranch, currentCommit, file.Path, file.Patch)
+		}
+		autoViewed := !viewed && state.MatchesAnyPattern(file.Path, s.AutoViewPatterns)
+		directories.add(file.Path, viewed || autoViewed)
+		if unviewedOnly && (viewed || autoViewed) {
+			continue
+		}
+
+		fileDiffs = append(fileDiffs, FileDiff{
+			Path:               file.Path,
+			Status:             file.Status,
+			Kind:               file.Kind,
+			Additions:          file.Additions,
+			Deletions:          file.Deletions,
+			Patch:              file.Patch,
+			Viewed:             viewed || autoViewed,
+			StagingStatus:      string(git.StagingStatusCommitted),
+			Index:              len(fileDiffs),
+			PatchHash:          hash,
+			AutoViewed:         autoViewed,
+			ChangedSinceViewed: changedSinceViewed,
+			OldPath:            file.OldPath,
+		})
+	}
+
+	// Get uncommitted changes
+	uncommittedFiles, err := gitRepo.GetUncommittedChangesWithOptions(git.DiffOptions{Algorithm: algorithm})
+	if err == nil && len(pathspec) > 0 {
+		var matching map[string]bool
+		if matching, err = gitRepo.UncommittedPathsMatching(pathspec); err == nil {
+			var kept []git.FileInfo
+			for _, file := range uncommittedFiles {
+				if matching[file.Path] {
+					kept = append(kept, file)
+				}
+			}
+			uncommittedFiles = kept
+		}
+	}
+	uncommittedFileDiffs := []FileDiff{}
+	if err == nil {
+		for _, file := range uncommittedFiles {
+			// Use a special commit identifier for uncommitted changes state
+			uncommittedCommit := "__uncommitted__"
+			hash := state.PatchHash(file.Patch)
+			viewed, changedSinceViewed := s.viewedInCurrentForm(currentBranch, uncommittedCommit, file.Path+":"+string(file.StagingStatus), file.Patch)
+			autoViewed := !viewed && state.MatchesAnyPattern(file.Path, s.AutoViewPatterns)
+
+			uncommittedFileDiffs = append(uncommittedFileDiffs, FileDiff{
+				Path:               file.Path,
+				Status:             file.Status,
+				Kind:               file.Kind,
+				Additions:          file.Additions,
+				Deletions:          file.Deletions,
+				Patch:              file.Patch,
+				Viewed:             viewed || autoViewed,
+				StagingStatus:      string(file.StagingStatus),
+				Index:              len(uncommittedFileDiffs),
+				PatchHash:          hash,
+				AutoViewed:         autoViewed,
+				ChangedSinceViewed: changedSinceViewed,
+			})
+		}
+	}
+
+	s.setCommentCounts(currentBranch, currentCommit, fileDiffs)
+	s.setCommentCounts(currentBranch, currentCommit, uncommittedFileDiffs)
+
+	// The base may be missing locally or cut off by a shallow clone, which
+	// leaves the counts out rather than failing the whole diff
+	var ahead, behind *int
+	if a, b, err := gitRepo.AheadBehind(s.BaseBranch); err == nil {
+		ahead, behind = &a, &b
+	} else {
+		s.logger.Warn("failed to count commits ahead of and behind the base branch", "base_branch", s.BaseBranch, "error", err)
+	}
+
+	baseMoved, err := s.baseMovement(gitRepo, currentBranch, r.URL.Query().Get("acknowledge_base") == "true")
+	if err != nil {
+		http.Error(w, err.Error(), http.StatusInternalServerError)
+		return
+	}
+
+	response := DiffResponse{
+		Files:            pg.apply(fileDiffs),
+		UncommittedFiles: uncommittedFileDiffs,
+		Branch:           currentBranch,
+		Commit:           currentCommit,
+		CommitShort:      git.Abbreviate(currentCommit, s.CommitAbbrevLen),
+		RepoPath:         s.RepoPath,
+		RemoteURL:        remoteURL,
+		AmendedFrom:      amendedFrom,
+		OnlyFilesFrom:    onlyFilesFrom,
+		RevertedFiles:    revertedFiles,
+		Since:            since,
+		Total:            len(fileDiffs),
+		TotalUnfiltered:  totalUnfiltered,
+		DiffAlgorithm:    algorithm,
+		Ahead:            ahead,
+		Behind:           behind,
+		Offset:           pg.offset,
+		Limit:            pg.limit,
+		BaseMoved:        baseMoved,
+		Suppressed:       suppressed,
+		Directories:      directories.list(r),
+	}
+
+	s.writeDiffResponse(w, r, response)
+}
+
+// maxFullPatchBytes bounds the patches /api/diff/file serves with ?full=true,
+// so a huge generated file can't exhaust the daemon's or the browser's memory
+const maxFullPatchBytes = 16 << 20
+
+// truncatePatch cuts patch down to at most limit bytes, ending at a line
+// boundary, and reports whether it did. A limit of 0 or less means no cap.
+func truncatePatch(patch string, limit int) (string, bool) {
+	if limit <= 0 || len(patch) <= limit {
+		return patch, false
+	}
+	cut := patch[:limit]
+	if i := strings.LastIndexByte(cut, '\n'); i != -1 {
+		cut = cut[:i+1]
+	}
+	return cut, true
+}
+
+// setCommentCounts fills in each file's comment counts from the review's
+// comments on that file
+func (s *AppState) setCommentCounts(branch, commit string, files []FileDiff) {
+	for i := range files {
+		for _, comment := range s.StateManager.GetComments(s.RepoPath, branch, commit, &files[i].Path) {
+			files[i].CommentCount++
+			if !comment.Resolved {
+				files[i].UnresolvedCommentCount++
+			}
+		}
+	}
+}
+
+// diffAlgorithm picks the diff algorithm for a request: the ?algorithm
+// parameter, then the configured one, then the repository's diff.algorithm
+func (s *AppState) diffAlgorithm(r *http.Request, gitRepo *git.Repo) (string, error) {
+	algorithm := r.URL.Query().Get("algorithm")
+	if err := git.ValidateDiffAlgorithm(algorithm); err != nil {
+		return "", err
+	}
+	if algorithm == "" {
+		algorithm = s.DiffAlgorithm
+	}
+	if algorithm == "" {
+		algorithm = gitRepo.ConfiguredDiffAlgorithm()
+	}
+	if git.ValidateDiffAlgorithm(algorithm) != nil {
+		// The repo's diff.algorithm may be a value we don't support, such as "minimal"
+		algorithm = ""
+	}
+	return algorithm, nil
+}
+
+// writeDiffResponse encodes a diff response, truncating patches over the
+// max_patch_kb cap and adding each file's parsed patch lines when the request
+// asks for them with ?lines=true, or its side-by-side hunks with ?split=true
+func (s *AppState) writeDiffResponse(w http.ResponseWriter, r *http.Request, response DiffResponse) {
+	withLines, _ := parseOptionalBool(r, "lines") // Validated by diffHandler
+	withSplit, _ := parseOptionalBool(r, "split") // Validated by diffHandler
+	for _, files := range [][]FileDiff{response.Files, response.UncommittedFiles} {
+		for i := range files {
+			// PatchHash stays the full patch's, so viewed state isn't affected
+			files[i].Patch, files[i].Truncated = truncatePatch(files[i].Patch, s.MaxPatchKB*1024)
+			if withLines {
+				files[i].Lines = git.PatchLines(files[i].Patch)
+			}
+			if withSplit {
+				files[i].Split = git.SplitPatch(files[i].Patch)
+			}
+		}
+	}
+
+	w.Header().Set("Content-Type", "application/json")
+	_ = json.NewEncoder(w).Encode(response) // Ignore encode error for HTTP response
+}
+
+// baseMovement compares the base tip to the one the branch's review started
+// against, recording the current tip on the branch's first diff or when the
+// reviewer acknowledges the move. Callers must hold s.mu.
+func (s *AppState) baseMovement(gitRepo *git.Repo, branch string, acknowledge bool) (*BaseMoved, error) {
+	if s.StaleBaseCommits <= 0 {
+		return nil, nil
+	}
+
+	tip, err := gitRepo.BaseTip(s.BaseBranch)
+	if err != nil {
+		return nil, err
+	}
+
+	from, ok := s.reviewBases[branch]
+	if !ok || acknowledge {
+		if s.reviewBases == nil {
+			s.reviewBases = make(map[string]string)
+		}
+		s.reviewBases[branch] = tip
+		return nil, nil
+	}
+	if from == tip {
+		return nil, nil
+	}
+
+	moved, err := gitRepo.CountCommits(from, tip)
+	if err != nil {
+		return nil, err
+	}
+	if moved <= s.StaleBaseCommits {
+		return nil, nil
+	}
+
+	return &BaseMoved{From: from, To: tip, Behind: moved}, nil
+}
+
+// viewedInCurrentForm reports whether a file is marked viewed and its patch is
+// unchanged since then, and whether it was viewed but its patch changed since.
+// Reading the diff never changes state, so a stale mark stays until the file
+// is marked or unmarked again. Callers must hold s.mu.
+func (s *AppState) viewedInCurrentForm(branch, commit, filePath, patch string) (bool, bool) {
+	return s.StateManager.ViewedInForm(s.RepoPath, branch, commit, filePath, patch)
+}
+
+// canonicalizeStatePaths moves review state stored under a differently-cased
+// path, such as the old name of a case-only rename, to the path in the diff
+func (s *AppState) canonicalizeStatePaths(branch, commit string, files []git.FileInfo) error {
+	paths := make([]string, len(files))
+	for i, file := range files {
+		paths[i] = file.Path
+	}
+	_, err := s.StateManager.CanonicalizePaths(s.RepoPath, branch, commit, paths)
+	return err
+}
+
+// sortFilesByPath gives files a stable order so indices match between reloads
+func sortFilesByPath(files []git.FileInfo) {
+	sort.SliceStable(files, func(i, j int) bool {
+		return files[i].Path < files[j].Path
+	})
+}
+
+// parseOptionalBool parses a boolean query parameter, defaulting to false when absent
+func parseOptionalBool(r *http.Request, name string) (bool, error) {
+	value := r.URL.Query().Get(name)
+	if value == "" {
+		return false, nil
+	}
+
+	parsed, err := strconv.ParseBool(value)
+	if err != nil {
+		return false, fmt.Errorf("invalid %s: %v", name, err)
+	}
+	return parsed, nil
+}
+
+// parsePathspec reads the repeatable ?pathspec parameter, git pathspecs the
+// diff is limited to
+func parsePathspec(r *http.Request) ([]string, error) {
+	pathspec := r.URL.Query()["pathspec"]
+	if err := git.ValidatePathspec(pathspec); err != nil {
+		return nil, err
+	}
+	return pathspec, nil
+}
+
+// page is the window of files requested with ?offset= and ?limit=
+type page struct {
+	offset int
+	limit  int // 0 means no limit
+}
+
+// parsePage reads the offset and limit query parameters. The limit defaults
+// to, and can't exceed, maxFiles when that is positive.
+func parsePage(r *http.Request, maxFiles int) (page, error) {
+	pg := page{limit: maxFiles}
+
+	for name, target := range map[string]*int{"offset": &pg.offset, "limit": &pg.limit} {
+		value := r.URL.Query().Get(name)
+		if value == "" {
+			continue
+		}
+		parsed, err := strconv.Atoi(value)
+		if err != nil || parsed < 0 {
+			return page{}, fmt.Errorf("invalid %s: must be a non-negative integer", name)
+		}
+		*target = parsed
+	}
+
+	if maxFiles > 0 && (pg.limit == 0 || pg.limit > maxFiles) {
+		pg.limit = maxFiles
+	}
+	return pg, nil
+}
+
+// apply returns the files within the page. Files keep their Index, so it stays
+// the position in the full list across pages.
+func (pg page) apply(files []FileDiff) []FileDiff {
+	if pg.offset >= len(files) {
+		return []FileDiff{}
+	}
+	files = files[pg.offset:]
+	if pg.limit > 0 && pg.limit < len(files) {
+		files = files[:pg.limit]
+	}
+	return files
+}
+
+// filterFilesByPaths keeps the files whose path is in paths, and returns the
+// paths that had no matching file
+func filterFilesByPaths(files []git.FileInfo, paths []string) ([]git.FileInfo, []string) {
+	wanted := make(map[string]bool, len(paths))
+	for _, p := range paths {
+		wanted[p] = true
+	}
+
+	filtered := []git.FileInfo{}
+	found := make(map[string]bool, len(paths))
+	for _, file := range files {
+		if wanted[file.Path] {
+			filtered = append(filtered, file)
+			found[file.Path] = true
+		}
+	}
+
+	missing := []string{}
+	for _, p := range paths {
+		if !found[p] {
+			missing = append(missing, p)
+		}
+	}
+
+	return filtered, missing
+}
+
+// workingDiff serves the working tree diffed against HEAD, with viewed state
+// keyed separately from the branch review
+func (s *AppState) workingDiff(w http.ResponseWriter, r *http.Request, gitRepo *git.Repo, currentBranch, currentCommit, remoteURL string) {
+	unviewedOnly, err := parseOptionalBool(r, "unviewed_only")
+	if err != nil {
+		http.Error(w, err.Error(), http.StatusBadRequest)
+		return
+	}
+
+	pg, err := parsePage(r, s.MaxFiles)
+	if err != nil {
+		http.Error(w, err.Error(), http.StatusBadRequest)
+		return
+	}
+
+	algorithm, err := s.diffAlgorithm(r, gitRepo)
+	if err != nil {
+		http.Error(w, err.Error(), http.StatusBadRequest)
+		return
+	}
+
+	files, err := gitRepo.GetWorkingChangesWithOptions(git.DiffOptions{
+		Algorithm: algorithm,
+		Pathspec:  r.URL.Query()["pathspec"], // Validated by diffHandler
+	})
+	if err != nil {
+		status := http.StatusInternalServerError
+		if errors.Is(err, git.ErrBareRepository) {
+			status = http.StatusBadRequest
+		}
+		http.Error(w, err.Error(), status)
+		return
+	}
+	sortFilesByPath(files)
+
+	workingCommit := reviewCommit(DiffModeWorking, currentCommit)
+	if err := s.canonicalizeStatePaths(currentBranch, workingCommit, files); err != nil {
+		http.Error(w, err.Error(), stateErrorStatus(err))
+		return
+	}
+
+	fileDiffs := []FileDiff{}
+	directories := directoryRollup{}
+	for _, file := range files {
+		hash := state.PatchHash(file.Patch)
+		viewed, changedSinceViewed := s.viewedInCurrentForm(currentBranch, workingCommit, file.Path, file.Patch)
+		autoViewed := !viewed && state.MatchesAnyPattern(file.Path, s.AutoViewPatterns)
+		directories.add(file.Path, viewed || autoViewed)
+		if unviewedOnly && (viewed || autoViewed) {
+			continue
+		}
+
+		fileDiffs = append(fileDiffs, FileDiff{
+			Path:               file.Path,
+			Status:             file.Status,
+			Kind:               file.Kind,
+			Additions:          file.Additions,
+			Deletions:          file.Deletions,
+			Patch:              file.Patch,
+			Viewed:             viewed || autoViewed,
+			Index:              len(fileDiffs),
+			PatchHash:          hash,
+			AutoViewed:         autoViewed,
+			ChangedSinceViewed: changedSinceViewed,
+		})
+	}
+
+	s.setCommentCounts(currentBranch, currentCommit, fileDiffs)
+
+	response := DiffResponse{
+		Files:           pg.apply(fileDiffs),
+		Branch:          currentBranch,
+		Commit:          currentCommit,
+		CommitShort:     git.Abbreviate(currentCommit, s.CommitAbbrevLen),
+		RepoPath:        s.RepoPath,
+		RemoteURL:       remoteURL,
+		Mode:            DiffModeWorking,
+		Total:           len(fileDiffs),
+		TotalUnfiltered: len(files),
+		Offset:          pg.offset,
+		Limit:           pg.limit,
+		Directories:     directories.list(r),
+	}
+
+	s.writeDiffResponse(w, r, response)
+}
+
+// commitDiff serves the change made by the commit named by ?commit, which
+// must be in HEAD's history. Merge commits get a combined diff against all
+// their parents.
+func (s *AppState) commitDiff(w http.ResponseWriter, r *http.Request, gitRepo *git.Repo, currentBranch, currentCommit, remoteURL string) {
+	rev := r.URL.Query().Get("commit")
+	if rev == "" {
+		http.Error(w, "commit is required with mode commit", http.StatusBadRequest)
+		return
+	}
+
+	unviewedOnly, err := parseOptionalBool(r, "unviewed_only")
+	if err != nil {
+		http.Error(w, err.Error(), http.StatusBadRequest)
+		return
+	}
+
+	pg, err := parsePage(r, s.MaxFiles)
+	if err != nil {
+		http.Error(w, err.Error(), http.StatusBadRequest)
+		return
+	}
+
+	algorithm, err := s.diffAlgorithm(r, gitRepo)
+	if err != nil {
+		http.Error(w, err.Error(), http.StatusBadRequest)
+		return
+	}
+
+	diff, err := gitRepo.GetCommitDiff(r.Context(), rev, git.DiffOptions{
+		Algorithm: algorithm,
+		Backend:   s.DiffBackend,
+		Textconv:  s.Textconv,
+		Pathspec:  r.URL.Query()["pathspec"], // Validated by diffHandler
+	})
+	if r.Context().Err() != nil {
+		return
+	}
+	if err != nil {
+		status := http.StatusInternalServerError
+		if errors.Is(err, git.ErrCommitNotInHistory) {
+			status = http.StatusBadRequest
+		}
+		http.Error(w, err.Error(), status)
+		return
+	}
+	files := diff.Files
+	sortFilesByPath(files)
+
+	commitKey := reviewCommit(DiffModeCommit, diff.Commit)
+	if err := s.canonicalizeStatePaths(currentBranch, commitKey, files); err != nil {
+		http.Error(w, err.Error(), stateErrorStatus(err))
+		return
+	}
+
+	fileDiffs := []FileDiff{}
+	directories := directoryRollup{}
+	for _, file := range files {
+		hash := state.PatchHash(file.Patch)
+		viewed, changedSinceViewed := s.viewedInCurrentForm(currentBranch, commitKey, file.Path, file.Patch)
+		autoViewed := !viewed && state.MatchesAnyPattern(file.Path, s.AutoViewPatterns)
+		directories.add(file.Path, viewed || autoViewed)
+		if unviewedOnly && (viewed || autoViewed) {
+			continue
+		}
+
+		fileDiffs = append(fileDiffs, FileDiff{
+			Path:               file.Path,
+			Status:             file.Status,
+			Kind:               file.Kind,
+			Additions:          file.Additions,
+			Deletions:          file.Deletions,
+			Patch:              file.Patch,
+			Viewed:             viewed || autoViewed,
+			StagingStatus:      string(git.StagingStatusCommitted),
+			Index:              len(fileDiffs),
+			PatchHash:          hash,
+			AutoViewed:         autoViewed,
+			ChangedSinceViewed: changedSinceViewed,
+			OldPath:            file.OldPath,
+		})
+	}
+
+	// Comments are kept with the checked-out commit whatever the diff mode
+	s.setCommentCounts(currentBranch, currentCommit, fileDiffs)
+
+	var merge *MergeInfo
+	if diff.Combined {
+		merge = &MergeInfo{Parents: diff.Parents, Octopus: len(diff.Parents) > 2}
+	}
+
+	response := DiffResponse{
+		Files:           pg.apply(fileDiffs),
+		Branch:          currentBranch,
+		Commit:          diff.Commit,
+		CommitShort:     git.Abbreviate(diff.Commit, s.CommitAbbrevLen),
+		RepoPath:        s.RepoPath,
+		RemoteURL:       remoteURL,
+		Mode:            DiffModeCommit,
+		Total:           len(fileDiffs),
+		TotalUnfiltered: len(files),
+		DiffAlgorithm:   algorithm,
+		Offset:          pg.offset,
+		Limit:           pg.limit,
+		Merge:           merge,
+		Directories:     directories.list(r),
+	}
+
+	s.writeDiffResponse(w, r, response)
+}
+
+// FilePatchResponse is a single file's patch, for fetching the whole patch of
+// a file whose patch the diff response truncated
+type FilePatchResponse struct {
+	Path      string `json:"path"`
+	Patch     string `json:"patch"`
+	PatchHash string `json:"patch_hash"`
+	// Truncated is set when the patch is still cut short: at the max_patch_kb
+	// cap without ?full=true, or at maxFullPatchBytes with it
+	Truncated bool `json:"truncated,omitempty"`
+}
+
+// fileDiffHandler serves one file of the diff the query selects, in full with
+// ?full=true, so the bulk diff response can stay small
+func (s *AppState) fileDiffHandler(w http.ResponseWriter, r *http.Request) {
+	s.mu.Lock()
+	defer s.mu.Unlock()
+
+	filePath := r.URL.Query().Get("file_path")
+	if filePath == "" {
+		http.Error(w, "file_path is required", http.StatusBadRequest)
+		return
+	}
+
+	full, err := parseOptionalBool(r, "full")
+	if err != nil {
+		http.Error(w, err.Error(), http.StatusBadRequest)
+		return
+	}
+
+	files, status, err := s.diffFiles(r)
+	if r.Context().Err() != nil {
+		return
+	}
+	if err != nil {
+		http.Error(w, err.Error(), status)
+		return
+	}
+
+	for _, file := range files {
+		if file.Path != filePath {
+			continue
+		}
+
+		limit := s.MaxPatchKB * 1024
+		if full {
+			limit = maxFullPatchBytes
+		}
+		patch, truncated := truncatePatch(file.Patch, limit)
+		response := FilePatchResponse{
+			Path:      file.Path,
+			Patch:     patch,
+			PatchHash: state.PatchHash(file.Patch),
+			Truncated: truncated,
+		}
+
+		w.Header().Set("Content-Type", "application/json")
+		_ = json.NewEncoder(w).Encode(response) // Ignore encode error for HTTP response
+		return
+	}
+
+	http.Error(w, fmt.Sprintf("%s is not part of the diff", filePath), http.StatusNotFound)
+}
+
+// diffPatchHandler serves the whole diff the query selects as one unified
+// diff, for piping a review into tools such as patch or delta. Besides the
+// parameters diffFiles takes it honors only_files_from and
+// ignore_cherry_picks, so it matches the files /api/diff lists.
+func (s *AppState) diffPatchHandler(w http.ResponseWriter, r *http.Request) {
+	s.mu.Lock()
+	defer s.mu.Unlock()
+
+	ignoreCherryPicks, err := parseOptionalBool(r, "ignore_cherry_picks")
+	if err != nil {
+		http.Error(w, err.Error(), http.StatusBadRequest)
+		return
+	}
+
+	files, status, err := s.diffFiles(r)
+	if r.Context().Err() != nil {
+		return
+	}
+	if err != nil {
+		http.Error(w, err.Error(), status)
+		return
+	}
+
+	if s.patch == nil {
+		gitRepo, err := git.Open(".")
+		if err != nil {
+			http.Error(w, err.Error(), http.StatusInternalServerError)
+			return
+		}
+
+		if rev := r.URL.Query().Get("only_files_from"); rev != "" {
+			_, touched, err := gitRepo.CommitChangedPaths(rev)
+			if err != nil {
+				status := http.StatusInternalServerError
+				if errors.Is(err, git.ErrCommitNotInHistory) {
+					status = http.StatusBadRequest
+				}
+				http.Error(w, err.Error(), status)
+				return
+			}
+			files, _ = filterFilesByPaths(files, touched)
+		}
+
+		query := r.URL.Query()
+		branchDiff := (query.Get("mode") == "" || query.Get("mode") == "branch") && query.Get("since") == "" &&
+			(query.Get("staging_status") == "" || query.Get("staging_status") == string(git.StagingStatusCommitted))
+		if ignoreCherryPicks && branchDiff {
+			files, _, err = gitRepo.SuppressCherryPicks(r.Context(), s.BaseBranch, files)
+			if r.Context().Err() != nil {
+				return
+			}
+			if err != nil {
+				http.Error(w, err.Error(), http.StatusInternalServerError)
+				return
+			}
+		}
+	}
+	sortFilesByPath(files)
+
+	w.Header().Set("Content-Type", "text/plain; charset=utf-8")
+	w.Header().Set("Content-Disposition", `attachment; filename="review.patch"`)
+	_ = git.WritePatch(w, files) // Ignore write error for HTTP response
+}
+
+// exportMarkdownHandler renders the current review as a Markdown summary: the
+// changed files with their viewed status, then the comments grouped by file
+func (s *AppState) exportMarkdownHandler(w http.ResponseWriter, r *http.Request) {
+	s.mu.Lock()
+	defer s.mu.Unlock()
+
+	branch, commit, err := s.revision()
+	if err != nil {
+		http.Error(w, err.Error(), http.StatusInternalServerError)
+		return
+	}
+
+	files, status, err := s.diffFiles(r)
+	if r.Context().Err() != nil {
+		return
+	}
+	if err != nil {
+		http.Error(w, err.Error(), status)
+		return
+	}
+	sortFilesByPath(files)
+
+	// Viewed state is read from where /api/diff keeps it for the same query
+	query := r.URL.Query()
+	viewedCommit := commit
+	if s.patch == nil {
+		switch mode := query.Get("mode"); mode {
+		case DiffModeWorking:
+			viewedCommit = reviewCommit(mode, commit)
+		case DiffModeCommit:
+			viewedCommit = reviewCommit(mode, query.Get("commit"))
+		}
+	}
+	staging := query.Get("staging_status")
+	uncommitted := staging == string(git.StagingStatusStaged) || staging == string(git.StagingStatusUnstaged)
+	if uncommitted {
+		viewedCommit = "__uncommitted__"
+	}
+
+	reviewFiles := make([]export.ReviewFile, 0, len(files))
+	for _, file := range files {
+		viewedPath := file.Path
+		if uncommitted {
+			viewedPath += ":" + staging
+		}
+		var viewed bool
+		if query.Get("since") != "" {
+			// An incremental patch isn't the one a viewed hash was recorded against
+			viewed = s.StateManager.IsFileViewed(s.RepoPath, branch, viewedCommit, viewedPath)
+		} else {
+			viewed, _ = s.viewedInCurrentForm(branch, viewedCommit, viewedPath, file.Patch)
+		}
+		viewed = viewed || state.MatchesAnyPattern(file.Path, s.AutoViewPatterns)
+		reviewFiles = append(reviewFiles, export.ReviewFile{
+			Path:      file.Path,
+			Status:    file.Status,
+			Additions: file.Additions,
+			Deletions: file.Deletions,
+			Viewed:    viewed,
+		})
+	}
+
+	opts := export.Options{
+		Summary:   s.StateManager.GetReviewSummary(s.RepoPath, branch, commit),
+		Session:   s.StateManager.GetReviewSession(s.RepoPath, branch, commit),
+		Checklist: s.checklistProgress(branch, commit),
+	}
+	comments := s.StateManager.GetComments(s.RepoPath, branch, commit, nil)
+
+	w.Header().Set("Content-Type", "text/markdown; charset=utf-8")
+	_ = export.ReviewMarkdown(w, reviewFiles, comments, opts) // Ignore write error for HTTP response
+}
+
+// diffFiles computes the files of the diff selected by the same mode, commit,
+// since and algorithm parameters /api/diff takes, without any review state.
+// Uncommitted changes of a branch review are selected with staging_status.
+// On failure it also returns the HTTP status to answer with.
+func (s *AppState) diffFiles(r *http.Request) ([]git.FileInfo, int, error) {
+	pathspec, err := parsePathspec(r)
+	if err != nil {
+		return nil, http.StatusBadRequest, err
+	}
+	if s.patch != nil {
+		if len(pathspec) > 0 {
+			return nil, http.StatusBadRequest, errors.New("pathspec is not supported when reviewing a patch file")
+		}
+		if r.URL.Query().Get("algorithm") != "" {
+			return nil, http.StatusBadRequest, errors.New("algorithm is not supported when reviewing a patch file")
+		}
+		return s.patch.files, http.StatusOK, nil
+	}
+
+	gitRepo, err := git.Open(".")
+	if err != nil {
+		return nil, http.StatusInternalServerError, err
+	}
+
+	algorithm, err := s.diffAlgorithm(r, gitRepo)
+	if err != nil {
+		return nil, http.StatusBadRequest, err
+	}
+	opts := git.DiffOptions{
+		Algorithm:         algorithm,
+		RecurseSubmodules: s.RecurseSubmodules,
+		Backend:           s.DiffBackend,
+		Textconv:          s.Textconv,
+		Pathspec:          pathspec,
+	}
+
+	var files []git.FileInfo
+	switch mode := r.URL.Query().Get("mode"); mode {
+	case DiffModeWorking:
+		files, err = gitRepo.GetWorkingChangesWithOptions(opts)
+	case DiffModeCommit:
+		rev := r.URL.Query().Get("commit")
+		if rev == "" {
+			return nil, http.StatusBadRequest, errors.New("commit is required with mode commit")
+		}
+		var diff *git.CommitDiff
+		if diff, err = gitRepo.GetCommitDiff(r.Context(), rev, opts); err == nil {
+			files = diff.Files
+		}
+	case "", "branch":
+		switch staging := r.URL.Query().Get("staging_status"); staging {
+		case "", string(git.StagingStatusCommitted):
+			if since := r.URL.Query().Get("since"); since != "" {
+				files, err = gitRepo.GetDiffSince(r.Context(), since, opts)
+			} else {
+				files, err = gitRepo.GetDiffFilesContext(r.Context(), s.BaseBranch, opts)
+			}
+		case string(git.StagingStatusStaged), string(git.StagingStatusUnstaged):
+			if len(pathspec) > 0 {
+				return nil, http.StatusBadRequest, fmt.Errorf("pathspec is not supported with staging_status %s", staging)
+			}
+			var uncommitted []git.FileInfo
+			uncommitted, err = gitRepo.GetUncommittedChangesWithOptions(opts)
+			for _, file := range uncommitted {
+				if string(file.StagingStatus) == staging {
+					files = append(files, file)
+				}
+			}
+		default:
+			return nil, http.StatusBadRequest, fmt.Errorf("invalid staging_status: %s (expected committed, staged or unstaged)", staging)
+		}
+	default:
+		return nil, http.StatusBadRequest, fmt.Errorf("invalid mode: %s (expected branch, working or commit)", mode)
+	}
+	if err != nil {
+		if errors.Is(err, git.ErrCommitNotInHistory) || errors.Is(err, git.ErrBareRepository) {
+			return nil, http.StatusBadRequest, err
+		}
+		return nil, http.StatusInternalServerError, err
+	}
+
+	return files, http.StatusOK, nil
+}
+
+// patchDiff serves the files of the patch file under review
+func (s *AppState) patchDiff(w http.ResponseWriter, r *http.Request) {
+	unviewedOnly, err := parseOptionalBool(r, "unviewed_only")
+	if err != nil {
+		http.Error(w, err.Error(), http.StatusBadRequest)
+		return
+	}
+
+	pg, err := parsePage(r, s.MaxFiles)
+	if err != nil {
+		http.Error(w, err.Error(), http.StatusBadRequest)
+		return
+	}
+
+	fileDiffs := []FileDiff{}
+	directories := directoryRollup{}
+	for _, file := range s.patch.files {
+		hash := state.PatchHash(file.Patch)
+		viewed, changedSinceViewed := s.viewedInCurrentForm(PatchBranch, s.patch.hash, file.Path, file.Patch)
+		autoViewed := !viewed && state.MatchesAnyPattern(file.Path, s.AutoViewPatterns)
+		directories.add(file.Path, viewed || autoViewed)
+		if unviewedOnly && (viewed || autoViewed) {
+			continue
+		}
+
+		fileDiffs = append(fileDiffs, FileDiff{
+			Path:               file.Path,
+			Status:             file.Status,
+			Kind:               file.Kind,
+			Additions:          file.Additions,
+			Deletions:          file.Deletions,
+			Patch:              file.Patch,
+			Viewed:             viewed || autoViewed,
+			Index:              len(fileDiffs),
+			PatchHash:          hash,
+			AutoViewed:         autoViewed,
+			ChangedSinceViewed: changedSinceViewed,
+			OldPath:            file.OldPath,
+		})
+	}
+
+	s.setCommentCounts(PatchBranch, s.patch.hash, fileDiffs)
+
+	response := DiffResponse{
+		Files:           pg.apply(fileDiffs),
+		Branch:          PatchBranch,
+		Commit:          s.patch.hash,
+		CommitShort:     git.Abbreviate(s.patch.hash, s.CommitAbbrevLen),
+		RepoPath:        s.RepoPath,
+		Mode:            DiffModePatch,
+		Total:           len(fileDiffs),
+		TotalUnfiltered: len(s.patch.files),
+		Offset:          pg.offset,
+		Limit:           pg.limit,
+		Directories:     directories.list(r),
+	}
+
+	s.writeDiffResponse(w, r, response)
+}
+
+func (s *AppState) markViewedHandler(w http.ResponseWriter, r *http.Request) {
+	s.mu.Lock()
+	defer s.mu.Unlock()
+
+	var payload MarkViewedRequest
+	if err := json.NewDecoder(r.Body).Decode(&payload); err != nil {
+		http.Error(w, err.Error(), http.StatusBadRequest)
+		return
+	}
+
+	currentBranch, currentCommit, err := s.revision()
+	if err != nil {
+		http.Error(w, err.Error(), http.StatusInternalServerError)
+		return
+	}
+
+	commit, err := payload.reviewedCommit(currentCommit)
+	if err != nil {
+		http.Error(w, err.Error(), http.StatusBadRequest)
+		return
+	}
+
+	if payload.Directory != "" {
+		s.markDirectoryViewed(w, r, payload, currentBranch, commit)
+		return
+	}
+
+	if err := s.StateManager.MarkFileViewedWithHash(s.RepoPath, currentBranch, reviewCommit(payload.Mode, commit), payload.FilePath, payload.PatchHash); err != nil {
+		http.Error(w, err.Error(), stateErrorStatus(err))
+		return
+	}
+
+	w.WriteHeader(http.StatusOK)
+}
+
+func (s *AppState) unmarkViewedHandler(w http.ResponseWriter, r *http.Request) {
+	s.mu.Lock()
+	defer s.mu.Unlock()
+
+	var payload MarkViewedRequest
+	if err := json.NewDecoder(r.Body).Decode(&payload); err != nil {
+		http.Error(w, err.Error(), http.StatusBadRequest)
+		return
+	}
+
+	currentBranch, currentCommit, err := s.revision()
+	if err != nil {
+		http.Error(w, err.Error(), http.StatusInternalServerError)
+		return
+	}
+
+	commit, err := payload.reviewedCommit(currentCommit)
+	if err != nil {
+		http.Error(w, err.Error(), http.StatusBadRequest)
+		return
+	}
+
+	if payload.Directory != "" {
+		if _, err := s.StateManager.UnmarkDirectoryViewed(s.RepoPath, currentBranch, reviewCommit(payload.Mode, commit), payload.Directory); err != nil {
+			http.Error(w, err.Error(), stateErrorStatus(err))
+			return
+		}
+		w.WriteHeader(http.StatusOK)
+		return
+	}
+
+	if err := s.StateManager.UnmarkFileViewed(s.RepoPath, currentBranch, reviewCommit(payload.Mode, commit), payload.FilePath); err != nil {
+		http.Error(w, err.Error(), stateErrorStatus(err))
+		return
+	}
+
+	w.WriteHeader(http.StatusOK)
+}
+
+// markDirectoryViewed marks every file the diff currently has under
+// payload.Directory viewed at its current patch hash, in one save, so files
+// added to the directory since the client's last diff are included and
+// removed ones aren't resurrected. Callers must hold s.mu.
+func (s *AppState) markDirectoryViewed(w http.ResponseWriter, r *http.Request, payload MarkViewedRequest, branch, commit string) {
+	query := url.Values{}
+	if payload.Mode != "" && payload.Mode != DiffModePatch {
+		query.Set("mode", payload.Mode)
+	}
+	if payload.Mode == DiffModeCommit {
+		query.Set("commit", commit)
+	}
+	diffRequest := r.Clone(r.Context())
+	diffRequest.URL.RawQuery = query.Encode()
+
+	files, status, err := s.diffFiles(diffRequest)
+	if r.Context().Err() != nil {
+		return
+	}
+	if err != nil {
+		http.Error(w, err.Error(), status)
+		return
+	}
+
+	patchHashes := make(map[string]string)
+	for _, file := range files {
+		if inDirectory(file.Path, payload.Directory) {
+			patchHashes[file.Path] = state.PatchHash(file.Patch)
+		}
+	}
+	if len(patchHashes) == 0 {
+		http.Error(w, fmt.Sprintf("no changed files under %s", payload.Directory), http.StatusNotFound)
+		return
+	}
+
+	if err := s.StateManager.MarkFilesViewed(s.RepoPath, branch, reviewCommit(payload.Mode, commit), patchHashes); err != nil {
+		http.Error(w, err.Error(), stateErrorStatus(err))
+		return
+	}
+
+	w.WriteHeader(http.StatusOK)
+}
+
+func (s *AppState) statusHandler(w http.ResponseWriter, r *http.Request) {
+	s.mu.Lock()
+	defer s.mu.Unlock()
+
+	if s.patch != nil {
+		response := StatusResponse{
+			RepoPath:    s.RepoPath,
+			Branch:      PatchBranch,
+			Commit:      s.patch.hash,
+			CommitShort: git.Abbreviate(s.patch.hash, s.CommitAbbrevLen),
+			Review:      s.StateManager.GetReviewSession(s.RepoPath, PatchBranch, s.patch.hash),
+			Checklist:   s.checklistProgress(PatchBranch, s.patch.hash),
+		}
+		w.Header().Set("Content-Type", "application/json")
+		_ = json.NewEncoder(w).Encode(response) // Ignore encode error for HTTP response
+		return
+	}
+
+	gitRepo, err := git.Open(".")
+	if err != nil {
+		http.Error(w, err.Error(), http.StatusInternalServerError)
+		return
+	}
+
+	currentBranch, err := gitRepo.CurrentBranch()
+	if err != nil {
+		http.Error(w, err.Error(), http.StatusInternalServerError)
+		return
+	}
+
+	currentCommit, err := gitRepo.CurrentCommit()
+	if err != nil {
+		http.Error(w, err.Error(), http.StatusInternalServerError)
+		return
+	}
+
+	dirty, err := gitRepo.IsDirty()
+	if err != nil {
+		http.Error(w, err.Error(), http.StatusInternalServerError)
+		return
+	}
+
+	response := StatusResponse{
+		RepoPath:    s.RepoPath,
+		Branch:      currentBranch,
+		Commit:      currentCommit,
+		CommitShort: git.Abbreviate(currentCommit, s.CommitAbbrevLen),
+		Dirty:       dirty,
+		Bare:        gitRepo.IsBare(),
+		Review:      s.StateManager.GetReviewSession(s.RepoPath, currentBranch, currentCommit),
+		Checklist:   s.checklistProgress(currentBranch, currentCommit),
+	}
+
+	w.Header().Set("Content-Type", "application/json")
+	_ = json.NewEncoder(w).Encode(response) // Ignore encode error for HTTP response
+}
+
+func (s *AppState) fetchBaseHandler(w http.ResponseWriter, r *http.Request) {
+	gitRepo, err := git.Open(".")
+	if err != nil {
+		http.Error(w, err.Error(), http.StatusInternalServerError)
+		return
+	}
+
+	gitRepo.SetOffline(s.Offline)
+
+	if err := gitRepo.FetchBase(s.BaseBranch); err != nil {
+		if errors.Is(err, git.ErrOffline) {
+			http.Error(w, err.Error(), http.StatusConflict)
+			return
+		}
+		http.Error(w, err.Error(), http.StatusBadGateway)
+		return
+	}
+
+	w.WriteHeader(http.StatusOK)
+}
+
+func (s *AppState) getCommentsHandler(w http.ResponseWriter, r *http.Request) {
+	s.mu.Lock()
+	defer s.mu.Unlock()
+
+	currentBranch, currentCommit, err := s.revision()
+	if err != nil {
+		http.Error(w, err.Error(), http.StatusInternalServerError)
+		return
+	}
+
+	general, err := parseOptionalBool(r, "general")
+	if err != nil {
+		http.Error(w, err.Error(), http.StatusBadRequest)
+		return
+	}
+
+	// General comments have an empty file path, so they only match a file_path
+	// filter when asked for explicitly
+	filePath := r.URL.Query().Get("file_path")
+	if general && filePath != "" {
+		http.Error(w, "general and file_path can't be combined", http.StatusBadRequest)
+		return
+	}
+	var filePathPtr *string
+	if filePath != "" || general {
+		filePathPtr = &filePath
+	}
+
+	var resolvedPtr *bool
+	if resolvedParam := r.URL.Query().Get("resolved"); resolvedParam != "" {
+		resolved, err := strconv.ParseBool(resolvedParam)
+		if err != nil {
+			http.Error(w, fmt.Sprintf("invalid resolved: %v", err), http.StatusBadRequest)
+			return
+		}
+		resolvedPtr = &resolved
+	}
+
+	order := r.URL.Query().Get("order")
+	switch order {
+	case "", "insertion", "priority", "oldest", "newest":
+	default:
+		http.Error(w, fmt.Sprintf("invalid order: %s (expected insertion, priority, oldest or newest)", order), http.StatusBadRequest)
+		return
+	}
+
+	limit := 0
+	if raw := r.URL.Query().Get("limit"); raw != "" {
+		limit, err = strconv.Atoi(raw)
+		if err != nil || limit < 1 {
+			http.Error(w, "limit must be a positive number", http.StatusBadRequest)
+			return
+		}
+	}
+
+	// Best effort, comments are otherwise served at their stored lines
+	_, _ = s.StateManager.ReanchorComments(s.RepoPath, currentBranch, currentCommit, s.worktreeLines)
+
+	// Incremental fetch: only return comments changed since the given timestamp
+	if updatedSince := r.URL.Query().Get("updated_since"); updatedSince != "" {
+		since, err := strconv.ParseInt(updatedSince, 10, 64)
+		if err != nil {
+			http.Error(w, fmt.Sprintf("invalid updated_since: %v", err), http.StatusBadRequest)
+			return
+		}
+
+		// Captured before reading state so changes made during this request are seen next poll
+		now := time.Now().Unix()
+		changed, tombstones := s.StateManager.GetCommentChanges(s.RepoPath, currentBranch, currentCommit, filePathPtr, since)
+
+		deleted := make([]string, len(tombstones))
+		for i, tombstone := range tombstones {
+			deleted[i] = tombstone.ID
+		}
+
+		response := CommentChangesResponse{
+			Comments:  changed,
+			Deleted:   deleted,
+			Timestamp: now,
+		}
+
+		w.Header().Set("Content-Type", "application/json")
+		_ = json.NewEncoder(w).Encode(response) // Ignore encode error for HTTP response
+		return
+	}
+
+	comments := s.StateManager.GetComments(s.RepoPath, currentBranch, currentCommit, filePathPtr)
+
+	// Resolved comments may have been moved to the repository's archive
+	if resolvedPtr != nil && *resolvedPtr {
+		archived, err := s.StateManager.GetArchivedComments(s.RepoPath, currentBranch, currentCommit, filePathPtr)
+		if err != nil {
+			http.Error(w, err.Error(), http.StatusInternalServerError)
+			return
+		}
+		comments = state.MergeArchived(comments, archived)
+	}
+
+	if resolvedPtr != nil {
+		filtered := []*state.Comment{}
+		for _, comment := range comments {
+			if comment.Resolved == *resolvedPtr {
+				filtered = append(filtered, comment)
+			}
+		}
+		comments = filtered
+	}
+
+	switch order {
+	case "priority":
+		comments = state.SortByPriority(comments)
+	case "oldest", "newest":
+		comments = state.SortByAge(comments, order == "newest")
+	}
+
+	if limit > 0 && len(comments) > limit {
+		comments = comments[:limit]
+	}
+
+	w.Header().Set("Content-Type", "application/json")
+	_ = json.NewEncoder(w).Encode(comments) // Ignore encode error for HTTP response
+}
+
+func (s *AppState) addCommentHandler(w http.ResponseWriter, r *http.Request) {
+	s.mu.Lock()
+	defer s.mu.Unlock()
+
+	var payload AddCommentRequest
+	if err := json.NewDecoder(r.Body).Decode(&payload); err != nil {
+		http.Error(w, err.Error(), http.StatusBadRequest)
+		return
+	}
+
+	if payload.TemplateKey != "" {
+		template, ok := s.CommentTemplates[payload.TemplateKey]
+		if !ok {
+			http.Error(w, fmt.Sprintf("unknown comment template '%s'", payload.TemplateKey), http.StatusBadRequest)
+			return
+		}
+		text := expandCommentTemplate(template, payload.FilePath, payload.LineNumber)
+		if strings.TrimSpace(payload.Text) != "" {
+			text += "\n\n" + payload.Text
+		}
+		payload.Text = text
+	}
+
+	if err := state.ValidateCommentText(payload.Text, s.MaxCommentLength); err != nil {
+		http.Error(w, err.Error(), http.StatusBadRequest)
+		return
+	}
+
+	// An empty file path makes a general comment on the whole review, which has no lines
+	if payload.FilePath == "" && payload.LineNumber != nil {
+		http.Error(w, "line_number requires a file_path", http.StatusBadRequest)
+		return
+	}
+
+	currentBranch, currentCommit, err := s.revision()
+	if err != nil {
+		http.Error(w, err.Error(), http.StatusInternalServerError)
+		return
+	}
+
+	if payload.ParentID != "" {
+		reply, err := s.StateManager.AddReply(s.RepoPath, currentBranch, currentCommit, payload.ParentID, payload.Text, payload.Author)
+		if errors.Is(err, state.ErrCommentNotFound) {
+			http.Error(w, fmt.Sprintf("parent comment not found: %s", payload.ParentID), http.StatusBadRequest)
+			return
+		}
+		if err != nil {
+			http.Error(w, err.Error(), stateErrorStatus(err))
+			return
+		}
+
+		w.Header().Set("Content-Type", "application/json")
+		_ = json.NewEncoder(w).Encode(reply) // Ignore encode error for HTTP response
+		return
+	}
+
+	comment, err := s.StateManager.AddCommentWithAuthor(s.RepoPath, currentBranch, currentCommit, payload.FilePath, payload.LineNumber, payload.Text, payload.Author)
+	if err != nil {
+		http.Error(w, err.Error(), stateErrorStatus(err))
+		return
+	}
+
+	// Anchor line comments to their line's content, so they follow it when
+	// lines are inserted or removed above
+	if comment.LineNumber != nil {
+		lines, err := s.worktreeLines(comment.FilePath)
+		if line := *comment.LineNumber; err == nil && line >= 1 && line <= len(lines) {
+			if err := s.StateManager.SetCommentLineHash(s.RepoPath, currentBranch, currentCommit, comment.ID, state.LineHash(lines[line-1])); err != nil {
+				http.Error(w, err.Error(), stateErrorStatus(err))
+				return
+			}
+		}
+	}
+
+	w.Header().Set("Content-Type", "application/json")
+	_ = json.NewEncoder(w).Encode(comment) // Ignore encode error for HTTP response
+}
+
+// defaultContextLines is how many lines around a comment's line are returned by the context endpoint
+const defaultContextLines = 5
+
+func (s *AppState) commentContextHandler(w http.ResponseWriter, r *http.Request) {
+	s.mu.Lock()
+	defer s.mu.Unlock()
+
+	commentID := mux.Vars(r)["id"]
+	comment := s.StateManager.FindComment(s.RepoPath, commentID)
+	if comment == nil {
+		http.Error(w, fmt.Sprintf("comment not found: %s", commentID), http.StatusNotFound)
+		return
+	}
+	if comment.IsGeneral() {
+		http.Error(w, fmt.Sprintf("comment %s is a general comment and has no file context", commentID), http.StatusBadRequest)
+		return
+	}
+
+	contextLines := defaultContextLines
+	if param := r.URL.Query().Get("lines"); param != "" {
+		n, err := strconv.Atoi(param)
+		if err != nil || n < 0 {
+			http.Error(w, fmt.Sprintf("invalid lines: %s", param), http.StatusBadRequest)
+			return
+		}
+		contextLines = n
+	}
+
+	gitRepo, err := git.Open(".")
+	if err != nil {
+		http.Error(w, err.Error(), http.StatusInternalServerError)
+		return
+	}
+
+	currentCommit, err := gitRepo.CurrentCommit()
+	if err != nil {
+		http.Error(w, err.Error(), http.StatusInternalServerError)
+		return
+	}
+
+	contents, err := gitRepo.FileAtCommit(comment.Commit, comment.FilePath)
+	if err != nil {
+		http.Error(w, err.Error(), http.StatusNotFound)
+		return
+	}
+
+	lines := strings.Split(strings.TrimSuffix(contents, "\n"), "\n")
+
+	// File-level comments get the whole file, line comments a window around the line
+	start, end := 0, len(lines)
+	if comment.LineNumber != nil {
+		start = max(*comment.LineNumber-1-contextLines, 0)
+		end = min(*comment.LineNumber+contextLines, len(lines))
+		if start > end {
+			start = end
+		}
+	}
+
+	response := CommentContextResponse{
+		CommentID:  comment.ID,
+		Commit:     comment.Commit,
+		FilePath:   comment.FilePath,
+		LineNumber: comment.LineNumber,
+		StartLine:  start + 1,
+		Lines:      lines[start:end],
+		Outdated:   comment.Commit != currentCommit,
+	}
+
+	w.Header().Set("Content-Type", "application/json")
+	_ = json.NewEncoder(w).Encode(response) // Ignore encode error for HTTP response
+}
+
+// worktreeLines reads a file of the working tree as lines. Patch reviews have
+// no working tree.
+func (s *AppState) worktreeLines(filePath string) ([]string, error) {
+	if s.patch != nil {
+		return nil, errors.New("patch reviews have no working tree")
+	}
+
+	gitRepo, err := git.Open(".")
+	if err != nil {
+		return nil, err
+	}
+	lines, _, err := gitRepo.FileLines("", filePath, 1, math.MaxInt)
+	return lines, err
+}
+
+// expandCommentTemplate fills in a comment template's {file} and {line}
+// placeholders. {line} is empty for comments on a whole file.
+func expandCommentTemplate(template, filePath string, lineNumber *int) string {
+	line := ""
+	if lineNumber != nil {
+		line = strconv.Itoa(*lineNumber)
+	}
+	return strings.NewReplacer("{file}", filePath, "{line}", line).Replace(template)
+}
+
+// carryForwardViewed lets the first diff of a new commit, such as an amend or
+// rebase, inherit the viewed files of the branch's last reviewed commit whose
+// content it didn't change. Each commit is only looked at once per server.
+func (s *AppState) carryForwardViewed(gitRepo *git.Repo, branch, commit string, files []git.FileInfo) error {
+	if !s.CarryForwardViewed || s.StateManager.Granularity(s.RepoPath) != state.GranularityCommit || s.viewedCarried[branch] == commit {
+		return nil
+	}
+	s.viewedCarried[branch] = commit
+
+	previous := s.StateManager.LastReviewedCommit(s.RepoPath, branch, commit)
+	if previous == "" {
+		return nil
+	}
+	changed, err := gitRepo.ChangedPathsBetween(previous, commit)
+	if err != nil {
+		// The previous commit was garbage collected after a rebase
+		return nil
+	}
+
+	var unchanged []string
+	for _, file := range files {
+		if !changed[file.Path] {
+			unchanged = append(unchanged, file.Path)
+		}
+	}
+	if _, err := s.StateManager.CarryForwardViewed(s.RepoPath, branch, previous, commit, unchanged); err != nil {
+		// Try again on the next diff, e.g. once another daemon released the state lock
+		delete(s.viewedCarried, branch)
+		return err
+	}
+	return nil
+}
+
+// stateErrorStatus picks the HTTP status for a failed state update. Another
+// daemon holding the state file lock is temporary, so that's reported as 503,
+// and an unknown comment ID as 404.
+func stateErrorStatus(err error) int {
+	if errors.Is(err, state.ErrStateLocked) {
+		return http.StatusServiceUnavailable
+	}
+	if errors.Is(err, state.ErrCommentNotFound) {
+		return http.StatusNotFound
+	}
+	return http.StatusInternalServerError
+}
+
+func (s *AppState) diffExpandHandler(w http.ResponseWriter, r *http.Request) {
+	s.mu.Lock()
+	defer s.mu.Unlock()
 
-func Start(port int, baseBranch string) error {
-	gitRepo, err := git.Open(".")
-	if err != nil {
-		return err
+	filePath := r.URL.Query().Get("file_path")
+	if filePath == "" {
+		http.Error(w, "file_path is required", http.StatusBadRequest)
+		return
 	}
 
-	repoPath, err := gitRepo.RepoPath()
+	fromLine, err := strconv.Atoi(r.URL.Query().Get("from_line"))
 	if err != nil {
-		return err
+		http.Error(w, "from_line must be a line number", http.StatusBadRequest)
+		return
 	}
-
-	stateMgr, err := state.NewManager()
+	toLine, err := strconv.Atoi(r.URL.Query().Get("to_line"))
 	if err != nil {
-		return err
+		http.Error(w, "to_line must be a line number", http.StatusBadRequest)
+		return
 	}
 
-	appState := &AppState{
-		RepoPath:     repoPath,
-		BaseBranch:   baseBranch,
-		StateManager: stateMgr,
+	gitRepo, err := git.Open(".")
+	if err != nil {
+		http.Error(w, err.Error(), http.StatusInternalServerError)
+		return
 	}
 
-	r := mux.NewRouter()
-	r.HandleFunc("/", appState.indexHandler).Methods("GET")
-	r.HandleFunc("/api/diff", appState.diffHandler).Methods("GET")
-	r.HandleFunc("/api/mark-viewed", appState.markViewedHandler).Methods("POST")
-	r.HandleFunc("/api/unmark-viewed", appState.unmarkViewedHandler).Methods("POST")
-	r.HandleFunc("/api/status", appState.statusHandler).Methods("GET")
-	r.HandleFunc("/api/comments", appState.getCommentsHandler).Methods("GET")
-	r.HandleFunc("/api/comments", appState.addCommentHandler).Methods("POST")
-	r.HandleFunc("/api/comments/resolve", appState.resolveCommentHandler).Methods("POST")
-	r.HandleFunc("/api/notes", appState.getNotesHandler).Methods("GET")
-	r.HandleFunc("/api/notes", appState.addNoteHandler).Methods("POST")
-	r.HandleFunc("/api/notes/dismiss", appState.dismissNoteHandler).Methods("POST")
+	// The new side of a working-tree review is the file on disk, otherwise it's HEAD
+	commit := ""
+	if r.URL.Query().Get("mode") != DiffModeWorking {
+		commit, err = gitRepo.CurrentCommit()
+		if err != nil {
+			http.Error(w, err.Error(), http.StatusInternalServerError)
+			return
+		}
+	}
 
-	addr := fmt.Sprintf("127.0.0.1:%d", port)
-	fmt.Printf("Starting server on http://%s\n", addr)
-	fmt.Printf("Comparing against base branch: %s\n", baseBranch)
+	lines, total, err := gitRepo.FileLines(commit, filePath, fromLine, toLine)
+	if err != nil {
+		http.Error(w, err.Error(), http.StatusNotFound)
+		return
+	}
 
-	return http.ListenAndServe(addr, r)
-}
+	from := max(fromLine, 1)
+	response := DiffExpandResponse{
+		FilePath:   filePath,
+		FromLine:   from,
+		ToLine:     from + len(lines) - 1,
+		TotalLines: total,
+		Lines:      lines,
+	}
 
-func (s *AppState) indexHandler(w http.ResponseWriter, r *http.Request) {
-	w.Header().Set("Content-Type", "text/html")
-	_, _ = w.Write([]byte(indexHTML)) // Ignore write error for HTTP response
+	w.Header().Set("Content-Type", "application/json")
+	_ = json.NewEncoder(w).Encode(response) // Ignore encode error for HTTP response
 }
 
-func (s *AppState) diffHandler(w http.ResponseWriter, r *http.Request) {
+func (s *AppState) baseCommitsHandler(w http.ResponseWriter, r *http.Request) {
 	s.mu.Lock()
 	defer s.mu.Unlock()
 
+	limit := defaultBaseCommitsLimit
+	if raw := r.URL.Query().Get("limit"); raw != "" {
+		parsed, err := strconv.Atoi(raw)
+		if err != nil || parsed < 1 {
+			http.Error(w, "limit must be a positive number", http.StatusBadRequest)
+			return
+		}
+		limit = parsed
+	}
+
 	gitRepo, err := git.Open(".")
 	if err != nil {
 		http.Error(w, err.Error(), http.StatusInternalServerError)
 		return
 	}
 
-	currentBranch, err := gitRepo.CurrentBranch()
+	_, behind, err := gitRepo.AheadBehind(s.BaseBranch)
 	if err != nil {
 		http.Error(w, err.Error(), http.StatusInternalServerError)
 		return
 	}
 
-	currentCommit, err := gitRepo.CurrentCommit()
+	commits, err := gitRepo.BehindCommits(s.BaseBranch, limit)
 	if err != nil {
 		http.Error(w, err.Error(), http.StatusInternalServerError)
 		return
 	}
 
-	remoteURL, _ := gitRepo.GetRemoteURL() // Ignore error, remote is optional
+	response := BaseCommitsResponse{
+		BaseBranch: s.BaseBranch,
+		Behind:     behind,
+		Commits:    commits,
+	}
+
+	w.Header().Set("Content-Type", "application/json")
+	_ = json.NewEncoder(w).Encode(response) // Ignore encode error for HTTP response
+}
+
+func (s *AppState) blameHandler(w http.ResponseWriter, r *http.Request) {
+	s.mu.Lock()
+	defer s.mu.Unlock()
+
+	filePath := r.URL.Query().Get("file_path")
+	if filePath == "" {
+		http.Error(w, "file_path is required", http.StatusBadRequest)
+		return
+	}
+
+	var line *int
+	if param := r.URL.Query().Get("line"); param != "" {
+		n, err := strconv.Atoi(param)
+		if err != nil || n < 1 {
+			http.Error(w, fmt.Sprintf("invalid line: %s", param), http.StatusBadRequest)
+			return
+		}
+		line = &n
+	}
 
-	files, err := gitRepo.GetDiffFiles(s.BaseBranch)
+	contextLines := defaultContextLines
+	if param := r.URL.Query().Get("lines"); param != "" {
+		n, err := strconv.Atoi(param)
+		if err != nil || n < 0 {
+			http.Error(w, fmt.Sprintf("invalid lines: %s", param), http.StatusBadRequest)
+			return
+		}
+		contextLines = n
+	}
+
+	gitRepo, err := git.Open(".")
 	if err != nil {
 		http.Error(w, err.Error(), http.StatusInternalServerError)
 		return
 	}
 
-	fileDiffs := []FileDiff{}
-	for _, file := range files {
-		viewed := s.StateManager.IsFileViewed(s.RepoPath, currentBranch, currentCommit, file.Path)
+	currentCommit, err := gitRepo.CurrentCommit()
+	if err != nil {
+		http.Error(w, err.Error(), http.StatusInternalServerError)
+		return
+	}
 
-		fileDiffs = append(fileDiffs, FileDiff{
-			Path:          file.Path,
-			Status:        file.Status,
-			Additions:     file.Additions,
-			Deletions:     file.Deletions,
-			Patch:         file.Patch,
-			Viewed:        viewed,
-			StagingStatus: string(git.StagingStatusCommitted),
-		})
+	response := BlameResponse{
+		FilePath: filePath,
+		Commit:   currentCommit,
+		Lines:    []git.BlameLine{},
 	}
 
-	// Get uncommitted changes
-	uncommittedFiles, err := gitRepo.GetUncommittedChanges()
-	uncommittedFileDiffs := []FileDiff{}
-	if err == nil {
-		for _, file := range uncommittedFiles {
-			// Use a special commit identifier for uncommitted changes state
-			uncommittedCommit := "__uncommitted__"
-			viewed := s.StateManager.IsFileViewed(s.RepoPath, currentBranch, uncommittedCommit, file.Path+":"+string(file.StagingStatus))
+	key := blameKey{commit: currentCommit, filePath: filePath}
+	lines, ok := s.blameCache[key]
+	if !ok {
+		lines, err = gitRepo.Blame(currentCommit, filePath)
+		if errors.Is(err, git.ErrFileNotInCommit) {
+			response.Uncommitted = true
+			w.Header().Set("Content-Type", "application/json")
+			_ = json.NewEncoder(w).Encode(response) // Ignore encode error for HTTP response
+			return
+		}
+		if err != nil {
+			http.Error(w, err.Error(), http.StatusInternalServerError)
+			return
+		}
 
-			uncommittedFileDiffs = append(uncommittedFileDiffs, FileDiff{
-				Path:          file.Path,
-				Status:        file.Status,
-				Additions:     file.Additions,
-				Deletions:     file.Deletions,
-				Patch:         file.Patch,
-				Viewed:        viewed,
-				StagingStatus: string(file.StagingStatus),
-			})
+		if s.blameCache == nil || len(s.blameCache) >= maxBlameCacheEntries {
+			s.blameCache = make(map[blameKey][]git.BlameLine)
 		}
+		s.blameCache[key] = lines
 	}
 
-	response := DiffResponse{
-		Files:            fileDiffs,
-		UncommittedFiles: uncommittedFileDiffs,
-		Branch:           currentBranch,
-		Commit:           currentCommit,
-		RepoPath:         s.RepoPath,
-		RemoteURL:        remoteURL,
+	// Without a line the whole file is returned, like the comment context endpoint
+	start, end := 0, len(lines)
+	if line != nil {
+		start = min(max(*line-1-contextLines, 0), len(lines))
+		end = min(*line+contextLines, len(lines))
 	}
+	response.Lines = lines[start:end]
 
 	w.Header().Set("Content-Type", "application/json")
 	_ = json.NewEncoder(w).Encode(response) // Ignore encode error for HTTP response
 }
 
-func (s *AppState) markViewedHandler(w http.ResponseWriter, r *http.Request) {
+func (s *AppState) editCommentHandler(w http.ResponseWriter, r *http.Request) {
 	s.mu.Lock()
 	defer s.mu.Unlock()
 
-	var payload MarkViewedRequest
+	var payload EditCommentRequest
 	if err := json.NewDecoder(r.Body).Decode(&payload); err != nil {
 		http.Error(w, err.Error(), http.StatusBadRequest)
 		return
 	}
 
-	gitRepo, err := git.Open(".")
+	if err := state.ValidateCommentText(payload.Text, s.MaxCommentLength); err != nil {
+		http.Error(w, err.Error(), http.StatusBadRequest)
+		return
+	}
+
+	currentBranch, currentCommit, err := s.revision()
 	if err != nil {
 		http.Error(w, err.Error(), http.StatusInternalServerError)
 		return
 	}
 
-	currentBranch, err := gitRepo.CurrentBranch()
+	comment, err := s.StateManager.EditComment(s.RepoPath, currentBranch, currentCommit, payload.CommentID, payload.Text)
 	if err != nil {
-		http.Error(w, err.Error(), http.StatusInternalServerError)
+		http.Error(w, err.Error(), stateErrorStatus(err))
 		return
 	}
 
-	currentCommit, err := gitRepo.CurrentCommit()
+	w.Header().Set("Content-Type", "application/json")
+	_ = json.NewEncoder(w).Encode(comment) // Ignore encode error for HTTP response
+}
+
+func (s *AppState) deleteCommentHandler(w http.ResponseWriter, r *http.Request) {
+	s.mu.Lock()
+	defer s.mu.Unlock()
+
+	var payload DeleteCommentRequest
+	if err := json.NewDecoder(r.Body).Decode(&payload); err != nil {
+		http.Error(w, err.Error(), http.StatusBadRequest)
+		return
+	}
+
+	currentBranch, currentCommit, err := s.revision()
 	if err != nil {
 		http.Error(w, err.Error(), http.StatusInternalServerError)
 		return
 	}
 
-	if err := s.StateManager.MarkFileViewed(s.RepoPath, currentBranch, currentCommit, payload.FilePath); err != nil {
-		http.Error(w, err.Error(), http.StatusInternalServerError)
+	deleted, err := s.StateManager.DeleteComment(s.RepoPath, currentBranch, currentCommit, payload.CommentID)
+	if err != nil {
+		http.Error(w, err.Error(), stateErrorStatus(err))
+		return
+	}
+	if !deleted {
+		http.Error(w, state.ErrCommentNotFound.Error(), http.StatusNotFound)
 		return
 	}
 
 	w.WriteHeader(http.StatusOK)
 }
 
-func (s *AppState) unmarkViewedHandler(w http.ResponseWriter, r *http.Request) {
+func (s *AppState) resolveCommentHandler(w http.ResponseWriter, r *http.Request) {
 	s.mu.Lock()
 	defer s.mu.Unlock()
 
-	var payload MarkViewedRequest
+	var payload ResolveCommentRequest
 	if err := json.NewDecoder(r.Body).Decode(&payload); err != nil {
 		http.Error(w, err.Error(), http.StatusBadRequest)
 		return
 	}
 
-	gitRepo, err := git.Open(".")
+	currentBranch, currentCommit, err := s.revision()
 	if err != nil {
 		http.Error(w, err.Error(), http.StatusInternalServerError)
 		return
 	}
 
-	currentBranch, err := gitRepo.CurrentBranch()
-	if err != nil {
-		http.Error(w, err.Error(), http.StatusInternalServerError)
+	if err := s.StateManager.ResolveComment(s.RepoPath, currentBranch, currentCommit, payload.CommentID, "web-ui"); err != nil {
+		http.Error(w, err.Error(), stateErrorStatus(err))
 		return
 	}
 
-	currentCommit, err := gitRepo.CurrentCommit()
+	w.WriteHeader(http.StatusOK)
+}
+
+func (s *AppState) acknowledgeCommentHandler(w http.ResponseWriter, r *http.Request) {
+	s.mu.Lock()
+	defer s.mu.Unlock()
+
+	var payload AcknowledgeCommentRequest
+	if err := json.NewDecoder(r.Body).Decode(&payload); err != nil {
+		http.Error(w, err.Error(), http.StatusBadRequest)
+		return
+	}
+
+	currentBranch, currentCommit, err := s.revision()
 	if err != nil {
 		http.Error(w, err.Error(), http.StatusInternalServerError)
 		return
 	}
 
-	if err := s.StateManager.UnmarkFileViewed(s.RepoPath, currentBranch, currentCommit, payload.FilePath); err != nil {
-		http.Error(w, err.Error(), http.StatusInternalServerError)
+	if err := s.StateManager.AcknowledgeComment(s.RepoPath, currentBranch, currentCommit, payload.CommentID, "web-ui"); err != nil {
+		http.Error(w, err.Error(), stateErrorStatus(err))
 		return
 	}
 
 	w.WriteHeader(http.StatusOK)
 }
 
-func (s *AppState) statusHandler(w http.ResponseWriter, r *http.Request) {
-	gitRepo, err := git.Open(".")
-	if err != nil {
-		http.Error(w, err.Error(), http.StatusInternalServerError)
+func (s *AppState) pinCommentHandler(w http.ResponseWriter, r *http.Request) {
+	s.mu.Lock()
+	defer s.mu.Unlock()
+
+	var payload PinCommentRequest
+	if err := json.NewDecoder(r.Body).Decode(&payload); err != nil {
+		http.Error(w, err.Error(), http.StatusBadRequest)
 		return
 	}
 
-	currentBranch, err := gitRepo.CurrentBranch()
+	currentBranch, currentCommit, err := s.revision()
 	if err != nil {
 		http.Error(w, err.Error(), http.StatusInternalServerError)
 		return
 	}
 
-	currentCommit, err := gitRepo.CurrentCommit()
-	if err != nil {
-		http.Error(w, err.Error(), http.StatusInternalServerError)
+	if err := s.StateManager.SetCommentPinned(s.RepoPath, currentBranch, currentCommit, payload.CommentID, payload.Pinned); err != nil {
+		http.Error(w, err.Error(), stateErrorStatus(err))
 		return
 	}
 
-	response := StatusResponse{
+	w.WriteHeader(http.StatusOK)
+}
+
+func (s *AppState) reviewsHandler(w http.ResponseWriter, r *http.Request) {
+	s.mu.Lock()
+	defer s.mu.Unlock()
+
+	response := ReviewsResponse{
 		RepoPath: s.RepoPath,
-		Branch:   currentBranch,
-		Commit:   currentCommit,
+		Branches: make(map[string][]ReviewEntry),
+	}
+	for _, record := range s.StateManager.ListReviews(s.RepoPath) {
+		mode, commit := splitReviewCommit(record.Commit)
+		response.Branches[record.Branch] = append(response.Branches[record.Branch], ReviewEntry{
+			Commit:             commit,
+			CommitShort:        git.Abbreviate(commit, s.CommitAbbrevLen),
+			Mode:               mode,
+			ViewedFiles:        record.ViewedFiles,
+			Comments:           record.Comments,
+			UnresolvedComments: record.UnresolvedComments,
+			LastActivityAt:     record.LastActivityAt,
+		})
 	}
 
 	w.Header().Set("Content-Type", "application/json")
 	_ = json.NewEncoder(w).Encode(response) // Ignore encode error for HTTP response
 }
 
-func (s *AppState) getCommentsHandler(w http.ResponseWriter, r *http.Request) {
+func (s *AppState) eventsHandler(w http.ResponseWriter, r *http.Request) {
+	var since int64
+	if sinceParam := r.URL.Query().Get("since"); sinceParam != "" {
+		var err error
+		since, err = strconv.ParseInt(sinceParam, 10, 64)
+		if err != nil {
+			http.Error(w, fmt.Sprintf("invalid since: %v", err), http.StatusBadRequest)
+			return
+		}
+	}
+
 	s.mu.Lock()
 	defer s.mu.Unlock()
 
-	gitRepo, err := git.Open(".")
+	events, err := s.StateManager.Events(s.RepoPath, since)
 	if err != nil {
 		http.Error(w, err.Error(), http.StatusInternalServerError)
 		return
 	}
 
-	currentBranch, err := gitRepo.CurrentBranch()
-	if err != nil {
-		http.Error(w, err.Error(), http.StatusInternalServerError)
-		return
-	}
+	w.Header().Set("Content-Type", "application/json")
+	_ = json.NewEncoder(w).Encode(events) // Ignore encode error for HTTP response
+}
 
-	currentCommit, err := gitRepo.CurrentCommit()
+func (s *AppState) getReviewSummaryHandler(w http.ResponseWriter, r *http.Request) {
+	s.mu.Lock()
+	defer s.mu.Unlock()
+
+	currentBranch, currentCommit, err := s.revision()
 	if err != nil {
 		http.Error(w, err.Error(), http.StatusInternalServerError)
 		return
 	}
 
-	filePath := r.URL.Query().Get("file_path")
-	var filePathPtr *string
-	if filePath != "" {
-		filePathPtr = &filePath
+	mode := r.URL.Query().Get("mode")
+	response := ReviewSummary{
+		Summary: s.StateManager.GetReviewSummary(s.RepoPath, currentBranch, reviewCommit(mode, currentCommit)),
+		Mode:    mode,
 	}
 
-	comments := s.StateManager.GetComments(s.RepoPath, currentBranch, currentCommit, filePathPtr)
-
 	w.Header().Set("Content-Type", "application/json")
-	_ = json.NewEncoder(w).Encode(comments) // Ignore encode error for HTTP response
+	_ = json.NewEncoder(w).Encode(response) // Ignore encode error for HTTP response
 }
 
-func (s *AppState) addCommentHandler(w http.ResponseWriter, r *http.Request) {
+func (s *AppState) setReviewSummaryHandler(w http.ResponseWriter, r *http.Request) {
 	s.mu.Lock()
 	defer s.mu.Unlock()
 
-	var payload AddCommentRequest
+	var payload ReviewSummary
 	if err := json.NewDecoder(r.Body).Decode(&payload); err != nil {
 		http.Error(w, err.Error(), http.StatusBadRequest)
 		return
 	}
 
-	gitRepo, err := git.Open(".")
-	if err != nil {
-		http.Error(w, err.Error(), http.StatusInternalServerError)
+	// An empty summary clears it, so only the length is limited
+	if length := utf8.RuneCountInString(payload.Summary); s.MaxCommentLength > 0 && length > s.MaxCommentLength {
+		http.Error(w, fmt.Sprintf("summary is %d characters, exceeding the maximum of %d", length, s.MaxCommentLength), http.StatusBadRequest)
 		return
 	}
 
-	currentBranch, err := gitRepo.CurrentBranch()
+	currentBranch, currentCommit, err := s.revision()
 	if err != nil {
 		http.Error(w, err.Error(), http.StatusInternalServerError)
 		return
 	}
 
-	currentCommit, err := gitRepo.CurrentCommit()
-	if err != nil {
-		http.Error(w, err.Error(), http.StatusInternalServerError)
+	if err := s.StateManager.SetReviewSummary(s.RepoPath, currentBranch, reviewCommit(payload.Mode, currentCommit), payload.Summary); err != nil {
+		http.Error(w, err.Error(), stateErrorStatus(err))
 		return
 	}
 
-	comment, err := s.StateManager.AddComment(s.RepoPath, currentBranch, currentCommit, payload.FilePath, payload.LineNumber, payload.Text)
-	if err != nil {
-		http.Error(w, err.Error(), http.StatusInternalServerError)
-		return
-	}
+	w.WriteHeader(http.StatusOK)
+}
 
-	w.Header().Set("Content-Type", "application/json")
-	_ = json.NewEncoder(w).Encode(comment) // Ignore encode error for HTTP response
+// checklistProgress returns how much of the review checklist is ticked for a
+// review, or nil when no checklist is configured. Callers must hold s.mu.
+func (s *AppState) checklistProgress(branch, commit string) *state.ChecklistProgress {
+	if len(s.ReviewChecklist) == 0 {
+		return nil
+	}
+	progress := s.StateManager.GetChecklistProgress(s.RepoPath, branch, commit, s.ReviewChecklist)
+	return &progress
 }
 
-func (s *AppState) resolveCommentHandler(w http.ResponseWriter, r *http.Request) {
+func (s *AppState) setChecklistItemHandler(w http.ResponseWriter, r *http.Request) {
 	s.mu.Lock()
 	defer s.mu.Unlock()
 
-	var payload ResolveCommentRequest
+	var payload ChecklistRequest
 	if err := json.NewDecoder(r.Body).Decode(&payload); err != nil {
 		http.Error(w, err.Error(), http.StatusBadRequest)
 		return
 	}
 
-	gitRepo, err := git.Open(".")
-	if err != nil {
-		http.Error(w, err.Error(), http.StatusInternalServerError)
-		return
-	}
-
-	currentBranch, err := gitRepo.CurrentBranch()
-	if err != nil {
-		http.Error(w, err.Error(), http.StatusInternalServerError)
+	// Items that are no longer configured can still be unticked
+	if payload.Checked && !slices.Contains(s.ReviewChecklist, payload.Item) {
+		http.Error(w, fmt.Sprintf("unknown checklist item: %q", payload.Item), http.StatusBadRequest)
 		return
 	}
 
-	currentCommit, err := gitRepo.CurrentCommit()
+	currentBranch, currentCommit, err := s.revision()
 	if err != nil {
 		http.Error(w, err.Error(), http.StatusInternalServerError)
 		return
 	}
 
-	if err := s.StateManager.ResolveComment(s.RepoPath, currentBranch, currentCommit, payload.CommentID, "web-ui"); err != nil {
-		http.Error(w, err.Error(), http.StatusInternalServerError)
+	commit := reviewCommit(payload.Mode, currentCommit)
+	if err := s.StateManager.SetChecklistItem(s.RepoPath, currentBranch, commit, payload.Item, payload.Checked); err != nil {
+		http.Error(w, err.Error(), stateErrorStatus(err))
 		return
 	}
 
-	w.WriteHeader(http.StatusOK)
+	w.Header().Set("Content-Type", "application/json")
+	_ = json.NewEncoder(w).Encode(s.StateManager.GetChecklistProgress(s.RepoPath, currentBranch, commit, s.ReviewChecklist)) // Ignore encode error for HTTP response
 }
 
 func (s *AppState) getNotesHandler(w http.ResponseWriter, r *http.Request) {
 	s.mu.Lock()
 	defer s.mu.Unlock()
 
-	gitRepo, err := git.Open(".")
-	if err != nil {
-		http.Error(w, err.Error(), http.StatusInternalServerError)
-		return
-	}
-
-	currentBranch, err := gitRepo.CurrentBranch()
-	if err != nil {
-		http.Error(w, err.Error(), http.StatusInternalServerError)
-		return
-	}
-
-	currentCommit, err := gitRepo.CurrentCommit()
+	currentBranch, currentCommit, err := s.revision()
 	if err != nil {
 		http.Error(w, err.Error(), http.StatusInternalServerError)
 		return
@@ -457,19 +3011,7 @@ func (s *AppState) addNoteHandler(w http.ResponseWriter, r *http.Request) {
 		return
 	}
 
-	gitRepo, err := git.Open(".")
-	if err != nil {
-		http.Error(w, err.Error(), http.StatusInternalServerError)
-		return
-	}
-
-	currentBranch, err := gitRepo.CurrentBranch()
-	if err != nil {
-		http.Error(w, err.Error(), http.StatusInternalServerError)
-		return
-	}
-
-	currentCommit, err := gitRepo.CurrentCommit()
+	currentBranch, currentCommit, err := s.revision()
 	if err != nil {
 		http.Error(w, err.Error(), http.StatusInternalServerError)
 		return
@@ -493,7 +3035,7 @@ func (s *AppState) addNoteHandler(w http.ResponseWriter, r *http.Request) {
 		payload.Metadata,
 	)
 	if err != nil {
-		http.Error(w, err.Error(), http.StatusInternalServerError)
+		http.Error(w, err.Error(), stateErrorStatus(err))
 		return
 	}
 
@@ -511,26 +3053,14 @@ func (s *AppState) dismissNoteHandler(w http.ResponseWriter, r *http.Request) {
 		return
 	}
 
-	gitRepo, err := git.Open(".")
-	if err != nil {
-		http.Error(w, err.Error(), http.StatusInternalServerError)
-		return
-	}
-
-	currentBranch, err := gitRepo.CurrentBranch()
-	if err != nil {
-		http.Error(w, err.Error(), http.StatusInternalServerError)
-		return
-	}
-
-	currentCommit, err := gitRepo.CurrentCommit()
+	currentBranch, currentCommit, err := s.revision()
 	if err != nil {
 		http.Error(w, err.Error(), http.StatusInternalServerError)
 		return
 	}
 
 	if err := s.StateManager.DismissNote(s.RepoPath, currentBranch, currentCommit, payload.NoteID, "web-ui"); err != nil {
-		http.Error(w, err.Error(), http.StatusInternalServerError)
+		http.Error(w, err.Error(), stateErrorStatus(err))
 		return
 	}
 
@@ -0,0 +1,313 @@
+package server
+
+import (
+	"encoding/json"
+	"fmt"
+	"net/http"
+	"net/http/httptest"
+	"os"
+	"os/exec"
+	"path/filepath"
+	"strings"
+	"testing"
+
+	"github.com/tuist/guck/internal/git"
+	"github.com/tuist/guck/internal/state"
+)
+
+func TestParsePage(t *testing.T) {
+	tests := []struct {
+		query    string
+		maxFiles int
+		expected page
+		wantErr  bool
+	}{
+		{query: "", maxFiles: 0, expected: page{}},
+		{query: "offset=10&limit=5", maxFiles: 0, expected: page{offset: 10, limit: 5}},
+		{query: "", maxFiles: 100, expected: page{limit: 100}},
+		{query: "limit=500", maxFiles: 100, expected: page{limit: 100}},
+		{query: "limit=20", maxFiles: 100, expected: page{limit: 20}},
+		{query: "offset=-1", wantErr: true},
+		{query: "limit=abc", wantErr: true},
+	}
+
+	for _, tt := range tests {
+		r := httptest.NewRequest("GET", "/api/diff?"+tt.query, nil)
+		pg, err := parsePage(r, tt.maxFiles)
+		if tt.wantErr {
+			if err == nil {
+				t.Errorf("parsePage(%q): expected an error", tt.query)
+			}
+			continue
+		}
+		if err != nil {
+			t.Errorf("parsePage(%q): unexpected error: %v", tt.query, err)
+			continue
+		}
+		if pg != tt.expected {
+			t.Errorf("parsePage(%q, %d) = %+v, expected %+v", tt.query, tt.maxFiles, pg, tt.expected)
+		}
+	}
+}
+
+func TestPageApplyKeepsIndices(t *testing.T) {
+	files := []FileDiff{}
+	for i := 0; i < 5; i++ {
+		files = append(files, FileDiff{Index: i})
+	}
+
+	got := page{offset: 2, limit: 2}.apply(files)
+	if len(got) != 2 || got[0].Index != 2 || got[1].Index != 3 {
+		t.Errorf("Expected files 2 and 3, got %+v", got)
+	}
+
+	if got := (page{offset: 10}).apply(files); len(got) != 0 {
+		t.Errorf("Expected no files past the end, got %+v", got)
+	}
+}
+
+func TestLoadTLSConfig(t *testing.T) {
+	tlsConfig, err := LoadTLSConfig("", "")
+	if err != nil || tlsConfig != nil {
+		t.Errorf("Expected no TLS config when unset, got %v, %v", tlsConfig, err)
+	}
+
+	if _, err := LoadTLSConfig("cert.pem", ""); err == nil {
+		t.Error("Expected an error when only the certificate is set")
+	}
+
+	if _, err := LoadTLSConfig("/does/not/exist/cert.pem", "/does/not/exist/key.pem"); err == nil {
+		t.Error("Expected an error for missing certificate files")
+	}
+}
+
+func TestExpandCommentTemplate(t *testing.T) {
+	line := 42
+	if got := expandCommentTemplate("Please add a test for {file}:{line}", "main.go", &line); got != "Please add a test for main.go:42" {
+		t.Errorf("Unexpected expansion: %q", got)
+	}
+	if got := expandCommentTemplate("{file} needs docs (line {line})", "README.md", nil); got != "README.md needs docs (line )" {
+		t.Errorf("Expected an empty line for file comments, got %q", got)
+	}
+}
+
+func TestMarkViewedRequestReviewedCommit(t *testing.T) {
+	if got, err := (MarkViewedRequest{}).reviewedCommit("head"); err != nil || got != "head" {
+		t.Errorf("Expected HEAD for branch reviews, got %q, %v", got, err)
+	}
+	if got, err := (MarkViewedRequest{Mode: DiffModeCommit, Commit: "abc"}).reviewedCommit("head"); err != nil || got != "abc" {
+		t.Errorf("Expected the requested commit in commit mode, got %q, %v", got, err)
+	}
+	if _, err := (MarkViewedRequest{Mode: DiffModeCommit}).reviewedCommit("head"); err == nil {
+		t.Error("Expected an error for commit mode without a commit")
+	}
+}
+
+func TestSetCommentCounts(t *testing.T) {
+	t.Setenv("GUCK_STATE_DIR", t.TempDir())
+	manager, err := state.NewManager()
+	if err != nil {
+		t.Fatalf("Failed to create state manager: %v", err)
+	}
+	s := &AppState{StateManager: manager, RepoPath: "/test/repo"}
+
+	line := 3
+	for _, text := range []string{"open", "resolved", "also open"} {
+		comment, err := manager.AddComment(s.RepoPath, "main", "abc123", "main.go", &line, text)
+		if err != nil {
+			t.Fatalf("Failed to add comment: %v", err)
+		}
+		if text == "resolved" {
+			if err := manager.ResolveComment(s.RepoPath, "main", "abc123", comment.ID, "reviewer"); err != nil {
+				t.Fatalf("Failed to resolve comment: %v", err)
+			}
+		}
+	}
+
+	files := []FileDiff{{Path: "main.go"}, {Path: "other.go"}}
+	s.setCommentCounts("main", "abc123", files)
+
+	if files[0].CommentCount != 3 || files[0].UnresolvedCommentCount != 2 {
+		t.Errorf("Expected 3 comments with 2 unresolved on main.go, got %d and %d", files[0].CommentCount, files[0].UnresolvedCommentCount)
+	}
+	if files[1].CommentCount != 0 || files[1].UnresolvedCommentCount != 0 {
+		t.Errorf("Expected no comments on other.go, got %d and %d", files[1].CommentCount, files[1].UnresolvedCommentCount)
+	}
+}
+
+func TestTruncatePatch(t *testing.T) {
+	patch := "@@ -1,3 +1,3 @@\n-old\n+new\n context\n"
+
+	if got, truncated := truncatePatch(patch, 0); got != patch || truncated {
+		t.Errorf("Expected no cap with a zero limit, got %q, %v", got, truncated)
+	}
+	if got, truncated := truncatePatch(patch, len(patch)); got != patch || truncated {
+		t.Errorf("Expected a patch at the limit to be kept, got %q, %v", got, truncated)
+	}
+	if got, truncated := truncatePatch(patch, 24); got != "@@ -1,3 +1,3 @@\n-old\n" || !truncated {
+		t.Errorf("Expected the patch cut at the last whole line, got %q, %v", got, truncated)
+	}
+}
+
+func TestWriteDiffResponseSplitsTruncatedPatch(t *testing.T) {
+	patch := "diff --git a/big.txt b/big.txt\n--- a/big.txt\n+++ b/big.txt\n@@ -1,100 +1,100 @@\n"
+	for i := 0; i < 100; i++ {
+		patch += fmt.Sprintf("-old line %03d\n", i)
+	}
+	for i := 0; i < 100; i++ {
+		patch += fmt.Sprintf("+new line %03d\n", i)
+	}
+	s := &AppState{MaxPatchKB: 1}
+
+	rec := httptest.NewRecorder()
+	s.writeDiffResponse(rec, httptest.NewRequest("GET", "/api/diff?split=true&lines=true", nil), DiffResponse{
+		Files: []FileDiff{{Path: "big.txt", Patch: patch}},
+	})
+
+	var response DiffResponse
+	if err := json.NewDecoder(rec.Body).Decode(&response); err != nil {
+		t.Fatalf("Failed to decode response: %v", err)
+	}
+	file := response.Files[0]
+	if !file.Truncated || len(file.Patch) > 1024 {
+		t.Fatalf("Expected the patch truncated to 1 KB, got %d bytes, truncated %v", len(file.Patch), file.Truncated)
+	}
+	if len(file.Split) != 1 || len(file.Lines) == 0 {
+		t.Fatalf("Expected one split hunk and the hunk lines, got %+v and %d lines", file.Split, len(file.Lines))
+	}
+
+	// Both layouts hold exactly the lines left in the truncated patch
+	hunk := file.Split[0]
+	if len(hunk.Left) != len(file.Lines) || len(hunk.Right) != len(file.Lines) {
+		t.Errorf("Expected %d rows, got %d left and %d right", len(file.Lines), len(hunk.Left), len(hunk.Right))
+	}
+	for i, line := range hunk.Left {
+		if line.Type != git.SplitRemoved || !strings.Contains(file.Patch, "-"+line.Content+"\n") {
+			t.Errorf("Row %d: expected a removed line kept in the patch, got %+v", i, line)
+		}
+		if hunk.Right[i].Type != git.SplitEmpty {
+			t.Errorf("Row %d: expected the added lines cut off by truncation to be gone, got %+v", i, hunk.Right[i])
+		}
+	}
+}
+
+func TestListenUnixSocket(t *testing.T) {
+	socketPath := filepath.Join(t.TempDir(), "sockets", "guck.sock")
+	if err := os.MkdirAll(filepath.Dir(socketPath), 0700); err != nil {
+		t.Fatalf("Failed to create socket directory: %v", err)
+	}
+	// Left behind by a daemon that didn't shut down cleanly
+	if err := os.WriteFile(socketPath, nil, 0644); err != nil {
+		t.Fatalf("Failed to create stale socket: %v", err)
+	}
+
+	ln, err := listen("unix", socketPath)
+	if err != nil {
+		t.Fatalf("Expected the stale socket to be replaced, got %v", err)
+	}
+	defer ln.Close()
+
+	info, err := os.Stat(socketPath)
+	if err != nil {
+		t.Fatalf("Failed to stat socket: %v", err)
+	}
+	if info.Mode()&os.ModeSocket == 0 || info.Mode().Perm() != 0600 {
+		t.Errorf("Expected a socket only its owner can use, got %v", info.Mode())
+	}
+
+	if _, err := listen("unix", socketPath); err == nil {
+		t.Error("Expected a socket in use to be rejected")
+	}
+}
+
+func TestDirectoryRollup(t *testing.T) {
+	directories := directoryRollup{}
+	directories.add("main.go", true)
+	directories.add("pkg/api/handler.go", true)
+	directories.add("pkg/api/routes.go", false)
+	directories.add("pkg/util.go", true)
+
+	if got := directories.list(httptest.NewRequest("GET", "/api/diff", nil)); got != nil {
+		t.Errorf("Expected no rollups without ?group=directory, got %v", got)
+	}
+
+	got := directories.list(httptest.NewRequest("GET", "/api/diff?group=directory", nil))
+	want := []DirectoryProgress{
+		{Path: "pkg", Viewed: 2, Total: 3},
+		{Path: "pkg/api", Viewed: 1, Total: 2},
+	}
+	if len(got) != len(want) {
+		t.Fatalf("Expected %v, got %v", want, got)
+	}
+	for i := range want {
+		if got[i] != want[i] {
+			t.Errorf("Expected %v, got %v", want[i], got[i])
+		}
+	}
+
+	if !inDirectory("pkg/api/handler.go", "pkg/") || inDirectory("pkgs/other.go", "pkg") {
+		t.Error("Expected inDirectory to match whole path components only")
+	}
+}
+
+func TestReviewStateExistsPrunesByCommitKey(t *testing.T) {
+	dir := t.TempDir()
+	git := func(args ...string) string {
+		t.Helper()
+		cmd := exec.Command("git", args...)
+		cmd.Dir = dir
+		output, err := cmd.CombinedOutput()
+		if err != nil {
+			t.Fatalf("git %v failed: %v\n%s", args, err, output)
+		}
+		return strings.TrimSpace(string(output))
+	}
+	git("init")
+	git("config", "user.email", "test@test.com")
+	git("config", "user.name", "Test User")
+	if err := os.WriteFile(filepath.Join(dir, "a.go"), []byte("package a\n"), 0644); err != nil {
+		t.Fatalf("Failed to write file: %v", err)
+	}
+	git("add", ".")
+	git("commit", "-m", "Initial commit")
+	branch := git("symbolic-ref", "--short", "HEAD")
+	head := git("rev-parse", "HEAD")
+	gone := strings.Repeat("0", len(head))
+
+	t.Setenv("GUCK_STATE_DIR", t.TempDir())
+	manager, err := state.NewManager()
+	if err != nil {
+		t.Fatalf("Failed to create state manager: %v", err)
+	}
+	for _, key := range []string{head, reviewCommit(DiffModeCommit, head), reviewCommit(DiffModeWorking, head), reviewCommit(DiffModeCommit, gone)} {
+		if err := manager.MarkFileViewed(dir, branch, key, "a.go"); err != nil {
+			t.Fatalf("Failed to mark file viewed: %v", err)
+		}
+	}
+	if err := manager.MarkFileViewed(filepath.Join(dir, "missing"), branch, head, "a.go"); err != nil {
+		t.Fatalf("Failed to mark file viewed: %v", err)
+	}
+
+	removed, err := manager.Prune(ReviewStateExists())
+	if err != nil {
+		t.Fatalf("Failed to prune: %v", err)
+	}
+	if removed != 2 {
+		t.Errorf("Expected the unknown commit and the missing repository to be pruned, removed %d", removed)
+	}
+	for _, key := range []string{head, reviewCommit(DiffModeCommit, head), reviewCommit(DiffModeWorking, head)} {
+		if !manager.IsFileViewed(dir, branch, key, "a.go") {
+			t.Errorf("Expected the review under %s to be kept", key)
+		}
+	}
+}
+
+func TestDiffHandlerRejectsAlgorithmForPatchFiles(t *testing.T) {
+	s := &AppState{patch: &patchReview{}}
+
+	rec := httptest.NewRecorder()
+	s.diffHandler(rec, httptest.NewRequest("GET", "/api/diff?algorithm=histogram", nil))
+	if rec.Code != http.StatusBadRequest || !strings.Contains(rec.Body.String(), "algorithm is not supported") {
+		t.Errorf("Expected a 400 for an algorithm on a patch file, got %d: %s", rec.Code, rec.Body.String())
+	}
+}
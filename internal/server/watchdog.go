@@ -0,0 +1,197 @@
+package server
+
+import (
+	"context"
+	"encoding/json"
+	"fmt"
+	"net"
+	"net/http"
+	"os"
+	"runtime/debug"
+	"sync"
+	"time"
+)
+
+// Exit codes returned via ExitError when the watchdog gives up, so a
+// supervisor can tell why the server stopped
+const (
+	ExitRepoGone      = 3 // The repository was deleted or became inaccessible
+	ExitPortLost      = 4 // The server stopped accepting connections on its port
+	ExitUnrecoverable = 5 // A handler panicked or diffs keep failing for another reason
+)
+
+const (
+	watchdogInterval           = 10 * time.Second
+	maxConsecutiveDiffFailures = 5
+	maxConsecutivePortFailures = 3
+)
+
+// ExitError is returned by Start when the watchdog stops the server
+type ExitError struct {
+	Code   int
+	Reason string
+	Err    error
+}
+
+func (e *ExitError) Error() string {
+	if e.Err != nil {
+		return fmt.Sprintf("%s: %v", e.Reason, e.Err)
+	}
+	return e.Reason
+}
+
+func (e *ExitError) Unwrap() error {
+	return e.Err
+}
+
+// watchdog detects states the server can't recover from on its own and
+// reports them on exit, instead of leaving the process running half-broken
+type watchdog struct {
+	repoPath string
+	network  string
+	addr     string
+	exit     chan *ExitError
+
+	mu           sync.Mutex
+	diffFailures int
+}
+
+func newWatchdog(repoPath, network, addr string) *watchdog {
+	return &watchdog{
+		repoPath: repoPath,
+		network:  network,
+		addr:     addr,
+		exit:     make(chan *ExitError, 1),
+	}
+}
+
+// trip logs structured diagnostics and requests an exit. Only the first trip wins.
+func (wd *watchdog) trip(code int, reason string, err error, extra map[string]interface{}) {
+	diagnostics := map[string]interface{}{
+		"event":     "watchdog_exit",
+		"time":      time.Now().UTC().Format(time.RFC3339),
+		"exit_code": code,
+		"reason":    reason,
+		"repo_path": wd.repoPath,
+		"addr":      wd.addr,
+	}
+	if err != nil {
+		diagnostics["error"] = err.Error()
+	}
+	for k, v := range extra {
+		diagnostics[k] = v
+	}
+	_ = json.NewEncoder(os.Stderr).Encode(diagnostics) // Best effort, we're exiting anyway
+
+	select {
+	case wd.exit <- &ExitError{Code: code, Reason: reason, Err: err}:
+	default:
+	}
+}
+
+// recordDiffResult tracks consecutive diff failures. A nil watchdog is a no-op.
+func (wd *watchdog) recordDiffResult(err error) {
+	if wd == nil {
+		return
+	}
+
+	wd.mu.Lock()
+	if err == nil {
+		wd.diffFailures = 0
+		wd.mu.Unlock()
+		return
+	}
+	wd.diffFailures++
+	failures := wd.diffFailures
+	wd.mu.Unlock()
+
+	if failures < maxConsecutiveDiffFailures {
+		return
+	}
+
+	extra := map[string]interface{}{"consecutive_failures": failures}
+	if !wd.repoExists() {
+		wd.trip(ExitRepoGone, "repository is no longer accessible", err, extra)
+		return
+	}
+	wd.trip(ExitUnrecoverable, "diffs are failing consistently", err, extra)
+}
+
+// recoverPanics turns a handler panic into a 500 and an unrecoverable exit
+func (wd *watchdog) recoverPanics(next http.Handler) http.Handler {
+	return http.HandlerFunc(func(w http.ResponseWriter, r *http.Request) {
+		defer func() {
+			if recovered := recover(); recovered != nil {
+				http.Error(w, "internal server error", http.StatusInternalServerError)
+				wd.trip(ExitUnrecoverable, "handler panicked", fmt.Errorf("%v", recovered), map[string]interface{}{
+					"method": r.Method,
+					"path":   r.URL.Path,
+					"stack":  string(debug.Stack()),
+				})
+			}
+		}()
+		next.ServeHTTP(w, r)
+	})
+}
+
+// monitor periodically checks that the repository exists and the port or
+// socket still accepts connections, until stop is closed
+func (wd *watchdog) monitor(stop <-chan struct{}) {
+	ticker := time.NewTicker(watchdogInterval)
+	defer ticker.Stop()
+
+	portFailures := 0
+	for {
+		select {
+		case <-stop:
+			return
+		case <-ticker.C:
+		}
+
+		if !wd.repoExists() {
+			wd.trip(ExitRepoGone, "repository is no longer accessible", nil, nil)
+			return
+		}
+
+		conn, err := net.DialTimeout(wd.network, wd.addr, time.Second)
+		if err != nil {
+			portFailures++
+			if portFailures >= maxConsecutivePortFailures {
+				wd.trip(ExitPortLost, "server stopped accepting connections", err, map[string]interface{}{"consecutive_failures": portFailures})
+				return
+			}
+			continue
+		}
+		conn.Close()
+		portFailures = 0
+	}
+}
+
+func (wd *watchdog) repoExists() bool {
+	_, err := os.Stat(wd.repoPath)
+	return err == nil
+}
+
+// serveWithWatchdog runs srv on ln until it fails, the watchdog trips or ctx is cancelled
+func serveWithWatchdog(ctx context.Context, srv *http.Server, ln net.Listener, wd *watchdog, cancelRequests context.CancelFunc) error {
+	serveErr := make(chan error, 1)
+	go func() {
+		serveErr <- serveListener(srv, ln)
+	}()
+
+	stop := make(chan struct{})
+	defer close(stop)
+	go wd.monitor(stop)
+
+	select {
+	case err := <-serveErr:
+		wd.trip(ExitPortLost, "server stopped listening", err, nil)
+		return <-wd.exit
+	case exitErr := <-wd.exit:
+		cancelRequests()
+		_ = srv.Close()
+		return exitErr
+	case <-ctx.Done():
+		return shutdown(srv, cancelRequests)
+	}
+}
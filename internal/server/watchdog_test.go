@@ -0,0 +1,90 @@
+package server
+
+import (
+	"errors"
+	"net/http"
+	"net/http/httptest"
+	"path/filepath"
+	"testing"
+)
+
+func TestWatchdogDiffFailures(t *testing.T) {
+	tests := []struct {
+		name     string
+		repoPath string
+		wantCode int
+	}{
+		{"repo gone", filepath.Join(t.TempDir(), "missing"), ExitRepoGone},
+		{"repo present", t.TempDir(), ExitUnrecoverable},
+	}
+
+	for _, tt := range tests {
+		t.Run(tt.name, func(t *testing.T) {
+			wd := newWatchdog(tt.repoPath, "tcp", "127.0.0.1:0")
+			diffErr := errors.New("diff failed")
+
+			for i := 0; i < maxConsecutiveDiffFailures-1; i++ {
+				wd.recordDiffResult(diffErr)
+			}
+			select {
+			case exitErr := <-wd.exit:
+				t.Fatalf("Watchdog tripped too early: %v", exitErr)
+			default:
+			}
+
+			wd.recordDiffResult(diffErr)
+			select {
+			case exitErr := <-wd.exit:
+				if exitErr.Code != tt.wantCode {
+					t.Errorf("Expected exit code %d, got %d", tt.wantCode, exitErr.Code)
+				}
+				if !errors.Is(exitErr, diffErr) {
+					t.Errorf("Expected exit error to wrap the diff error, got %v", exitErr)
+				}
+			default:
+				t.Fatal("Expected watchdog to trip")
+			}
+		})
+	}
+}
+
+func TestWatchdogDiffSuccessResetsFailures(t *testing.T) {
+	wd := newWatchdog(t.TempDir(), "tcp", "127.0.0.1:0")
+
+	for i := 0; i < maxConsecutiveDiffFailures*2; i++ {
+		if i%2 == 0 {
+			wd.recordDiffResult(errors.New("diff failed"))
+		} else {
+			wd.recordDiffResult(nil)
+		}
+	}
+
+	select {
+	case exitErr := <-wd.exit:
+		t.Fatalf("Expected intermittent failures not to trip the watchdog: %v", exitErr)
+	default:
+	}
+}
+
+func TestWatchdogRecoversPanics(t *testing.T) {
+	wd := newWatchdog(t.TempDir(), "tcp", "127.0.0.1:0")
+	handler := wd.recoverPanics(http.HandlerFunc(func(w http.ResponseWriter, r *http.Request) {
+		panic("boom")
+	}))
+
+	rec := httptest.NewRecorder()
+	handler.ServeHTTP(rec, httptest.NewRequest("GET", "/api/diff", nil))
+
+	if rec.Code != http.StatusInternalServerError {
+		t.Errorf("Expected 500, got %d", rec.Code)
+	}
+
+	select {
+	case exitErr := <-wd.exit:
+		if exitErr.Code != ExitUnrecoverable {
+			t.Errorf("Expected exit code %d, got %d", ExitUnrecoverable, exitErr.Code)
+		}
+	default:
+		t.Fatal("Expected watchdog to trip on panic")
+	}
+}
@@ -0,0 +1,265 @@
+package server
+
+import (
+	"errors"
+	"io/fs"
+	"os"
+	"path/filepath"
+	"strings"
+	"time"
+
+	"github.com/tuist/guck/internal/git"
+)
+
+// watchDebounce is how long the watcher lets a burst of file system events,
+// such as a checkout or a build, settle before reporting it
+const watchDebounce = 300 * time.Millisecond
+
+// errWatchUnsupported is returned by newFileWatcher on platforms without a
+// file system watcher, where the repository is polled instead
+var errWatchUnsupported = errors.New("file system watching is not supported on this platform")
+
+// fileWatcher reports changes to the entries of the directories it watches,
+// without descending into subdirectories
+type fileWatcher interface {
+	add(dir string) error
+	// events receives the changed paths, or "" when events were lost, and is
+	// closed once the watcher stops
+	events() <-chan string
+	close() error
+}
+
+// repoWatcher reports changes to a repository's working tree, HEAD, index
+// and refs. Objects are never watched, and working tree changes .gitignore
+// excludes are dropped.
+type repoWatcher struct {
+	repo      *git.Repo
+	root      string
+	gitDir    string
+	commonDir string
+	files     fileWatcher
+	// changes receives a value once a burst of relevant changes settled, and
+	// is closed if the watcher stops working
+	changes chan struct{}
+}
+
+func newRepoWatcher(repoPath string) (*repoWatcher, error) {
+	// git reports its directories with symlinks resolved, so the watched
+	// paths must be too for them to be told apart
+	repoPath, err := filepath.EvalSymlinks(repoPath)
+	if err != nil {
+		return nil, err
+	}
+	gitRepo, err := git.Open(repoPath)
+	if err != nil {
+		return nil, err
+	}
+	gitDir, commonDir, err := gitRepo.GitDirs()
+	if err != nil {
+		return nil, err
+	}
+	ignored, err := gitRepo.IgnoredDirs()
+	if err != nil {
+		return nil, err
+	}
+
+	files, err := newFileWatcher()
+	if err != nil {
+		return nil, err
+	}
+	w := &repoWatcher{
+		repo:      gitRepo,
+		root:      repoPath,
+		gitDir:    gitDir,
+		commonDir: commonDir,
+		files:     files,
+		changes:   make(chan struct{}, 1),
+	}
+
+	skip := make(map[string]bool, len(ignored))
+	for _, dir := range ignored {
+		skip[filepath.Join(repoPath, dir)] = true
+	}
+	if err := w.addTree(repoPath, skip); err != nil {
+		_ = files.close()
+		return nil, err
+	}
+	// HEAD and the index live in the git directory, refs in the common one
+	if err := files.add(gitDir); err != nil {
+		_ = files.close()
+		return nil, err
+	}
+	if commonDir != gitDir {
+		if err := files.add(commonDir); err != nil {
+			_ = files.close()
+			return nil, err
+		}
+	}
+	if err := w.addTree(filepath.Join(commonDir, "refs"), nil); err != nil {
+		_ = files.close()
+		return nil, err
+	}
+
+	go w.run()
+	return w, nil
+}
+
+// addTree watches dir and its subdirectories, except those in skip and
+// nested git directories
+func (w *repoWatcher) addTree(dir string, skip map[string]bool) error {
+	return filepath.WalkDir(dir, func(path string, d fs.DirEntry, err error) error {
+		if err != nil {
+			// Removed while walking; its parent reports that
+			if errors.Is(err, fs.ErrNotExist) {
+				return nil
+			}
+			return err
+		}
+		if !d.IsDir() {
+			return nil
+		}
+		if path != dir && (d.Name() == ".git" || skip[path]) {
+			return filepath.SkipDir
+		}
+		return w.files.add(path)
+	})
+}
+
+func (w *repoWatcher) close() error {
+	return w.files.close()
+}
+
+// run collects events until they settle for watchDebounce, then reports
+// them if any is relevant
+func (w *repoWatcher) run() {
+	defer close(w.changes)
+
+	timer := time.NewTimer(watchDebounce)
+	timer.Stop()
+
+	pending := make(map[string]bool)
+	lost := false
+	for {
+		select {
+		case path, ok := <-w.files.events():
+			if !ok {
+				return
+			}
+			if path == "" {
+				lost = true
+			} else {
+				pending[path] = true
+			}
+			timer.Reset(watchDebounce)
+		case <-timer.C:
+			if lost || w.relevant(pending) {
+				select {
+				case w.changes <- struct{}{}:
+				default:
+				}
+			}
+			pending = make(map[string]bool)
+			lost = false
+		}
+	}
+}
+
+// relevant reports whether any of the changed paths affects the diff, and
+// starts watching directories created in the working tree
+func (w *repoWatcher) relevant(paths map[string]bool) bool {
+	changed := false
+	worktree := []string{}
+	for path := range paths {
+		switch {
+		case w.inGitDir(path):
+			changed = changed || w.relevantGitFile(path)
+		case isNestedGitPath(w.root, path):
+			// Submodules and nested repositories report through their own files
+		default:
+			if rel, err := filepath.Rel(w.root, path); err == nil {
+				worktree = append(worktree, filepath.ToSlash(rel))
+			}
+		}
+	}
+	if len(worktree) == 0 {
+		return changed
+	}
+
+	ignored, err := w.repo.IgnoredPaths(worktree)
+	if err != nil {
+		// Better to refetch once too often than to miss a change
+		return true
+	}
+	for _, rel := range worktree {
+		if ignored[rel] {
+			continue
+		}
+		changed = true
+		path := filepath.Join(w.root, filepath.FromSlash(rel))
+		if info, err := os.Lstat(path); err == nil && info.IsDir() {
+			_ = w.addTree(path, w.ignoredUnder(path))
+		}
+	}
+	return changed
+}
+
+// ignoredUnder lists the ignored directories inside a newly created one, so
+// that e.g. a fresh node_modules isn't watched
+func (w *repoWatcher) ignoredUnder(dir string) map[string]bool {
+	ignored, err := w.repo.IgnoredDirs()
+	if err != nil {
+		return nil
+	}
+	skip := make(map[string]bool)
+	for _, rel := range ignored {
+		path := filepath.Join(w.root, rel)
+		if strings.HasPrefix(path, dir+string(filepath.Separator)) || path == dir {
+			skip[path] = true
+		}
+	}
+	return skip
+}
+
+func (w *repoWatcher) inGitDir(path string) bool {
+	for _, dir := range []string{w.gitDir, w.commonDir} {
+		if path == dir || strings.HasPrefix(path, dir+string(filepath.Separator)) {
+			return true
+		}
+	}
+	return false
+}
+
+// relevantGitFile reports whether a change in the git directory moves HEAD,
+// the index or a ref. Lock files are written first and renamed into place,
+// so only the rename counts.
+func (w *repoWatcher) relevantGitFile(path string) bool {
+	if strings.HasSuffix(path, ".lock") {
+		return false
+	}
+	for _, dir := range []string{w.gitDir, w.commonDir} {
+		if strings.HasPrefix(path, filepath.Join(dir, "refs")+string(filepath.Separator)) {
+			return true
+		}
+		switch filepath.Base(path) {
+		case "HEAD", "index", "packed-refs":
+			if filepath.Dir(path) == dir {
+				return true
+			}
+		}
+	}
+	return false
+}
+
+// isNestedGitPath reports whether path is inside a .git directory below root
+func isNestedGitPath(root, path string) bool {
+	rel, err := filepath.Rel(root, path)
+	if err != nil {
+		return false
+	}
+	for _, part := range strings.Split(filepath.ToSlash(rel), "/") {
+		if part == ".git" {
+			return true
+		}
+	}
+	return false
+}
@@ -0,0 +1,274 @@
+//go:build darwin || dragonfly || freebsd || netbsd || openbsd
+
+package server
+
+import (
+	"errors"
+	"fmt"
+	"io/fs"
+	"os"
+	"path/filepath"
+	"sync"
+	"syscall"
+)
+
+const kqueueNotes = syscall.NOTE_WRITE | syscall.NOTE_EXTEND | syscall.NOTE_ATTRIB |
+	syscall.NOTE_DELETE | syscall.NOTE_RENAME
+
+// kqueueWatcher watches directories with kqueue. A directory's vnode only
+// reports that its entries changed, so the regular files in it are watched
+// too, and the entries are listed again to tell which ones came and went.
+type kqueueWatcher struct {
+	kq int
+	// stop is a pipe close writes to, waking up the goroutine waiting for
+	// events on its read end
+	stop [2]int
+	out  chan string
+
+	mu    sync.Mutex
+	paths map[int]string
+	fds   map[string]int
+	// dirs holds the entries of each watched directory as last listed
+	dirs   map[string]map[string]bool
+	closed bool
+}
+
+func newFileWatcher() (fileWatcher, error) {
+	kq, err := syscall.Kqueue()
+	if err != nil {
+		return nil, os.NewSyscallError("kqueue", err)
+	}
+	syscall.CloseOnExec(kq)
+
+	w := &kqueueWatcher{
+		kq:    kq,
+		out:   make(chan string, 64),
+		paths: make(map[int]string),
+		fds:   make(map[string]int),
+		dirs:  make(map[string]map[string]bool),
+	}
+	if err := syscall.Pipe(w.stop[:]); err != nil {
+		_ = syscall.Close(kq)
+		return nil, os.NewSyscallError("pipe", err)
+	}
+	syscall.CloseOnExec(w.stop[0])
+	syscall.CloseOnExec(w.stop[1])
+
+	var ev syscall.Kevent_t
+	syscall.SetKevent(&ev, w.stop[0], syscall.EVFILT_READ, syscall.EV_ADD)
+	if _, err := syscall.Kevent(kq, []syscall.Kevent_t{ev}, nil, nil); err != nil {
+		_ = syscall.Close(kq)
+		_ = syscall.Close(w.stop[0])
+		_ = syscall.Close(w.stop[1])
+		return nil, os.NewSyscallError("kevent", err)
+	}
+
+	go w.read()
+	return w, nil
+}
+
+func (w *kqueueWatcher) add(dir string) error {
+	watched, err := w.watch(dir, true)
+	if err != nil || !watched {
+		return err
+	}
+
+	entries, err := os.ReadDir(dir)
+	if err != nil {
+		// Removed before it could be listed; its parent reports that
+		if errors.Is(err, fs.ErrNotExist) {
+			return nil
+		}
+		return err
+	}
+	names := make(map[string]bool, len(entries))
+	for _, entry := range entries {
+		names[entry.Name()] = true
+		if entry.Type().IsRegular() {
+			if _, err := w.watch(filepath.Join(dir, entry.Name()), false); err != nil {
+				return err
+			}
+		}
+	}
+
+	w.mu.Lock()
+	defer w.mu.Unlock()
+	if _, ok := w.dirs[dir]; ok {
+		w.dirs[dir] = names
+	}
+	return nil
+}
+
+// watch opens path and registers it with the kqueue, reporting whether it
+// started watching it. Paths already watched or gone are left alone, and so
+// are files that can't be opened, since git can't read them either.
+func (w *kqueueWatcher) watch(path string, dir bool) (bool, error) {
+	w.mu.Lock()
+	defer w.mu.Unlock()
+	if _, ok := w.fds[path]; ok || w.closed {
+		return false, nil
+	}
+
+	// Without O_NONBLOCK, opening a FIFO that replaced a file would block
+	fd, err := syscall.Open(path, syscall.O_RDONLY|syscall.O_NONBLOCK|syscall.O_CLOEXEC, 0)
+	if err != nil {
+		if errors.Is(err, syscall.EMFILE) || errors.Is(err, syscall.ENFILE) {
+			return false, fmt.Errorf("too many files to watch, raise the open file limit: %w", err)
+		}
+		if errors.Is(err, syscall.ENOENT) || !dir {
+			return false, nil
+		}
+		return false, &os.PathError{Op: "open", Path: path, Err: err}
+	}
+
+	var ev syscall.Kevent_t
+	syscall.SetKevent(&ev, fd, syscall.EVFILT_VNODE, syscall.EV_ADD|syscall.EV_CLEAR)
+	ev.Fflags = kqueueNotes
+	if _, err := syscall.Kevent(w.kq, []syscall.Kevent_t{ev}, nil, nil); err != nil {
+		_ = syscall.Close(fd)
+		return false, os.NewSyscallError("kevent", err)
+	}
+
+	w.paths[fd] = path
+	w.fds[path] = fd
+	if dir {
+		w.dirs[path] = map[string]bool{}
+	}
+	return true, nil
+}
+
+// unwatch stops watching path. Closing the descriptor removes its events
+// from the kqueue.
+func (w *kqueueWatcher) unwatch(path string) {
+	w.mu.Lock()
+	defer w.mu.Unlock()
+	fd, ok := w.fds[path]
+	if !ok {
+		return
+	}
+	_ = syscall.Close(fd)
+	delete(w.paths, fd)
+	delete(w.fds, path)
+	delete(w.dirs, path)
+}
+
+func (w *kqueueWatcher) events() <-chan string {
+	return w.out
+}
+
+func (w *kqueueWatcher) close() error {
+	w.mu.Lock()
+	defer w.mu.Unlock()
+	// Once released, the pipe's descriptors may already belong to other files
+	if !w.closed {
+		_, _ = syscall.Write(w.stop[1], []byte{0})
+	}
+	return nil
+}
+
+func (w *kqueueWatcher) read() {
+	defer close(w.out)
+	defer w.release()
+
+	events := make([]syscall.Kevent_t, 64)
+	for {
+		n, err := syscall.Kevent(w.kq, nil, events, nil)
+		if errors.Is(err, syscall.EINTR) {
+			continue
+		}
+		if err != nil {
+			return
+		}
+		for _, ev := range events[:n] {
+			if int(ev.Ident) == w.stop[0] {
+				return
+			}
+			w.handle(int(ev.Ident), ev.Fflags)
+		}
+	}
+}
+
+// handle reports the paths an event on a watched descriptor changed
+func (w *kqueueWatcher) handle(fd int, notes uint32) {
+	w.mu.Lock()
+	path, ok := w.paths[fd]
+	_, dir := w.dirs[path]
+	w.mu.Unlock()
+	if !ok {
+		return
+	}
+
+	switch {
+	case notes&(syscall.NOTE_DELETE|syscall.NOTE_RENAME) != 0:
+		w.unwatch(path)
+		w.out <- path
+		// A file replaced by renaming another one over it, as git does with
+		// the index and refs, is watched again
+		if !dir {
+			_, _ = w.watch(path, false)
+		}
+	case dir && notes&syscall.NOTE_WRITE != 0:
+		for _, changed := range w.relist(path) {
+			w.out <- changed
+		}
+	case !dir:
+		w.out <- path
+	}
+}
+
+// relist lists a directory's entries again, watching the new files, and
+// returns the paths of the entries that were added or removed
+func (w *kqueueWatcher) relist(dir string) []string {
+	entries, err := os.ReadDir(dir)
+	if err != nil {
+		// Removed; the directory's own event reports that
+		return nil
+	}
+
+	w.mu.Lock()
+	previous := w.dirs[dir]
+	w.mu.Unlock()
+
+	var changed []string
+	names := make(map[string]bool, len(entries))
+	for _, entry := range entries {
+		names[entry.Name()] = true
+		path := filepath.Join(dir, entry.Name())
+		if !previous[entry.Name()] {
+			changed = append(changed, path)
+		}
+		if entry.Type().IsRegular() {
+			_, _ = w.watch(path, false)
+		}
+	}
+	for name := range previous {
+		if !names[name] {
+			path := filepath.Join(dir, name)
+			changed = append(changed, path)
+			w.unwatch(path)
+		}
+	}
+
+	w.mu.Lock()
+	defer w.mu.Unlock()
+	if _, ok := w.dirs[dir]; ok {
+		w.dirs[dir] = names
+	}
+	return changed
+}
+
+// release closes every descriptor once the watcher stopped
+func (w *kqueueWatcher) release() {
+	w.mu.Lock()
+	defer w.mu.Unlock()
+	w.closed = true
+	for fd := range w.paths {
+		_ = syscall.Close(fd)
+	}
+	w.paths = map[int]string{}
+	w.fds = map[string]int{}
+	w.dirs = map[string]map[string]bool{}
+	_ = syscall.Close(w.kq)
+	_ = syscall.Close(w.stop[0])
+	_ = syscall.Close(w.stop[1])
+}
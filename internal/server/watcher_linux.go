@@ -0,0 +1,107 @@
+//go:build linux
+
+package server
+
+import (
+	"encoding/binary"
+	"errors"
+	"fmt"
+	"os"
+	"path/filepath"
+	"strings"
+	"sync"
+	"syscall"
+)
+
+const inotifyMask = syscall.IN_CREATE | syscall.IN_DELETE | syscall.IN_MODIFY | syscall.IN_CLOSE_WRITE |
+	syscall.IN_MOVED_FROM | syscall.IN_MOVED_TO | syscall.IN_ATTRIB
+
+// inotifyWatcher watches directories with inotify
+type inotifyWatcher struct {
+	file *os.File
+	fd   int
+	out  chan string
+
+	mu   sync.Mutex
+	dirs map[int32]string
+}
+
+func newFileWatcher() (fileWatcher, error) {
+	fd, err := syscall.InotifyInit1(syscall.IN_CLOEXEC | syscall.IN_NONBLOCK)
+	if err != nil {
+		return nil, os.NewSyscallError("inotify_init1", err)
+	}
+	// A non-blocking descriptor goes through the runtime poller, so closing
+	// the file interrupts a pending read
+	w := &inotifyWatcher{
+		file: os.NewFile(uintptr(fd), "inotify"),
+		fd:   fd,
+		out:  make(chan string, 64),
+		dirs: make(map[int32]string),
+	}
+	go w.read()
+	return w, nil
+}
+
+func (w *inotifyWatcher) add(dir string) error {
+	wd, err := syscall.InotifyAddWatch(w.fd, dir, inotifyMask|syscall.IN_ONLYDIR)
+	if err != nil {
+		// Removed before it could be watched; its parent reports that
+		if errors.Is(err, syscall.ENOENT) {
+			return nil
+		}
+		if errors.Is(err, syscall.ENOSPC) {
+			return fmt.Errorf("too many directories to watch, raise fs.inotify.max_user_watches: %w", err)
+		}
+		return &os.PathError{Op: "inotify_add_watch", Path: dir, Err: err}
+	}
+	w.mu.Lock()
+	defer w.mu.Unlock()
+	w.dirs[int32(wd)] = dir
+	return nil
+}
+
+func (w *inotifyWatcher) events() <-chan string {
+	return w.out
+}
+
+func (w *inotifyWatcher) close() error {
+	return w.file.Close()
+}
+
+func (w *inotifyWatcher) read() {
+	defer close(w.out)
+
+	// Room for 64 events with names of up to 255 bytes
+	buf := make([]byte, 64*(syscall.SizeofInotifyEvent+256))
+	for {
+		n, err := w.file.Read(buf)
+		if err != nil {
+			return
+		}
+		for offset := 0; offset+syscall.SizeofInotifyEvent <= n; {
+			wd := int32(binary.NativeEndian.Uint32(buf[offset:]))
+			mask := binary.NativeEndian.Uint32(buf[offset+4:])
+			nameLen := int(binary.NativeEndian.Uint32(buf[offset+12:]))
+			start := offset + syscall.SizeofInotifyEvent
+			name := strings.TrimRight(string(buf[start:start+nameLen]), "\x00")
+			offset = start + nameLen
+
+			if mask&syscall.IN_Q_OVERFLOW != 0 {
+				w.out <- ""
+				continue
+			}
+
+			w.mu.Lock()
+			dir, ok := w.dirs[wd]
+			if mask&syscall.IN_IGNORED != 0 {
+				delete(w.dirs, wd)
+			}
+			w.mu.Unlock()
+			if !ok || mask&syscall.IN_IGNORED != 0 {
+				continue
+			}
+			w.out <- filepath.Join(dir, name)
+		}
+	}
+}
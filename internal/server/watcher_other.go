@@ -0,0 +1,7 @@
+//go:build !linux && !darwin && !dragonfly && !freebsd && !netbsd && !openbsd
+
+package server
+
+func newFileWatcher() (fileWatcher, error) {
+	return nil, errWatchUnsupported
+}
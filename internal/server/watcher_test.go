@@ -0,0 +1,112 @@
+package server
+
+import (
+	"errors"
+	"os"
+	"os/exec"
+	"path/filepath"
+	"testing"
+	"time"
+)
+
+func TestRepoWatcher(t *testing.T) {
+	dir := t.TempDir()
+	git := func(args ...string) {
+		t.Helper()
+		cmd := exec.Command("git", args...)
+		cmd.Dir = dir
+		if output, err := cmd.CombinedOutput(); err != nil {
+			t.Fatalf("git %v failed: %v\n%s", args, err, output)
+		}
+	}
+	write := func(name, content string) {
+		t.Helper()
+		path := filepath.Join(dir, name)
+		if err := os.MkdirAll(filepath.Dir(path), 0755); err != nil {
+			t.Fatalf("Failed to create directory: %v", err)
+		}
+		if err := os.WriteFile(path, []byte(content), 0644); err != nil {
+			t.Fatalf("Failed to write %s: %v", name, err)
+		}
+	}
+
+	git("init")
+	git("config", "user.email", "test@test.com")
+	git("config", "user.name", "Test User")
+	write(".gitignore", "build/\n")
+	write("README.md", "# Test\n")
+	write("build/out.txt", "old\n")
+	git("add", ".")
+	git("commit", "-m", "Initial commit")
+
+	w, err := newRepoWatcher(dir)
+	if errors.Is(err, errWatchUnsupported) {
+		t.Skip(err)
+	}
+	if err != nil {
+		t.Fatalf("Failed to watch repository: %v", err)
+	}
+	defer func() { _ = w.close() }()
+
+	expectChange := func(what string) {
+		t.Helper()
+		select {
+		case <-w.changes:
+		case <-time.After(5 * time.Second):
+			t.Fatalf("Expected %s to be reported", what)
+		}
+	}
+	expectQuiet := func(what string) {
+		t.Helper()
+		select {
+		case <-w.changes:
+			t.Fatalf("Expected %s not to be reported", what)
+		case <-time.After(3 * watchDebounce):
+		}
+	}
+
+	write("README.md", "# Changed\n")
+	expectChange("an edited file")
+
+	write("build/out.txt", "new\n")
+	write("build/more/out.txt", "new\n")
+	write("debug/trace.txt", "ignored\n")
+	expectChange("a new directory")
+
+	// Files in a directory created after the watcher started are watched too
+	write("debug/trace.txt", "changed\n")
+	expectChange("a file in a new directory")
+
+	write(".gitignore", "build/\ndebug/\n")
+	expectChange("an edited .gitignore")
+	write("debug/trace.txt", "ignored now\n")
+	expectQuiet("an ignored file")
+
+	git("add", "README.md")
+	expectChange("a staged file")
+
+	git("commit", "-m", "Second commit")
+	expectChange("a commit")
+}
+
+func TestRelevantGitFile(t *testing.T) {
+	w := &repoWatcher{gitDir: "/repo/.git", commonDir: "/repo/.git"}
+	tests := []struct {
+		path     string
+		relevant bool
+	}{
+		{path: "/repo/.git/HEAD", relevant: true},
+		{path: "/repo/.git/index", relevant: true},
+		{path: "/repo/.git/index.lock", relevant: false},
+		{path: "/repo/.git/packed-refs", relevant: true},
+		{path: "/repo/.git/refs/heads/main", relevant: true},
+		{path: "/repo/.git/refs/heads/main.lock", relevant: false},
+		{path: "/repo/.git/FETCH_HEAD", relevant: false},
+		{path: "/repo/.git/logs/HEAD", relevant: false},
+	}
+	for _, tt := range tests {
+		if got := w.relevantGitFile(tt.path); got != tt.relevant {
+			t.Errorf("%s: expected relevant=%t, got %t", tt.path, tt.relevant, got)
+		}
+	}
+}
@@ -0,0 +1,182 @@
+package state
+
+import (
+	"crypto/sha256"
+	"encoding/hex"
+	"encoding/json"
+	"errors"
+	"fmt"
+	"io/fs"
+	"os"
+	"path/filepath"
+	"time"
+
+	"github.com/tuist/guck/internal/atomicfile"
+)
+
+// resolvedArchiveFileName is the name of the per-repository file that resolved
+// comments are moved to by ArchiveResolved
+const resolvedArchiveFileName = "resolved-archive.json"
+
+// resolvedArchive holds the archived resolved comments of one repository
+type resolvedArchive struct {
+	RepoPath string     `json:"repo_path"`
+	Comments []*Comment `json:"comments"`
+}
+
+// repoDir returns the directory next to the state file holding a repository's
+// own files, named after a hash of the repository path
+func (m *Manager) repoDir(repoPath string) string {
+	sum := sha256.Sum256([]byte(repoPath))
+	return filepath.Join(filepath.Dir(m.stateFile), "archive", hex.EncodeToString(sum[:8]))
+}
+
+// archivePath returns where the resolved comments of a repository are archived
+func (m *Manager) archivePath(repoPath string) string {
+	return filepath.Join(m.repoDir(repoPath), resolvedArchiveFileName)
+}
+
+func (m *Manager) loadArchive(repoPath string) (*resolvedArchive, error) {
+	archive := &resolvedArchive{RepoPath: repoPath, Comments: []*Comment{}}
+
+	data, err := os.ReadFile(m.archivePath(repoPath))
+	if errors.Is(err, fs.ErrNotExist) {
+		return archive, nil
+	}
+	if err != nil {
+		return nil, fmt.Errorf("failed to read resolved comment archive: %w", err)
+	}
+	if err := json.Unmarshal(data, archive); err != nil {
+		return nil, fmt.Errorf("failed to parse resolved comment archive: %w", err)
+	}
+	return archive, nil
+}
+
+func (m *Manager) saveArchive(archive *resolvedArchive) error {
+	archiveFile := m.archivePath(archive.RepoPath)
+	if err := os.MkdirAll(filepath.Dir(archiveFile), 0755); err != nil {
+		return fmt.Errorf("failed to create archive directory: %w", err)
+	}
+
+	data, err := json.MarshalIndent(archive, "", "  ")
+	if err != nil {
+		return fmt.Errorf("failed to serialize resolved comment archive: %w", err)
+	}
+
+	unlock, err := lockStateFile(archiveFile)
+	if err != nil {
+		return err
+	}
+	defer unlock()
+
+	if err := atomicfile.Write(archiveFile, data); err != nil {
+		return fmt.Errorf("failed to write resolved comment archive: %w", err)
+	}
+	return nil
+}
+
+// ArchiveResolved moves the comments of a repository that were resolved more
+// than olderThan ago out of the shared state file into the repository's
+// resolved-archive.json, so the file rewritten on every change stays small.
+// Pinned comments are kept. Returns the number of comments archived.
+func (m *Manager) ArchiveResolved(repoPath string, olderThan time.Duration) (int, error) {
+	unlock, err := m.lock()
+	if err != nil {
+		return 0, err
+	}
+	defer unlock()
+
+	branches, ok := m.state.Repos[repoPath]
+	if !ok {
+		return 0, nil
+	}
+
+	cutoff := time.Now().Add(-olderThan).Unix()
+	var stale []*Comment
+	for _, commits := range branches {
+		for _, repoState := range commits {
+			archived := make(map[string]bool)
+			for _, comment := range repoState.Comments {
+				if comment.Resolved && !comment.Pinned && comment.ResolvedAt < cutoff {
+					archived[comment.ID] = true
+				}
+			}
+			// Replies go with their thread
+			kept := make([]*Comment, 0, len(repoState.Comments))
+			for _, comment := range repoState.Comments {
+				if archived[comment.ID] || archived[comment.ParentID] {
+					stale = append(stale, comment)
+				} else {
+					kept = append(kept, comment)
+				}
+			}
+			repoState.Comments = kept
+		}
+	}
+
+	if len(stale) == 0 {
+		return 0, nil
+	}
+
+	archive, err := m.loadArchive(repoPath)
+	if err != nil {
+		return 0, err
+	}
+	archive.Comments = MergeArchived(archive.Comments, stale)
+
+	// Write the archive first: if saving the state fails afterwards the
+	// comments are in both files, which MergeArchived tolerates
+	if err := m.saveArchive(archive); err != nil {
+		return 0, err
+	}
+	return len(stale), m.save()
+}
+
+// GetArchivedComments returns the archived resolved comments of a branch and
+// commit, optionally limited to one file
+func (m *Manager) GetArchivedComments(repoPath, branch, commit string, filePath *string) ([]*Comment, error) {
+	commit = m.commitKey(repoPath, branch, commit)
+	archive, err := m.loadArchive(repoPath)
+	if err != nil {
+		return nil, err
+	}
+
+	filtered := []*Comment{}
+	for _, comment := range archive.Comments {
+		if comment.Branch != branch || comment.Commit != commit {
+			continue
+		}
+		if filePath != nil && comment.FilePath != *filePath {
+			continue
+		}
+		filtered = append(filtered, comment)
+	}
+	return filtered, nil
+}
+
+// GetAllArchivedComments returns every archived resolved comment of a repository
+func (m *Manager) GetAllArchivedComments(repoPath string) ([]*Comment, error) {
+	archive, err := m.loadArchive(repoPath)
+	if err != nil {
+		return nil, err
+	}
+	return archive.Comments, nil
+}
+
+// MergeArchived appends archived comments to comments, skipping any whose ID
+// is already present
+func MergeArchived(comments, archived []*Comment) []*Comment {
+	seen := make(map[string]bool, len(comments))
+	for _, comment := range comments {
+		seen[comment.ID] = true
+	}
+
+	merged := append([]*Comment{}, comments...)
+	for _, comment := range archived {
+		if !seen[comment.ID] {
+			seen[comment.ID] = true
+			merged = append(merged, comment)
+		}
+	}
+	return merged
+}
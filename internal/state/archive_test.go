@@ -0,0 +1,64 @@
+package state
+
+import (
+	"os"
+	"testing"
+	"time"
+)
+
+func TestArchiveResolved(t *testing.T) {
+	manager, _ := setupTestManager(t)
+
+	old, _ := manager.AddComment("/repo", "main", "abc", "a.go", nil, "Old")
+	recent, _ := manager.AddComment("/repo", "main", "abc", "a.go", nil, "Recent")
+	pinned, _ := manager.AddComment("/repo", "main", "abc", "b.go", nil, "Pinned")
+	_, _ = manager.AddComment("/repo", "main", "abc", "b.go", nil, "Open")
+	for _, comment := range []*Comment{old, recent, pinned} {
+		_ = manager.ResolveComment("/repo", "main", "abc", comment.ID, "alice")
+	}
+	_ = manager.SetCommentPinned("/repo", "main", "abc", pinned.ID, true)
+	old.ResolvedAt = time.Now().Add(-48 * time.Hour).Unix()
+	pinned.ResolvedAt = old.ResolvedAt
+
+	archived, err := manager.ArchiveResolved("/repo", 24*time.Hour)
+	if err != nil {
+		t.Fatalf("Failed to archive resolved comments: %v", err)
+	}
+	if archived != 1 {
+		t.Fatalf("Expected 1 comment archived, got %d", archived)
+	}
+
+	live := manager.GetComments("/repo", "main", "abc", nil)
+	if len(live) != 3 {
+		t.Fatalf("Expected 3 live comments, got %d", len(live))
+	}
+	for _, comment := range live {
+		if comment.ID == old.ID {
+			t.Error("Expected the old resolved comment to leave the live state")
+		}
+	}
+	if _, err := os.Stat(manager.archivePath("/repo")); err != nil {
+		t.Errorf("Expected the archive file to be written: %v", err)
+	}
+
+	filePath := "a.go"
+	fromArchive, err := manager.GetArchivedComments("/repo", "main", "abc", &filePath)
+	if err != nil {
+		t.Fatalf("Failed to read archive: %v", err)
+	}
+	if len(fromArchive) != 1 || fromArchive[0].ID != old.ID || fromArchive[0].ResolvedBy != "alice" {
+		t.Errorf("Expected the old comment in the archive, got %+v", fromArchive)
+	}
+	if other, _ := manager.GetArchivedComments("/repo", "main", "other", nil); len(other) != 0 {
+		t.Errorf("Expected no archived comments for another commit, got %d", len(other))
+	}
+
+	merged := MergeArchived(live, append(fromArchive, recent))
+	if len(merged) != 4 {
+		t.Errorf("Expected duplicates to be skipped when merging, got %d comments", len(merged))
+	}
+
+	if archived, _ := manager.ArchiveResolved("/repo", 24*time.Hour); archived != 0 {
+		t.Errorf("Expected nothing left to archive, got %d", archived)
+	}
+}
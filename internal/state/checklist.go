@@ -0,0 +1,70 @@
+package state
+
+// ChecklistItem is one item of the configured review checklist and whether
+// the reviewer ticked it
+type ChecklistItem struct {
+	Item    string `json:"item"`
+	Checked bool   `json:"checked"`
+}
+
+// ChecklistProgress is how much of the review checklist is ticked
+type ChecklistProgress struct {
+	Checked int             `json:"checked"`
+	Total   int             `json:"total"`
+	Items   []ChecklistItem `json:"items"`
+}
+
+// Complete reports whether every item is ticked. An empty checklist is never
+// complete, so it isn't mistaken for a finished review.
+func (p ChecklistProgress) Complete() bool {
+	return p.Total > 0 && p.Checked == p.Total
+}
+
+// SetChecklistItem ticks or unticks an item of a review's checklist. Items
+// are keyed by their text, like viewed files are by path.
+func (m *Manager) SetChecklistItem(repoPath, branch, commit, item string, checked bool) error {
+	unlock, err := m.lock()
+	if err != nil {
+		return err
+	}
+	defer unlock()
+
+	commit = m.commitKey(repoPath, branch, commit)
+	repoState := m.repoState(repoPath, branch, commit)
+	action := EventChecklistChecked
+	if checked {
+		if repoState.Checklist == nil {
+			repoState.Checklist = make(map[string]bool)
+		}
+		repoState.Checklist[item] = true
+	} else {
+		delete(repoState.Checklist, item)
+		action = EventChecklistUnchecked
+	}
+	touchReview(repoState, "")
+	return m.saveWithEvents(repoPath, Event{Action: action, Branch: branch, Commit: commit, Item: item})
+}
+
+// GetChecklistProgress matches a review's ticked items against the
+// configured checklist items. Ticked items no longer configured are ignored.
+func (m *Manager) GetChecklistProgress(repoPath, branch, commit string, items []string) ChecklistProgress {
+	commit = m.commitKey(repoPath, branch, commit)
+	var checklist map[string]bool
+	if branches, ok := m.state.Repos[repoPath]; ok {
+		if commits, ok := branches[branch]; ok {
+			if repoState, ok := commits[commit]; ok {
+				checklist = repoState.Checklist
+			}
+		}
+	}
+
+	progress := ChecklistProgress{Total: len(items), Items: make([]ChecklistItem, 0, len(items))}
+	for _, item := range items {
+		checked := checklist[item]
+		if checked {
+			progress.Checked++
+		}
+		progress.Items = append(progress.Items, ChecklistItem{Item: item, Checked: checked})
+	}
+	return progress
+}
@@ -0,0 +1,54 @@
+package state
+
+import "testing"
+
+func TestChecklist(t *testing.T) {
+	manager, _ := setupTestManager(t)
+
+	repoPath := "/test/repo"
+	items := []string{"Tests added?", "Docs updated?"}
+	progress := manager.GetChecklistProgress(repoPath, "main", "abc123", items)
+	if progress.Checked != 0 || progress.Total != 2 || progress.Complete() {
+		t.Errorf("Expected nothing ticked initially, got %+v", progress)
+	}
+
+	if err := manager.SetChecklistItem(repoPath, "main", "abc123", "Tests added?", true); err != nil {
+		t.Fatalf("Failed to tick item: %v", err)
+	}
+	// An item that is no longer configured doesn't count
+	if err := manager.SetChecklistItem(repoPath, "main", "abc123", "Changelog?", true); err != nil {
+		t.Fatalf("Failed to tick item: %v", err)
+	}
+	progress = manager.GetChecklistProgress(repoPath, "main", "abc123", items)
+	if progress.Checked != 1 || !progress.Items[0].Checked || progress.Items[1].Checked {
+		t.Errorf("Expected only the first item ticked, got %+v", progress)
+	}
+	if other := manager.GetChecklistProgress(repoPath, "main", "def456", items); other.Checked != 0 {
+		t.Errorf("Expected checklists to be per commit, got %+v", other)
+	}
+	if !manager.HasReviewState(repoPath, "main", "abc123") {
+		t.Error("Expected a ticked item to count as review state")
+	}
+
+	if err := manager.SetChecklistItem(repoPath, "main", "abc123", "Docs updated?", true); err != nil {
+		t.Fatalf("Failed to tick item: %v", err)
+	}
+	if progress := manager.GetChecklistProgress(repoPath, "main", "abc123", items); !progress.Complete() {
+		t.Errorf("Expected the checklist to be complete, got %+v", progress)
+	}
+
+	// Ticked items follow the review when it's carried to an amended commit
+	if _, _, err := manager.CarryForward(repoPath, "main", "abc123", "fed321"); err != nil {
+		t.Fatalf("Failed to carry forward: %v", err)
+	}
+	if progress := manager.GetChecklistProgress(repoPath, "main", "fed321", items); !progress.Complete() {
+		t.Errorf("Expected the checklist to be carried forward, got %+v", progress)
+	}
+
+	if err := manager.SetChecklistItem(repoPath, "main", "fed321", "Tests added?", false); err != nil {
+		t.Fatalf("Failed to untick item: %v", err)
+	}
+	if progress := manager.GetChecklistProgress(repoPath, "main", "fed321", items); progress.Checked != 1 || progress.Items[0].Checked {
+		t.Errorf("Expected the first item to be unticked, got %+v", progress)
+	}
+}
@@ -0,0 +1,141 @@
+package state
+
+import (
+	"bufio"
+	"encoding/json"
+	"errors"
+	"fmt"
+	"io/fs"
+	"os"
+	"path/filepath"
+	"time"
+)
+
+// eventsFileName is the name of the per-repository log of review actions
+const eventsFileName = "events.log"
+
+// Actions recorded in the events log
+const (
+	EventFileViewed          = "file_viewed"
+	EventFileUnviewed        = "file_unviewed"
+	EventCommentAdded        = "comment_added"
+	EventCommentEdited       = "comment_edited"
+	EventCommentDeleted      = "comment_deleted"
+	EventCommentResolved     = "comment_resolved"
+	EventCommentAcknowledged = "comment_acknowledged"
+	EventCommentPinned       = "comment_pinned"
+	EventCommentUnpinned     = "comment_unpinned"
+	EventNoteAdded           = "note_added"
+	EventNoteDismissed       = "note_dismissed"
+	EventSummarySet          = "summary_set"
+	EventChecklistChecked    = "checklist_checked"
+	EventChecklistUnchecked  = "checklist_unchecked"
+)
+
+// Event is one review action in a repository's events log. Commit is the key
+// the review state is stored under, so working-tree and commit reviews carry
+// their prefix and reviews kept per branch have BranchReviewKey.
+type Event struct {
+	Time   int64  `json:"time"`
+	Action string `json:"action"`
+	// Actor is who acted, when the action names them, such as a comment's author
+	Actor  string `json:"actor,omitempty"`
+	Branch string `json:"branch"`
+	Commit string `json:"commit"`
+	// FilePath is the file viewed or unviewed, or the one commented on
+	FilePath string `json:"file_path,omitempty"`
+	// ID is the comment or note acted on
+	ID string `json:"id,omitempty"`
+	// Item is the checklist item ticked or unticked
+	Item string `json:"item,omitempty"`
+}
+
+// eventsPath returns where the events of a repository are logged, next to its
+// resolved comment archive
+func (m *Manager) eventsPath(repoPath string) string {
+	return filepath.Join(m.repoDir(repoPath), eventsFileName)
+}
+
+// saveWithEvents saves the state and then appends events to the repository's
+// events log, so the log only records changes that were persisted. The log
+// is append-only: entries are never rewritten or pruned. Once the state is
+// saved the change succeeded, so failing to log it is only warned about;
+// reporting it as failed would have callers retry and apply it twice.
+func (m *Manager) saveWithEvents(repoPath string, events ...Event) error {
+	if err := m.save(); err != nil {
+		return err
+	}
+	if err := m.appendEvents(repoPath, events); err != nil {
+		fmt.Fprintf(os.Stderr, "Warning: failed to log review events for %s: %v\n", repoPath, err)
+	}
+	return nil
+}
+
+func (m *Manager) appendEvents(repoPath string, events []Event) error {
+	if len(events) == 0 {
+		return nil
+	}
+
+	now := time.Now().Unix()
+	var data []byte
+	for _, event := range events {
+		event.Time = now
+		line, err := json.Marshal(event)
+		if err != nil {
+			return fmt.Errorf("failed to serialize event: %w", err)
+		}
+		data = append(append(data, line...), '\n')
+	}
+
+	eventsFile := m.eventsPath(repoPath)
+	if err := os.MkdirAll(filepath.Dir(eventsFile), 0755); err != nil {
+		return fmt.Errorf("failed to create events directory: %w", err)
+	}
+	unlock, err := lockStateFile(eventsFile)
+	if err != nil {
+		return err
+	}
+	defer unlock()
+
+	f, err := os.OpenFile(eventsFile, os.O_CREATE|os.O_APPEND|os.O_WRONLY, 0644)
+	if err != nil {
+		return fmt.Errorf("failed to open events log: %w", err)
+	}
+	if _, err := f.Write(data); err != nil {
+		_ = f.Close()
+		return fmt.Errorf("failed to write events log: %w", err)
+	}
+	return f.Close()
+}
+
+// Events returns the logged events of a repository at or after since, a Unix
+// timestamp, oldest first. A since of 0 returns every event. Lines that don't
+// parse, such as one cut short by a crash, are skipped.
+func (m *Manager) Events(repoPath string, since int64) ([]Event, error) {
+	f, err := os.Open(m.eventsPath(repoPath))
+	if errors.Is(err, fs.ErrNotExist) {
+		return []Event{}, nil
+	}
+	if err != nil {
+		return nil, fmt.Errorf("failed to read events log: %w", err)
+	}
+	defer f.Close()
+
+	events := []Event{}
+	scanner := bufio.NewScanner(f)
+	// Comment paths and checklist items are short, but don't choke on long ones
+	scanner.Buffer(make([]byte, 0, 64*1024), 1024*1024)
+	for scanner.Scan() {
+		var event Event
+		if err := json.Unmarshal(scanner.Bytes(), &event); err != nil {
+			continue
+		}
+		if event.Time >= since {
+			events = append(events, event)
+		}
+	}
+	if err := scanner.Err(); err != nil {
+		return nil, fmt.Errorf("failed to read events log: %w", err)
+	}
+	return events, nil
+}
@@ -0,0 +1,114 @@
+package state
+
+import (
+	"os"
+	"strings"
+	"testing"
+)
+
+func TestEvents(t *testing.T) {
+	manager, _ := setupTestManager(t)
+
+	repoPath := "/test/repo"
+	events, err := manager.Events(repoPath, 0)
+	if err != nil {
+		t.Fatalf("Failed to read events: %v", err)
+	}
+	if len(events) != 0 {
+		t.Fatalf("Expected no events before any action, got %+v", events)
+	}
+
+	if err := manager.MarkFileViewed(repoPath, "main", "abc123", "file.go"); err != nil {
+		t.Fatalf("Failed to mark file viewed: %v", err)
+	}
+	comment, err := manager.AddCommentWithAuthor(repoPath, "main", "abc123", "file.go", nil, "Looks off", "alice")
+	if err != nil {
+		t.Fatalf("Failed to add comment: %v", err)
+	}
+	if err := manager.ResolveComment(repoPath, "main", "abc123", comment.ID, "bob"); err != nil {
+		t.Fatalf("Failed to resolve comment: %v", err)
+	}
+	if err := manager.UnmarkFileViewed(repoPath, "main", "abc123", "file.go"); err != nil {
+		t.Fatalf("Failed to unmark file: %v", err)
+	}
+	// Other repositories keep their own log
+	if err := manager.MarkFileViewed("/test/other", "main", "abc123", "file.go"); err != nil {
+		t.Fatalf("Failed to mark file viewed: %v", err)
+	}
+
+	events, err = manager.Events(repoPath, 0)
+	if err != nil {
+		t.Fatalf("Failed to read events: %v", err)
+	}
+	want := []Event{
+		{Action: EventFileViewed, Branch: "main", Commit: "abc123", FilePath: "file.go"},
+		{Action: EventCommentAdded, Actor: "alice", Branch: "main", Commit: "abc123", FilePath: "file.go", ID: comment.ID},
+		{Action: EventCommentResolved, Actor: "bob", Branch: "main", Commit: "abc123", FilePath: "file.go", ID: comment.ID},
+		{Action: EventFileUnviewed, Branch: "main", Commit: "abc123", FilePath: "file.go"},
+	}
+	if len(events) != len(want) {
+		t.Fatalf("Expected %d events, got %+v", len(want), events)
+	}
+	for i, event := range events {
+		if event.Time == 0 {
+			t.Errorf("Expected event %d to be timestamped", i)
+		}
+		event.Time = 0
+		if event != want[i] {
+			t.Errorf("Event %d: expected %+v, got %+v", i, want[i], event)
+		}
+	}
+
+	if future, err := manager.Events(repoPath, events[len(events)-1].Time+1); err != nil || len(future) != 0 {
+		t.Errorf("Expected no events after the last one, got %+v (%v)", future, err)
+	}
+}
+
+func TestEventsSkipsMalformedLines(t *testing.T) {
+	manager, _ := setupTestManager(t)
+
+	repoPath := "/test/repo"
+	if err := manager.MarkFileViewed(repoPath, "main", "abc123", "file.go"); err != nil {
+		t.Fatalf("Failed to mark file viewed: %v", err)
+	}
+
+	// A write cut short leaves a partial line at the end of the log
+	f, err := os.OpenFile(manager.eventsPath(repoPath), os.O_APPEND|os.O_WRONLY, 0644)
+	if err != nil {
+		t.Fatalf("Failed to open events log: %v", err)
+	}
+	if _, err := f.WriteString(`{"time":1,"act`); err != nil {
+		t.Fatalf("Failed to write events log: %v", err)
+	}
+	_ = f.Close()
+
+	events, err := manager.Events(repoPath, 0)
+	if err != nil {
+		t.Fatalf("Failed to read events: %v", err)
+	}
+	if len(events) != 1 || events[0].Action != EventFileViewed {
+		t.Errorf("Expected the malformed line to be skipped, got %+v", events)
+	}
+}
+
+func TestSaveSucceedsWhenEventsLogFails(t *testing.T) {
+	manager, _ := setupTestManager(t)
+
+	repoPath := "/test/repo"
+	// A directory in place of the log makes appending to it fail
+	if err := os.MkdirAll(manager.eventsPath(repoPath), 0755); err != nil {
+		t.Fatalf("Failed to create directory: %v", err)
+	}
+
+	if _, err := manager.AddComment(repoPath, "main", "abc123", "file.go", nil, "Persisted"); err != nil {
+		t.Fatalf("Expected a persisted change to succeed despite the events log, got %v", err)
+	}
+
+	data, err := os.ReadFile(manager.stateFile)
+	if err != nil {
+		t.Fatalf("Failed to read state file: %v", err)
+	}
+	if !strings.Contains(string(data), "Persisted") {
+		t.Error("Expected the comment to be saved")
+	}
+}
@@ -0,0 +1,137 @@
+package state
+
+import (
+	"fmt"
+	"sort"
+	"strings"
+)
+
+// Granularity decides what a repository's branch review state is keyed by
+type Granularity string
+
+const (
+	// GranularityCommit keeps review state per commit, so every new commit
+	// starts a fresh review. This is the default.
+	GranularityCommit Granularity = "commit"
+	// GranularityBranch keeps review state per branch: viewed files and
+	// comments survive new commits, even when a viewed file changes again
+	GranularityBranch Granularity = "branch"
+	// GranularityContent keeps review state per branch like GranularityBranch,
+	// but a viewed file only stays viewed while its patch is the one reviewed
+	GranularityContent Granularity = "content"
+)
+
+// BranchReviewKey is the commit key a branch's review state is stored under
+// with branch or content granularity
+const BranchReviewKey = "__branch__"
+
+// ParseGranularity validates a granularity name. An empty name is the default.
+func ParseGranularity(name string) (Granularity, error) {
+	switch granularity := Granularity(name); granularity {
+	case "":
+		return GranularityCommit, nil
+	case GranularityCommit, GranularityBranch, GranularityContent:
+		return granularity, nil
+	}
+	return "", fmt.Errorf("unknown state granularity '%s' (expected commit, branch or content)", name)
+}
+
+// Granularity returns the granularity a repository's review state is kept at
+func (m *Manager) Granularity(repoPath string) Granularity {
+	if granularity, ok := m.state.Granularities[repoPath]; ok {
+		return granularity
+	}
+	return GranularityCommit
+}
+
+// commitKey returns the key the review state of a commit is stored under.
+// Synthetic keys, such as working-tree reviews, patch file reviews and
+// detached HEADs, which have no branch to key by, are always kept per commit.
+func (m *Manager) commitKey(repoPath, branch, commit string) string {
+	if m.Granularity(repoPath) == GranularityCommit || strings.HasPrefix(commit, "__") ||
+		strings.HasPrefix(branch, "__") || branch == "HEAD" {
+		return commit
+	}
+	return BranchReviewKey
+}
+
+// SetGranularity switches the granularity of a repository's review state,
+// migrating what is stored. Moving from per-commit to per-branch state folds
+// every commit's viewed files, comments and notes of a branch into one, the
+// most recently worked on commit winning where they disagree. Moving back
+// moves each branch's state onto the commit head returns for the branch;
+// state of branches head returns no commit for is left for Prune. It reports
+// whether anything changed.
+func (m *Manager) SetGranularity(repoPath string, granularity Granularity, head func(branch string) string) (bool, error) {
+	unlock, err := m.lock()
+	if err != nil {
+		return false, err
+	}
+	defer unlock()
+
+	previous := m.Granularity(repoPath)
+	if granularity == previous {
+		return false, nil
+	}
+
+	perBranch := func(g Granularity) bool { return g != GranularityCommit }
+	for branch, commits := range m.state.Repos[repoPath] {
+		if strings.HasPrefix(branch, "__") || branch == "HEAD" {
+			continue
+		}
+
+		switch {
+		case perBranch(granularity) && !perBranch(previous):
+			var keys []string
+			for commit := range commits {
+				if !strings.HasPrefix(commit, "__") {
+					keys = append(keys, commit)
+				}
+			}
+			// Newest first, since foldState keeps what the target already has
+			sort.Slice(keys, func(i, j int) bool {
+				a, b := commits[keys[i]], commits[keys[j]]
+				if a.ReviewLastActivityAt != b.ReviewLastActivityAt {
+					return a.ReviewLastActivityAt > b.ReviewLastActivityAt
+				}
+				return keys[i] < keys[j]
+			})
+			for _, commit := range keys {
+				foldState(commits[commit], m.repoState(repoPath, branch, BranchReviewKey), BranchReviewKey)
+				delete(commits, commit)
+			}
+		case !perBranch(granularity) && perBranch(previous):
+			from, ok := commits[BranchReviewKey]
+			commit := head(branch)
+			if !ok || commit == "" {
+				continue
+			}
+			foldState(from, m.repoState(repoPath, branch, commit), commit)
+			delete(commits, BranchReviewKey)
+		}
+	}
+
+	if m.state.Granularities == nil {
+		m.state.Granularities = make(map[string]Granularity)
+	}
+	if granularity == GranularityCommit {
+		delete(m.state.Granularities, repoPath)
+	} else {
+		m.state.Granularities[repoPath] = granularity
+	}
+	return true, m.save()
+}
+
+// foldState moves everything one review state holds onto another, whose
+// commit key is toCommit, leaving it empty
+func foldState(from, to *RepoState, toCommit string) {
+	carryState(from, to, toCommit)
+	for _, note := range from.Notes {
+		note.Commit = toCommit
+		to.Notes = append(to.Notes, note)
+	}
+	from.Notes = []*Note{}
+	to.DeletedComments = append(to.DeletedComments, from.DeletedComments...)
+	from.DeletedComments = nil
+	to.ReviewedAt = max(to.ReviewedAt, from.ReviewedAt)
+}
@@ -0,0 +1,65 @@
+package state
+
+import "testing"
+
+func TestSetGranularity(t *testing.T) {
+	manager, _ := setupTestManager(t)
+	repoPath := "/test/repo"
+
+	if err := manager.MarkFileViewedWithHash(repoPath, "main", "old", "a.go", "hash-a"); err != nil {
+		t.Fatalf("Failed to mark file as viewed: %v", err)
+	}
+	if _, err := manager.AddComment(repoPath, "main", "old", "a.go", nil, "On the old commit"); err != nil {
+		t.Fatalf("Failed to add comment: %v", err)
+	}
+	if err := manager.MarkFileViewed(repoPath, "main", "__working__:old", "b.go"); err != nil {
+		t.Fatalf("Failed to mark file as viewed: %v", err)
+	}
+
+	changed, err := manager.SetGranularity(repoPath, GranularityContent, nil)
+	if err != nil || !changed {
+		t.Fatalf("Expected the granularity to change, got %v, %v", changed, err)
+	}
+
+	// Any commit of the branch now shares the folded state
+	if !manager.IsFileViewed(repoPath, "main", "new", "a.go") {
+		t.Error("Expected a.go to stay viewed on a new commit")
+	}
+	if hash := manager.ViewedPatchHash(repoPath, "main", "new", "a.go"); hash != "hash-a" {
+		t.Errorf("Expected content granularity to keep the patch hash, got %q", hash)
+	}
+	comments := manager.GetComments(repoPath, "main", "new", nil)
+	if len(comments) != 1 || comments[0].Commit != BranchReviewKey {
+		t.Errorf("Expected the comment to move to the branch, got %+v", comments)
+	}
+	if !manager.IsFileViewed(repoPath, "main", "__working__:old", "b.go") || manager.IsFileViewed(repoPath, "main", "__working__:new", "b.go") {
+		t.Error("Expected working-tree reviews to stay per commit")
+	}
+
+	if _, err := manager.SetGranularity(repoPath, GranularityBranch, nil); err != nil {
+		t.Fatalf("Failed to switch to branch granularity: %v", err)
+	}
+	if hash := manager.ViewedPatchHash(repoPath, "main", "new", "a.go"); hash != "" {
+		t.Errorf("Expected branch granularity to ignore patch hashes, got %q", hash)
+	}
+
+	head := func(branch string) string { return "head" }
+	if _, err := manager.SetGranularity(repoPath, GranularityCommit, head); err != nil {
+		t.Fatalf("Failed to switch back to commit granularity: %v", err)
+	}
+	if !manager.IsFileViewed(repoPath, "main", "head", "a.go") || manager.IsFileViewed(repoPath, "main", "new", "a.go") {
+		t.Error("Expected the branch state to move onto the branch's head commit")
+	}
+	if len(manager.GetComments(repoPath, "main", "head", nil)) != 1 {
+		t.Error("Expected the comment to move onto the branch's head commit")
+	}
+}
+
+func TestParseGranularity(t *testing.T) {
+	if granularity, err := ParseGranularity(""); err != nil || granularity != GranularityCommit {
+		t.Errorf("Expected the default to be commit, got %q, %v", granularity, err)
+	}
+	if _, err := ParseGranularity("file"); err == nil {
+		t.Error("Expected an error for an unknown granularity")
+	}
+}
@@ -0,0 +1,146 @@
+package state
+
+import (
+	"fmt"
+	"time"
+)
+
+// MergeStrategy decides which comment wins when an imported comment has the
+// same ID as a stored one but different content
+type MergeStrategy string
+
+const (
+	// MergeOurs keeps the stored comment
+	MergeOurs MergeStrategy = "ours"
+	// MergeTheirs replaces the stored comment with the imported one
+	MergeTheirs MergeStrategy = "theirs"
+	// MergeNewest keeps whichever comment was modified last
+	MergeNewest MergeStrategy = "newest"
+	// MergeKeepBoth keeps the stored comment and adds the imported one under a
+	// new ID, recording the original in ImportedFrom
+	MergeKeepBoth MergeStrategy = "keep-both"
+)
+
+// ParseMergeStrategy validates a strategy name
+func ParseMergeStrategy(name string) (MergeStrategy, error) {
+	switch strategy := MergeStrategy(name); strategy {
+	case MergeOurs, MergeTheirs, MergeNewest, MergeKeepBoth:
+		return strategy, nil
+	}
+	return "", fmt.Errorf("unknown merge strategy '%s' (expected ours, theirs, newest or keep-both)", name)
+}
+
+// MergeResult counts what MergeComments did with the imported comments
+type MergeResult struct {
+	Added     int // New comments
+	Unchanged int // Identical to a stored comment
+	Replaced  int // Replaced a stored comment with the same ID
+	Kept      int // Lost to the stored comment with the same ID
+	Rekeyed   int // Added under a new ID next to the stored comment
+}
+
+// MergeComments imports comments, such as ones exported from another
+// machine, into a repository's review state. Each comment goes to the branch
+// and commit it records, or to the given ones if it has none. Comments whose
+// ID is unknown are added as they are; on an ID collision with different
+// content the strategy decides. A comment colliding with one stored at
+// another branch or commit is always rekeyed, since it can't be the same one.
+func (m *Manager) MergeComments(repoPath, branch, commit string, incoming []*Comment, strategy MergeStrategy) (MergeResult, error) {
+	unlock, err := m.lock()
+	if err != nil {
+		return MergeResult{}, err
+	}
+	defer unlock()
+
+	var result MergeResult
+	if _, err := ParseMergeStrategy(string(strategy)); err != nil {
+		return result, err
+	}
+
+	// Replies follow their parent to its new ID; parents come before their
+	// replies, as they were added first
+	rekeyed := make(map[string]string)
+	for _, comment := range incoming {
+		imported := *comment
+		if imported.Branch == "" || imported.Commit == "" {
+			imported.Branch, imported.Commit = branch, commit
+		}
+		if parentID, ok := rekeyed[imported.ParentID]; ok && imported.ParentID != "" {
+			imported.ParentID = parentID
+		}
+		imported.Commit = m.commitKey(repoPath, imported.Branch, imported.Commit)
+		repoState := m.repoState(repoPath, imported.Branch, imported.Commit)
+
+		existing := m.FindComment(repoPath, imported.ID)
+		switch {
+		case imported.ID == "" || (existing != nil && (existing.Branch != imported.Branch || existing.Commit != imported.Commit)):
+			m.rekey(repoPath, &imported)
+			rekeyed[comment.ID] = imported.ID
+			repoState.Comments = append(repoState.Comments, &imported)
+			result.Rekeyed++
+		case existing == nil:
+			repoState.Comments = append(repoState.Comments, &imported)
+			result.Added++
+		case sameContent(existing, &imported):
+			result.Unchanged++
+		case strategy == MergeTheirs || (strategy == MergeNewest && imported.LastModified() > existing.LastModified()):
+			*existing = imported
+			result.Replaced++
+		case strategy == MergeKeepBoth:
+			m.rekey(repoPath, &imported)
+			rekeyed[comment.ID] = imported.ID
+			repoState.Comments = append(repoState.Comments, &imported)
+			result.Rekeyed++
+		default:
+			result.Kept++
+		}
+	}
+
+	if result.Added+result.Replaced+result.Rekeyed == 0 {
+		return result, nil
+	}
+	return result, m.save()
+}
+
+// repoState returns the review state of a branch and commit, creating it if needed
+func (m *Manager) repoState(repoPath, branch, commit string) *RepoState {
+	if m.state.Repos[repoPath] == nil {
+		m.state.Repos[repoPath] = make(map[string]map[string]*RepoState)
+	}
+	if m.state.Repos[repoPath][branch] == nil {
+		m.state.Repos[repoPath][branch] = make(map[string]*RepoState)
+	}
+	if m.state.Repos[repoPath][branch][commit] == nil {
+		m.state.Repos[repoPath][branch][commit] = &RepoState{
+			ViewedFiles: []string{},
+			Comments:    []*Comment{},
+			Notes:       []*Note{},
+		}
+	}
+	return m.state.Repos[repoPath][branch][commit]
+}
+
+// rekey gives an imported comment a fresh ID, remembering the one it had
+func (m *Manager) rekey(repoPath string, comment *Comment) {
+	if comment.ID != "" && comment.ImportedFrom == "" {
+		comment.ImportedFrom = comment.ID
+	}
+	timestamp := comment.Timestamp
+	if timestamp == 0 {
+		timestamp = time.Now().Unix()
+	}
+	comment.ID = m.reserveID(repoPath, timestamp)
+}
+
+// sameContent reports whether two comments say the same thing in the same
+// place with the same triage state
+func sameContent(a, b *Comment) bool {
+	if a.Text != b.Text || a.FilePath != b.FilePath || a.Resolved != b.Resolved ||
+		a.Acknowledged != b.Acknowledged || a.Pinned != b.Pinned {
+		return false
+	}
+	if a.LineNumber == nil || b.LineNumber == nil {
+		return a.LineNumber == nil && b.LineNumber == nil
+	}
+	return *a.LineNumber == *b.LineNumber
+}
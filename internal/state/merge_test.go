@@ -0,0 +1,94 @@
+package state
+
+import "testing"
+
+func TestMergeComments(t *testing.T) {
+	stored := func(t *testing.T) (*Manager, *Comment) {
+		t.Helper()
+		manager, _ := setupTestManager(t)
+		comment, err := manager.AddComment("/repo", "main", "abc", "a.go", nil, "Ours")
+		if err != nil {
+			t.Fatalf("Failed to add comment: %v", err)
+		}
+		return manager, comment
+	}
+	conflicting := func(comment *Comment, updatedAt int64) *Comment {
+		theirs := *comment
+		theirs.Text = "Theirs"
+		theirs.UpdatedAt = updatedAt
+		return &theirs
+	}
+
+	tests := []struct {
+		strategy  MergeStrategy
+		updatedAt int64
+		wantText  string
+		wantCount int
+	}{
+		{strategy: MergeOurs, updatedAt: 1 << 40, wantText: "Ours", wantCount: 1},
+		{strategy: MergeTheirs, updatedAt: 1, wantText: "Theirs", wantCount: 1},
+		{strategy: MergeNewest, updatedAt: 1, wantText: "Ours", wantCount: 1},
+		{strategy: MergeNewest, updatedAt: 1 << 40, wantText: "Theirs", wantCount: 1},
+		{strategy: MergeKeepBoth, updatedAt: 1, wantText: "Ours", wantCount: 2},
+	}
+
+	for _, tt := range tests {
+		t.Run(string(tt.strategy), func(t *testing.T) {
+			manager, comment := stored(t)
+			if _, err := manager.MergeComments("/repo", "main", "abc", []*Comment{conflicting(comment, tt.updatedAt)}, tt.strategy); err != nil {
+				t.Fatalf("Failed to merge: %v", err)
+			}
+
+			comments := manager.GetComments("/repo", "main", "abc", nil)
+			if len(comments) != tt.wantCount {
+				t.Fatalf("Expected %d comments, got %d", tt.wantCount, len(comments))
+			}
+			if found := manager.FindComment("/repo", comment.ID); found.Text != tt.wantText {
+				t.Errorf("Expected %q under the original ID, got %q", tt.wantText, found.Text)
+			}
+			if tt.wantCount == 2 {
+				rekeyed := comments[1]
+				if rekeyed.ID == comment.ID || rekeyed.ImportedFrom != comment.ID || rekeyed.Text != "Theirs" {
+					t.Errorf("Expected the imported comment under a new ID, got %+v", rekeyed)
+				}
+			}
+		})
+	}
+}
+
+func TestMergeCommentsAddsAndSkips(t *testing.T) {
+	manager, _ := setupTestManager(t)
+	comment, _ := manager.AddComment("/repo", "main", "abc", "a.go", nil, "Same")
+	elsewhere, _ := manager.AddComment("/repo", "feature", "def", "b.go", nil, "Elsewhere")
+
+	same := *comment
+	clash := Comment{ID: elsewhere.ID, FilePath: "c.go", Text: "Clash", Branch: "main", Commit: "abc"}
+	incoming := []*Comment{
+		&same,
+		{ID: "1-99", FilePath: "new.go", Text: "New", Branch: "main", Commit: "abc"},
+		{ID: "1-100", FilePath: "current.go", Text: "No branch"},
+		&clash,
+	}
+
+	result, err := manager.MergeComments("/repo", "main", "xyz", incoming, MergeOurs)
+	if err != nil {
+		t.Fatalf("Failed to merge: %v", err)
+	}
+	if result != (MergeResult{Added: 2, Unchanged: 1, Rekeyed: 1}) {
+		t.Errorf("Unexpected result %+v", result)
+	}
+
+	if len(manager.GetComments("/repo", "main", "abc", nil)) != 3 {
+		t.Error("Expected the new and clashing comments at their recorded commit")
+	}
+	if comments := manager.GetComments("/repo", "main", "xyz", nil); len(comments) != 1 || comments[0].Text != "No branch" {
+		t.Error("Expected a comment without branch and commit to go to the current ones")
+	}
+	if manager.FindComment("/repo", elsewhere.ID).Text != "Elsewhere" {
+		t.Error("Expected a comment at another commit to be left alone")
+	}
+
+	if _, err := manager.MergeComments("/repo", "main", "abc", nil, MergeStrategy("mine")); err == nil {
+		t.Error("Expected an unknown strategy to be rejected")
+	}
+}
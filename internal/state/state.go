@@ -1,11 +1,22 @@
 package state
 
 import (
+	"bytes"
+	"crypto/sha256"
+	"encoding/hex"
 	"encoding/json"
+	"errors"
 	"fmt"
+	"io/fs"
 	"os"
 	"path/filepath"
+	"sort"
+	"strings"
+	"sync"
 	"time"
+	"unicode/utf8"
+
+	"github.com/tuist/guck/internal/atomicfile"
 )
 
 type Comment struct {
@@ -19,6 +30,50 @@ type Comment struct {
 	Resolved   bool   `json:"resolved"`
 	ResolvedBy string `json:"resolved_by,omitempty"`
 	ResolvedAt int64  `json:"resolved_at,omitempty"`
+	UpdatedAt  int64  `json:"updated_at,omitempty"`
+	Author     string `json:"author,omitempty"`
+	Pinned     bool   `json:"pinned,omitempty"`
+	// Acknowledged is set by the change's author to say they've seen the
+	// comment, before a reviewer resolves it
+	Acknowledged   bool   `json:"acknowledged"`
+	AcknowledgedBy string `json:"acknowledged_by,omitempty"`
+	AcknowledgedAt int64  `json:"acknowledged_at,omitempty"`
+	// ParentID is the ID of the comment this one replies to, see AddReply
+	ParentID string `json:"parent_id,omitempty"`
+	// EditedAt is when the comment's text was last changed after it was added
+	EditedAt int64 `json:"edited_at,omitempty"`
+	// LineHash is the content hash of the commented line, see LineHash
+	LineHash string `json:"line_hash,omitempty"`
+	// Moved is set when the comment followed its line's content to another line
+	Moved bool `json:"moved,omitempty"`
+	// ImportedFrom is the ID an imported comment had before it was given a new
+	// one to avoid colliding with a stored comment, see MergeComments
+	ImportedFrom string `json:"imported_from,omitempty"`
+}
+
+// IsGeneral reports whether the comment is about the review as a whole rather
+// than a particular file
+func (c *Comment) IsGeneral() bool {
+	return c.FilePath == ""
+}
+
+// LastModified returns when the comment was last created or changed.
+// Comments persisted before UpdatedAt existed fall back to their other timestamps.
+func (c *Comment) LastModified() int64 {
+	if c.UpdatedAt != 0 {
+		return c.UpdatedAt
+	}
+	if c.ResolvedAt > c.Timestamp {
+		return c.ResolvedAt
+	}
+	return c.Timestamp
+}
+
+// CommentTombstone records a deleted comment so incremental fetches can report it
+type CommentTombstone struct {
+	ID        string `json:"id"`
+	FilePath  string `json:"file_path"`
+	DeletedAt int64  `json:"deleted_at"`
 }
 
 type Note struct {
@@ -38,18 +93,84 @@ type Note struct {
 }
 
 type RepoState struct {
-	ViewedFiles []string   `json:"viewed_files"`
-	Comments    []*Comment `json:"comments"`
-	Notes       []*Note    `json:"notes"`
+	ViewedFiles []string `json:"viewed_files"`
+	// ViewedPatchHashes holds the hash of each viewed file's patch at the time it
+	// was marked, so a file that changes afterwards can be flagged unviewed again
+	ViewedPatchHashes map[string]string   `json:"viewed_patch_hashes,omitempty"`
+	Comments          []*Comment          `json:"comments"`
+	Notes             []*Note             `json:"notes"`
+	DeletedComments   []*CommentTombstone `json:"deleted_comments,omitempty"`
+	// ReviewSummary is the reviewer's overall verdict, e.g. "LGTM with nits"
+	ReviewSummary string `json:"review_summary,omitempty"`
+	// Checklist holds the review checklist items ticked, by their text
+	Checklist map[string]bool `json:"checklist,omitempty"`
+	// ReviewedAt is when a file was last marked viewed at this commit
+	ReviewedAt int64 `json:"reviewed_at,omitempty"`
+	// ReviewStartedAt and ReviewLastActivityAt are when the reviewer first and
+	// last changed this review, see ReviewSession
+	ReviewStartedAt      int64 `json:"review_started_at,omitempty"`
+	ReviewLastActivityAt int64 `json:"review_last_activity_at,omitempty"`
+	// Reviewer is the first author who identified themselves while reviewing
+	Reviewer string `json:"reviewer,omitempty"`
+}
+
+// ReviewSession describes when a review was worked on and by whom. Times are
+// Unix timestamps, zero for reviews nobody has acted on yet.
+type ReviewSession struct {
+	StartedAt      int64  `json:"review_started_at,omitempty"`
+	LastActivityAt int64  `json:"review_last_activity_at,omitempty"`
+	Reviewer       string `json:"reviewer,omitempty"`
+}
+
+// touchReview records the reviewer acting on a review, starting its session
+// on the first action. An empty reviewer leaves the recorded one unchanged.
+func touchReview(repoState *RepoState, reviewer string) {
+	now := time.Now().Unix()
+	if repoState.ReviewStartedAt == 0 {
+		repoState.ReviewStartedAt = now
+	}
+	repoState.ReviewLastActivityAt = now
+	if repoState.Reviewer == "" {
+		repoState.Reviewer = reviewer
+	}
 }
 
 type ViewedState struct {
 	Repos map[string]map[string]map[string]*RepoState `json:"repos"`
+	// NextIDs holds the next comment/note sequence number per repository
+	NextIDs map[string]int64 `json:"next_ids,omitempty"`
+	// Granularities holds the state granularity of repositories not keeping
+	// review state per commit, see SetGranularity
+	Granularities map[string]Granularity `json:"granularities,omitempty"`
 }
 
+// stateFileName is the name of the review state file inside the state directory
+const stateFileName = "viewed.json"
+
+const (
+	// lockTimeout bounds how long a save waits for another process's lock
+	lockTimeout        = time.Second
+	lockInitialBackoff = 10 * time.Millisecond
+	// staleLockAge is how old a lock must be before it's assumed to have been
+	// left behind by a crashed process
+	staleLockAge = 10 * time.Second
+)
+
+// ErrStateLocked is returned when another guck process held the state file
+// lock for the whole lockTimeout. The change wasn't made, so it can be made
+// again.
+var ErrStateLocked = errors.New("review state is locked by another guck process, try again")
+
+// ErrCommentNotFound is returned when no comment of the review has the given ID
+var ErrCommentNotFound = errors.New("comment not found")
+
 type Manager struct {
 	stateFile string
 	state     *ViewedState
+	// saved is the state file's contents as last read or written, which a
+	// failed save rolls back to
+	saved []byte
+	idMu  sync.Mutex
 }
 
 func NewManager() (*Manager, error) {
@@ -62,33 +183,46 @@ func NewManager() (*Manager, error) {
 		return nil, fmt.Errorf("failed to create state directory: %w", err)
 	}
 
-	stateFile := filepath.Join(stateDir, "viewed.json")
+	stateFile := filepath.Join(stateDir, stateFileName)
 
 	state := &ViewedState{
 		Repos: make(map[string]map[string]map[string]*RepoState),
 	}
 
+	var saved []byte
 	if _, err := os.Stat(stateFile); err == nil {
+		unlock, err := lockStateFile(stateFile)
+		if err != nil {
+			return nil, err
+		}
 		data, err := os.ReadFile(stateFile)
+		unlock()
 		if err != nil {
 			return nil, fmt.Errorf("failed to read state file: %w", err)
 		}
 
 		if err := json.Unmarshal(data, state); err != nil {
-			// If unmarshal fails, start with empty state
-			state = &ViewedState{
-				Repos: make(map[string]map[string]map[string]*RepoState),
-			}
+			state = recoverState(stateFile, err)
+		} else {
+			saved = data
+		}
+	}
+	if saved == nil {
+		// What a failed save rolls back to while the file is missing or corrupt
+		if saved, err = json.Marshal(state); err != nil {
+			return nil, fmt.Errorf("failed to serialize state: %w", err)
 		}
 	}
 
 	return &Manager{
 		stateFile: stateFile,
 		state:     state,
+		saved:     saved,
 	}, nil
 }
 
 func (m *Manager) IsFileViewed(repoPath, branch, commit, filePath string) bool {
+	commit = m.commitKey(repoPath, branch, commit)
 	if branches, ok := m.state.Repos[repoPath]; ok {
 		if commits, ok := branches[branch]; ok {
 			if repoState, ok := commits[commit]; ok {
@@ -104,6 +238,19 @@ func (m *Manager) IsFileViewed(repoPath, branch, commit, filePath string) bool {
 }
 
 func (m *Manager) MarkFileViewed(repoPath, branch, commit, filePath string) error {
+	return m.MarkFileViewedWithHash(repoPath, branch, commit, filePath, "")
+}
+
+// MarkFileViewedWithHash marks a file viewed and records the hash of the patch
+// that was reviewed. An empty hash marks the file viewed regardless of later changes.
+func (m *Manager) MarkFileViewedWithHash(repoPath, branch, commit, filePath, patchHash string) error {
+	unlock, err := m.lock()
+	if err != nil {
+		return err
+	}
+	defer unlock()
+
+	commit = m.commitKey(repoPath, branch, commit)
 	if m.state.Repos[repoPath] == nil {
 		m.state.Repos[repoPath] = make(map[string]map[string]*RepoState)
 	}
@@ -121,19 +268,60 @@ func (m *Manager) MarkFileViewed(repoPath, branch, commit, filePath string) erro
 	}
 
 	repoState := m.state.Repos[repoPath][branch][commit]
+	repoState.ReviewedAt = time.Now().Unix()
+	touchReview(repoState, "")
+	setViewedPatchHash(repoState, filePath, patchHash)
+
+	event := Event{Action: EventFileViewed, Branch: branch, Commit: commit, FilePath: filePath}
 
 	// Check if already viewed
 	for _, viewed := range repoState.ViewedFiles {
 		if viewed == filePath {
-			return m.save()
+			return m.saveWithEvents(repoPath, event)
 		}
 	}
 
 	repoState.ViewedFiles = append(repoState.ViewedFiles, filePath)
-	return m.save()
+	return m.saveWithEvents(repoPath, event)
+}
+
+// ViewedPatchHash returns the patch hash recorded when a file was marked
+// viewed, or an empty string if none was recorded. With branch granularity it
+// is always empty, since a viewed file stays viewed whatever changes.
+func (m *Manager) ViewedPatchHash(repoPath, branch, commit, filePath string) string {
+	commit = m.commitKey(repoPath, branch, commit)
+	if commit == BranchReviewKey && m.Granularity(repoPath) == GranularityBranch {
+		return ""
+	}
+	if branches, ok := m.state.Repos[repoPath]; ok {
+		if commits, ok := branches[branch]; ok {
+			if repoState, ok := commits[commit]; ok {
+				return repoState.ViewedPatchHashes[filePath]
+			}
+		}
+	}
+	return ""
+}
+
+func setViewedPatchHash(repoState *RepoState, filePath, patchHash string) {
+	if patchHash == "" {
+		delete(repoState.ViewedPatchHashes, filePath)
+		return
+	}
+	if repoState.ViewedPatchHashes == nil {
+		repoState.ViewedPatchHashes = make(map[string]string)
+	}
+	repoState.ViewedPatchHashes[filePath] = patchHash
 }
 
 func (m *Manager) UnmarkFileViewed(repoPath, branch, commit, filePath string) error {
+	unlock, err := m.lock()
+	if err != nil {
+		return err
+	}
+	defer unlock()
+
+	commit = m.commitKey(repoPath, branch, commit)
 	if branches, ok := m.state.Repos[repoPath]; ok {
 		if commits, ok := branches[branch]; ok {
 			if repoState, ok := commits[commit]; ok {
@@ -144,14 +332,507 @@ func (m *Manager) UnmarkFileViewed(repoPath, branch, commit, filePath string) er
 					}
 				}
 				repoState.ViewedFiles = filtered
+				delete(repoState.ViewedPatchHashes, filePath)
+				touchReview(repoState, "")
 			}
 		}
 	}
 
-	return m.save()
+	return m.saveWithEvents(repoPath, Event{Action: EventFileUnviewed, Branch: branch, Commit: commit, FilePath: filePath})
+}
+
+// MarkFilesViewed marks several files viewed in a single save, each with the
+// patch hash it was reviewed at (or an empty string for none)
+func (m *Manager) MarkFilesViewed(repoPath, branch, commit string, patchHashes map[string]string) error {
+	unlock, err := m.lock()
+	if err != nil {
+		return err
+	}
+	defer unlock()
+
+	commit = m.commitKey(repoPath, branch, commit)
+	repoState := m.repoState(repoPath, branch, commit)
+	repoState.ReviewedAt = time.Now().Unix()
+	touchReview(repoState, "")
+
+	viewed := make(map[string]bool, len(repoState.ViewedFiles))
+	for _, filePath := range repoState.ViewedFiles {
+		viewed[filePath] = true
+	}
+
+	filePaths := make([]string, 0, len(patchHashes))
+	for filePath := range patchHashes {
+		filePaths = append(filePaths, filePath)
+	}
+	sort.Strings(filePaths)
+
+	events := make([]Event, 0, len(filePaths))
+	for _, filePath := range filePaths {
+		setViewedPatchHash(repoState, filePath, patchHashes[filePath])
+		if !viewed[filePath] {
+			repoState.ViewedFiles = append(repoState.ViewedFiles, filePath)
+		}
+		events = append(events, Event{Action: EventFileViewed, Branch: branch, Commit: commit, FilePath: filePath})
+	}
+	return m.saveWithEvents(repoPath, events...)
+}
+
+// UnmarkDirectoryViewed unmarks every viewed file under a directory in a
+// single save, including files that have since left the diff. It returns how
+// many files were unmarked.
+func (m *Manager) UnmarkDirectoryViewed(repoPath, branch, commit, dir string) (int, error) {
+	unlock, err := m.lock()
+	if err != nil {
+		return 0, err
+	}
+	defer unlock()
+
+	commit = m.commitKey(repoPath, branch, commit)
+	repoState := m.state.Repos[repoPath][branch][commit]
+	if repoState == nil {
+		return 0, nil
+	}
+
+	prefix := strings.Trim(dir, "/") + "/"
+	filtered := []string{}
+	var events []Event
+	for _, viewed := range repoState.ViewedFiles {
+		if strings.HasPrefix(viewed, prefix) {
+			delete(repoState.ViewedPatchHashes, viewed)
+			events = append(events, Event{Action: EventFileUnviewed, Branch: branch, Commit: commit, FilePath: viewed})
+			continue
+		}
+		filtered = append(filtered, viewed)
+	}
+
+	if len(events) == 0 {
+		return 0, nil
+	}
+	repoState.ViewedFiles = filtered
+	touchReview(repoState, "")
+	return len(events), m.saveWithEvents(repoPath, events...)
+}
+
+// CanonicalizePaths rewrites the file paths of a commit's viewed files,
+// comments and notes to the casing git uses in paths, the files in the diff.
+// A stored path that isn't in the diff but matches one of its paths ignoring
+// case, such as the old name of a case-only rename or a path typed with the
+// wrong casing on a case-insensitive filesystem, would otherwise split the
+// file's review state in two. It returns how many entries were rewritten.
+func (m *Manager) CanonicalizePaths(repoPath, branch, commit string, paths []string) (int, error) {
+	unlock, err := m.lock()
+	if err != nil {
+		return 0, err
+	}
+	defer unlock()
+
+	commit = m.commitKey(repoPath, branch, commit)
+	repoState := m.state.Repos[repoPath][branch][commit]
+	if repoState == nil {
+		return 0, nil
+	}
+
+	exact := make(map[string]bool, len(paths))
+	folded := make(map[string]string, len(paths))
+	for _, p := range paths {
+		exact[p] = true
+		key := strings.ToLower(p)
+		if _, ok := folded[key]; ok {
+			// Paths differing only in case both exist, so neither is canonical
+			folded[key] = ""
+			continue
+		}
+		folded[key] = p
+	}
+	canonical := func(p string) (string, bool) {
+		if p == "" || exact[p] {
+			return p, false
+		}
+		target := folded[strings.ToLower(p)]
+		return target, target != ""
+	}
+
+	rewritten := 0
+	viewed := make([]string, 0, len(repoState.ViewedFiles))
+	seen := make(map[string]bool, len(repoState.ViewedFiles))
+	for _, p := range repoState.ViewedFiles {
+		if target, ok := canonical(p); ok {
+			if hash, ok := repoState.ViewedPatchHashes[p]; ok {
+				delete(repoState.ViewedPatchHashes, p)
+				if _, exists := repoState.ViewedPatchHashes[target]; !exists {
+					repoState.ViewedPatchHashes[target] = hash
+				}
+			}
+			p = target
+			rewritten++
+		}
+		if !seen[p] {
+			seen[p] = true
+			viewed = append(viewed, p)
+		}
+	}
+	repoState.ViewedFiles = viewed
+
+	for _, comment := range repoState.Comments {
+		if target, ok := canonical(comment.FilePath); ok {
+			comment.FilePath = target
+			// So incremental fetches pick up the move
+			comment.UpdatedAt = time.Now().Unix()
+			rewritten++
+		}
+	}
+	for _, note := range repoState.Notes {
+		if target, ok := canonical(note.FilePath); ok {
+			note.FilePath = target
+			rewritten++
+		}
+	}
+
+	if rewritten == 0 {
+		return 0, nil
+	}
+	return rewritten, m.save()
+}
+
+// LastReviewedCommit returns the commit of a branch, other than exclude, that
+// was most recently reviewed by marking files viewed or commenting, or an
+// empty string if there is none. Synthetic commit keys such as working-tree
+// reviews are skipped.
+func (m *Manager) LastReviewedCommit(repoPath, branch, exclude string) string {
+	lastCommit, lastReviewed := "", int64(0)
+	for commit, repoState := range m.state.Repos[repoPath][branch] {
+		if commit == exclude || strings.HasPrefix(commit, "__") {
+			continue
+		}
+
+		reviewed := repoState.ReviewedAt
+		for _, comment := range repoState.Comments {
+			reviewed = max(reviewed, comment.Timestamp)
+		}
+		if reviewed > lastReviewed || (reviewed == lastReviewed && reviewed > 0 && commit < lastCommit) {
+			lastCommit, lastReviewed = commit, reviewed
+		}
+	}
+	return lastCommit
+}
+
+// HasReviewState reports whether any viewed files or comments are stored for a commit
+func (m *Manager) HasReviewState(repoPath, branch, commit string) bool {
+	commit = m.commitKey(repoPath, branch, commit)
+	if branches, ok := m.state.Repos[repoPath]; ok {
+		if commits, ok := branches[branch]; ok {
+			if repoState, ok := commits[commit]; ok {
+				return len(repoState.ViewedFiles) > 0 || len(repoState.Comments) > 0 || repoState.ReviewSummary != "" ||
+					len(repoState.Checklist) > 0
+			}
+		}
+	}
+	return false
+}
+
+// CarryForwardViewed marks the files of unchangedFiles that were viewed at
+// oldCommit as viewed at newCommit, with the patch hashes they were viewed
+// with, so files a rebase or amend left alone needn't be reviewed again.
+// Only a commit without review state of its own inherits, so files unmarked
+// there stay unmarked. It returns how many files were carried.
+func (m *Manager) CarryForwardViewed(repoPath, branch, oldCommit, newCommit string, unchangedFiles []string) (int, error) {
+	unlock, err := m.lock()
+	if err != nil {
+		return 0, err
+	}
+	defer unlock()
+
+	oldCommit, newCommit = m.commitKey(repoPath, branch, oldCommit), m.commitKey(repoPath, branch, newCommit)
+	from := m.state.Repos[repoPath][branch][oldCommit]
+	if oldCommit == newCommit || from == nil || m.HasReviewState(repoPath, branch, newCommit) {
+		return 0, nil
+	}
+
+	viewed := make(map[string]bool, len(from.ViewedFiles))
+	for _, filePath := range from.ViewedFiles {
+		viewed[filePath] = true
+	}
+	var carried []string
+	for _, filePath := range unchangedFiles {
+		if viewed[filePath] {
+			carried = append(carried, filePath)
+		}
+	}
+	if len(carried) == 0 {
+		return 0, nil
+	}
+
+	to := m.repoState(repoPath, branch, newCommit)
+	for _, filePath := range carried {
+		to.ViewedFiles = append(to.ViewedFiles, filePath)
+		setViewedPatchHash(to, filePath, from.ViewedPatchHashes[filePath])
+	}
+	return len(carried), m.save()
+}
+
+// CarryForward moves review state from one commit to another on the same
+// branch, e.g. after an amend. Viewed files are merged by path and comments
+// are re-homed onto the new commit, leaving the old commit without review
+// state. Returns how many viewed files and comments were carried.
+func (m *Manager) CarryForward(repoPath, branch, fromCommit, toCommit string) (int, int, error) {
+	unlock, err := m.lock()
+	if err != nil {
+		return 0, 0, err
+	}
+	defer unlock()
+
+	fromCommit, toCommit = m.commitKey(repoPath, branch, fromCommit), m.commitKey(repoPath, branch, toCommit)
+	if fromCommit == toCommit {
+		return 0, 0, nil
+	}
+
+	branches, ok := m.state.Repos[repoPath]
+	if !ok {
+		return 0, 0, nil
+	}
+	commits, ok := branches[branch]
+	if !ok {
+		return 0, 0, nil
+	}
+	from, ok := commits[fromCommit]
+	if !ok {
+		return 0, 0, nil
+	}
+
+	viewedCarried, commentsCarried := carryState(from, m.repoState(repoPath, branch, toCommit), toCommit)
+	return viewedCarried, commentsCarried, m.save()
+}
+
+// carryState moves the viewed files, comments, summary, checklist and session
+// of one review state onto another, whose commit key is toCommit, without
+// replacing what the target already has. Returns how many viewed files and
+// comments were carried.
+func carryState(from, to *RepoState, toCommit string) (int, int) {
+	viewedCarried := 0
+	for _, filePath := range from.ViewedFiles {
+		alreadyViewed := false
+		for _, viewed := range to.ViewedFiles {
+			if viewed == filePath {
+				alreadyViewed = true
+				break
+			}
+		}
+		if !alreadyViewed {
+			to.ViewedFiles = append(to.ViewedFiles, filePath)
+			setViewedPatchHash(to, filePath, from.ViewedPatchHashes[filePath])
+			viewedCarried++
+		}
+	}
+	from.ViewedFiles = []string{}
+	from.ViewedPatchHashes = nil
+
+	for _, comment := range from.Comments {
+		comment.Commit = toCommit
+		to.Comments = append(to.Comments, comment)
+	}
+	commentsCarried := len(from.Comments)
+	from.Comments = []*Comment{}
+
+	if to.ReviewSummary == "" {
+		to.ReviewSummary = from.ReviewSummary
+	}
+	from.ReviewSummary = ""
+
+	for item, checked := range from.Checklist {
+		if _, ok := to.Checklist[item]; !ok {
+			if to.Checklist == nil {
+				to.Checklist = make(map[string]bool)
+			}
+			to.Checklist[item] = checked
+		}
+	}
+	from.Checklist = nil
+
+	// The carried review started when the earlier commit's did
+	if from.ReviewStartedAt != 0 && (to.ReviewStartedAt == 0 || from.ReviewStartedAt < to.ReviewStartedAt) {
+		to.ReviewStartedAt = from.ReviewStartedAt
+	}
+	to.ReviewLastActivityAt = max(to.ReviewLastActivityAt, from.ReviewLastActivityAt)
+	if to.Reviewer == "" {
+		to.Reviewer = from.Reviewer
+	}
+	from.ReviewStartedAt, from.ReviewLastActivityAt, from.Reviewer = 0, 0, ""
+
+	return viewedCarried, commentsCarried
+}
+
+// Prune removes the review state of every branch/commit for which keep returns
+// false, dropping repositories and branches left empty. It returns the number
+// of commit entries removed.
+func (m *Manager) Prune(keep func(repoPath, branch, commit string) bool) (int, error) {
+	unlock, err := m.lock()
+	if err != nil {
+		return 0, err
+	}
+	defer unlock()
+
+	removed := 0
+	for repoPath, branches := range m.state.Repos {
+		for branch, commits := range branches {
+			for commit, repoState := range commits {
+				if repoState.isEmpty() || !keep(repoPath, branch, commit) {
+					delete(commits, commit)
+					removed++
+				}
+			}
+			if len(commits) == 0 {
+				delete(branches, branch)
+			}
+		}
+		if len(branches) == 0 {
+			delete(m.state.Repos, repoPath)
+		}
+	}
+
+	if removed == 0 {
+		return 0, nil
+	}
+
+	return removed, m.save()
+}
+
+// SetReviewSummary stores the overall summary of a review. An empty summary clears it.
+func (m *Manager) SetReviewSummary(repoPath, branch, commit, summary string) error {
+	unlock, err := m.lock()
+	if err != nil {
+		return err
+	}
+	defer unlock()
+
+	commit = m.commitKey(repoPath, branch, commit)
+	if m.state.Repos[repoPath] == nil {
+		m.state.Repos[repoPath] = make(map[string]map[string]*RepoState)
+	}
+
+	if m.state.Repos[repoPath][branch] == nil {
+		m.state.Repos[repoPath][branch] = make(map[string]*RepoState)
+	}
+
+	if m.state.Repos[repoPath][branch][commit] == nil {
+		m.state.Repos[repoPath][branch][commit] = &RepoState{
+			ViewedFiles: []string{},
+			Comments:    []*Comment{},
+			Notes:       []*Note{},
+		}
+	}
+
+	repoState := m.state.Repos[repoPath][branch][commit]
+	repoState.ReviewSummary = strings.TrimSpace(summary)
+	touchReview(repoState, "")
+	return m.saveWithEvents(repoPath, Event{Action: EventSummarySet, Branch: branch, Commit: commit})
+}
+
+// GetReviewSummary returns the overall summary of a review, or an empty string
+func (m *Manager) GetReviewSummary(repoPath, branch, commit string) string {
+	commit = m.commitKey(repoPath, branch, commit)
+	if branches, ok := m.state.Repos[repoPath]; ok {
+		if commits, ok := branches[branch]; ok {
+			if repoState, ok := commits[commit]; ok {
+				return repoState.ReviewSummary
+			}
+		}
+	}
+	return ""
+}
+
+// GetReviewSession returns when a review was started and last worked on, and by whom
+func (m *Manager) GetReviewSession(repoPath, branch, commit string) ReviewSession {
+	commit = m.commitKey(repoPath, branch, commit)
+	if branches, ok := m.state.Repos[repoPath]; ok {
+		if commits, ok := branches[branch]; ok {
+			if repoState, ok := commits[commit]; ok {
+				return ReviewSession{
+					StartedAt:      repoState.ReviewStartedAt,
+					LastActivityAt: repoState.ReviewLastActivityAt,
+					Reviewer:       repoState.Reviewer,
+				}
+			}
+		}
+	}
+	return ReviewSession{}
+}
+
+// ReviewRecord counts the review state stored for one branch and commit
+type ReviewRecord struct {
+	Branch             string
+	Commit             string
+	ViewedFiles        int
+	Comments           int
+	UnresolvedComments int
+	// LastActivityAt is when the review was last worked on, zero if unknown
+	LastActivityAt int64
+}
+
+// ListReviews returns every branch and commit of a repository with viewed
+// files or comments, most recently worked on first
+func (m *Manager) ListReviews(repoPath string) []ReviewRecord {
+	records := []ReviewRecord{}
+	for branch, commits := range m.state.Repos[repoPath] {
+		for commit, repoState := range commits {
+			if len(repoState.ViewedFiles) == 0 && len(repoState.Comments) == 0 {
+				continue
+			}
+
+			record := ReviewRecord{
+				Branch:         branch,
+				Commit:         commit,
+				ViewedFiles:    len(repoState.ViewedFiles),
+				Comments:       len(repoState.Comments),
+				LastActivityAt: max(repoState.ReviewLastActivityAt, repoState.ReviewedAt),
+			}
+			for _, comment := range repoState.Comments {
+				if !comment.Resolved {
+					record.UnresolvedComments++
+				}
+			}
+			records = append(records, record)
+		}
+	}
+
+	sort.Slice(records, func(i, j int) bool {
+		a, b := records[i], records[j]
+		if a.LastActivityAt != b.LastActivityAt {
+			return a.LastActivityAt > b.LastActivityAt
+		}
+		if a.Branch != b.Branch {
+			return a.Branch < b.Branch
+		}
+		return a.Commit < b.Commit
+	})
+	return records
+}
+
+// ValidateCommentText rejects empty or whitespace-only comments, and comments
+// longer than maxLength characters. A maxLength of zero disables the limit.
+func ValidateCommentText(text string, maxLength int) error {
+	if strings.TrimSpace(text) == "" {
+		return fmt.Errorf("comment text must not be empty")
+	}
+	if length := utf8.RuneCountInString(text); maxLength > 0 && length > maxLength {
+		return fmt.Errorf("comment text is %d characters, exceeding the maximum of %d", length, maxLength)
+	}
+	return nil
 }
 
 func (m *Manager) AddComment(repoPath, branch, commit, filePath string, lineNumber *int, text string) (*Comment, error) {
+	return m.AddCommentWithAuthor(repoPath, branch, commit, filePath, lineNumber, text, "")
+}
+
+// AddCommentWithAuthor adds a comment attributed to the given author.
+// An empty author leaves the comment unattributed.
+func (m *Manager) AddCommentWithAuthor(repoPath, branch, commit, filePath string, lineNumber *int, text, author string) (*Comment, error) {
+	unlock, err := m.lock()
+	if err != nil {
+		return nil, err
+	}
+	defer unlock()
+
+	commit = m.commitKey(repoPath, branch, commit)
 	if m.state.Repos[repoPath] == nil {
 		m.state.Repos[repoPath] = make(map[string]map[string]*RepoState)
 	}
@@ -172,7 +853,7 @@ func (m *Manager) AddComment(repoPath, branch, commit, filePath string, lineNumb
 
 	timestamp := time.Now().Unix()
 	comment := &Comment{
-		ID:         fmt.Sprintf("%d-%d", timestamp, len(repoState.Comments)),
+		ID:         m.reserveID(repoPath, timestamp),
 		FilePath:   filePath,
 		LineNumber: lineNumber,
 		Text:       text,
@@ -180,18 +861,82 @@ func (m *Manager) AddComment(repoPath, branch, commit, filePath string, lineNumb
 		Branch:     branch,
 		Commit:     commit,
 		Resolved:   false,
+		UpdatedAt:  timestamp,
+		Author:     author,
 	}
 
 	repoState.Comments = append(repoState.Comments, comment)
+	touchReview(repoState, author)
 
-	if err := m.save(); err != nil {
+	event := Event{Action: EventCommentAdded, Actor: author, Branch: branch, Commit: commit, FilePath: filePath, ID: comment.ID}
+	if err := m.saveWithEvents(repoPath, event); err != nil {
 		return nil, err
 	}
 
 	return comment, nil
 }
 
+// AddReply adds a reply to a comment. Threads are one level deep, so replying
+// to a reply adds to its thread. The reply takes its parent's file and line so
+// that it's shown, and moved, along with it.
+func (m *Manager) AddReply(repoPath, branch, commit, parentID, text, author string) (*Comment, error) {
+	unlock, err := m.lock()
+	if err != nil {
+		return nil, err
+	}
+	defer unlock()
+
+	key := m.commitKey(repoPath, branch, commit)
+	repoState := m.state.Repos[repoPath][branch][key]
+	if repoState == nil {
+		return nil, ErrCommentNotFound
+	}
+
+	var parent *Comment
+	for _, comment := range repoState.Comments {
+		if comment.ID == parentID {
+			parent = comment
+			break
+		}
+	}
+	if parent == nil {
+		return nil, ErrCommentNotFound
+	}
+	if parent.ParentID != "" {
+		parentID = parent.ParentID
+	}
+
+	timestamp := time.Now().Unix()
+	reply := &Comment{
+		ID:        m.reserveID(repoPath, timestamp),
+		FilePath:  parent.FilePath,
+		Text:      text,
+		Timestamp: timestamp,
+		Branch:    branch,
+		Commit:    key,
+		UpdatedAt: timestamp,
+		Author:    author,
+		ParentID:  parentID,
+		LineHash:  parent.LineHash,
+	}
+	if parent.LineNumber != nil {
+		line := *parent.LineNumber
+		reply.LineNumber = &line
+	}
+
+	repoState.Comments = append(repoState.Comments, reply)
+	touchReview(repoState, author)
+
+	event := Event{Action: EventCommentAdded, Actor: author, Branch: branch, Commit: key, FilePath: reply.FilePath, ID: reply.ID}
+	if err := m.saveWithEvents(repoPath, event); err != nil {
+		return nil, err
+	}
+
+	return reply, nil
+}
+
 func (m *Manager) GetComments(repoPath, branch, commit string, filePath *string) []*Comment {
+	commit = m.commitKey(repoPath, branch, commit)
 	if branches, ok := m.state.Repos[repoPath]; ok {
 		if commits, ok := branches[branch]; ok {
 			if repoState, ok := commits[commit]; ok {
@@ -213,7 +958,48 @@ func (m *Manager) GetComments(repoPath, branch, commit string, filePath *string)
 	return []*Comment{}
 }
 
+// GetCommentChanges returns comments modified at or after since, along with
+// tombstones for comments deleted in that window
+func (m *Manager) GetCommentChanges(repoPath, branch, commit string, filePath *string, since int64) ([]*Comment, []*CommentTombstone) {
+	commit = m.commitKey(repoPath, branch, commit)
+	changed := []*Comment{}
+	deleted := []*CommentTombstone{}
+
+	if branches, ok := m.state.Repos[repoPath]; ok {
+		if commits, ok := branches[branch]; ok {
+			if repoState, ok := commits[commit]; ok {
+				for _, comment := range repoState.Comments {
+					if filePath != nil && comment.FilePath != *filePath {
+						continue
+					}
+					if comment.LastModified() >= since {
+						changed = append(changed, comment)
+					}
+				}
+
+				for _, tombstone := range repoState.DeletedComments {
+					if filePath != nil && tombstone.FilePath != *filePath {
+						continue
+					}
+					if tombstone.DeletedAt >= since {
+						deleted = append(deleted, tombstone)
+					}
+				}
+			}
+		}
+	}
+
+	return changed, deleted
+}
+
 func (m *Manager) ResolveComment(repoPath, branch, commit, commentID, resolvedBy string) error {
+	unlock, err := m.lock()
+	if err != nil {
+		return err
+	}
+	defer unlock()
+
+	commit = m.commitKey(repoPath, branch, commit)
 	if branches, ok := m.state.Repos[repoPath]; ok {
 		if commits, ok := branches[branch]; ok {
 			if repoState, ok := commits[commit]; ok {
@@ -222,6 +1008,166 @@ func (m *Manager) ResolveComment(repoPath, branch, commit, commentID, resolvedBy
 						comment.Resolved = true
 						comment.ResolvedBy = resolvedBy
 						comment.ResolvedAt = time.Now().Unix()
+						comment.UpdatedAt = comment.ResolvedAt
+						touchReview(repoState, resolvedBy)
+						return m.saveWithEvents(repoPath, Event{Action: EventCommentResolved, Actor: resolvedBy, Branch: branch, Commit: commit, FilePath: comment.FilePath, ID: commentID})
+					}
+				}
+			}
+		}
+	}
+
+	return ErrCommentNotFound
+}
+
+// EditComment replaces the text of a comment, keeping its author and
+// position, and returns the edited comment
+func (m *Manager) EditComment(repoPath, branch, commit, commentID, text string) (*Comment, error) {
+	unlock, err := m.lock()
+	if err != nil {
+		return nil, err
+	}
+	defer unlock()
+
+	commit = m.commitKey(repoPath, branch, commit)
+	if branches, ok := m.state.Repos[repoPath]; ok {
+		if commits, ok := branches[branch]; ok {
+			if repoState, ok := commits[commit]; ok {
+				for _, comment := range repoState.Comments {
+					if comment.ID == commentID {
+						comment.Text = text
+						comment.EditedAt = time.Now().Unix()
+						comment.UpdatedAt = comment.EditedAt
+						touchReview(repoState, "")
+						if err := m.saveWithEvents(repoPath, Event{Action: EventCommentEdited, Branch: branch, Commit: commit, FilePath: comment.FilePath, ID: commentID}); err != nil {
+							return nil, err
+						}
+						return comment, nil
+					}
+				}
+			}
+		}
+	}
+
+	return nil, ErrCommentNotFound
+}
+
+// DeleteComment removes a comment along with its replies, leaving tombstones
+// so incremental fetches report them gone. It returns whether a comment was
+// deleted.
+func (m *Manager) DeleteComment(repoPath, branch, commit, commentID string) (bool, error) {
+	unlock, err := m.lock()
+	if err != nil {
+		return false, err
+	}
+	defer unlock()
+
+	commit = m.commitKey(repoPath, branch, commit)
+	repoState := m.state.Repos[repoPath][branch][commit]
+	if repoState == nil {
+		return false, nil
+	}
+
+	now := time.Now().Unix()
+	kept := make([]*Comment, 0, len(repoState.Comments))
+	var events []Event
+	for _, comment := range repoState.Comments {
+		if comment.ID != commentID && comment.ParentID != commentID {
+			kept = append(kept, comment)
+			continue
+		}
+		repoState.DeletedComments = append(repoState.DeletedComments, &CommentTombstone{
+			ID:        comment.ID,
+			FilePath:  comment.FilePath,
+			DeletedAt: now,
+		})
+		events = append(events, Event{Action: EventCommentDeleted, Branch: branch, Commit: commit, FilePath: comment.FilePath, ID: comment.ID})
+	}
+	if len(events) == 0 {
+		return false, nil
+	}
+
+	repoState.Comments = kept
+	touchReview(repoState, "")
+	return true, m.saveWithEvents(repoPath, events...)
+}
+
+// AcknowledgeComment records that the author has seen a comment. Unlike
+// ResolveComment, it leaves the comment open for the reviewer to resolve.
+func (m *Manager) AcknowledgeComment(repoPath, branch, commit, commentID, acknowledgedBy string) error {
+	unlock, err := m.lock()
+	if err != nil {
+		return err
+	}
+	defer unlock()
+
+	commit = m.commitKey(repoPath, branch, commit)
+	if branches, ok := m.state.Repos[repoPath]; ok {
+		if commits, ok := branches[branch]; ok {
+			if repoState, ok := commits[commit]; ok {
+				for _, comment := range repoState.Comments {
+					if comment.ID == commentID {
+						comment.Acknowledged = true
+						comment.AcknowledgedBy = acknowledgedBy
+						comment.AcknowledgedAt = time.Now().Unix()
+						comment.UpdatedAt = comment.AcknowledgedAt
+						return m.saveWithEvents(repoPath, Event{Action: EventCommentAcknowledged, Actor: acknowledgedBy, Branch: branch, Commit: commit, FilePath: comment.FilePath, ID: commentID})
+					}
+				}
+			}
+		}
+	}
+
+	return ErrCommentNotFound
+}
+
+// SetCommentPinned pins or unpins a comment so priority ordering surfaces it first
+func (m *Manager) SetCommentPinned(repoPath, branch, commit, commentID string, pinned bool) error {
+	unlock, err := m.lock()
+	if err != nil {
+		return err
+	}
+	defer unlock()
+
+	commit = m.commitKey(repoPath, branch, commit)
+	if branches, ok := m.state.Repos[repoPath]; ok {
+		if commits, ok := branches[branch]; ok {
+			if repoState, ok := commits[commit]; ok {
+				for _, comment := range repoState.Comments {
+					if comment.ID == commentID {
+						comment.Pinned = pinned
+						comment.UpdatedAt = time.Now().Unix()
+						touchReview(repoState, "")
+						action := EventCommentPinned
+						if !pinned {
+							action = EventCommentUnpinned
+						}
+						return m.saveWithEvents(repoPath, Event{Action: action, Branch: branch, Commit: commit, FilePath: comment.FilePath, ID: commentID})
+					}
+				}
+			}
+		}
+	}
+
+	return ErrCommentNotFound
+}
+
+// SetCommentLineHash anchors a line comment to the content of its line, so
+// ReanchorComments can follow the line when lines above it change
+func (m *Manager) SetCommentLineHash(repoPath, branch, commit, commentID, lineHash string) error {
+	unlock, err := m.lock()
+	if err != nil {
+		return err
+	}
+	defer unlock()
+
+	commit = m.commitKey(repoPath, branch, commit)
+	if branches, ok := m.state.Repos[repoPath]; ok {
+		if commits, ok := branches[branch]; ok {
+			if repoState, ok := commits[commit]; ok {
+				for _, comment := range repoState.Comments {
+					if comment.ID == commentID {
+						comment.LineHash = lineHash
 						return m.save()
 					}
 				}
@@ -229,7 +1175,122 @@ func (m *Manager) ResolveComment(repoPath, branch, commit, commentID, resolvedBy
 		}
 	}
 
-	return fmt.Errorf("comment not found")
+	return ErrCommentNotFound
+}
+
+// reanchorWindow is how many lines away from a comment's line its content is
+// searched for
+const reanchorWindow = 100
+
+// LineHash returns the hash a line comment is anchored by. Trailing whitespace
+// is ignored, so a change of line endings doesn't count as a different line.
+func LineHash(line string) string {
+	sum := sha256.Sum256([]byte(strings.TrimRight(line, " \t\r")))
+	return hex.EncodeToString(sum[:8])
+}
+
+// ReanchorComments moves line comments whose line no longer matches their
+// LineHash to the nearest line within reanchorWindow that does, flagging them
+// as moved. fileLines returns the current lines of a file; comments on files
+// it can't read, or whose content isn't found, stay where they are. It returns
+// how many comments were moved.
+func (m *Manager) ReanchorComments(repoPath, branch, commit string, fileLines func(filePath string) ([]string, error)) (int, error) {
+	unlock, err := m.lock()
+	if err != nil {
+		return 0, err
+	}
+	defer unlock()
+
+	commit = m.commitKey(repoPath, branch, commit)
+	repoState := m.state.Repos[repoPath][branch][commit]
+	if repoState == nil {
+		return 0, nil
+	}
+
+	files := make(map[string][]string)
+	now := time.Now().Unix()
+	moved := 0
+	for _, comment := range repoState.Comments {
+		if comment.LineHash == "" || comment.LineNumber == nil {
+			continue
+		}
+
+		lines, ok := files[comment.FilePath]
+		if !ok {
+			lines, _ = fileLines(comment.FilePath)
+			files[comment.FilePath] = lines
+		}
+
+		line, found := findLine(lines, *comment.LineNumber, comment.LineHash)
+		if !found || line == *comment.LineNumber {
+			continue
+		}
+		comment.LineNumber = &line
+		comment.Moved = true
+		comment.UpdatedAt = now
+		moved++
+	}
+
+	if moved == 0 {
+		return 0, nil
+	}
+	return moved, m.save()
+}
+
+// findLine returns the 1-based line nearest to lineNumber whose LineHash is
+// lineHash, preferring the line below on a tie since lines are more often
+// inserted above a comment than removed
+func findLine(lines []string, lineNumber int, lineHash string) (int, bool) {
+	for distance := 0; distance <= reanchorWindow; distance++ {
+		for _, candidate := range []int{lineNumber + distance, lineNumber - distance} {
+			if candidate >= 1 && candidate <= len(lines) && LineHash(lines[candidate-1]) == lineHash {
+				return candidate, true
+			}
+		}
+	}
+	return 0, false
+}
+
+// SortByPriority returns the comments with pinned ones first, each group
+// oldest first. The input slice is left in insertion order.
+func SortByPriority(comments []*Comment) []*Comment {
+	sorted := make([]*Comment, len(comments))
+	copy(sorted, comments)
+
+	sort.SliceStable(sorted, func(i, j int) bool {
+		if sorted[i].Pinned != sorted[j].Pinned {
+			return sorted[i].Pinned
+		}
+		return sorted[i].Timestamp < sorted[j].Timestamp
+	})
+
+	return sorted
+}
+
+// SortByAge returns the comments ordered by creation time, oldest first unless
+// newestFirst is set. Comments created in the same second are ordered by ID,
+// whose sequence number reflects creation order. The input slice is left as is.
+func SortByAge(comments []*Comment, newestFirst bool) []*Comment {
+	sorted := make([]*Comment, len(comments))
+	copy(sorted, comments)
+
+	sort.Slice(sorted, func(i, j int) bool {
+		a, b := sorted[i], sorted[j]
+		if newestFirst {
+			a, b = b, a
+		}
+		if a.Timestamp != b.Timestamp {
+			return a.Timestamp < b.Timestamp
+		}
+		// IDs of the same second share their timestamp prefix, so a shorter
+		// sequence number is a smaller one
+		if len(a.ID) != len(b.ID) {
+			return len(a.ID) < len(b.ID)
+		}
+		return a.ID < b.ID
+	})
+
+	return sorted
 }
 
 func (m *Manager) GetAllComments(repoPath string) []*Comment {
@@ -246,7 +1307,24 @@ func (m *Manager) GetAllComments(repoPath string) []*Comment {
 	return allComments
 }
 
+// FindComment looks up a comment by ID across all branches and commits of a repository
+func (m *Manager) FindComment(repoPath, commentID string) *Comment {
+	for _, comment := range m.GetAllComments(repoPath) {
+		if comment.ID == commentID {
+			return comment
+		}
+	}
+	return nil
+}
+
 func (m *Manager) AddNote(repoPath, branch, commit, filePath string, lineNumber *int, text, author, noteType string, metadata map[string]string) (*Note, error) {
+	unlock, err := m.lock()
+	if err != nil {
+		return nil, err
+	}
+	defer unlock()
+
+	commit = m.commitKey(repoPath, branch, commit)
 	if m.state.Repos[repoPath] == nil {
 		m.state.Repos[repoPath] = make(map[string]map[string]*RepoState)
 	}
@@ -267,7 +1345,7 @@ func (m *Manager) AddNote(repoPath, branch, commit, filePath string, lineNumber
 
 	timestamp := time.Now().Unix()
 	note := &Note{
-		ID:         fmt.Sprintf("%d-%d", timestamp, len(repoState.Notes)),
+		ID:         m.reserveID(repoPath, timestamp),
 		FilePath:   filePath,
 		LineNumber: lineNumber,
 		Text:       text,
@@ -282,7 +1360,8 @@ func (m *Manager) AddNote(repoPath, branch, commit, filePath string, lineNumber
 
 	repoState.Notes = append(repoState.Notes, note)
 
-	if err := m.save(); err != nil {
+	event := Event{Action: EventNoteAdded, Actor: author, Branch: branch, Commit: commit, FilePath: filePath, ID: note.ID}
+	if err := m.saveWithEvents(repoPath, event); err != nil {
 		return nil, err
 	}
 
@@ -290,6 +1369,7 @@ func (m *Manager) AddNote(repoPath, branch, commit, filePath string, lineNumber
 }
 
 func (m *Manager) GetNotes(repoPath, branch, commit string, filePath *string) []*Note {
+	commit = m.commitKey(repoPath, branch, commit)
 	if branches, ok := m.state.Repos[repoPath]; ok {
 		if commits, ok := branches[branch]; ok {
 			if repoState, ok := commits[commit]; ok {
@@ -326,6 +1406,13 @@ func (m *Manager) GetAllNotes(repoPath string) []*Note {
 }
 
 func (m *Manager) DismissNote(repoPath, branch, commit, noteID, dismissedBy string) error {
+	unlock, err := m.lock()
+	if err != nil {
+		return err
+	}
+	defer unlock()
+
+	commit = m.commitKey(repoPath, branch, commit)
 	if branches, ok := m.state.Repos[repoPath]; ok {
 		if commits, ok := branches[branch]; ok {
 			if repoState, ok := commits[commit]; ok {
@@ -334,7 +1421,7 @@ func (m *Manager) DismissNote(repoPath, branch, commit, noteID, dismissedBy stri
 						note.Dismissed = true
 						note.DismissedBy = dismissedBy
 						note.DismissedAt = time.Now().Unix()
-						return m.save()
+						return m.saveWithEvents(repoPath, Event{Action: EventNoteDismissed, Actor: dismissedBy, Branch: branch, Commit: commit, FilePath: note.FilePath, ID: noteID})
 					}
 				}
 			}
@@ -344,20 +1431,234 @@ func (m *Manager) DismissNote(repoPath, branch, commit, noteID, dismissedBy stri
 	return fmt.Errorf("note not found")
 }
 
+// reserveID atomically takes the next sequence number for a repository and
+// formats it into an ID. The counter is persisted with the state, so IDs stay
+// unique even after comments or notes are removed.
+func (m *Manager) reserveID(repoPath string, timestamp int64) string {
+	m.idMu.Lock()
+	defer m.idMu.Unlock()
+
+	if m.state.NextIDs == nil {
+		m.state.NextIDs = make(map[string]int64)
+	}
+
+	next, ok := m.state.NextIDs[repoPath]
+	if !ok {
+		// Seed past anything created before the counter existed
+		for _, commits := range m.state.Repos[repoPath] {
+			for _, repoState := range commits {
+				next += int64(len(repoState.Comments) + len(repoState.Notes))
+			}
+		}
+	}
+
+	m.state.NextIDs[repoPath] = next + 1
+	return fmt.Sprintf("%d-%d", timestamp, next)
+}
+
+// isEmpty reports whether a commit's review state holds nothing worth saving
+func (rs *RepoState) isEmpty() bool {
+	return len(rs.ViewedFiles) == 0 && len(rs.Comments) == 0 && len(rs.Notes) == 0 &&
+		len(rs.DeletedComments) == 0 && rs.ReviewSummary == "" && len(rs.Checklist) == 0
+}
+
+// compact drops review state entries left empty, e.g. after every file was
+// unmarked, so the shared state file doesn't accumulate them
+func (m *Manager) compact() {
+	for repoPath, branches := range m.state.Repos {
+		for branch, commits := range branches {
+			for commit, repoState := range commits {
+				if repoState.isEmpty() {
+					delete(commits, commit)
+				}
+			}
+			if len(commits) == 0 {
+				delete(branches, branch)
+			}
+		}
+		if len(branches) == 0 {
+			delete(m.state.Repos, repoPath)
+		}
+	}
+}
+
+// FileSize returns the size of the review state file in bytes, or 0 if it
+// hasn't been written yet
+func (m *Manager) FileSize() (int64, error) {
+	info, err := os.Stat(m.stateFile)
+	if errors.Is(err, fs.ErrNotExist) {
+		return 0, nil
+	}
+	if err != nil {
+		return 0, fmt.Errorf("failed to stat state file: %w", err)
+	}
+	return info.Size(), nil
+}
+
+// lock takes the state file lock for a change and reloads the state file,
+// so the change is made to what other guck processes sharing it have saved
+// instead of overwriting their changes with this process's copy. Callers
+// hold the lock until they've saved; the returned function releases it.
+func (m *Manager) lock() (func(), error) {
+	unlock, err := lockStateFile(m.stateFile)
+	if err != nil {
+		return nil, err
+	}
+	if err := m.reload(); err != nil {
+		unlock()
+		return nil, err
+	}
+	return unlock, nil
+}
+
+// reload reads the state file again if it changed since it was last read or
+// written. A state file that doesn't exist yet or can't be parsed leaves the
+// state as it is.
+func (m *Manager) reload() error {
+	data, err := os.ReadFile(m.stateFile)
+	if errors.Is(err, fs.ErrNotExist) {
+		return nil
+	}
+	if err != nil {
+		return fmt.Errorf("failed to read state file: %w", err)
+	}
+	if bytes.Equal(data, m.saved) {
+		return nil
+	}
+
+	state := &ViewedState{}
+	if json.Unmarshal(data, state) != nil {
+		// Corrupt, which NewManager warned about; saving replaces it with the
+		// state recovered then
+		return nil
+	}
+	if state.Repos == nil {
+		state.Repos = make(map[string]map[string]map[string]*RepoState)
+	}
+	m.state = state
+	m.saved = data
+	return nil
+}
+
+// save writes the state to the state file. Callers hold the lock taken by
+// lock. If writing fails, the state is rolled back to the file's contents,
+// undoing the change being saved, so memory never holds changes the file
+// doesn't and making the change again is safe.
 func (m *Manager) save() error {
+	m.compact()
+
 	data, err := json.MarshalIndent(m.state, "", "  ")
+	if err == nil {
+		err = m.write(data)
+	} else {
+		err = fmt.Errorf("failed to serialize state: %w", err)
+	}
 	if err != nil {
-		return fmt.Errorf("failed to serialize state: %w", err)
+		m.rollback()
+		return err
 	}
 
-	if err := os.WriteFile(m.stateFile, data, 0644); err != nil {
+	m.saved = data
+	return nil
+}
+
+func (m *Manager) write(data []byte) error {
+	// NewManager falls back to the backup when the state file can't be parsed
+	if err := atomicfile.Backup(m.stateFile); err != nil {
+		return fmt.Errorf("failed to back up state file: %w", err)
+	}
+	if err := atomicfile.Write(m.stateFile, data); err != nil {
 		return fmt.Errorf("failed to write state file: %w", err)
 	}
 
 	return nil
 }
 
+// rollback restores the state file's contents as last read or written, or
+// empty state if the file hasn't been
+func (m *Manager) rollback() {
+	state := &ViewedState{}
+	if m.saved == nil || json.Unmarshal(m.saved, state) != nil || state.Repos == nil {
+		state = &ViewedState{
+			Repos: make(map[string]map[string]map[string]*RepoState),
+		}
+	}
+	m.state = state
+}
+
+// recoverState loads the backup of a state file that failed to parse with
+// parseErr, or returns empty state if there's no usable backup. Either way it
+// warns, since review state may have been lost.
+func recoverState(stateFile string, parseErr error) *ViewedState {
+	backup := stateFile + atomicfile.BackupSuffix
+	if data, err := os.ReadFile(backup); err == nil {
+		state := &ViewedState{}
+		if err := json.Unmarshal(data, state); err == nil && state.Repos != nil {
+			fmt.Fprintf(os.Stderr, "Warning: review state in %s is corrupt (%v); recovered the previous state from %s.\n", stateFile, parseErr, backup)
+			return state
+		}
+	}
+
+	fmt.Fprintf(os.Stderr, "Warning: review state in %s is corrupt (%v) and has no usable backup; starting with empty state.\n", stateFile, parseErr)
+	return &ViewedState{
+		Repos: make(map[string]map[string]map[string]*RepoState),
+	}
+}
+
+// lockStateFile takes an advisory lock on the state file, shared by every
+// daemon, by creating a lock file next to it. While another process holds the
+// lock it retries with exponential backoff for up to lockTimeout before giving
+// up with ErrStateLocked. The returned function releases the lock.
+func lockStateFile(stateFile string) (func(), error) {
+	lockFile := stateFile + ".lock"
+	deadline := time.Now().Add(lockTimeout)
+	backoff := lockInitialBackoff
+
+	for {
+		f, err := os.OpenFile(lockFile, os.O_CREATE|os.O_EXCL|os.O_WRONLY, 0644)
+		if err == nil {
+			_ = f.Close()
+			return func() { _ = os.Remove(lockFile) }, nil
+		}
+		if !errors.Is(err, fs.ErrExist) {
+			return nil, fmt.Errorf("failed to lock state file: %w", err)
+		}
+
+		if info, err := os.Stat(lockFile); err == nil && time.Since(info.ModTime()) > staleLockAge {
+			if os.Remove(lockFile) == nil {
+				continue
+			}
+		}
+
+		remaining := time.Until(deadline)
+		if remaining <= 0 {
+			return nil, ErrStateLocked
+		}
+		time.Sleep(min(backoff, remaining))
+		backoff *= 2
+	}
+}
+
+// Dir returns the directory guck keeps its state in. GUCK_STATE_DIR
+// overrides the XDG-based default.
+func Dir() (string, error) {
+	return getStateDir()
+}
+
+// FilePath returns the path of the review state file
+func FilePath() (string, error) {
+	stateDir, err := getStateDir()
+	if err != nil {
+		return "", err
+	}
+	return filepath.Join(stateDir, stateFileName), nil
+}
+
 func getStateDir() (string, error) {
+	if dir := os.Getenv("GUCK_STATE_DIR"); dir != "" {
+		return dir, nil
+	}
+
 	// Use XDG_STATE_HOME on Unix, or fallback to XDG_DATA_HOME/LocalAppData
 	if stateHome := os.Getenv("XDG_STATE_HOME"); stateHome != "" {
 		return filepath.Join(stateHome, "guck"), nil
@@ -369,9 +1670,18 @@ func getStateDir() (string, error) {
 
 	home, err := os.UserHomeDir()
 	if err != nil {
-		return "", fmt.Errorf("failed to determine home directory: %w", err)
+		// Minimal containers and CI runners may have no HOME; keep working from a
+		// temp directory rather than refusing to start
+		dir := filepath.Join(os.TempDir(), fmt.Sprintf("guck-%d", os.Getuid()))
+		fallbackWarning.Do(func() {
+			fmt.Fprintf(os.Stderr, "Warning: could not determine home directory (%v); storing state in %s. Set GUCK_STATE_DIR to choose a location.\n", err, dir)
+		})
+		return dir, nil
 	}
 
 	// Platform-specific defaults
 	return filepath.Join(home, ".local", "state", "guck"), nil
 }
+
+// fallbackWarning prints the temp-directory fallback warning once per process
+var fallbackWarning sync.Once
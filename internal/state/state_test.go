@@ -1,9 +1,15 @@
 package state
 
 import (
+	"encoding/json"
+	"errors"
 	"os"
 	"path/filepath"
+	"strings"
 	"testing"
+	"time"
+
+	"github.com/tuist/guck/internal/atomicfile"
 )
 
 func setupTestManager(t *testing.T) (*Manager, string) {
@@ -245,6 +251,218 @@ func TestResolveComment(t *testing.T) {
 	}
 }
 
+func TestAcknowledgeComment(t *testing.T) {
+	manager, _ := setupTestManager(t)
+
+	comment, err := manager.AddComment("/test/repo", "main", "abc123", "test.go", nil, "Test comment")
+	if err != nil {
+		t.Fatalf("Failed to add comment: %v", err)
+	}
+
+	if err := manager.AcknowledgeComment("/test/repo", "main", "abc123", comment.ID, "author"); err != nil {
+		t.Fatalf("Failed to acknowledge comment: %v", err)
+	}
+
+	acknowledged := manager.GetComments("/test/repo", "main", "abc123", nil)[0]
+	if !acknowledged.Acknowledged || acknowledged.AcknowledgedBy != "author" || acknowledged.AcknowledgedAt == 0 {
+		t.Errorf("Expected the comment to be acknowledged by author, got %+v", acknowledged)
+	}
+	if acknowledged.Resolved {
+		t.Error("Acknowledging shouldn't resolve the comment")
+	}
+
+	if err := manager.AcknowledgeComment("/test/repo", "main", "abc123", "missing", "author"); err == nil {
+		t.Error("Expected an error for an unknown comment")
+	}
+}
+
+func TestEditComment(t *testing.T) {
+	manager, _ := setupTestManager(t)
+
+	line := 3
+	comment, err := manager.AddCommentWithAuthor("/test/repo", "main", "abc123", "test.go", &line, "Tpyo here", "alice")
+	if err != nil {
+		t.Fatalf("Failed to add comment: %v", err)
+	}
+
+	edited, err := manager.EditComment("/test/repo", "main", "abc123", comment.ID, "Typo here")
+	if err != nil {
+		t.Fatalf("Failed to edit comment: %v", err)
+	}
+	if edited.Text != "Typo here" || edited.EditedAt == 0 || edited.UpdatedAt != edited.EditedAt {
+		t.Errorf("Expected the edited text and timestamps, got %+v", edited)
+	}
+
+	stored := manager.GetComments("/test/repo", "main", "abc123", nil)[0]
+	if stored.Text != "Typo here" || stored.Author != "alice" || stored.LineNumber == nil || *stored.LineNumber != 3 {
+		t.Errorf("Expected only the text to change, got %+v", stored)
+	}
+
+	if _, err := manager.EditComment("/test/repo", "main", "abc123", "missing", "text"); !errors.Is(err, ErrCommentNotFound) {
+		t.Errorf("Expected ErrCommentNotFound for an unknown comment, got %v", err)
+	}
+}
+
+func TestDeleteComment(t *testing.T) {
+	manager, _ := setupTestManager(t)
+
+	comment, err := manager.AddComment("/test/repo", "main", "abc123", "test.go", nil, "Wrong file")
+	if err != nil {
+		t.Fatalf("Failed to add comment: %v", err)
+	}
+
+	deleted, err := manager.DeleteComment("/test/repo", "main", "abc123", comment.ID)
+	if err != nil || !deleted {
+		t.Fatalf("Expected the comment to be deleted, got %t (%v)", deleted, err)
+	}
+	if comments := manager.GetComments("/test/repo", "main", "abc123", nil); len(comments) != 0 {
+		t.Errorf("Expected no comments after deleting the last one, got %+v", comments)
+	}
+	_, tombstones := manager.GetCommentChanges("/test/repo", "main", "abc123", nil, 0)
+	if len(tombstones) != 1 || tombstones[0].ID != comment.ID || tombstones[0].FilePath != "test.go" {
+		t.Errorf("Expected a tombstone for the deleted comment, got %+v", tombstones)
+	}
+
+	// The emptied review still loads and takes new comments
+	data, err := os.ReadFile(manager.stateFile)
+	if err != nil {
+		t.Fatalf("Failed to read state file: %v", err)
+	}
+	reloaded := &Manager{stateFile: manager.stateFile, state: &ViewedState{}}
+	if err := json.Unmarshal(data, reloaded.state); err != nil {
+		t.Fatalf("Failed to parse state file: %v", err)
+	}
+	if comments := reloaded.GetComments("/test/repo", "main", "abc123", nil); comments == nil || len(comments) != 0 {
+		t.Errorf("Expected an empty comment list after reloading, got %#v", comments)
+	}
+	if _, err := reloaded.AddComment("/test/repo", "main", "abc123", "test.go", nil, "Right file"); err != nil {
+		t.Fatalf("Failed to add comment after deleting: %v", err)
+	}
+
+	if deleted, err := manager.DeleteComment("/test/repo", "main", "abc123", "missing"); err != nil || deleted {
+		t.Errorf("Expected nothing to be deleted for an unknown comment, got %t (%v)", deleted, err)
+	}
+	if deleted, err := manager.DeleteComment("/test/other", "main", "abc123", comment.ID); err != nil || deleted {
+		t.Errorf("Expected nothing to be deleted in another repository, got %t (%v)", deleted, err)
+	}
+}
+
+func TestAddReply(t *testing.T) {
+	manager, _ := setupTestManager(t)
+
+	line := 7
+	parent, err := manager.AddComment("/test/repo", "main", "abc123", "test.go", &line, "Why this?")
+	if err != nil {
+		t.Fatalf("Failed to add comment: %v", err)
+	}
+
+	reply, err := manager.AddReply("/test/repo", "main", "abc123", parent.ID, "Because", "bob")
+	if err != nil {
+		t.Fatalf("Failed to add reply: %v", err)
+	}
+	if reply.ParentID != parent.ID || reply.FilePath != "test.go" || reply.LineNumber == nil || *reply.LineNumber != 7 || reply.Author != "bob" {
+		t.Errorf("Expected a reply on the parent's line, got %+v", reply)
+	}
+
+	// Replying to a reply continues the thread
+	nested, err := manager.AddReply("/test/repo", "main", "abc123", reply.ID, "Thanks", "")
+	if err != nil {
+		t.Fatalf("Failed to add reply: %v", err)
+	}
+	if nested.ParentID != parent.ID {
+		t.Errorf("Expected the reply to join %s's thread, got parent %s", parent.ID, nested.ParentID)
+	}
+
+	if comments := manager.GetComments("/test/repo", "main", "abc123", nil); len(comments) != 3 {
+		t.Errorf("Expected replies in the flat comment list, got %+v", comments)
+	}
+
+	if _, err := manager.AddReply("/test/repo", "main", "abc123", "missing", "text", ""); !errors.Is(err, ErrCommentNotFound) {
+		t.Errorf("Expected ErrCommentNotFound for an unknown parent, got %v", err)
+	}
+
+	// Deleting a comment deletes its thread
+	if deleted, err := manager.DeleteComment("/test/repo", "main", "abc123", parent.ID); err != nil || !deleted {
+		t.Fatalf("Expected the thread to be deleted, got %t (%v)", deleted, err)
+	}
+	if comments := manager.GetComments("/test/repo", "main", "abc123", nil); len(comments) != 0 {
+		t.Errorf("Expected the replies to be deleted with their parent, got %+v", comments)
+	}
+}
+
+func TestReanchorComments(t *testing.T) {
+	manager, _ := setupTestManager(t)
+
+	line := 2
+	comment, err := manager.AddComment("/test/repo", "main", "abc123", "test.go", &line, "Check this")
+	if err != nil {
+		t.Fatalf("Failed to add comment: %v", err)
+	}
+	if err := manager.SetCommentLineHash("/test/repo", "main", "abc123", comment.ID, LineHash("return nil")); err != nil {
+		t.Fatalf("Failed to anchor comment: %v", err)
+	}
+
+	fileLines := func(lines ...string) func(string) ([]string, error) {
+		return func(string) ([]string, error) { return lines, nil }
+	}
+
+	moved, err := manager.ReanchorComments("/test/repo", "main", "abc123", fileLines("func f() {", "return nil", "}"))
+	if err != nil || moved != 0 || comment.Moved {
+		t.Fatalf("Expected an unchanged line to leave the comment in place, got %d moved (%v)", moved, err)
+	}
+
+	// Two lines inserted above the commented line
+	moved, err = manager.ReanchorComments("/test/repo", "main", "abc123", fileLines("func f() {", "x := 1", "_ = x", "return nil  ", "}"))
+	if err != nil || moved != 1 {
+		t.Fatalf("Expected the comment to move, got %d moved (%v)", moved, err)
+	}
+	if *comment.LineNumber != 4 || !comment.Moved {
+		t.Errorf("Expected the comment to follow its line to line 4, got line %d (moved %v)", *comment.LineNumber, comment.Moved)
+	}
+
+	// The line is gone, so the comment stays where it was
+	if moved, _ := manager.ReanchorComments("/test/repo", "main", "abc123", fileLines("func f() {", "}")); moved != 0 || *comment.LineNumber != 4 {
+		t.Errorf("Expected the comment to stay on line 4, got line %d", *comment.LineNumber)
+	}
+}
+
+func TestCanonicalizePaths(t *testing.T) {
+	manager, _ := setupTestManager(t)
+
+	if err := manager.MarkFileViewedWithHash("/test/repo", "main", "abc123", "Src/Main.go", "hash"); err != nil {
+		t.Fatalf("Failed to mark file viewed: %v", err)
+	}
+	if err := manager.MarkFileViewed("/test/repo", "main", "abc123", "README"); err != nil {
+		t.Fatalf("Failed to mark file viewed: %v", err)
+	}
+	comment, err := manager.AddComment("/test/repo", "main", "abc123", "Src/Main.go", nil, "Rename this")
+	if err != nil {
+		t.Fatalf("Failed to add comment: %v", err)
+	}
+
+	// README and readme both exist, so neither casing is moved to the other
+	rewritten, err := manager.CanonicalizePaths("/test/repo", "main", "abc123", []string{"src/main.go", "README", "readme"})
+	if err != nil {
+		t.Fatalf("Failed to canonicalize paths: %v", err)
+	}
+	if rewritten != 2 {
+		t.Errorf("Expected the viewed entry and the comment to be moved, got %d", rewritten)
+	}
+
+	if !manager.IsFileViewed("/test/repo", "main", "abc123", "src/main.go") || manager.IsFileViewed("/test/repo", "main", "abc123", "Src/Main.go") {
+		t.Error("Expected the viewed state to move to src/main.go")
+	}
+	if hash := manager.ViewedPatchHash("/test/repo", "main", "abc123", "src/main.go"); hash != "hash" {
+		t.Errorf("Expected the patch hash to move along, got %q", hash)
+	}
+	if !manager.IsFileViewed("/test/repo", "main", "abc123", "README") || manager.IsFileViewed("/test/repo", "main", "abc123", "readme") {
+		t.Error("Expected README's viewed state to stay put")
+	}
+	if moved := manager.FindComment("/test/repo", comment.ID); moved == nil || moved.FilePath != "src/main.go" {
+		t.Errorf("Expected the comment to move to src/main.go, got %+v", moved)
+	}
+}
+
 func TestGetAllComments(t *testing.T) {
 	manager, _ := setupTestManager(t)
 
@@ -381,3 +599,746 @@ func TestCommentWithoutLineNumber(t *testing.T) {
 		t.Errorf("Expected 'File-level comment', got %s", comment.Text)
 	}
 }
+
+func TestGetCommentChanges(t *testing.T) {
+	manager, _ := setupTestManager(t)
+
+	repoPath := "/test/repo"
+	branch := "main"
+	commit := "abc123"
+	filePath := "test.go"
+	lineNumber := 42
+
+	oldComment, err := manager.AddComment(repoPath, branch, commit, filePath, &lineNumber, "Old comment")
+	if err != nil {
+		t.Fatalf("Failed to add comment: %v", err)
+	}
+	oldComment.UpdatedAt = 100
+
+	newComment, err := manager.AddComment(repoPath, branch, commit, "other.go", &lineNumber, "New comment")
+	if err != nil {
+		t.Fatalf("Failed to add comment: %v", err)
+	}
+	newComment.UpdatedAt = 200
+
+	repoState := manager.state.Repos[repoPath][branch][commit]
+	repoState.DeletedComments = append(repoState.DeletedComments, &CommentTombstone{
+		ID:        "deleted-1",
+		FilePath:  filePath,
+		DeletedAt: 150,
+	})
+
+	changed, deleted := manager.GetCommentChanges(repoPath, branch, commit, nil, 150)
+	if len(changed) != 1 || changed[0].ID != newComment.ID {
+		t.Errorf("Expected only the new comment to be returned, got %d comments", len(changed))
+	}
+	if len(deleted) != 1 || deleted[0].ID != "deleted-1" {
+		t.Errorf("Expected one tombstone, got %d", len(deleted))
+	}
+
+	// File filter applies to both comments and tombstones
+	changed, deleted = manager.GetCommentChanges(repoPath, branch, commit, &filePath, 0)
+	if len(changed) != 1 || changed[0].ID != oldComment.ID {
+		t.Errorf("Expected only the comment on %s, got %d comments", filePath, len(changed))
+	}
+	if len(deleted) != 1 {
+		t.Errorf("Expected one tombstone for %s, got %d", filePath, len(deleted))
+	}
+
+	// Resolving bumps the last-modified time
+	if err := manager.ResolveComment(repoPath, branch, commit, oldComment.ID, "tester"); err != nil {
+		t.Fatalf("Failed to resolve comment: %v", err)
+	}
+	changed, _ = manager.GetCommentChanges(repoPath, branch, commit, &filePath, 150)
+	if len(changed) != 1 {
+		t.Errorf("Expected resolved comment to be reported as changed, got %d", len(changed))
+	}
+}
+
+func TestCarryForwardViewed(t *testing.T) {
+	manager, _ := setupTestManager(t)
+
+	repoPath, branch := "/test/repo", "feature"
+	if err := manager.MarkFileViewedWithHash(repoPath, branch, "abc123", "same.go", "hash1"); err != nil {
+		t.Fatalf("Failed to mark file viewed: %v", err)
+	}
+	if err := manager.MarkFileViewed(repoPath, branch, "abc123", "changed.go"); err != nil {
+		t.Fatalf("Failed to mark file viewed: %v", err)
+	}
+
+	carried, err := manager.CarryForwardViewed(repoPath, branch, "abc123", "def456", []string{"same.go", "unviewed.go"})
+	if err != nil {
+		t.Fatalf("Failed to carry viewed files: %v", err)
+	}
+	if carried != 1 {
+		t.Errorf("Expected 1 carried file, got %d", carried)
+	}
+	if !manager.IsFileViewed(repoPath, branch, "def456", "same.go") || manager.ViewedPatchHash(repoPath, branch, "def456", "same.go") != "hash1" {
+		t.Error("Expected the unchanged file to stay viewed with its patch hash")
+	}
+	if manager.IsFileViewed(repoPath, branch, "def456", "changed.go") || manager.IsFileViewed(repoPath, branch, "def456", "unviewed.go") {
+		t.Error("Expected only unchanged, viewed files to be carried")
+	}
+	if !manager.IsFileViewed(repoPath, branch, "abc123", "changed.go") {
+		t.Error("Expected the old commit's review state to be left alone")
+	}
+
+	// A commit with review state of its own doesn't inherit again
+	if err := manager.UnmarkFileViewed(repoPath, branch, "def456", "same.go"); err != nil {
+		t.Fatalf("Failed to unmark file: %v", err)
+	}
+	if err := manager.MarkFileViewed(repoPath, branch, "def456", "other.go"); err != nil {
+		t.Fatalf("Failed to mark file viewed: %v", err)
+	}
+	if carried, err := manager.CarryForwardViewed(repoPath, branch, "abc123", "def456", []string{"same.go"}); err != nil || carried != 0 {
+		t.Errorf("Expected nothing to be carried onto a reviewed commit, got %d (%v)", carried, err)
+	}
+}
+
+func TestCarryForward(t *testing.T) {
+	manager, _ := setupTestManager(t)
+
+	repoPath := "/test/repo"
+	branch := "feature"
+	oldCommit := "abc123"
+	newCommit := "def456"
+	lineNumber := 10
+
+	if err := manager.MarkFileViewed(repoPath, branch, oldCommit, "a.go"); err != nil {
+		t.Fatalf("Failed to mark file viewed: %v", err)
+	}
+	if err := manager.MarkFileViewed(repoPath, branch, newCommit, "a.go"); err != nil {
+		t.Fatalf("Failed to mark file viewed: %v", err)
+	}
+	if err := manager.MarkFileViewed(repoPath, branch, oldCommit, "b.go"); err != nil {
+		t.Fatalf("Failed to mark file viewed: %v", err)
+	}
+	comment, err := manager.AddComment(repoPath, branch, oldCommit, "a.go", &lineNumber, "Needs a test")
+	if err != nil {
+		t.Fatalf("Failed to add comment: %v", err)
+	}
+
+	if !manager.HasReviewState(repoPath, branch, oldCommit) {
+		t.Error("Expected review state for the old commit")
+	}
+
+	viewed, comments, err := manager.CarryForward(repoPath, branch, oldCommit, newCommit)
+	if err != nil {
+		t.Fatalf("Failed to carry forward: %v", err)
+	}
+
+	if viewed != 1 {
+		t.Errorf("Expected 1 newly viewed file, got %d", viewed)
+	}
+	if comments != 1 {
+		t.Errorf("Expected 1 carried comment, got %d", comments)
+	}
+
+	if !manager.IsFileViewed(repoPath, branch, newCommit, "b.go") {
+		t.Error("Expected b.go to be viewed on the new commit")
+	}
+
+	newComments := manager.GetComments(repoPath, branch, newCommit, nil)
+	if len(newComments) != 1 || newComments[0].ID != comment.ID || newComments[0].Commit != newCommit {
+		t.Errorf("Expected comment to be re-homed onto the new commit, got %+v", newComments)
+	}
+
+	if manager.HasReviewState(repoPath, branch, oldCommit) {
+		t.Error("Expected no review state left on the old commit")
+	}
+}
+
+func TestReviewSession(t *testing.T) {
+	manager, _ := setupTestManager(t)
+
+	repoPath := "/test/repo"
+	branch := "main"
+	commit := "abc123"
+
+	if session := manager.GetReviewSession(repoPath, branch, commit); session != (ReviewSession{}) {
+		t.Errorf("Expected no session before any activity, got %+v", session)
+	}
+
+	before := time.Now().Unix()
+	if err := manager.MarkFileViewed(repoPath, branch, commit, "a.go"); err != nil {
+		t.Fatalf("Failed to mark file viewed: %v", err)
+	}
+	session := manager.GetReviewSession(repoPath, branch, commit)
+	if session.StartedAt < before || session.LastActivityAt < session.StartedAt || session.Reviewer != "" {
+		t.Errorf("Expected a started session without a reviewer, got %+v", session)
+	}
+
+	// Pretend the review started a while ago, so later activity is distinguishable
+	manager.state.Repos[repoPath][branch][commit].ReviewStartedAt = before - 3600
+	manager.state.Repos[repoPath][branch][commit].ReviewLastActivityAt = before - 3600
+
+	if _, err := manager.AddCommentWithAuthor(repoPath, branch, commit, "a.go", nil, "Nit", "alice"); err != nil {
+		t.Fatalf("Failed to add comment: %v", err)
+	}
+	if _, err := manager.AddCommentWithAuthor(repoPath, branch, commit, "a.go", nil, "Reply", "bob"); err != nil {
+		t.Fatalf("Failed to add comment: %v", err)
+	}
+
+	session = manager.GetReviewSession(repoPath, branch, commit)
+	if session.StartedAt != before-3600 {
+		t.Errorf("Expected the start time to stay put, got %d", session.StartedAt)
+	}
+	if session.LastActivityAt < before {
+		t.Errorf("Expected the last activity to move forward, got %d", session.LastActivityAt)
+	}
+	if session.Reviewer != "alice" {
+		t.Errorf("Expected the first author as reviewer, got %q", session.Reviewer)
+	}
+
+	if _, _, err := manager.CarryForward(repoPath, branch, commit, "def456"); err != nil {
+		t.Fatalf("Failed to carry forward: %v", err)
+	}
+	if carried := manager.GetReviewSession(repoPath, branch, "def456"); carried != session {
+		t.Errorf("Expected the session to be carried forward, got %+v", carried)
+	}
+}
+
+func TestCommentIDsUniqueAcrossRemovals(t *testing.T) {
+	manager, _ := setupTestManager(t)
+
+	repoPath := "/test/repo"
+	branch := "main"
+	commit := "abc123"
+	filePath := "test.go"
+
+	seen := make(map[string]bool)
+	for i := 0; i < 3; i++ {
+		comment, err := manager.AddComment(repoPath, branch, commit, filePath, nil, "Comment")
+		if err != nil {
+			t.Fatalf("Failed to add comment: %v", err)
+		}
+		seen[comment.ID] = true
+	}
+
+	// Drop the middle comment, which used to make the next list-length based ID collide
+	repoState := manager.state.Repos[repoPath][branch][commit]
+	repoState.Comments = append(repoState.Comments[:1], repoState.Comments[2:]...)
+
+	for i := 0; i < 3; i++ {
+		comment, err := manager.AddComment(repoPath, branch, commit, filePath, nil, "Comment")
+		if err != nil {
+			t.Fatalf("Failed to add comment: %v", err)
+		}
+		if seen[comment.ID] {
+			t.Fatalf("Duplicate comment ID %s", comment.ID)
+		}
+		seen[comment.ID] = true
+	}
+
+	note, err := manager.AddNote(repoPath, branch, commit, filePath, nil, "Note", "claude", "explanation", nil)
+	if err != nil {
+		t.Fatalf("Failed to add note: %v", err)
+	}
+	if seen[note.ID] {
+		t.Fatalf("Note ID %s collides with a comment ID", note.ID)
+	}
+
+	if manager.state.NextIDs[repoPath] != 7 {
+		t.Errorf("Expected next ID counter to be 7, got %d", manager.state.NextIDs[repoPath])
+	}
+}
+
+func TestPinCommentPriorityOrder(t *testing.T) {
+	manager, _ := setupTestManager(t)
+
+	repoPath := "/test/repo"
+	branch := "main"
+	commit := "abc123"
+
+	first, _ := manager.AddComment(repoPath, branch, commit, "a.go", nil, "First")
+	second, _ := manager.AddComment(repoPath, branch, commit, "b.go", nil, "Second")
+	third, _ := manager.AddComment(repoPath, branch, commit, "c.go", nil, "Third")
+
+	if err := manager.SetCommentPinned(repoPath, branch, commit, third.ID, true); err != nil {
+		t.Fatalf("Failed to pin comment: %v", err)
+	}
+
+	comments := manager.GetComments(repoPath, branch, commit, nil)
+	sorted := SortByPriority(comments)
+
+	expected := []string{third.ID, first.ID, second.ID}
+	for i, id := range expected {
+		if sorted[i].ID != id {
+			t.Errorf("Position %d: expected %s, got %s", i, id, sorted[i].ID)
+		}
+	}
+
+	// Insertion order is preserved in state
+	if comments[0].ID != first.ID || comments[2].ID != third.ID {
+		t.Error("SortByPriority should not reorder the stored comments")
+	}
+
+	if err := manager.SetCommentPinned(repoPath, branch, commit, third.ID, false); err != nil {
+		t.Fatalf("Failed to unpin comment: %v", err)
+	}
+	if SortByPriority(manager.GetComments(repoPath, branch, commit, nil))[0].ID != first.ID {
+		t.Error("Expected unpinned comment to return to its insertion position")
+	}
+
+	if err := manager.SetCommentPinned(repoPath, branch, commit, "missing", true); err == nil {
+		t.Error("Expected error pinning a missing comment")
+	}
+}
+
+func TestValidateCommentText(t *testing.T) {
+	tests := []struct {
+		name      string
+		text      string
+		maxLength int
+		wantErr   bool
+	}{
+		{"valid", "Looks good", 100, false},
+		{"empty", "", 100, true},
+		{"whitespace only", " \n\t ", 100, true},
+		{"at limit", "abcde", 5, false},
+		{"over limit", "abcdef", 5, true},
+		{"multibyte at limit", "äöüßé", 5, false},
+		{"no limit", "abcdef", 0, false},
+	}
+
+	for _, tt := range tests {
+		t.Run(tt.name, func(t *testing.T) {
+			err := ValidateCommentText(tt.text, tt.maxLength)
+			if (err != nil) != tt.wantErr {
+				t.Errorf("ValidateCommentText(%q, %d) error = %v, wantErr %v", tt.text, tt.maxLength, err, tt.wantErr)
+			}
+		})
+	}
+}
+
+func TestPrune(t *testing.T) {
+	manager, _ := setupTestManager(t)
+
+	_ = manager.MarkFileViewed("/repo", "main", "keep", "a.go")
+	_ = manager.MarkFileViewed("/repo", "main", "gone", "a.go")
+	_ = manager.MarkFileViewed("/repo", "deleted-branch", "abc", "a.go")
+	_ = manager.MarkFileViewed("/deleted-repo", "main", "abc", "a.go")
+
+	removed, err := manager.Prune(func(repoPath, branch, commit string) bool {
+		return repoPath == "/repo" && branch == "main" && commit == "keep"
+	})
+	if err != nil {
+		t.Fatalf("Failed to prune: %v", err)
+	}
+
+	if removed != 3 {
+		t.Errorf("Expected 3 entries removed, got %d", removed)
+	}
+	if !manager.IsFileViewed("/repo", "main", "keep", "a.go") {
+		t.Error("Expected kept entry to survive pruning")
+	}
+	if _, ok := manager.state.Repos["/repo"]["deleted-branch"]; ok {
+		t.Error("Expected empty branch to be removed")
+	}
+	if _, ok := manager.state.Repos["/deleted-repo"]; ok {
+		t.Error("Expected empty repository to be removed")
+	}
+}
+
+func TestSortByAge(t *testing.T) {
+	// The same second, with sequence numbers that sort wrongly as plain strings
+	comments := []*Comment{
+		{ID: "100-10", Timestamp: 100},
+		{ID: "100-9", Timestamp: 100},
+		{ID: "50-11", Timestamp: 50},
+	}
+
+	oldest := SortByAge(comments, false)
+	if oldest[0].ID != "50-11" || oldest[1].ID != "100-9" || oldest[2].ID != "100-10" {
+		t.Errorf("Expected oldest first with ties broken by ID, got %s, %s, %s", oldest[0].ID, oldest[1].ID, oldest[2].ID)
+	}
+
+	newest := SortByAge(comments, true)
+	if newest[0].ID != "100-10" || newest[1].ID != "100-9" || newest[2].ID != "50-11" {
+		t.Errorf("Expected newest first, got %s, %s, %s", newest[0].ID, newest[1].ID, newest[2].ID)
+	}
+
+	if comments[0].ID != "100-10" {
+		t.Error("SortByAge should not reorder its input")
+	}
+}
+
+func TestSaveDropsEmptyReviewState(t *testing.T) {
+	manager, _ := setupTestManager(t)
+
+	if size, err := manager.FileSize(); err != nil || size != 0 {
+		t.Errorf("Expected no state file yet, got %d bytes (%v)", size, err)
+	}
+
+	_ = manager.MarkFileViewed("/repo", "main", "abc", "a.go")
+	_ = manager.MarkFileViewed("/repo", "main", "def", "a.go")
+	if size, err := manager.FileSize(); err != nil || size == 0 {
+		t.Errorf("Expected the state file to be written, got %d bytes (%v)", size, err)
+	}
+
+	if err := manager.UnmarkFileViewed("/repo", "main", "abc", "a.go"); err != nil {
+		t.Fatalf("Failed to unmark file: %v", err)
+	}
+	if _, ok := manager.state.Repos["/repo"]["main"]["abc"]; ok {
+		t.Error("Expected the emptied commit to be dropped on save")
+	}
+	if !manager.IsFileViewed("/repo", "main", "def", "a.go") {
+		t.Error("Expected other commits to be kept")
+	}
+}
+
+func TestMarkFileViewedWithHash(t *testing.T) {
+	manager, _ := setupTestManager(t)
+
+	repoPath := "/test/repo"
+	if err := manager.MarkFileViewedWithHash(repoPath, "main", "abc123", "a.go", "hash1"); err != nil {
+		t.Fatalf("Failed to mark file viewed: %v", err)
+	}
+
+	if hash := manager.ViewedPatchHash(repoPath, "main", "abc123", "a.go"); hash != "hash1" {
+		t.Errorf("Expected patch hash 'hash1', got '%s'", hash)
+	}
+
+	// Re-marking updates the hash to the newly reviewed patch
+	if err := manager.MarkFileViewedWithHash(repoPath, "main", "abc123", "a.go", "hash2"); err != nil {
+		t.Fatalf("Failed to mark file viewed: %v", err)
+	}
+	if hash := manager.ViewedPatchHash(repoPath, "main", "abc123", "a.go"); hash != "hash2" {
+		t.Errorf("Expected patch hash 'hash2', got '%s'", hash)
+	}
+
+	if err := manager.UnmarkFileViewed(repoPath, "main", "abc123", "a.go"); err != nil {
+		t.Fatalf("Failed to unmark file: %v", err)
+	}
+	if hash := manager.ViewedPatchHash(repoPath, "main", "abc123", "a.go"); hash != "" {
+		t.Errorf("Expected patch hash to be cleared on unmark, got '%s'", hash)
+	}
+}
+
+func TestReviewSummary(t *testing.T) {
+	manager, _ := setupTestManager(t)
+
+	repoPath := "/test/repo"
+	if summary := manager.GetReviewSummary(repoPath, "main", "abc123"); summary != "" {
+		t.Errorf("Expected no summary initially, got '%s'", summary)
+	}
+
+	if err := manager.SetReviewSummary(repoPath, "main", "abc123", "  Needs rework on error handling\n"); err != nil {
+		t.Fatalf("Failed to set summary: %v", err)
+	}
+	if summary := manager.GetReviewSummary(repoPath, "main", "abc123"); summary != "Needs rework on error handling" {
+		t.Errorf("Expected trimmed summary, got '%s'", summary)
+	}
+	if summary := manager.GetReviewSummary(repoPath, "main", "def456"); summary != "" {
+		t.Errorf("Expected summaries to be per commit, got '%s'", summary)
+	}
+	if !manager.HasReviewState(repoPath, "main", "abc123") {
+		t.Error("Expected a summary to count as review state")
+	}
+
+	if err := manager.SetReviewSummary(repoPath, "main", "abc123", ""); err != nil {
+		t.Fatalf("Failed to clear summary: %v", err)
+	}
+	if summary := manager.GetReviewSummary(repoPath, "main", "abc123"); summary != "" {
+		t.Errorf("Expected summary to be cleared, got '%s'", summary)
+	}
+}
+
+func TestLastReviewedCommit(t *testing.T) {
+	manager, _ := setupTestManager(t)
+
+	repoPath := "/test/repo"
+	if commit := manager.LastReviewedCommit(repoPath, "main", ""); commit != "" {
+		t.Errorf("Expected no reviewed commit initially, got '%s'", commit)
+	}
+
+	for _, commit := range []string{"abc123", "def456", "__uncommitted__"} {
+		if err := manager.MarkFileViewed(repoPath, "main", commit, "file.go"); err != nil {
+			t.Fatalf("Failed to mark file viewed: %v", err)
+		}
+	}
+	// Backdate the first review so the order doesn't depend on the clock
+	manager.state.Repos[repoPath]["main"]["abc123"].ReviewedAt = 1
+
+	if commit := manager.LastReviewedCommit(repoPath, "main", ""); commit != "def456" {
+		t.Errorf("Expected def456 to be the last reviewed commit, got '%s'", commit)
+	}
+	if commit := manager.LastReviewedCommit(repoPath, "main", "def456"); commit != "abc123" {
+		t.Errorf("Expected the excluded commit to be skipped, got '%s'", commit)
+	}
+}
+
+func TestDirFallsBackWithoutHome(t *testing.T) {
+	t.Setenv("GUCK_STATE_DIR", "")
+	t.Setenv("XDG_STATE_HOME", "")
+	t.Setenv("XDG_DATA_HOME", "")
+	t.Setenv("HOME", "")
+
+	dir, err := Dir()
+	if err != nil {
+		t.Fatalf("Expected a fallback state directory, got error: %v", err)
+	}
+	if !strings.HasPrefix(dir, os.TempDir()) {
+		t.Errorf("Expected a directory under %s, got %s", os.TempDir(), dir)
+	}
+
+	override := t.TempDir()
+	t.Setenv("GUCK_STATE_DIR", override)
+	if dir, _ := Dir(); dir != override {
+		t.Errorf("Expected GUCK_STATE_DIR %s to take precedence, got %s", override, dir)
+	}
+}
+
+func TestSaveWaitsForStateLock(t *testing.T) {
+	manager, _ := setupTestManager(t)
+	lockFile := manager.stateFile + ".lock"
+	if err := manager.MarkFileViewed("/repo", "main", "abc123", "saved.go"); err != nil {
+		t.Fatalf("Failed to mark file as viewed: %v", err)
+	}
+
+	// Another process holds the lock for longer than the retry budget
+	if err := os.WriteFile(lockFile, nil, 0644); err != nil {
+		t.Fatalf("Failed to create lock file: %v", err)
+	}
+	start := time.Now()
+	err := manager.MarkFileViewed("/repo", "main", "abc123", "file.go")
+	if !errors.Is(err, ErrStateLocked) {
+		t.Fatalf("Expected ErrStateLocked, got %v", err)
+	}
+	if elapsed := time.Since(start); elapsed < lockTimeout {
+		t.Errorf("Expected to retry for %v before giving up, gave up after %v", lockTimeout, elapsed)
+	}
+	// The change that couldn't be saved isn't made, earlier ones are kept
+	if manager.IsFileViewed("/repo", "main", "abc123", "file.go") {
+		t.Error("Expected the unsaved change to be rolled back")
+	}
+	if !manager.IsFileViewed("/repo", "main", "abc123", "saved.go") {
+		t.Error("Expected the saved change to be kept")
+	}
+
+	// A lock released during the retries is picked up
+	go func() {
+		time.Sleep(50 * time.Millisecond)
+		_ = os.Remove(lockFile)
+	}()
+	if err := os.WriteFile(lockFile, nil, 0644); err != nil {
+		t.Fatalf("Failed to create lock file: %v", err)
+	}
+	if err := manager.MarkFileViewed("/repo", "main", "abc123", "file.go"); err != nil {
+		t.Fatalf("Expected save to succeed once the lock is released, got %v", err)
+	}
+	if _, err := os.Stat(lockFile); !os.IsNotExist(err) {
+		t.Error("Expected the lock to be released after saving")
+	}
+
+	// A lock left behind by a crashed process is taken over
+	if err := os.WriteFile(lockFile, nil, 0644); err != nil {
+		t.Fatalf("Failed to create lock file: %v", err)
+	}
+	old := time.Now().Add(-2 * staleLockAge)
+	if err := os.Chtimes(lockFile, old, old); err != nil {
+		t.Fatalf("Failed to age lock file: %v", err)
+	}
+	if err := manager.MarkFileViewed("/repo", "main", "abc123", "other.go"); err != nil {
+		t.Errorf("Expected a stale lock to be taken over, got %v", err)
+	}
+}
+
+func TestManagersSharingStateKeepEachOthersChanges(t *testing.T) {
+	t.Setenv("GUCK_STATE_DIR", t.TempDir())
+	first, err := NewManager()
+	if err != nil {
+		t.Fatalf("Failed to create first manager: %v", err)
+	}
+	second, err := NewManager()
+	if err != nil {
+		t.Fatalf("Failed to create second manager: %v", err)
+	}
+
+	// Each daemon changes the state loaded before the other one saved
+	fromFirst, err := first.AddComment("/repo", "main", "abc123", "a.go", nil, "From the first daemon")
+	if err != nil {
+		t.Fatalf("Failed to add comment: %v", err)
+	}
+	fromSecond, err := second.AddComment("/repo", "main", "abc123", "b.go", nil, "From the second daemon")
+	if err != nil {
+		t.Fatalf("Failed to add comment: %v", err)
+	}
+	if err := first.MarkFileViewed("/repo", "main", "abc123", "a.go"); err != nil {
+		t.Fatalf("Failed to mark file as viewed: %v", err)
+	}
+	if fromFirst.ID == fromSecond.ID {
+		t.Errorf("Expected the comments to get different IDs, both got %s", fromFirst.ID)
+	}
+
+	reloaded, err := NewManager()
+	if err != nil {
+		t.Fatalf("Failed to reload state: %v", err)
+	}
+	comments := reloaded.GetComments("/repo", "main", "abc123", nil)
+	if len(comments) != 2 {
+		t.Fatalf("Expected both daemons' comments to survive, got %+v", comments)
+	}
+	if !reloaded.IsFileViewed("/repo", "main", "abc123", "a.go") {
+		t.Error("Expected the viewed mark to be saved")
+	}
+
+	// A change blocked by the lock isn't made, and the next one builds on the file
+	lockFile := second.stateFile + ".lock"
+	if err := os.WriteFile(lockFile, nil, 0644); err != nil {
+		t.Fatalf("Failed to create lock file: %v", err)
+	}
+	if err := second.MarkFileViewed("/repo", "main", "abc123", "b.go"); !errors.Is(err, ErrStateLocked) {
+		t.Fatalf("Expected ErrStateLocked, got %v", err)
+	}
+	if err := os.Remove(lockFile); err != nil {
+		t.Fatalf("Failed to remove lock file: %v", err)
+	}
+	if err := second.MarkFileViewed("/repo", "main", "abc123", "b.go"); err != nil {
+		t.Fatalf("Failed to mark file as viewed: %v", err)
+	}
+	if !second.IsFileViewed("/repo", "main", "abc123", "a.go") || len(second.GetComments("/repo", "main", "abc123", nil)) != 2 {
+		t.Error("Expected the second daemon to pick up the first one's changes when saving")
+	}
+}
+
+func TestListReviews(t *testing.T) {
+	manager, _ := setupTestManager(t)
+
+	_ = manager.MarkFileViewed("/repo", "main", "abc", "a.go")
+	_ = manager.MarkFileViewed("/repo", "main", "abc", "b.go")
+	comment, _ := manager.AddComment("/repo", "feature", "def", "a.go", nil, "Fix this")
+	_, _ = manager.AddComment("/repo", "feature", "def", "a.go", nil, "And this")
+	_ = manager.ResolveComment("/repo", "feature", "def", comment.ID, "alice")
+	_ = manager.SetReviewSummary("/repo", "summary-only", "ghi", "LGTM")
+	_ = manager.MarkFileViewed("/other", "main", "abc", "a.go")
+	manager.state.Repos["/repo"]["main"]["abc"].ReviewedAt = 100
+	manager.state.Repos["/repo"]["main"]["abc"].ReviewLastActivityAt = 100
+	manager.state.Repos["/repo"]["feature"]["def"].ReviewLastActivityAt = 200
+
+	reviews := manager.ListReviews("/repo")
+	if len(reviews) != 2 {
+		t.Fatalf("Expected 2 reviews, got %+v", reviews)
+	}
+
+	feature := reviews[0]
+	if feature.Branch != "feature" || feature.Commit != "def" || feature.Comments != 2 || feature.UnresolvedComments != 1 || feature.ViewedFiles != 0 {
+		t.Errorf("Unexpected first review: %+v", feature)
+	}
+	mainReview := reviews[1]
+	if mainReview.Branch != "main" || mainReview.Commit != "abc" || mainReview.ViewedFiles != 2 || mainReview.Comments != 0 {
+		t.Errorf("Unexpected second review: %+v", mainReview)
+	}
+
+	if reviews := manager.ListReviews("/missing"); len(reviews) != 0 {
+		t.Errorf("Expected no reviews for an unknown repository, got %d", len(reviews))
+	}
+}
+
+func TestMarkDirectoryViewed(t *testing.T) {
+	manager, _ := setupTestManager(t)
+	repoPath, branch, commit := "/test/repo", "main", "abc123"
+
+	if err := manager.MarkFileViewed(repoPath, branch, commit, "pkg/api/old.go"); err != nil {
+		t.Fatalf("Failed to mark file as viewed: %v", err)
+	}
+	err := manager.MarkFilesViewed(repoPath, branch, commit, map[string]string{
+		"pkg/api/handler.go": "hash1",
+		"pkg/api/old.go":     "hash2",
+		"pkg/util.go":        "",
+	})
+	if err != nil {
+		t.Fatalf("Failed to mark files as viewed: %v", err)
+	}
+
+	viewed := manager.state.Repos[repoPath][branch][commit].ViewedFiles
+	if len(viewed) != 3 {
+		t.Fatalf("Expected 3 viewed files without duplicates, got %v", viewed)
+	}
+	if hash := manager.ViewedPatchHash(repoPath, branch, commit, "pkg/api/old.go"); hash != "hash2" {
+		t.Errorf("Expected the patch hash of an already viewed file to be updated, got %q", hash)
+	}
+
+	unmarked, err := manager.UnmarkDirectoryViewed(repoPath, branch, commit, "pkg/api/")
+	if err != nil {
+		t.Fatalf("Failed to unmark directory: %v", err)
+	}
+	if unmarked != 2 {
+		t.Errorf("Expected 2 files unmarked, got %d", unmarked)
+	}
+	if !manager.IsFileViewed(repoPath, branch, commit, "pkg/util.go") {
+		t.Error("Files outside the directory should stay viewed")
+	}
+	if manager.ViewedPatchHash(repoPath, branch, commit, "pkg/api/handler.go") != "" {
+		t.Error("Expected the patch hashes under the directory to be cleared")
+	}
+}
+
+func TestSaveReplacesStateFileAtomically(t *testing.T) {
+	manager, _ := setupTestManager(t)
+
+	if err := manager.MarkFileViewed("/repo", "main", "abc123", "file.go"); err != nil {
+		t.Fatalf("Failed to mark file viewed: %v", err)
+	}
+	if err := manager.MarkFileViewed("/repo", "main", "abc123", "other.go"); err != nil {
+		t.Fatalf("Failed to mark file viewed: %v", err)
+	}
+
+	entries, err := os.ReadDir(filepath.Dir(manager.stateFile))
+	if err != nil {
+		t.Fatalf("Failed to read state directory: %v", err)
+	}
+	for _, entry := range entries {
+		if strings.HasSuffix(entry.Name(), ".tmp") {
+			t.Errorf("Expected no temporary file to be left behind, found %s", entry.Name())
+		}
+	}
+
+	data, err := os.ReadFile(manager.stateFile)
+	if err != nil {
+		t.Fatalf("Failed to read state file: %v", err)
+	}
+	if !strings.Contains(string(data), "other.go") {
+		t.Error("Expected the state file to have the last write")
+	}
+}
+
+func TestNewManagerRecoversFromBackup(t *testing.T) {
+	t.Setenv("GUCK_STATE_DIR", t.TempDir())
+
+	manager, err := NewManager()
+	if err != nil {
+		t.Fatalf("Failed to create manager: %v", err)
+	}
+	if err := manager.MarkFileViewed("/repo", "main", "abc123", "file.go"); err != nil {
+		t.Fatalf("Failed to mark file viewed: %v", err)
+	}
+	// The second save backs up the state the first one wrote
+	if err := manager.MarkFileViewed("/repo", "main", "abc123", "other.go"); err != nil {
+		t.Fatalf("Failed to mark file viewed: %v", err)
+	}
+
+	// A crash that somehow truncated the state file
+	if err := os.WriteFile(manager.stateFile, []byte(`{"repos": {"/re`), 0644); err != nil {
+		t.Fatalf("Failed to corrupt state file: %v", err)
+	}
+	recovered, err := NewManager()
+	if err != nil {
+		t.Fatalf("Failed to create manager: %v", err)
+	}
+	if !recovered.IsFileViewed("/repo", "main", "abc123", "file.go") {
+		t.Error("Expected the state to be recovered from the backup")
+	}
+
+	// Without a usable backup, loading still succeeds with empty state
+	if err := os.WriteFile(manager.stateFile+atomicfile.BackupSuffix, []byte("garbage"), 0644); err != nil {
+		t.Fatalf("Failed to corrupt backup: %v", err)
+	}
+	empty, err := NewManager()
+	if err != nil {
+		t.Fatalf("Failed to create manager: %v", err)
+	}
+	if empty.IsFileViewed("/repo", "main", "abc123", "file.go") {
+		t.Error("Expected empty state when the backup is unusable too")
+	}
+}
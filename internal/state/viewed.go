@@ -0,0 +1,99 @@
+package state
+
+import (
+	"fmt"
+	"hash/fnv"
+	"path"
+	"strings"
+)
+
+// PatchHash identifies a file's patch so viewed state can detect later changes.
+// Only the removed and added lines count, so the same change hashes the same
+// however it is rendered: with other file headers or abbreviated index lines,
+// more or less context, or hunks split differently by another diff backend.
+func PatchHash(patch string) string {
+	var removed, added strings.Builder
+	last := &removed
+	inHunk, sawHunk := false, false
+	for _, line := range strings.Split(patch, "\n") {
+		switch {
+		case strings.HasPrefix(line, "diff "):
+			inHunk = false
+		case strings.HasPrefix(line, "@@"):
+			inHunk, sawHunk = true, true
+		case !inHunk:
+			// File headers, including the ---/+++ lines
+		case strings.HasPrefix(line, "-"):
+			last = &removed
+			last.WriteString(line[1:] + "\n")
+		case strings.HasPrefix(line, "+"):
+			last = &added
+			last.WriteString(line[1:] + "\n")
+		case strings.HasPrefix(line, `\`):
+			// "\ No newline at end of file" belongs to the line before it
+			last.WriteString(line + "\n")
+		}
+	}
+	if !sawHunk {
+		// Binary and mode changes only differ in their headers
+		return rawPatchHash(patch)
+	}
+
+	h := fnv.New64a()
+	_, _ = h.Write([]byte(removed.String()))
+	_, _ = h.Write([]byte{0})
+	_, _ = h.Write([]byte(added.String()))
+	return fmt.Sprintf("%016x", h.Sum64())
+}
+
+// PatchHashMatches reports whether hash, recorded when a file was marked
+// viewed, is the hash of patch. Hashes recorded before PatchHash ignored how
+// the patch was rendered hashed it whole, and still match the same patch.
+func PatchHashMatches(hash, patch string) bool {
+	return hash == PatchHash(patch) || hash == rawPatchHash(patch)
+}
+
+func rawPatchHash(patch string) string {
+	h := fnv.New64a()
+	_, _ = h.Write([]byte(patch))
+	return fmt.Sprintf("%016x", h.Sum64())
+}
+
+// ViewedInForm reports whether a file is marked viewed with patch unchanged
+// since, and whether it is marked viewed but its patch changed since, as
+// told by the hash recorded with the mark. It never changes state: a stale
+// mark stays until the file is marked or unmarked again.
+func (m *Manager) ViewedInForm(repoPath, branch, commit, filePath, patch string) (viewed, changed bool) {
+	if !m.IsFileViewed(repoPath, branch, commit, filePath) {
+		return false, false
+	}
+	hash := m.ViewedPatchHash(repoPath, branch, commit, filePath)
+	if hash == "" || PatchHashMatches(hash, patch) {
+		return true, false
+	}
+	return false, true
+}
+
+// MatchesAnyPattern reports whether filePath matches one of the globs, such
+// as the auto_view_patterns of files that count as viewed. A pattern without
+// a slash matches the file name in any directory, and a pattern ending in
+// "/**" matches everything under that directory.
+func MatchesAnyPattern(filePath string, patterns []string) bool {
+	for _, pattern := range patterns {
+		if dir, ok := strings.CutSuffix(pattern, "/**"); ok {
+			if strings.HasPrefix(filePath, dir+"/") {
+				return true
+			}
+			continue
+		}
+
+		target := filePath
+		if !strings.Contains(pattern, "/") {
+			target = path.Base(filePath)
+		}
+		if matched, _ := path.Match(pattern, target); matched {
+			return true
+		}
+	}
+	return false
+}
@@ -0,0 +1,101 @@
+package state
+
+import (
+	"strings"
+	"testing"
+)
+
+func TestPatchHashIgnoresRendering(t *testing.T) {
+	patch := "diff --git a/main.go b/main.go\n" +
+		"index 1111111..2222222 100644\n" +
+		"--- a/main.go\n" +
+		"+++ b/main.go\n" +
+		"@@ -1,3 +1,3 @@\n" +
+		" package main\n" +
+		"-var a = 1\n" +
+		"+var a = 2\n" +
+		" \n"
+	rendered := "diff --git a/main.go b/main.go\n" +
+		"index 1111111111..2222222222 100644\n" +
+		"--- a/main.go\n" +
+		"+++ b/main.go\n" +
+		"@@ -2 +2 @@ package main\n" +
+		"-var a = 1\n" +
+		"+var a = 2\n"
+	if PatchHash(patch) != PatchHash(rendered) {
+		t.Error("Expected the same change to hash the same however it is rendered")
+	}
+
+	changed := strings.Replace(patch, "+var a = 2", "+var a = 3", 1)
+	if PatchHash(patch) == PatchHash(changed) {
+		t.Error("Expected a different change to hash differently")
+	}
+
+	noNewline := patch + "\\ No newline at end of file\n"
+	if PatchHash(patch) == PatchHash(noNewline) {
+		t.Error("Expected a lost trailing newline to change the hash")
+	}
+}
+
+func TestPatchHashWithoutHunks(t *testing.T) {
+	binary := "diff --git a/logo.png b/logo.png\n" +
+		"index 1111111..2222222 100644\n" +
+		"Binary files a/logo.png and b/logo.png differ\n"
+	other := strings.Replace(binary, "2222222", "3333333", 1)
+	if PatchHash(binary) == PatchHash(other) {
+		t.Error("Expected binary patches to be told apart by their headers")
+	}
+}
+
+func TestPatchHashMatchesLegacyHash(t *testing.T) {
+	patch := "@@ -1 +1 @@\n-a\n+b\n"
+	if !PatchHashMatches(rawPatchHash(patch), patch) {
+		t.Error("Expected a hash of the whole patch to still match")
+	}
+	if PatchHashMatches(rawPatchHash(patch), "@@ -1 +1 @@\n-a\n+c\n") {
+		t.Error("Expected a hash of another patch not to match")
+	}
+}
+
+func TestViewedInFormLeavesStaleMark(t *testing.T) {
+	manager, _ := setupTestManager(t)
+
+	repoPath, branch, commit, filePath := "/test/repo", "main", "abc123", "main.go"
+	patch := "@@ -1 +1 @@\n-a\n+b\n"
+	if err := manager.MarkFileViewedWithHash(repoPath, branch, commit, filePath, PatchHash(patch)); err != nil {
+		t.Fatalf("Failed to mark file as viewed: %v", err)
+	}
+
+	if viewed, changed := manager.ViewedInForm(repoPath, branch, commit, filePath, patch); !viewed || changed {
+		t.Errorf("Expected an unchanged patch to be viewed, got viewed=%v changed=%v", viewed, changed)
+	}
+
+	if viewed, changed := manager.ViewedInForm(repoPath, branch, commit, filePath, "@@ -1 +1 @@\n-a\n+c\n"); viewed || !changed {
+		t.Errorf("Expected a changed patch to be unviewed and changed, got viewed=%v changed=%v", viewed, changed)
+	}
+	if !manager.IsFileViewed(repoPath, branch, commit, filePath) {
+		t.Error("Expected reading viewed state to leave the mark in place")
+	}
+}
+
+func TestMatchesAnyPattern(t *testing.T) {
+	patterns := []string{"*.lock", "package-lock.json", "src/__snapshots__/**", "docs/*.generated.md"}
+
+	tests := map[string]bool{
+		"Cargo.lock":                     true,
+		"web/yarn.lock":                  true,
+		"web/package-lock.json":          true,
+		"src/__snapshots__/app.snap":     true,
+		"src/__snapshots__/deep/ui.snap": true,
+		"docs/api.generated.md":          true,
+		"docs/nested/api.generated.md":   false,
+		"src/main.go":                    false,
+		"src/__snapshots__.go":           false,
+	}
+
+	for filePath, expected := range tests {
+		if got := MatchesAnyPattern(filePath, patterns); got != expected {
+			t.Errorf("MatchesAnyPattern(%q) = %v, expected %v", filePath, got, expected)
+		}
+	}
+}
@@ -1,10 +1,20 @@
 package main
 
 import (
+	"context"
+	"encoding/json"
+	"errors"
 	"fmt"
 	"os"
 	"os/exec"
+	"os/signal"
+	"path/filepath"
 	"runtime"
+	"sort"
+	"strconv"
+	"strings"
+	"syscall"
+	"time"
 
 	"github.com/fatih/color"
 	"github.com/tuist/guck/internal/cli/commands"
@@ -12,7 +22,9 @@ import (
 	"github.com/tuist/guck/internal/config"
 	"github.com/tuist/guck/internal/daemon"
 	"github.com/tuist/guck/internal/git"
+	"github.com/tuist/guck/internal/logging"
 	"github.com/tuist/guck/internal/mcp"
+	"github.com/tuist/guck/internal/notify"
 	"github.com/tuist/guck/internal/server"
 	"github.com/tuist/guck/internal/state"
 	"github.com/urfave/cli/v2"
@@ -28,13 +40,19 @@ var (
 
 func main() {
 	app := &cli.App{
-		Name:  "guck",
-		Usage: "A Git diff review tool with a web interface",
+		Name:                 "guck",
+		Usage:                "A Git diff review tool with a web interface",
+		Flags:                []cli.Flag{repoFlag()},
+		Before:               useRepo,
+		EnableBashCompletion: true,
+		ExitErrHandler:       func(*cli.Context, error) {},
 		Commands: []*cli.Command{
 			{
-				Name:  "start",
-				Usage: "Start the server (run in foreground or use & to background)",
+				Name:   "start",
+				Usage:  "Start the server (run in foreground or use & to background)",
+				Before: useRepo,
 				Flags: []cli.Flag{
+					repoFlag(),
 					&cli.IntFlag{
 						Name:    "port",
 						Aliases: []string{"p"},
@@ -45,26 +63,78 @@ func main() {
 						Aliases: []string{"b"},
 						Usage:   "Base branch to compare against",
 					},
+					&cli.BoolFlag{
+						Name:  "offline",
+						Usage: "Disable network git operations and use local refs only",
+					},
+					&cli.BoolFlag{
+						Name:  "watchdog",
+						Usage: "Exit with a distinct code when the repo disappears, the port is lost, or a handler panics (for supervisors)",
+					},
 				},
 				Action: startServerForeground,
 			},
 			{
-				Name:   "init",
-				Usage:  "Initialize shell integration (outputs shell script to eval)",
+				Name:  "init",
+				Usage: "Initialize shell integration (outputs shell script to eval)",
+				Flags: []cli.Flag{
+					&cli.StringFlag{
+						Name:  "shell",
+						Usage: "Shell to output the integration for: bash, zsh or fish (default: detected)",
+					},
+				},
 				Action: printShellIntegration,
 			},
+			{
+				Name:      "completions",
+				Usage:     "Output a tab-completion script for bash, zsh, fish or powershell",
+				ArgsUsage: "<shell>",
+				Action:    commands.PrintCompletions,
+			},
 			{
 				Name:  "daemon",
 				Usage: "Daemon management commands",
 				Subcommands: []*cli.Command{
 					{
-						Name:  "start",
-						Usage: "Start daemon for current repository",
+						Name:   "start",
+						Usage:  "Start daemon for current repository",
+						Before: useRepo,
 						Flags: []cli.Flag{
+							repoFlag(),
 							&cli.StringFlag{
 								Name:    "base",
 								Aliases: []string{"b"},
-								Usage:   "Override base branch",
+								Usage:   "Override base branch, or review against a tag, commit or other revision",
+							},
+							&cli.BoolFlag{
+								Name:  "offline",
+								Usage: "Disable network git operations and use local refs only",
+							},
+							&cli.BoolFlag{
+								Name:  "auto",
+								Usage: "Skip repositories excluded from auto-start, as the shell integration does",
+							},
+							&cli.IntFlag{
+								Name:    "port",
+								Aliases: []string{"p"},
+								Usage:   "Serve on this port instead of a random available one, failing if it's taken",
+							},
+							&cli.BoolFlag{
+								Name:  "socket",
+								Usage: "Serve on a Unix domain socket in the state directory instead of a TCP port",
+							},
+							&cli.StringFlag{
+								Name:  "socket-path",
+								Usage: "Serve on the Unix domain socket at this path instead of a TCP port",
+							},
+							&cli.BoolFlag{
+								Name:  "foreground",
+								Usage: "Run the daemon attached to this terminal instead of in the background",
+							},
+							&cli.BoolFlag{
+								Name:  "detach",
+								Usage: "Run the daemon in the background; --detach=false is the same as --foreground",
+								Value: true,
 							},
 						},
 						Action: startDaemon,
@@ -72,8 +142,30 @@ func main() {
 					{
 						Name:   "stop",
 						Usage:  "Stop daemon for current repository",
+						Before: useRepo,
+						Flags:  []cli.Flag{repoFlag()},
 						Action: stopDaemon,
 					},
+					{
+						Name:   "status",
+						Usage:  "Report whether the current repository's daemon is running (exit 0 running, 1 not responding, 2 not registered, 3 not a git repository)",
+						Flags:  []cli.Flag{repoFlag()},
+						Action: daemonStatus,
+					},
+					{
+						Name:   "attach",
+						Usage:  "Follow the current repository's daemon log and periodically print its status",
+						Before: useRepo,
+						Flags: []cli.Flag{
+							repoFlag(),
+							&cli.DurationFlag{
+								Name:  "interval",
+								Usage: "Time between status lines",
+								Value: 10 * time.Second,
+							},
+						},
+						Action: attachDaemon,
+					},
 					{
 						Name:   "stop-all",
 						Usage:  "Stop all running daemons",
@@ -89,6 +181,50 @@ func main() {
 						Usage:  "Clean up stale daemon entries",
 						Action: cleanupDaemons,
 					},
+					{
+						Name:  "reconcile",
+						Usage: "Reconcile the daemon registry with running processes",
+						Flags: []cli.Flag{
+							&cli.BoolFlag{
+								Name:  "watch",
+								Usage: "Keep reconciling periodically until interrupted",
+							},
+							&cli.DurationFlag{
+								Name:  "interval",
+								Usage: "Time between reconciliations in watch mode (minimum 5s)",
+								Value: 30 * time.Second,
+							},
+						},
+						Action: reconcileDaemons,
+					},
+					{
+						Name:  "gc",
+						Usage: "Stop idle daemons and prune stale registry entries, logs and review state",
+						Flags: []cli.Flag{
+							&cli.DurationFlag{
+								Name:  "idle",
+								Usage: "Stop daemons that haven't served a request for this long",
+								Value: 24 * time.Hour,
+							},
+							&cli.BoolFlag{
+								Name:  "no-stop-idle",
+								Usage: "Don't stop idle daemons",
+							},
+							&cli.BoolFlag{
+								Name:  "no-prune-registry",
+								Usage: "Don't remove registry entries for dead daemons",
+							},
+							&cli.BoolFlag{
+								Name:  "no-prune-logs",
+								Usage: "Don't remove log files of daemons that are no longer registered",
+							},
+							&cli.BoolFlag{
+								Name:  "no-prune-state",
+								Usage: "Don't remove review state for deleted repositories, branches and commits",
+							},
+						},
+						Action: gcDaemons,
+					},
 				},
 			},
 			{
@@ -114,11 +250,63 @@ func main() {
 					},
 				},
 			},
+			{
+				Name:   "paths",
+				Usage:  "Show where guck keeps its config, state, daemon registry and logs",
+				Action: printPaths,
+			},
 			{
 				Name:   "mcp",
 				Usage:  "Start MCP (Model Context Protocol) server for LLM integrations",
 				Action: mcpStdio,
 			},
+			{
+				Name:  "state",
+				Usage: "Review state management",
+				Subcommands: []*cli.Command{
+					{
+						Name:   "carry-forward",
+						Usage:  "Move viewed files and comments from an amended-away commit onto HEAD",
+						Before: useRepo,
+						Flags: []cli.Flag{
+							repoFlag(),
+							&cli.StringFlag{
+								Name:  "from",
+								Usage: "Commit to carry state from (defaults to the commit HEAD amended)",
+							},
+						},
+						Action: carryForwardState,
+					},
+					{
+						Name:   "events",
+						Usage:  "Print the logged review actions of the repository, oldest first",
+						Before: useRepo,
+						Flags: []cli.Flag{
+							repoFlag(),
+							&cli.DurationFlag{
+								Name:  "since",
+								Usage: "Only print events from this long ago, such as 24h (default: all)",
+							},
+							&cli.BoolFlag{
+								Name:  "json",
+								Usage: "Print the events as JSON",
+							},
+						},
+						Action: stateEvents,
+					},
+				},
+			},
+			{
+				Name:  "api",
+				Usage: "HTTP API utilities",
+				Subcommands: []*cli.Command{
+					{
+						Name:   "schema",
+						Usage:  "Print the OpenAPI 3 description of the web server API",
+						Action: printAPISchema,
+					},
+				},
+			},
 			{
 				Name:  "dev",
 				Usage: "Development utilities",
@@ -139,19 +327,249 @@ func main() {
 				},
 			},
 			{
-				Name:  "comments",
-				Usage: "Code review comments management",
+				Name:      "pr",
+				Usage:     "Review a GitHub pull request by number in a separate worktree",
+				ArgsUsage: "<number>",
+				Before:    useRepo,
+				Flags: []cli.Flag{
+					repoFlag(),
+					&cli.StringFlag{
+						Name:    "base",
+						Aliases: []string{"b"},
+						Usage:   "Base branch (required when origin isn't on GitHub)",
+					},
+				},
+				Action: commands.ReviewPullRequest,
+			},
+			{
+				Name:   "diff",
+				Usage:  "Print the files changed in the review",
+				Before: useRepo,
+				Flags: []cli.Flag{
+					repoFlag(),
+					&cli.StringFlag{
+						Name:    "base",
+						Aliases: []string{"b"},
+						Usage:   "Base branch to compare against",
+					},
+					&cli.BoolFlag{
+						Name:    "working",
+						Aliases: []string{"w"},
+						Usage:   "Review uncommitted changes only (working tree vs HEAD)",
+					},
+					&cli.StringFlag{
+						Name:  "since-commit",
+						Usage: "Only show changes made since this commit, such as the last one you reviewed",
+					},
+					&cli.StringSliceFlag{
+						Name:  "pathspec",
+						Usage: "Limit the diff to paths matching a git pathspec, such as ':(exclude)*.lock' (repeatable)",
+					},
+					&cli.BoolFlag{
+						Name:  "ignore-cherry-picks",
+						Usage: "Leave out changes whose commit is already on the base branch under another hash (by patch-id)",
+					},
+					&cli.StringFlag{
+						Name:    "format",
+						Aliases: []string{"o"},
+						Usage:   "Output format: json, toon, patch (default: human-readable)",
+						Value:   "",
+					},
+				},
+				Action: commands.Diff,
+			},
+			{
+				Name:   "watch",
+				Usage:  "Keep a live summary of the review's changed files and viewed progress in the terminal",
+				Before: useRepo,
+				Flags: []cli.Flag{
+					repoFlag(),
+					&cli.StringFlag{
+						Name:    "base",
+						Aliases: []string{"b"},
+						Usage:   "Base branch to compare against",
+					},
+					&cli.BoolFlag{
+						Name:    "working",
+						Aliases: []string{"w"},
+						Usage:   "Review uncommitted changes only (working tree vs HEAD)",
+					},
+					&cli.DurationFlag{
+						Name:  "interval",
+						Usage: "Time between refreshes",
+						Value: 2 * time.Second,
+					},
+				},
+				Action: commands.Watch,
+			},
+			{
+				Name:  "prompt",
+				Usage: "Print a one-line review summary for shell prompts, e.g. \"guck: 3 unviewed, 2 open comments\"",
+				Flags: []cli.Flag{
+					&cli.StringFlag{
+						Name:    "base",
+						Aliases: []string{"b"},
+						Usage:   "Base branch to compare against",
+					},
+					&cli.BoolFlag{
+						Name:  "json",
+						Usage: "Print the counts as JSON",
+					},
+				},
+				Action: commands.Prompt,
+			},
+			{
+				Name:   "ignore",
+				Usage:  "Stop the shell integration from auto-starting a daemon in the current repository",
+				Before: useRepo,
+				Flags: []cli.Flag{
+					repoFlag(),
+					&cli.BoolFlag{
+						Name:  "undo",
+						Usage: "Let the repository auto-start again",
+					},
+				},
+				Action: ignoreRepo,
+			},
+			{
+				Name:   "enable",
+				Usage:  "Let the shell integration auto-start a daemon in the current repository when auto-start-allowlist is on",
+				Before: useRepo,
+				Flags: []cli.Flag{
+					repoFlag(),
+					&cli.BoolFlag{
+						Name:  "undo",
+						Usage: "Remove the repository from the allowlist",
+					},
+				},
+				Action: enableRepo,
+			},
+			{
+				Name:      "review-patch",
+				Usage:     "Review a patch file, such as one from git format-patch, without applying it",
+				ArgsUsage: "<file.patch>",
+				Flags: []cli.Flag{
+					&cli.IntFlag{
+						Name:    "port",
+						Aliases: []string{"p"},
+						Usage:   "Port to run the server on (defaults to random available port)",
+					},
+				},
+				Action: reviewPatch,
+			},
+			{
+				Name:  "import",
+				Usage: "Import review comments from other tools",
+				Subcommands: []*cli.Command{
+					{
+						Name:  "github",
+						Usage: "Import a GitHub pull request's review comments into the current commit's review",
+						Flags: []cli.Flag{
+							&cli.IntFlag{
+								Name:     "pr",
+								Usage:    "Pull request number",
+								Required: true,
+							},
+							&cli.StringFlag{
+								Name:  "github-repo",
+								Usage: "GitHub repository as owner/name (defaults to the origin remote)",
+							},
+						},
+						Action: commands.ImportGitHubComments,
+					},
+					{
+						Name:      "state",
+						Usage:     "Merge comments exported from another machine (JSON) into the review state",
+						ArgsUsage: "<file.json|->",
+						Flags: []cli.Flag{
+							&cli.StringFlag{
+								Name:  "strategy",
+								Usage: "How to settle comments with the same ID but different content: ours, theirs, newest or keep-both",
+								Value: string(state.MergeKeepBoth),
+							},
+						},
+						Action: commands.ImportState,
+					},
+				},
+			},
+			{
+				Name:  "export",
+				Usage: "Export the current review, or its comments to other tools",
+				Flags: []cli.Flag{
+					&cli.StringFlag{
+						Name:  "format",
+						Usage: "Output format (markdown)",
+						Value: "markdown",
+					},
+					&cli.StringFlag{
+						Name:  "base",
+						Usage: "Base branch the review was made against (defaults to base_branch in config)",
+					},
+					&cli.StringFlag{
+						Name:    "author",
+						Aliases: []string{"a"},
+						Usage:   "Only export comments by this author",
+					},
+					&cli.StringSliceFlag{
+						Name:    "exclude-author",
+						Aliases: []string{"x"},
+						Usage:   "Skip comments by this author (adds to exclude_authors in config)",
+					},
+					&cli.StringFlag{
+						Name:    "output",
+						Aliases: []string{"O"},
+						Usage:   "Write the export to a file instead of stdout",
+					},
+				},
+				Action: commands.ExportReview,
 				Subcommands: []*cli.Command{
 					{
-						Name:  "list",
-						Usage: "List code review comments",
+						Name:  "gitlab",
+						Usage: "Map the current commit's comments to GitLab merge request discussions",
 						Flags: []cli.Flag{
+							&cli.IntFlag{
+								Name:     "mr",
+								Usage:    "Merge request IID",
+								Required: true,
+							},
+							&cli.StringFlag{
+								Name:     "project",
+								Usage:    "GitLab project ID or full path, such as group/name",
+								Required: true,
+							},
 							&cli.StringFlag{
-								Name:    "repo",
-								Aliases: []string{"r"},
-								Usage:   "Repository path (defaults to current directory)",
-								Value:   ".",
+								Name:  "base",
+								Usage: "Base branch the review was made against (defaults to base_branch in config)",
 							},
+							&cli.StringFlag{
+								Name:    "author",
+								Aliases: []string{"a"},
+								Usage:   "Only export comments by this author",
+							},
+							&cli.StringSliceFlag{
+								Name:    "exclude-author",
+								Aliases: []string{"x"},
+								Usage:   "Skip comments by this author (adds to exclude_authors in config)",
+							},
+							&cli.BoolFlag{
+								Name:  "post",
+								Usage: "Post the discussions using GITLAB_TOKEN instead of printing them as JSON",
+							},
+						},
+						Action: commands.ExportGitLab,
+					},
+				},
+			},
+			{
+				Name:  "comments",
+				Usage: "Code review comments management",
+				Subcommands: []*cli.Command{
+					{
+						Name:   "list",
+						Usage:  "List code review comments",
+						Before: useRepo,
+						Flags: []cli.Flag{
+							repoFlag(),
 							&cli.StringFlag{
 								Name:    "branch",
 								Aliases: []string{"b"},
@@ -190,13 +608,9 @@ func main() {
 						Name:      "resolve",
 						Usage:     "Mark a comment as resolved",
 						ArgsUsage: "<comment-id>",
+						Before:    useRepo,
 						Flags: []cli.Flag{
-							&cli.StringFlag{
-								Name:    "repo",
-								Aliases: []string{"r"},
-								Usage:   "Repository path (defaults to current directory)",
-								Value:   ".",
-							},
+							repoFlag(),
 							&cli.StringFlag{
 								Name:     "by",
 								Aliases:  []string{"u"},
@@ -212,6 +626,40 @@ func main() {
 						},
 						Action: commands.ResolveComment,
 					},
+					{
+						Name:   "export",
+						Usage:  "Export the current review's comments as Markdown",
+						Before: useRepo,
+						Flags: []cli.Flag{
+							repoFlag(),
+							&cli.StringFlag{
+								Name:    "branch",
+								Aliases: []string{"b"},
+								Usage:   "Branch name (defaults to the current branch)",
+							},
+							&cli.StringFlag{
+								Name:    "commit",
+								Aliases: []string{"c"},
+								Usage:   "Commit hash (defaults to HEAD)",
+							},
+							&cli.StringFlag{
+								Name:    "author",
+								Aliases: []string{"a"},
+								Usage:   "Only export comments by this author",
+							},
+							&cli.StringSliceFlag{
+								Name:    "exclude-author",
+								Aliases: []string{"x"},
+								Usage:   "Skip comments by this author (adds to exclude_authors in config)",
+							},
+							&cli.StringFlag{
+								Name:    "output",
+								Aliases: []string{"O"},
+								Usage:   "Write the export to a file instead of stdout",
+							},
+						},
+						Action: commands.ExportComments,
+					},
 				},
 			},
 			{
@@ -219,15 +667,11 @@ func main() {
 				Usage: "AI agent notes management",
 				Subcommands: []*cli.Command{
 					{
-						Name:  "add",
-						Usage: "Add an AI agent note",
+						Name:   "add",
+						Usage:  "Add an AI agent note",
+						Before: useRepo,
 						Flags: []cli.Flag{
-							&cli.StringFlag{
-								Name:    "repo",
-								Aliases: []string{"r"},
-								Usage:   "Repository path (defaults to current directory)",
-								Value:   ".",
-							},
+							repoFlag(),
 							&cli.StringFlag{
 								Name:     "file",
 								Aliases:  []string{"f"},
@@ -272,15 +716,11 @@ func main() {
 						Action: commands.AddNote,
 					},
 					{
-						Name:  "list",
-						Usage: "List AI agent notes",
+						Name:   "list",
+						Usage:  "List AI agent notes",
+						Before: useRepo,
 						Flags: []cli.Flag{
-							&cli.StringFlag{
-								Name:    "repo",
-								Aliases: []string{"r"},
-								Usage:   "Repository path (defaults to current directory)",
-								Value:   ".",
-							},
+							repoFlag(),
 							&cli.StringFlag{
 								Name:    "branch",
 								Aliases: []string{"b"},
@@ -324,13 +764,9 @@ func main() {
 						Name:      "dismiss",
 						Usage:     "Dismiss an AI agent note",
 						ArgsUsage: "<note-id>",
+						Before:    useRepo,
 						Flags: []cli.Flag{
-							&cli.StringFlag{
-								Name:    "repo",
-								Aliases: []string{"r"},
-								Usage:   "Repository path (defaults to current directory)",
-								Value:   ".",
-							},
+							repoFlag(),
 							&cli.StringFlag{
 								Name:     "by",
 								Aliases:  []string{"u"},
@@ -354,42 +790,136 @@ func main() {
 
 	if err := app.Run(os.Args); err != nil {
 		errorColor.Fprintf(os.Stderr, "Error: %v\n", err)
+
+		var exitErr *server.ExitError
+		if errors.As(err, &exitErr) {
+			os.Exit(exitErr.Code)
+		}
+		// ExitErrHandler is a no-op, so commands' exit codes are applied here
+		var exitCoder cli.ExitCoder
+		if errors.As(err, &exitCoder) {
+			os.Exit(exitCoder.ExitCode())
+		}
 		os.Exit(1)
 	}
 }
 
-func startServerForeground(c *cli.Context) error {
-	gitRepo, err := git.Open(".")
-	if err != nil {
-		return err
+// desktopNotifier returns the server's notification hook, or nil when disabled
+func desktopNotifier(enabled bool) func(title, message string) {
+	if !enabled {
+		return nil
 	}
+	return func(title, message string) {
+		_ = notify.Send(title, message) // Best effort, a missing notifier shouldn't break the review
+	}
+}
 
-	repoPath, err := gitRepo.RepoPath()
-	if err != nil {
-		return err
+// repoFlag lets a command target a repository other than the current directory
+func repoFlag() *cli.StringFlag {
+	return &cli.StringFlag{
+		Name:    "repo",
+		Aliases: []string{"C"},
+		Usage:   "Run as if guck was started in this repository",
 	}
+}
 
-	daemonMgr, err := daemon.NewManager()
-	if err != nil {
-		return err
+// startDir is the directory guck was started from. Relative --repo paths are
+// resolved against it, even after a --repo before the command changed into
+// another repository.
+var startDir, _ = os.Getwd()
+
+// useRepo changes into the root of the repository given with --repo, so
+// every command that operates on "." targets it
+func useRepo(c *cli.Context) error {
+	path := c.String("repo")
+	if path == "" || path == "." {
+		return nil
+	}
+	if !filepath.IsAbs(path) && startDir != "" {
+		path = filepath.Join(startDir, path)
 	}
 
-	cfg, err := config.Load()
+	gitRepo, err := git.Open(path)
 	if err != nil {
 		return err
 	}
 
-	baseBranch := c.String("base")
-	if baseBranch == "" {
-		baseBranch = cfg.BaseBranch
+	repoPath, err := gitRepo.RepoPath()
+	if err != nil {
+		return err
 	}
 
-	port := c.Int("port")
-	if port == 0 {
-		port, err = daemonMgr.FindAvailablePort()
-		if err != nil {
-			return err
-		}
+	if err := os.Chdir(repoPath); err != nil {
+		return fmt.Errorf("failed to change to repository %s: %w", repoPath, err)
+	}
+
+	return nil
+}
+
+// checkBase fails when the base can't be resolved, rather than starting a
+// daemon whose branch diffs all fail
+func checkBase(gitRepo *git.Repo, baseBranch string) error {
+	if _, err := gitRepo.BaseTip(baseBranch); err != nil {
+		return fmt.Errorf("%w. Pass --base with a branch, tag or commit that exists", err)
+	}
+	return nil
+}
+
+// invocationDir is the directory guck was started from, which picks the
+// [dir."<path>"] section of the repository's .guck.toml. Spawned daemons run
+// from the repository root, so they're told it through GUCK_DIR.
+func invocationDir() string {
+	if dir := os.Getenv("GUCK_DIR"); dir != "" {
+		return dir
+	}
+	dir, _ := os.Getwd() // An unknown directory just matches no section
+	return dir
+}
+
+func startServerForeground(c *cli.Context) error {
+	gitRepo, err := git.Open(".")
+	if err != nil {
+		return err
+	}
+
+	repoPath, err := gitRepo.RepoPath()
+	if err != nil {
+		return err
+	}
+
+	daemonMgr, err := daemon.NewManager()
+	if err != nil {
+		return err
+	}
+
+	cfg, err := config.LoadForDir(repoPath, invocationDir())
+	if err != nil {
+		return err
+	}
+
+	baseBranch := c.String("base")
+	if baseBranch == "" {
+		baseBranch = cfg.BaseBranch
+	}
+	if err := checkBase(gitRepo, baseBranch); err != nil {
+		return err
+	}
+
+	checkStateSize(cfg.MaxStateSizeMB)
+	archiveResolvedComments(repoPath, cfg.ArchiveResolvedDays)
+	applyStateGranularity(repoPath, cfg.StateGranularity)
+
+	logFormat, err := logging.ResolveFormat(cfg.LogFormat)
+	if err != nil {
+		return err
+	}
+
+	port := c.Int("port")
+	if port == 0 {
+		port, err = daemonMgr.FindAvailablePort(cfg.PortRangeStart, cfg.PortRangeEnd)
+		if err != nil {
+			return err
+		}
 	}
 
 	daemonInfo := &daemon.Info{
@@ -397,6 +927,7 @@ func startServerForeground(c *cli.Context) error {
 		Port:       port,
 		RepoPath:   repoPath,
 		BaseBranch: baseBranch,
+		StartedAt:  time.Now().Unix(),
 	}
 
 	if err := daemonMgr.RegisterDaemon(daemonInfo); err != nil {
@@ -405,16 +936,86 @@ func startServerForeground(c *cli.Context) error {
 
 	successColor.Printf("✓ Starting guck server for %s\n", repoPath)
 	infoColor.Print("Server running on ")
-	urlColor.Printf("http://localhost:%d\n", port)
+	urlColor.Printf("%s://localhost:%d%s/\n", cfg.URLScheme(), port, config.NormalizeBasePath(cfg.BasePath))
 	infoColor.Println("Press Ctrl+C to stop")
 
-	return server.Start(port, baseBranch)
+	return server.Start(port, baseBranch, server.Options{
+		Offline:            c.Bool("offline") || cfg.Offline,
+		CommitAbbrevLen:    cfg.CommitAbbrevLen,
+		BasePath:           config.NormalizeBasePath(cfg.BasePath),
+		MaxCommentLength:   cfg.MaxCommentLength,
+		DiffAlgorithm:      cfg.DiffAlgorithm,
+		DiffBackend:        cfg.DiffBackend,
+		Textconv:           cfg.Textconv,
+		RecurseSubmodules:  cfg.RecurseSubmodules,
+		MaxFiles:           cfg.MaxFiles,
+		MaxPatchKB:         cfg.MaxPatchKB,
+		AutoViewPatterns:   cfg.AutoViewPatterns,
+		CommentTemplates:   cfg.CommentTemplates,
+		ReviewChecklist:    cfg.ReviewChecklist,
+		StaleBaseCommits:   cfg.StaleBaseCommits,
+		TLSCert:            cfg.TLSCert,
+		TLSKey:             cfg.TLSKey,
+		Watchdog:           c.Bool("watchdog"),
+		Notify:             desktopNotifier(cfg.Notifications),
+		Logger:             logging.New(os.Stdout, logFormat),
+		CarryForwardViewed: cfg.CarryForwardViewed,
+	})
 }
 
-func printShellIntegration(c *cli.Context) error {
-	script := `
+// reviewPatch serves a patch file in the foreground. It doesn't register a
+// daemon, since the daemon registry is keyed by repository.
+func reviewPatch(c *cli.Context) error {
+	if c.NArg() != 1 {
+		return fmt.Errorf("requires exactly 1 argument: the patch file")
+	}
+	patchFile := c.Args().Get(0)
+
+	cfg, err := config.Load()
+	if err != nil {
+		return err
+	}
+
+	port := c.Int("port")
+	if port == 0 {
+		daemonMgr, err := daemon.NewManager()
+		if err != nil {
+			return err
+		}
+		port, err = daemonMgr.FindAvailablePort(cfg.PortRangeStart, cfg.PortRangeEnd)
+		if err != nil {
+			return err
+		}
+	}
+
+	successColor.Printf("✓ Reviewing %s\n", patchFile)
+	infoColor.Print("Server running on ")
+	urlColor.Printf("%s://localhost:%d%s/\n", cfg.URLScheme(), port, config.NormalizeBasePath(cfg.BasePath))
+	infoColor.Println("Press Ctrl+C to stop")
+
+	return server.StartPatch(port, patchFile, server.Options{
+		CommitAbbrevLen:  cfg.CommitAbbrevLen,
+		BasePath:         config.NormalizeBasePath(cfg.BasePath),
+		MaxCommentLength: cfg.MaxCommentLength,
+		MaxFiles:         cfg.MaxFiles,
+		MaxPatchKB:       cfg.MaxPatchKB,
+		AutoViewPatterns: cfg.AutoViewPatterns,
+		CommentTemplates: cfg.CommentTemplates,
+		ReviewChecklist:  cfg.ReviewChecklist,
+		TLSCert:          cfg.TLSCert,
+		TLSKey:           cfg.TLSKey,
+	})
+}
+
+// posixShellIntegration is the integration for bash and zsh, which share
+// one script that picks its cd hook at runtime
+const posixShellIntegration = `
 # Guck shell integration
 
+# Re-evaluating the integration (nested rc files, re-sourcing) is a no-op
+if [ -z "$_GUCK_INIT_DONE" ]; then
+_GUCK_INIT_DONE=1
+
 # Track the current git repository path
 _GUCK_CURRENT_REPO=""
 
@@ -441,7 +1042,7 @@ _guck_auto_manage() {
 
     # If we entered a git repo, start its daemon
     if [ -n "$new_repo" ] && [ "$_GUCK_CURRENT_REPO" != "$new_repo" ]; then
-        (guck daemon start >/dev/null 2>&1 &)
+        (guck daemon start --auto >/dev/null 2>&1 &)
         if [ $? -eq 0 ]; then
             printf "\033[1;36m→\033[0m Run \033[1;34mguck\033[0m to inspect the project's diff\n"
         fi
@@ -451,25 +1052,111 @@ _guck_auto_manage() {
     _GUCK_CURRENT_REPO="$new_repo"
 }
 
-# Hook into cd command
+# Hook into cd command, without adding the hook twice
 if [ -n "$ZSH_VERSION" ]; then
-    chpwd_functions+=(_guck_auto_manage)
+    _guck_hooked=""
+    for _guck_fn in "${chpwd_functions[@]}"; do
+        [ "$_guck_fn" = "_guck_auto_manage" ] && _guck_hooked=1
+    done
+    [ -z "$_guck_hooked" ] && chpwd_functions+=(_guck_auto_manage)
+    unset _guck_hooked _guck_fn
 elif [ -n "$BASH_VERSION" ]; then
-    _guck_original_cd=$(declare -f cd)
-    cd() {
-        builtin cd "$@"
-        _guck_auto_manage
-    }
+    if ! declare -f cd 2>/dev/null | grep -q _guck_auto_manage; then
+        cd() {
+            builtin cd "$@" || return
+            _guck_auto_manage
+        }
+    fi
 fi
 
 # Initialize for current directory if it's a git repo
 _guck_auto_manage
+fi
+`
+
+// fishShellIntegration is the fish equivalent of posixShellIntegration,
+// hooked on changes to PWD rather than on cd
+const fishShellIntegration = `
+# Guck shell integration
+
+# Re-evaluating the integration (nested config files, re-sourcing) is a no-op
+if not set -q _GUCK_INIT_DONE
+    set -g _GUCK_INIT_DONE 1
+
+    # Track the current git repository path
+    set -g _GUCK_CURRENT_REPO ""
+
+    # Auto-start/stop daemons based on directory changes
+    function _guck_auto_start --on-variable PWD
+        if not command -q guck
+            return
+        end
+
+        set -l new_repo ""
+        if git rev-parse --git-dir >/dev/null 2>&1
+            set new_repo (git rev-parse --show-toplevel 2>/dev/null)
+        end
+
+        # If we left a git repo, stop its daemon
+        if test -n "$_GUCK_CURRENT_REPO"; and test "$_GUCK_CURRENT_REPO" != "$new_repo"
+            command guck daemon stop --repo "$_GUCK_CURRENT_REPO" >/dev/null 2>&1 &
+            disown
+        end
+
+        # If we entered a git repo, start its daemon
+        if test -n "$new_repo"; and test "$_GUCK_CURRENT_REPO" != "$new_repo"
+            command guck daemon start --auto >/dev/null 2>&1 &
+            disown
+            printf "\e[1;36m→\e[0m Run \e[1;34mguck\e[0m to inspect the project's diff\n"
+        end
+
+        # Update the tracked repo path
+        set -g _GUCK_CURRENT_REPO "$new_repo"
+    end
+
+    # Initialize for current directory if it's a git repo
+    _guck_auto_start
+end
 `
-	fmt.Println(script)
+
+func printShellIntegration(c *cli.Context) error {
+	shell := c.String("shell")
+	if shell == "" {
+		shell = detectShell()
+	}
+
+	switch shell {
+	case "bash", "zsh":
+		fmt.Println(posixShellIntegration)
+	case "fish":
+		fmt.Println(fishShellIntegration)
+	default:
+		return fmt.Errorf("unsupported shell '%s' (expected bash, zsh or fish)", shell)
+	}
 	return nil
 }
 
+// detectShell guesses the shell that will evaluate "guck init". Fish doesn't
+// export FISH_VERSION by default, so the login shell in SHELL is the fallback;
+// anything unrecognized gets the bash/zsh script.
+func detectShell() string {
+	if os.Getenv("FISH_VERSION") != "" {
+		return "fish"
+	}
+	if filepath.Base(os.Getenv("SHELL")) == "fish" {
+		return "fish"
+	}
+	return "bash"
+}
+
 func startDaemon(c *cli.Context) error {
+	// The shell integration runs this on every cd, so settle the common case of
+	// a daemon that's already running before opening the repository or
+	// loading any config
+	if repoPath, ok := git.FindRepoPath("."); ok && daemonAlreadyRunning(repoPath) {
+		return nil
+	}
+
 	gitRepo, err := git.Open(".")
 	if err != nil {
 		return err
@@ -485,17 +1172,52 @@ func startDaemon(c *cli.Context) error {
 		return err
 	}
 
+	if c.Bool("auto") {
+		allowed, err := autoStartAllowed(daemonMgr, repoPath)
+		if err != nil || !allowed {
+			return err
+		}
+	}
+
+	socketPath := c.String("socket-path")
+	if socketPath == "" && c.Bool("socket") {
+		socketPath = daemonMgr.SocketPath(repoPath)
+	}
+	if socketPath != "" {
+		// The daemon runs from the repository root, so pass it an absolute path
+		if socketPath, err = filepath.Abs(socketPath); err != nil {
+			return err
+		}
+	}
+
 	// Check if daemon already running
+	requestedPort := c.Int("port")
+	if requestedPort != 0 && socketPath != "" {
+		return fmt.Errorf("--port can't be combined with --socket or --socket-path")
+	}
 	if info, _ := daemonMgr.GetDaemonForRepo(repoPath); info != nil {
 		if daemonMgr.IsDaemonRunning(info.PID) {
+			if requestedPort != 0 && requestedPort != info.Port {
+				return fmt.Errorf("daemon for %s is already running on port %d; stop it first to use port %d", repoPath, info.Port, requestedPort)
+			}
+			if socketPath != "" && socketPath != info.Socket {
+				return fmt.Errorf("daemon for %s is already running on %s; stop it first to use socket %s", repoPath, info.Endpoint(), socketPath)
+			}
 			return nil
 		}
 		_ = daemonMgr.UnregisterDaemon(repoPath)
 	}
 
-	cfg, err := config.Load()
+	cfg, err := config.LoadForDir(repoPath, invocationDir())
 	if err != nil {
-		return err
+		// Inside the daemon process, fall back to defaults with a warning in the log
+		var parseErr *config.ParseError
+		if os.Getenv("GUCK_DAEMON") != "1" || !errors.As(err, &parseErr) {
+			return err
+		}
+		warningColor.Fprintf(os.Stderr, "⚠ %v\n", err)
+		warningColor.Fprintln(os.Stderr, "⚠ Falling back to default configuration")
+		cfg = config.Default()
 	}
 
 	baseBranch := c.String("base")
@@ -503,25 +1225,91 @@ func startDaemon(c *cli.Context) error {
 		baseBranch = cfg.BaseBranch
 	}
 
-	port, err := daemonMgr.FindAvailablePort()
+	offline := c.Bool("offline") || cfg.Offline
+
+	port := requestedPort
+	switch {
+	case socketPath != "":
+		// Served on the socket; no port is bound
+	case port != 0:
+		err = daemonMgr.CheckPort(port)
+	default:
+		port, err = daemonMgr.FindAvailablePort(cfg.PortRangeStart, cfg.PortRangeEnd)
+	}
+	if err != nil {
+		return err
+	}
+
+	// Resolved before spawning too, so a bad GUCK_LOG_FORMAT is reported here
+	logFormat, err := logging.ResolveFormat(cfg.LogFormat)
 	if err != nil {
 		return err
 	}
 
-	// Check if we're the daemon process
-	if os.Getenv("GUCK_DAEMON") == "1" {
+	isDaemonProcess := os.Getenv("GUCK_DAEMON") == "1"
+	if !isDaemonProcess {
+		// Fail here rather than in the background process, where the error would only reach the log
+		if _, err := server.LoadTLSConfig(cfg.TLSCert, cfg.TLSKey); err != nil {
+			return err
+		}
+		// The shell integration starts daemons in every repository, and
+		// working-tree reviews don't need the base
+		if !c.Bool("auto") {
+			if err := checkBase(gitRepo, baseBranch); err != nil {
+				return err
+			}
+		}
+
+		checkStateSize(cfg.MaxStateSizeMB)
+		archiveResolvedComments(repoPath, cfg.ArchiveResolvedDays)
+		applyStateGranularity(repoPath, cfg.StateGranularity)
+	}
+
+	// In the foreground, and in the background process spawned below, serve
+	// from this process
+	foreground := c.Bool("foreground") || !c.Bool("detach")
+	if foreground || isDaemonProcess {
 		daemonInfo := &daemon.Info{
 			PID:        os.Getpid(),
 			Port:       port,
+			Socket:     socketPath,
 			RepoPath:   repoPath,
 			BaseBranch: baseBranch,
+			StartedAt:  time.Now().Unix(),
 		}
 
 		if err := daemonMgr.RegisterDaemon(daemonInfo); err != nil {
 			return err
 		}
-
-		return server.Start(port, baseBranch)
+		// Unregister once the server has drained, unless another daemon has
+		// taken over the repository in the meantime
+		defer func() { _ = daemonMgr.UnregisterDaemonPID(repoPath, daemonInfo.PID) }()
+
+		return server.Start(port, baseBranch, server.Options{
+			Offline:           offline,
+			CommitAbbrevLen:   cfg.CommitAbbrevLen,
+			BasePath:          config.NormalizeBasePath(cfg.BasePath),
+			MaxCommentLength:  cfg.MaxCommentLength,
+			DiffAlgorithm:     cfg.DiffAlgorithm,
+			DiffBackend:       cfg.DiffBackend,
+			Textconv:          cfg.Textconv,
+			RecurseSubmodules: cfg.RecurseSubmodules,
+			MaxFiles:          cfg.MaxFiles,
+			MaxPatchKB:        cfg.MaxPatchKB,
+			AutoViewPatterns:  cfg.AutoViewPatterns,
+			CommentTemplates:  cfg.CommentTemplates,
+			ReviewChecklist:   cfg.ReviewChecklist,
+			StaleBaseCommits:  cfg.StaleBaseCommits,
+			Socket:            socketPath,
+			TLSCert:           cfg.TLSCert,
+			TLSKey:            cfg.TLSKey,
+			Notify:            desktopNotifier(cfg.Notifications),
+			OnActivity: func() {
+				_ = daemonMgr.TouchActivity(repoPath) // Best effort, only used to detect idle daemons
+			},
+			Logger:             logging.New(os.Stdout, logFormat),
+			CarryForwardViewed: cfg.CarryForwardViewed,
+		})
 	}
 
 	// Spawn daemon process
@@ -537,35 +1325,72 @@ func startDaemon(c *cli.Context) error {
 	}
 	defer logFile.Close()
 
-	args := []string{"daemon", "start"}
+	// Hand the port or socket over so the daemon serves on the one reported below
+	args := []string{"daemon", "start", "--port", strconv.Itoa(port)}
+	if socketPath != "" {
+		args = []string{"daemon", "start", "--socket-path", socketPath}
+	}
 	if baseBranch != "" {
 		args = append(args, "--base", baseBranch)
 	}
+	if offline {
+		args = append(args, "--offline")
+	}
 
 	cmd := exec.Command(exe, args...)
-	cmd.Env = append(os.Environ(), "GUCK_DAEMON=1")
+	cmd.Env = append(os.Environ(), "GUCK_DAEMON=1", "GUCK_DIR="+invocationDir())
 	cmd.Dir = repoPath
 	cmd.Stdout = logFile
 	cmd.Stderr = logFile
+	cmd.SysProcAttr = daemon.DetachAttr()
 
 	if err := cmd.Start(); err != nil {
 		return err
 	}
 
+	// Report a daemon that fails to start here, rather than leaving it to be
+	// found in the log
+	exited := make(chan error, 1)
+	go func() { exited <- cmd.Wait() }()
+	starting := &daemon.Info{Port: port, Socket: socketPath}
+	if err := starting.WaitForStartup(exited, daemonStartupTimeout); err != nil {
+		return fmt.Errorf("%w. Check its log at %s", err, logPath)
+	}
+
 	successColor.Printf("✓ Started daemon for %s\n", repoPath)
-	infoColor.Printf("  Port: %d | PID: %d\n", port, cmd.Process.Pid)
+	if socketPath != "" {
+		infoColor.Printf("  Socket: %s | PID: %d\n", socketPath, cmd.Process.Pid)
+	} else {
+		infoColor.Printf("  Port: %d | PID: %d\n", port, cmd.Process.Pid)
+	}
 	return nil
 }
 
-func stopDaemon(c *cli.Context) error {
+// daemonStartupTimeout bounds how long "guck daemon start" waits for the
+// spawned daemon to accept connections
+const daemonStartupTimeout = 10 * time.Second
+
+// Exit codes of "guck daemon status", so scripts can branch without parsing output
+const (
+	daemonStatusNotResponding = 1
+	daemonStatusNotRegistered = 2
+	daemonStatusNotARepo      = 3
+)
+
+func daemonStatus(c *cli.Context) error {
+	// Resolve --repo here rather than in a Before hook, so a bad path gets its own exit code
+	if err := useRepo(c); err != nil {
+		return cli.Exit(err, daemonStatusNotARepo)
+	}
+
 	gitRepo, err := git.Open(".")
 	if err != nil {
-		return err
+		return cli.Exit(err, daemonStatusNotARepo)
 	}
 
 	repoPath, err := gitRepo.RepoPath()
 	if err != nil {
-		return err
+		return cli.Exit(err, daemonStatusNotARepo)
 	}
 
 	daemonMgr, err := daemon.NewManager()
@@ -574,90 +1399,190 @@ func stopDaemon(c *cli.Context) error {
 	}
 
 	info, err := daemonMgr.GetDaemonForRepo(repoPath)
-	if err != nil || info == nil {
-		warningColor.Println("⚠ No daemon running for this repository")
-		return nil
-	}
-
-	if err := daemonMgr.StopDaemon(info.PID); err != nil {
+	if err != nil {
 		return err
 	}
+	if info == nil {
+		return cli.Exit(fmt.Sprintf("no daemon registered for %s", repoPath), daemonStatusNotRegistered)
+	}
 
-	if err := daemonMgr.UnregisterDaemon(repoPath); err != nil {
-		return err
+	if !daemonMgr.IsDaemonRunning(info.PID) {
+		return cli.Exit(fmt.Sprintf("daemon for %s (PID: %d) is registered but not running", repoPath, info.PID), daemonStatusNotResponding)
+	}
+	if !info.IsListening() {
+		return cli.Exit(fmt.Sprintf("daemon for %s (PID: %d) is not accepting connections on %s", repoPath, info.PID, info.Endpoint()), daemonStatusNotResponding)
 	}
 
-	successColor.Printf("✓ Stopped daemon for %s\n", repoPath)
+	successColor.Printf("✓ Daemon running for %s\n", repoPath)
+	if info.Socket != "" {
+		infoColor.Printf("  Socket: %s | PID: %d\n", info.Socket, info.PID)
+	} else {
+		infoColor.Printf("  Port: %d | PID: %d\n", info.Port, info.PID)
+	}
 	return nil
 }
 
-func stopAllDaemons(c *cli.Context) error {
-	daemonMgr, err := daemon.NewManager()
+func attachDaemon(c *cli.Context) error {
+	gitRepo, err := git.Open(".")
 	if err != nil {
 		return err
 	}
 
-	daemons, err := daemonMgr.ListDaemons()
+	repoPath, err := gitRepo.RepoPath()
 	if err != nil {
 		return err
 	}
 
-	for _, info := range daemons {
-		if daemonMgr.IsDaemonRunning(info.PID) {
-			_ = daemonMgr.StopDaemon(info.PID)
-			_ = daemonMgr.UnregisterDaemon(info.RepoPath)
-			successColor.Printf("✓ Stopped daemon for %s\n", info.RepoPath)
-		}
+	daemonMgr, err := daemon.NewManager()
+	if err != nil {
+		return err
+	}
+
+	info, err := daemonMgr.GetDaemonForRepo(repoPath)
+	if err != nil || info == nil {
+		return fmt.Errorf("no daemon running for this repository. Run 'guck daemon start' first")
+	}
+
+	ctx, stop := signal.NotifyContext(context.Background(), os.Interrupt, syscall.SIGTERM)
+	defer stop()
+
+	infoColor.Printf("Attached to daemon for %s (Ctrl+C to detach)\n", repoPath)
+	go printDaemonStatus(ctx, daemonMgr, repoPath, c.Duration("interval"))
+
+	if err := daemon.FollowFile(ctx, daemonMgr.GetLogPath(repoPath), os.Stdout); err != nil {
+		return err
 	}
 
+	infoColor.Println("Detached")
 	return nil
 }
 
-func listDaemons(c *cli.Context) error {
+// printDaemonStatus prints the daemon's liveness, port and uptime every interval until ctx is done
+func printDaemonStatus(ctx context.Context, daemonMgr *daemon.Manager, repoPath string, interval time.Duration) {
+	if interval < time.Second {
+		interval = time.Second
+	}
+	ticker := time.NewTicker(interval)
+	defer ticker.Stop()
+
+	for {
+		info, _ := daemonMgr.GetDaemonForRepo(repoPath)
+		switch {
+		case info == nil:
+			warningColor.Println("● Daemon is no longer registered")
+		case !daemonMgr.IsDaemonRunning(info.PID):
+			errorColor.Printf("● Daemon (PID: %d) is not running\n", info.PID)
+		default:
+			port := "listening"
+			if !info.IsListening() {
+				port = "not accepting connections"
+			}
+			uptime := "unknown"
+			if info.StartedAt != 0 {
+				uptime = time.Since(time.Unix(info.StartedAt, 0)).Truncate(time.Second).String()
+			}
+			successColor.Printf("● Daemon running (PID: %d, %s %s, up %s)\n", info.PID, info.Endpoint(), port, uptime)
+		}
+
+		select {
+		case <-ctx.Done():
+			return
+		case <-ticker.C:
+		}
+	}
+}
+
+// daemonAlreadyRunning looks up repoPath's daemon in the registry and checks
+// that its process is alive
+func daemonAlreadyRunning(repoPath string) bool {
 	daemonMgr, err := daemon.NewManager()
+	if err != nil {
+		return false
+	}
+
+	info, _ := daemonMgr.GetDaemonForRepo(repoPath)
+	return info != nil && daemonMgr.IsDaemonRunning(info.PID)
+}
+
+// autoStartAllowed reports whether the shell integration may start a daemon
+// for repoPath, given the ignored and, in allowlist mode, enabled repositories
+func autoStartAllowed(daemonMgr *daemon.Manager, repoPath string) (bool, error) {
+	cfg, err := config.Load()
+	if err != nil {
+		return false, err
+	}
+
+	autoStart, err := daemonMgr.LoadAutoStart()
+	if err != nil {
+		return false, err
+	}
+	return autoStart.Allows(repoPath, cfg.AutoStartAllowlist), nil
+}
+
+func ignoreRepo(c *cli.Context) error {
+	gitRepo, err := git.Open(".")
 	if err != nil {
 		return err
 	}
 
-	if err := daemonMgr.CleanupStaleDaemons(); err != nil {
+	repoPath, err := gitRepo.RepoPath()
+	if err != nil {
 		return err
 	}
 
-	daemons, err := daemonMgr.ListDaemons()
+	daemonMgr, err := daemon.NewManager()
 	if err != nil {
 		return err
 	}
 
-	if len(daemons) == 0 {
-		warningColor.Println("⚠ No running daemons")
+	if c.Bool("undo") {
+		if err := daemonMgr.SetIgnored(repoPath, false); err != nil {
+			return err
+		}
+		successColor.Printf("✓ %s will auto-start again\n", repoPath)
 		return nil
 	}
 
-	infoColor.Println("Running daemons:")
-	for _, info := range daemons {
-		fmt.Printf("  %s - ", info.RepoPath)
-		urlColor.Printf("http://localhost:%d", info.Port)
-		fmt.Printf(" (PID: %d)\n", info.PID)
+	if err := daemonMgr.SetIgnored(repoPath, true); err != nil {
+		return err
 	}
-
+	successColor.Printf("✓ The shell integration won't auto-start a daemon for %s\n", repoPath)
+	infoColor.Println("  Run guck daemon stop to stop a daemon that's already running")
 	return nil
 }
 
-func cleanupDaemons(c *cli.Context) error {
+func enableRepo(c *cli.Context) error {
+	gitRepo, err := git.Open(".")
+	if err != nil {
+		return err
+	}
+
+	repoPath, err := gitRepo.RepoPath()
+	if err != nil {
+		return err
+	}
+
 	daemonMgr, err := daemon.NewManager()
 	if err != nil {
 		return err
 	}
 
-	if err := daemonMgr.CleanupStaleDaemons(); err != nil {
+	if err := daemonMgr.SetEnabled(repoPath, !c.Bool("undo")); err != nil {
 		return err
 	}
+	if c.Bool("undo") {
+		successColor.Printf("✓ Removed %s from the auto-start allowlist\n", repoPath)
+	} else {
+		successColor.Printf("✓ Added %s to the auto-start allowlist\n", repoPath)
+	}
 
-	successColor.Println("✓ Cleaned up stale daemon entries")
+	if cfg, err := config.Load(); err == nil && !cfg.AutoStartAllowlist {
+		infoColor.Println("  The allowlist only applies with guck config set auto-start-allowlist true")
+	}
 	return nil
 }
 
-func openBrowser(c *cli.Context) error {
+func stopDaemon(c *cli.Context) error {
 	gitRepo, err := git.Open(".")
 	if err != nil {
 		return err
@@ -673,41 +1598,358 @@ func openBrowser(c *cli.Context) error {
 		return err
 	}
 
-	if err := daemonMgr.CleanupStaleDaemons(); err != nil {
-		return err
-	}
-
 	info, err := daemonMgr.GetDaemonForRepo(repoPath)
 	if err != nil || info == nil {
-		return fmt.Errorf("no daemon running for this repository. Run 'guck daemon start' first")
+		warningColor.Println("⚠ No daemon running for this repository")
+		return nil
 	}
 
-	if !daemonMgr.IsDaemonRunning(info.PID) {
-		_ = daemonMgr.UnregisterDaemon(repoPath)
-		return fmt.Errorf("daemon is not running. Run 'guck daemon start' first")
+	if err := daemonMgr.StopDaemon(info.PID); err != nil {
+		return err
 	}
 
-	url := fmt.Sprintf("http://localhost:%d", info.Port)
-	infoColor.Print("Opening ")
-	urlColor.Print(url)
-	infoColor.Println(" in your browser...")
-
-	var cmd *exec.Cmd
-	switch runtime.GOOS {
-	case "darwin":
-		cmd = exec.Command("open", url)
-	case "windows":
-		cmd = exec.Command("cmd", "/C", "start", url)
-	default:
-		cmd = exec.Command("xdg-open", url)
+	if err := daemonMgr.UnregisterDaemonPID(repoPath, info.PID); err != nil {
+		return err
 	}
 
-	return cmd.Start()
+	successColor.Printf("✓ Stopped daemon for %s\n", repoPath)
+	return nil
 }
 
-func setConfig(c *cli.Context) error {
-	if c.NArg() != 2 {
-		return fmt.Errorf("requires exactly 2 arguments: key and value")
+func stopAllDaemons(c *cli.Context) error {
+	daemonMgr, err := daemon.NewManager()
+	if err != nil {
+		return err
+	}
+
+	daemons, err := daemonMgr.ListDaemons()
+	if err != nil {
+		return err
+	}
+
+	for _, info := range daemons {
+		if daemonMgr.IsDaemonRunning(info.PID) {
+			_ = daemonMgr.StopDaemon(info.PID)
+			_ = daemonMgr.UnregisterDaemonPID(info.RepoPath, info.PID)
+			successColor.Printf("✓ Stopped daemon for %s\n", info.RepoPath)
+		}
+	}
+
+	return nil
+}
+
+func listDaemons(c *cli.Context) error {
+	daemonMgr, err := daemon.NewManager()
+	if err != nil {
+		return err
+	}
+
+	if err := daemonMgr.CleanupStaleDaemons(); err != nil {
+		return err
+	}
+
+	daemons, err := daemonMgr.ListDaemons()
+	if err != nil {
+		return err
+	}
+
+	if len(daemons) == 0 {
+		warningColor.Println("⚠ No running daemons")
+		return nil
+	}
+
+	scheme := "http"
+	if cfg, err := config.Load(); err == nil {
+		scheme = cfg.URLScheme()
+	}
+
+	infoColor.Println("Running daemons:")
+	for _, info := range daemons {
+		fmt.Printf("  %s - ", info.RepoPath)
+		if info.Socket != "" {
+			urlColor.Printf("unix:%s", info.Socket)
+		} else {
+			urlColor.Printf("%s://localhost:%d", scheme, info.Port)
+		}
+		fmt.Printf(" (PID: %d)\n", info.PID)
+	}
+
+	return nil
+}
+
+func cleanupDaemons(c *cli.Context) error {
+	daemonMgr, err := daemon.NewManager()
+	if err != nil {
+		return err
+	}
+
+	if err := daemonMgr.CleanupStaleDaemons(); err != nil {
+		return err
+	}
+
+	successColor.Println("✓ Cleaned up stale daemon entries")
+	return nil
+}
+
+// minReconcileInterval rate-limits reconcile --watch so it stays lightweight
+const minReconcileInterval = 5 * time.Second
+
+func reconcileDaemons(c *cli.Context) error {
+	daemonMgr, err := daemon.NewManager()
+	if err != nil {
+		return err
+	}
+
+	interval := c.Duration("interval")
+	if interval < minReconcileInterval {
+		warningColor.Printf("⚠ Interval %s is below the minimum, using %s\n", interval, minReconcileInterval)
+		interval = minReconcileInterval
+	}
+
+	for {
+		result, err := daemonMgr.Reconcile()
+		if err != nil {
+			return err
+		}
+
+		for _, info := range result.Removed {
+			successColor.Printf("✓ Removed stale entry for %s (PID: %d)\n", info.RepoPath, info.PID)
+		}
+		for _, info := range result.Unreachable {
+			warningColor.Printf("⚠ Daemon for %s (PID: %d) is not accepting connections on %s\n", info.RepoPath, info.PID, info.Endpoint())
+		}
+
+		if !c.Bool("watch") {
+			if len(result.Removed) == 0 && len(result.Unreachable) == 0 {
+				successColor.Println("✓ Daemon registry is up to date")
+			}
+			return nil
+		}
+
+		time.Sleep(interval)
+	}
+}
+
+func gcDaemons(c *cli.Context) error {
+	daemonMgr, err := daemon.NewManager()
+	if err != nil {
+		return err
+	}
+
+	if c.Bool("no-stop-idle") {
+		infoColor.Println("Skipping idle daemons")
+	} else {
+		daemons, err := daemonMgr.ListDaemons()
+		if err != nil {
+			return err
+		}
+
+		stopped := 0
+		for _, info := range daemons {
+			lastActivity, ok := daemonMgr.LastActivity(info.RepoPath)
+			if !ok || time.Since(lastActivity) < c.Duration("idle") || !daemonMgr.IsDaemonRunning(info.PID) {
+				continue
+			}
+
+			if err := daemonMgr.StopDaemon(info.PID); err != nil {
+				warningColor.Printf("⚠ Failed to stop idle daemon for %s: %v\n", info.RepoPath, err)
+				continue
+			}
+			_ = daemonMgr.UnregisterDaemonPID(info.RepoPath, info.PID)
+			successColor.Printf("✓ Stopped daemon for %s (idle since %s)\n", info.RepoPath, lastActivity.Format(time.RFC3339))
+			stopped++
+		}
+		infoColor.Printf("Stopped %d idle daemon(s)\n", stopped)
+	}
+
+	if c.Bool("no-prune-registry") {
+		infoColor.Println("Skipping registry pruning")
+	} else {
+		result, err := daemonMgr.Reconcile()
+		if err != nil {
+			return err
+		}
+		for _, info := range result.Unreachable {
+			warningColor.Printf("⚠ Daemon for %s (PID: %d) is not accepting connections on %s\n", info.RepoPath, info.PID, info.Endpoint())
+		}
+		infoColor.Printf("Removed %d stale registry entries\n", len(result.Removed))
+	}
+
+	if c.Bool("no-prune-logs") {
+		infoColor.Println("Skipping log pruning")
+	} else {
+		removed, err := daemonMgr.PruneOrphanedFiles()
+		if err != nil {
+			return err
+		}
+		infoColor.Printf("Removed %d orphaned log file(s)\n", len(removed))
+	}
+
+	if c.Bool("no-prune-state") {
+		infoColor.Println("Skipping review state pruning")
+	} else {
+		stateMgr, err := state.NewManager()
+		if err != nil {
+			return err
+		}
+
+		removed, err := stateMgr.Prune(server.ReviewStateExists())
+		if err != nil {
+			return err
+		}
+		infoColor.Printf("Removed review state for %d deleted branch/commit(s)\n", removed)
+	}
+
+	successColor.Println("✓ Garbage collection complete")
+	return nil
+}
+
+// checkStateSize warns when the review state file, shared by every repository,
+// has grown past maxSizeMB and prunes the state of deleted branches and
+// commits to shrink it. A limit of 0 disables the check.
+func checkStateSize(maxSizeMB int) {
+	if maxSizeMB <= 0 {
+		return
+	}
+
+	stateMgr, err := state.NewManager()
+	if err != nil {
+		return // The server reports state errors when it loads the state itself
+	}
+	size, err := stateMgr.FileSize()
+	if err != nil || size <= int64(maxSizeMB)<<20 {
+		return
+	}
+
+	warningColor.Printf("⚠ Review state file is %.1f MB, over the %d MB limit (max-state-size-mb); pruning deleted branches and commits\n", float64(size)/(1<<20), maxSizeMB)
+	removed, err := stateMgr.Prune(server.ReviewStateExists())
+	if err != nil {
+		warningColor.Printf("⚠ Failed to prune review state: %v\n", err)
+		return
+	}
+	infoColor.Printf("Removed review state for %d deleted branch/commit(s)\n", removed)
+
+	if size, err := stateMgr.FileSize(); err == nil && size > int64(maxSizeMB)<<20 {
+		warningColor.Printf("⚠ Review state file is still %.1f MB after pruning; raise max-state-size-mb to silence this warning\n", float64(size)/(1<<20))
+	}
+}
+
+// archiveResolvedComments moves the repository's comments resolved more than
+// days ago into its resolved comment archive. A value of 0 disables archiving.
+func archiveResolvedComments(repoPath string, days int) {
+	if days <= 0 {
+		return
+	}
+
+	stateMgr, err := state.NewManager()
+	if err != nil {
+		return // The server reports state errors when it loads the state itself
+	}
+	archived, err := stateMgr.ArchiveResolved(repoPath, time.Duration(days)*24*time.Hour)
+	if err != nil {
+		warningColor.Printf("⚠ Failed to archive resolved comments: %v\n", err)
+		return
+	}
+	if archived > 0 {
+		infoColor.Printf("Archived %d comment(s) resolved more than %d day(s) ago\n", archived, days)
+	}
+}
+
+// applyStateGranularity switches the repository's review state to the
+// configured granularity, migrating the stored state if it changed
+func applyStateGranularity(repoPath, name string) {
+	granularity, err := state.ParseGranularity(name)
+	if err != nil {
+		return // Validated with the rest of the config
+	}
+
+	stateMgr, err := state.NewManager()
+	if err != nil {
+		return // The server reports state errors when it loads the state itself
+	}
+	gitRepo, err := git.Open(repoPath)
+	if err != nil {
+		return
+	}
+	changed, err := stateMgr.SetGranularity(repoPath, granularity, gitRepo.BranchCommit)
+	if err != nil {
+		warningColor.Printf("⚠ Failed to migrate review state to %s granularity: %v\n", granularity, err)
+		return
+	}
+	if changed {
+		infoColor.Printf("Migrated review state to %s granularity\n", granularity)
+	}
+}
+
+// browserWaitTimeout bounds how long "guck open" waits for the daemon to accept connections
+const browserWaitTimeout = 3 * time.Second
+
+func openBrowser(c *cli.Context) error {
+	gitRepo, err := git.Open(".")
+	if err != nil {
+		return err
+	}
+
+	repoPath, err := gitRepo.RepoPath()
+	if err != nil {
+		return err
+	}
+
+	daemonMgr, err := daemon.NewManager()
+	if err != nil {
+		return err
+	}
+
+	if err := daemonMgr.CleanupStaleDaemons(); err != nil {
+		return err
+	}
+
+	info, err := daemonMgr.GetDaemonForRepo(repoPath)
+	if err != nil || info == nil {
+		return fmt.Errorf("no daemon running for this repository. Run 'guck daemon start' first")
+	}
+
+	if !daemonMgr.IsDaemonRunning(info.PID) {
+		_ = daemonMgr.UnregisterDaemon(repoPath)
+		return fmt.Errorf("daemon is not running. Run 'guck daemon start' first")
+	}
+
+	if info.Socket != "" {
+		return fmt.Errorf("daemon serves on socket %s, which browsers can't open. Use a socket-aware client (e.g. curl --unix-socket) or restart it without --socket", info.Socket)
+	}
+
+	cfg, err := config.Load()
+	if err != nil {
+		return err
+	}
+
+	url := fmt.Sprintf("%s://localhost:%d%s/", cfg.URLScheme(), info.Port, config.NormalizeBasePath(cfg.BasePath))
+
+	// Right after "guck daemon start" the server may not have bound its port yet
+	if !daemon.WaitForPort(info.Port, browserWaitTimeout) {
+		return fmt.Errorf("daemon (PID %d) is not accepting connections on port %d after %s. Check its log with 'guck daemon attach'", info.PID, info.Port, browserWaitTimeout)
+	}
+
+	infoColor.Print("Opening ")
+	urlColor.Print(url)
+	infoColor.Println(" in your browser...")
+
+	var cmd *exec.Cmd
+	switch runtime.GOOS {
+	case "darwin":
+		cmd = exec.Command("open", url)
+	case "windows":
+		cmd = exec.Command("cmd", "/C", "start", url)
+	default:
+		cmd = exec.Command("xdg-open", url)
+	}
+
+	return cmd.Start()
+}
+
+func setConfig(c *cli.Context) error {
+	if c.NArg() != 2 {
+		return fmt.Errorf("requires exactly 2 arguments: key and value")
 	}
 
 	key := c.Args().Get(0)
@@ -718,6 +1960,10 @@ func setConfig(c *cli.Context) error {
 		return err
 	}
 
+	if name, ok := strings.CutPrefix(key, commentTemplatePrefix); ok {
+		return setCommentTemplate(cfg, name, value)
+	}
+
 	switch key {
 	case "base-branch":
 		cfg.BaseBranch = value
@@ -727,6 +1973,283 @@ func setConfig(c *cli.Context) error {
 		successColor.Print("✓ Set ")
 		infoColor.Print("base-branch")
 		successColor.Printf(" to '%s'\n", value)
+	case "offline":
+		offline, err := strconv.ParseBool(value)
+		if err != nil {
+			return fmt.Errorf("invalid value for offline: %s (expected true or false)", value)
+		}
+		cfg.Offline = offline
+		if err := cfg.Save(); err != nil {
+			return err
+		}
+		successColor.Print("✓ Set ")
+		infoColor.Print("offline")
+		successColor.Printf(" to '%t'\n", offline)
+	case "commit-abbrev-len":
+		length, err := strconv.Atoi(value)
+		if err != nil {
+			return fmt.Errorf("invalid value for commit-abbrev-len: %s (expected a number)", value)
+		}
+		cfg.CommitAbbrevLen = length
+		if err := cfg.Validate(); err != nil {
+			return err
+		}
+		if err := cfg.Save(); err != nil {
+			return err
+		}
+		successColor.Print("✓ Set ")
+		infoColor.Print("commit-abbrev-len")
+		successColor.Printf(" to '%d'\n", length)
+	case "notifications":
+		enabled, err := strconv.ParseBool(value)
+		if err != nil {
+			return fmt.Errorf("invalid value for notifications: %s (expected true or false)", value)
+		}
+		cfg.Notifications = enabled
+		if err := cfg.Save(); err != nil {
+			return err
+		}
+		successColor.Print("✓ Set ")
+		infoColor.Print("notifications")
+		successColor.Printf(" to '%t'\n", enabled)
+	case "recurse-submodules":
+		enabled, err := strconv.ParseBool(value)
+		if err != nil {
+			return fmt.Errorf("invalid value for recurse-submodules: %s (expected true or false)", value)
+		}
+		cfg.RecurseSubmodules = enabled
+		if err := cfg.Save(); err != nil {
+			return err
+		}
+		successColor.Print("✓ Set ")
+		infoColor.Print("recurse-submodules")
+		successColor.Printf(" to '%t'\n", enabled)
+	case "carry-forward-viewed":
+		enabled, err := strconv.ParseBool(value)
+		if err != nil {
+			return fmt.Errorf("invalid value for carry-forward-viewed: %s (expected true or false)", value)
+		}
+		cfg.CarryForwardViewed = enabled
+		if err := cfg.Save(); err != nil {
+			return err
+		}
+		successColor.Print("✓ Set ")
+		infoColor.Print("carry-forward-viewed")
+		successColor.Printf(" to '%t'\n", enabled)
+	case "textconv":
+		enabled, err := strconv.ParseBool(value)
+		if err != nil {
+			return fmt.Errorf("invalid value for textconv: %s (expected true or false)", value)
+		}
+		cfg.Textconv = enabled
+		if err := cfg.Save(); err != nil {
+			return err
+		}
+		successColor.Print("✓ Set ")
+		infoColor.Print("textconv")
+		successColor.Printf(" to '%t'\n", enabled)
+	case "tls-cert", "tls-key":
+		if value != "" {
+			abs, err := filepath.Abs(value)
+			if err != nil {
+				return fmt.Errorf("failed to resolve %s: %w", value, err)
+			}
+			value = abs
+		}
+		if key == "tls-cert" {
+			cfg.TLSCert = value
+		} else {
+			cfg.TLSKey = value
+		}
+		if err := cfg.Save(); err != nil {
+			return err
+		}
+		successColor.Print("✓ Set ")
+		infoColor.Print(key)
+		successColor.Printf(" to '%s'\n", value)
+	case "auto-view-patterns":
+		cfg.AutoViewPatterns = splitList(value)
+		if err := cfg.Validate(); err != nil {
+			return err
+		}
+		if err := cfg.Save(); err != nil {
+			return err
+		}
+		successColor.Print("✓ Set ")
+		infoColor.Print("auto-view-patterns")
+		successColor.Printf(" to '%s'\n", strings.Join(cfg.AutoViewPatterns, ","))
+	case "review-checklist":
+		cfg.ReviewChecklist = splitList(value)
+		if err := cfg.Validate(); err != nil {
+			return err
+		}
+		if err := cfg.Save(); err != nil {
+			return err
+		}
+		successColor.Print("✓ Set ")
+		infoColor.Print("review-checklist")
+		successColor.Printf(" to '%s'\n", strings.Join(cfg.ReviewChecklist, ","))
+	case "stale-base-commits":
+		staleBaseCommits, err := strconv.Atoi(value)
+		if err != nil {
+			return fmt.Errorf("invalid value for stale-base-commits: %s (expected a number, 0 to disable)", value)
+		}
+		cfg.StaleBaseCommits = staleBaseCommits
+		if err := cfg.Validate(); err != nil {
+			return err
+		}
+		if err := cfg.Save(); err != nil {
+			return err
+		}
+		successColor.Print("✓ Set ")
+		infoColor.Print("stale-base-commits")
+		successColor.Printf(" to '%d'\n", staleBaseCommits)
+	case "auto-start-allowlist":
+		allowlist, err := strconv.ParseBool(value)
+		if err != nil {
+			return fmt.Errorf("invalid value for auto-start-allowlist: %s (expected true or false)", value)
+		}
+		cfg.AutoStartAllowlist = allowlist
+		if err := cfg.Save(); err != nil {
+			return err
+		}
+		successColor.Print("✓ Set ")
+		infoColor.Print("auto-start-allowlist")
+		successColor.Printf(" to '%t'\n", allowlist)
+	case "max-state-size-mb":
+		maxStateSizeMB, err := strconv.Atoi(value)
+		if err != nil {
+			return fmt.Errorf("invalid value for max-state-size-mb: %s (expected a number, 0 to disable)", value)
+		}
+		cfg.MaxStateSizeMB = maxStateSizeMB
+		if err := cfg.Validate(); err != nil {
+			return err
+		}
+		if err := cfg.Save(); err != nil {
+			return err
+		}
+		successColor.Print("✓ Set ")
+		infoColor.Print("max-state-size-mb")
+		successColor.Printf(" to '%d'\n", maxStateSizeMB)
+	case "archive-resolved-days":
+		archiveResolvedDays, err := strconv.Atoi(value)
+		if err != nil {
+			return fmt.Errorf("invalid value for archive-resolved-days: %s (expected a number, 0 to disable)", value)
+		}
+		cfg.ArchiveResolvedDays = archiveResolvedDays
+		if err := cfg.Validate(); err != nil {
+			return err
+		}
+		if err := cfg.Save(); err != nil {
+			return err
+		}
+		successColor.Print("✓ Set ")
+		infoColor.Print("archive-resolved-days")
+		successColor.Printf(" to '%d'\n", archiveResolvedDays)
+	case "port-range-start", "port-range-end":
+		port, err := strconv.Atoi(value)
+		if err != nil {
+			return fmt.Errorf("invalid value for %s: %s (expected a port number)", key, value)
+		}
+		if key == "port-range-start" {
+			cfg.PortRangeStart = port
+		} else {
+			cfg.PortRangeEnd = port
+		}
+		if err := cfg.Validate(); err != nil {
+			return err
+		}
+		if err := cfg.Save(); err != nil {
+			return err
+		}
+		successColor.Print("✓ Set ")
+		infoColor.Print(key)
+		successColor.Printf(" to '%d'\n", port)
+	case "max-patch-kb":
+		maxPatchKB, err := strconv.Atoi(value)
+		if err != nil {
+			return fmt.Errorf("invalid value for max-patch-kb: %s (expected a number, 0 for no limit)", value)
+		}
+		cfg.MaxPatchKB = maxPatchKB
+		if err := cfg.Validate(); err != nil {
+			return err
+		}
+		if err := cfg.Save(); err != nil {
+			return err
+		}
+		successColor.Print("✓ Set ")
+		infoColor.Print("max-patch-kb")
+		successColor.Printf(" to '%d'\n", maxPatchKB)
+	case "max-files":
+		maxFiles, err := strconv.Atoi(value)
+		if err != nil {
+			return fmt.Errorf("invalid value for max-files: %s (expected a number, 0 for no limit)", value)
+		}
+		cfg.MaxFiles = maxFiles
+		if err := cfg.Validate(); err != nil {
+			return err
+		}
+		if err := cfg.Save(); err != nil {
+			return err
+		}
+		successColor.Print("✓ Set ")
+		infoColor.Print("max-files")
+		successColor.Printf(" to '%d'\n", maxFiles)
+	case "diff-algorithm":
+		cfg.DiffAlgorithm = value
+		if err := cfg.Validate(); err != nil {
+			return err
+		}
+		if err := cfg.Save(); err != nil {
+			return err
+		}
+		successColor.Print("✓ Set ")
+		infoColor.Print("diff-algorithm")
+		successColor.Printf(" to '%s'\n", value)
+	case "state-granularity":
+		cfg.StateGranularity = value
+		if err := cfg.Validate(); err != nil {
+			return err
+		}
+		if err := cfg.Save(); err != nil {
+			return err
+		}
+		successColor.Print("✓ Set ")
+		infoColor.Print("state-granularity")
+		successColor.Printf(" to '%s' (takes effect when the daemon next starts)\n", value)
+	case "log-format":
+		cfg.LogFormat = value
+		if err := cfg.Validate(); err != nil {
+			return err
+		}
+		if err := cfg.Save(); err != nil {
+			return err
+		}
+		successColor.Print("✓ Set ")
+		infoColor.Print("log-format")
+		successColor.Printf(" to '%s'\n", value)
+	case "diff-backend":
+		cfg.DiffBackend = value
+		if err := cfg.Validate(); err != nil {
+			return err
+		}
+		if err := cfg.Save(); err != nil {
+			return err
+		}
+		successColor.Print("✓ Set ")
+		infoColor.Print("diff-backend")
+		successColor.Printf(" to '%s'\n", value)
+	case "base-path":
+		cfg.BasePath = config.NormalizeBasePath(value)
+		if err := cfg.Validate(); err != nil {
+			return err
+		}
+		if err := cfg.Save(); err != nil {
+			return err
+		}
+		successColor.Print("✓ Set ")
+		infoColor.Print("base-path")
+		successColor.Printf(" to '%s'\n", cfg.BasePath)
 	default:
 		return fmt.Errorf("unknown configuration key: %s", key)
 	}
@@ -734,6 +2257,50 @@ func setConfig(c *cli.Context) error {
 	return nil
 }
 
+// commentTemplatePrefix namespaces comment template keys, e.g.
+// "guck config set comment-template.test 'Please add a test'"
+const commentTemplatePrefix = "comment-template."
+
+// setCommentTemplate adds or replaces a comment template; an empty text removes it
+func setCommentTemplate(cfg *config.Config, name, text string) error {
+	if name == "" {
+		return fmt.Errorf("missing comment template name (expected %s<name>)", commentTemplatePrefix)
+	}
+
+	if text == "" {
+		delete(cfg.CommentTemplates, name)
+	} else {
+		if cfg.CommentTemplates == nil {
+			cfg.CommentTemplates = make(map[string]string)
+		}
+		cfg.CommentTemplates[name] = text
+	}
+	if err := cfg.Save(); err != nil {
+		return err
+	}
+
+	if text == "" {
+		successColor.Print("✓ Removed ")
+		infoColor.Println(commentTemplatePrefix + name)
+		return nil
+	}
+	successColor.Print("✓ Set ")
+	infoColor.Print(commentTemplatePrefix + name)
+	successColor.Printf(" to '%s'\n", text)
+	return nil
+}
+
+// splitList parses a comma-separated config value, dropping empty entries
+func splitList(value string) []string {
+	items := []string{}
+	for _, item := range strings.Split(value, ",") {
+		if item = strings.TrimSpace(item); item != "" {
+			items = append(items, item)
+		}
+	}
+	return items
+}
+
 func getConfig(c *cli.Context) error {
 	if c.NArg() != 1 {
 		return fmt.Errorf("requires exactly 1 argument: key")
@@ -746,9 +2313,65 @@ func getConfig(c *cli.Context) error {
 		return err
 	}
 
+	if name, ok := strings.CutPrefix(key, commentTemplatePrefix); ok {
+		template, ok := cfg.CommentTemplates[name]
+		if !ok {
+			return fmt.Errorf("no comment template named '%s'", name)
+		}
+		fmt.Println(template)
+		return nil
+	}
+
 	switch key {
 	case "base-branch":
 		fmt.Println(cfg.BaseBranch)
+	case "offline":
+		fmt.Println(cfg.Offline)
+	case "commit-abbrev-len":
+		fmt.Println(cfg.CommitAbbrevLen)
+	case "base-path":
+		fmt.Println(cfg.BasePath)
+	case "diff-algorithm":
+		fmt.Println(cfg.DiffAlgorithm)
+	case "diff-backend":
+		fmt.Println(cfg.DiffBackend)
+	case "log-format":
+		fmt.Println(cfg.LogFormat)
+	case "state-granularity":
+		granularity, _ := state.ParseGranularity(cfg.StateGranularity)
+		fmt.Println(granularity)
+	case "textconv":
+		fmt.Println(cfg.Textconv)
+	case "notifications":
+		fmt.Println(cfg.Notifications)
+	case "recurse-submodules":
+		fmt.Println(cfg.RecurseSubmodules)
+	case "carry-forward-viewed":
+		fmt.Println(cfg.CarryForwardViewed)
+	case "max-files":
+		fmt.Println(cfg.MaxFiles)
+	case "max-patch-kb":
+		fmt.Println(cfg.MaxPatchKB)
+	case "stale-base-commits":
+		fmt.Println(cfg.StaleBaseCommits)
+	case "max-state-size-mb":
+		fmt.Println(cfg.MaxStateSizeMB)
+	case "archive-resolved-days":
+		fmt.Println(cfg.ArchiveResolvedDays)
+	case "port-range-start":
+		fmt.Println(cfg.PortRangeStart)
+	case "port-range-end":
+		fmt.Println(cfg.PortRangeEnd)
+	case "auto-start-allowlist":
+		fmt.Println(cfg.AutoStartAllowlist)
+	case "auto-view-patterns":
+		fmt.Println(strings.Join(cfg.AutoViewPatterns, ","))
+	case "review-checklist":
+		fmt.Println(strings.Join(cfg.ReviewChecklist, ","))
+	case "tls-cert":
+		fmt.Println(cfg.TLSCert)
+	case "tls-key":
+		fmt.Println(cfg.TLSKey)
 	default:
 		return fmt.Errorf("unknown configuration key: %s", key)
 	}
@@ -764,6 +2387,104 @@ func showConfig(c *cli.Context) error {
 
 	infoColor.Print("base-branch = ")
 	successColor.Println(cfg.BaseBranch)
+	infoColor.Print("offline = ")
+	successColor.Println(cfg.Offline)
+	infoColor.Print("commit-abbrev-len = ")
+	successColor.Println(cfg.CommitAbbrevLen)
+	infoColor.Print("base-path = ")
+	successColor.Println(cfg.BasePath)
+	infoColor.Print("diff-algorithm = ")
+	successColor.Println(cfg.DiffAlgorithm)
+	infoColor.Print("diff-backend = ")
+	successColor.Println(cfg.DiffBackend)
+	infoColor.Print("log-format = ")
+	successColor.Println(cfg.LogFormat)
+	stateGranularity, _ := state.ParseGranularity(cfg.StateGranularity)
+	infoColor.Print("state-granularity = ")
+	successColor.Println(stateGranularity)
+	infoColor.Print("textconv = ")
+	successColor.Println(cfg.Textconv)
+	infoColor.Print("notifications = ")
+	successColor.Println(cfg.Notifications)
+	infoColor.Print("recurse-submodules = ")
+	successColor.Println(cfg.RecurseSubmodules)
+	infoColor.Print("carry-forward-viewed = ")
+	successColor.Println(cfg.CarryForwardViewed)
+	infoColor.Print("max-files = ")
+	successColor.Println(cfg.MaxFiles)
+	infoColor.Print("max-patch-kb = ")
+	successColor.Println(cfg.MaxPatchKB)
+	infoColor.Print("stale-base-commits = ")
+	successColor.Println(cfg.StaleBaseCommits)
+	infoColor.Print("max-state-size-mb = ")
+	successColor.Println(cfg.MaxStateSizeMB)
+	infoColor.Print("archive-resolved-days = ")
+	successColor.Println(cfg.ArchiveResolvedDays)
+	infoColor.Print("port-range-start = ")
+	successColor.Println(cfg.PortRangeStart)
+	infoColor.Print("port-range-end = ")
+	successColor.Println(cfg.PortRangeEnd)
+	infoColor.Print("auto-start-allowlist = ")
+	successColor.Println(cfg.AutoStartAllowlist)
+	infoColor.Print("auto-view-patterns = ")
+	successColor.Println(strings.Join(cfg.AutoViewPatterns, ","))
+	infoColor.Print("review-checklist = ")
+	successColor.Println(strings.Join(cfg.ReviewChecklist, ","))
+	infoColor.Print("tls-cert = ")
+	successColor.Println(cfg.TLSCert)
+	infoColor.Print("tls-key = ")
+	successColor.Println(cfg.TLSKey)
+
+	names := make([]string, 0, len(cfg.CommentTemplates))
+	for name := range cfg.CommentTemplates {
+		names = append(names, name)
+	}
+	sort.Strings(names)
+	for _, name := range names {
+		infoColor.Printf("%s%s = ", commentTemplatePrefix, name)
+		successColor.Println(cfg.CommentTemplates[name])
+	}
+	return nil
+}
+
+func printPaths(c *cli.Context) error {
+	configPath, err := config.Path()
+	if err != nil {
+		return err
+	}
+
+	stateDir, err := state.Dir()
+	if err != nil {
+		return err
+	}
+
+	stateFile, err := state.FilePath()
+	if err != nil {
+		return err
+	}
+
+	daemonMgr, err := daemon.NewManager()
+	if err != nil {
+		return err
+	}
+
+	infoColor.Print("config = ")
+	successColor.Println(configPath)
+	infoColor.Print("state-dir = ")
+	successColor.Println(stateDir)
+	infoColor.Print("state-file = ")
+	successColor.Println(stateFile)
+	infoColor.Print("registry = ")
+	successColor.Println(daemonMgr.RegistryPath())
+
+	// The log path is per repository, so it's only shown inside one
+	if gitRepo, err := git.Open("."); err == nil {
+		if repoPath, err := gitRepo.RepoPath(); err == nil {
+			infoColor.Print("log = ")
+			successColor.Println(daemonMgr.GetLogPath(repoPath))
+		}
+	}
+
 	return nil
 }
 
@@ -771,6 +2492,115 @@ func mcpStdio(c *cli.Context) error {
 	return mcp.StartStdioServer()
 }
 
+func carryForwardState(c *cli.Context) error {
+	gitRepo, err := git.Open(".")
+	if err != nil {
+		return err
+	}
+
+	repoPath, err := gitRepo.RepoPath()
+	if err != nil {
+		return err
+	}
+
+	branch, err := gitRepo.CurrentBranch()
+	if err != nil {
+		return err
+	}
+
+	commit, err := gitRepo.CurrentCommit()
+	if err != nil {
+		return err
+	}
+
+	fromCommit := c.String("from")
+	if fromCommit == "" {
+		fromCommit, err = gitRepo.AmendedFrom()
+		if err != nil {
+			return err
+		}
+		if fromCommit == "" {
+			return fmt.Errorf("HEAD was not created by an amend. Use --from to choose a commit")
+		}
+	}
+
+	mgr, err := state.NewManager()
+	if err != nil {
+		return err
+	}
+
+	viewed, comments, err := mgr.CarryForward(repoPath, branch, fromCommit, commit)
+	if err != nil {
+		return err
+	}
+
+	successColor.Printf("✓ Carried forward %d viewed file(s) and %d comment(s)\n", viewed, comments)
+	infoColor.Printf("  From: %s\n", fromCommit)
+	infoColor.Printf("  To: %s\n", commit)
+	return nil
+}
+
+func stateEvents(c *cli.Context) error {
+	gitRepo, err := git.Open(".")
+	if err != nil {
+		return err
+	}
+
+	repoPath, err := gitRepo.RepoPath()
+	if err != nil {
+		return err
+	}
+
+	var since int64
+	if window := c.Duration("since"); window > 0 {
+		since = time.Now().Add(-window).Unix()
+	}
+
+	mgr, err := state.NewManager()
+	if err != nil {
+		return err
+	}
+
+	events, err := mgr.Events(repoPath, since)
+	if err != nil {
+		return err
+	}
+
+	if c.Bool("json") {
+		encoder := json.NewEncoder(os.Stdout)
+		encoder.SetIndent("", "  ")
+		return encoder.Encode(events)
+	}
+
+	if len(events) == 0 {
+		infoColor.Println("No events")
+		return nil
+	}
+	for _, event := range events {
+		line := fmt.Sprintf("%s  %-20s  %s@%s", time.Unix(event.Time, 0).Format(time.RFC3339), event.Action, event.Branch, event.Commit)
+		switch {
+		case event.FilePath != "":
+			line += "  " + event.FilePath
+		case event.Item != "":
+			line += "  " + strconv.Quote(event.Item)
+		}
+		if event.ID != "" {
+			line += "  #" + event.ID
+		}
+		if event.Actor != "" {
+			line += "  by " + event.Actor
+		}
+		fmt.Println(line)
+	}
+	return nil
+}
+
+func printAPISchema(c *cli.Context) error {
+	encoder := json.NewEncoder(os.Stdout)
+	encoder.SetIndent("", "  ")
+	return encoder.Encode(server.OpenAPISpec())
+}
+
 func addSampleNotes(c *cli.Context) error {
 	gitRepo, err := git.Open(".")
 	if err != nil {